@@ -0,0 +1,55 @@
+//! Benchmarks for parsing queries
+//!
+//! Parsing queries is the main cost when many values are read from or written to documents with
+//! the `str` based functions. Run with `cargo bench --bench query`.
+
+#[macro_use]
+extern crate criterion;
+extern crate toml_query;
+
+use criterion::{black_box, Criterion};
+use toml_query::query::Query;
+
+/// Queries as they are found in configuration loading code
+const QUERIES: &[&str] = &[
+    "name",
+    "server.port",
+    "database.connection.pool.max_size",
+    "servers.[0].hosts.[12]",
+    "package.\"metadata.docs\".features",
+    "profiles.{dev,release}.opt-level",
+    "servers.[1:3].name",
+    "servers.[?(@.role == 'primary')].address",
+    "dependencies.*.version",
+    "workspace.**.path",
+];
+
+fn parse_queries(c: &mut Criterion) {
+    c.bench_function("parse all queries", |b| {
+        b.iter(|| {
+            for query in QUERIES {
+                black_box(Query::parse(black_box(query)).unwrap());
+            }
+        })
+    });
+
+    for query in QUERIES {
+        c.bench_function(&format!("parse {}", query), move |b| {
+            b.iter(|| Query::parse(black_box(query)).unwrap())
+        });
+    }
+}
+
+fn parse_long_query(c: &mut Criterion) {
+    let query = (0..64)
+        .map(|i| format!("table{}", i))
+        .collect::<Vec<_>>()
+        .join(".");
+
+    c.bench_function("parse 64 segments", move |b| {
+        b.iter(|| Query::parse(black_box(&query)).unwrap())
+    });
+}
+
+criterion_group!(benches, parse_queries, parse_long_query);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+/// The Toml Arithmetic extensions
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::util::name_of_val;
+
+pub trait TomlValueArithExt {
+    /// Extension function for adding `delta` to a number in the current toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// Integers can be changed by integers, floats by integers and floats. A negative `delta`
+    /// decrements the value. The new value is returned.
+    ///
+    /// * If there is no value at the queried path: `Error::NotAvailable`
+    /// * If the value or `delta` is no number, or `delta` is a float but the value an integer:
+    ///   `Error::TypeError`
+    /// * If the sum of integers overflows: `Error::OutOfRange`
+    ///
+    /// The document is left as it is if the operation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::arith::TomlValueArithExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[stats]\nvisits = 41").unwrap();
+    ///
+    /// let visits = toml.add_to("stats.visits", toml::Value::Integer(1)).unwrap();
+    /// assert_eq!(visits, toml::Value::Integer(42));
+    /// ```
+    fn add_to(&mut self, query: &str, delta: Value) -> Result<Value> {
+        self.add_to_query(&Query::parse(query)?, delta)
+    }
+
+    /// Extension function for adding `delta` to a number in the current toml::Value document
+    /// using a pre-compiled query
+    ///
+    /// See documentation of `TomlValueArithExt::add_to`
+    fn add_to_query(&mut self, query: &Query, delta: Value) -> Result<Value>;
+}
+
+impl TomlValueArithExt for Value {
+    fn add_to_query(&mut self, query: &Query, delta: Value) -> Result<Value> {
+        let value = self
+            .read_mut_query(query)?
+            .ok_or_else(|| Error::NotAvailable(query.to_string()))?;
+
+        let sum = match (&*value, &delta) {
+            (&Value::Integer(i), &Value::Integer(d)) => i
+                .checked_add(d)
+                .map(Value::Integer)
+                .ok_or_else(|| Error::OutOfRange(format!("{} + {}", i, d), "i64"))?,
+            (&Value::Float(f), &Value::Integer(d)) => Value::Float(f + d as f64),
+            (&Value::Float(f), &Value::Float(d)) => Value::Float(f + d),
+            (&Value::Integer(_), delta) => {
+                return Err(Error::TypeError("Integer", name_of_val(delta)));
+            }
+            (&Value::Float(_), delta) => {
+                return Err(Error::TypeError("Integer or Float", name_of_val(delta)));
+            }
+            (value, _) => return Err(Error::TypeError("Integer or Float", name_of_val(value))),
+        };
+
+        *value = sum.clone();
+        Ok(sum)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_add_to() {
+        let mut toml: Value = toml_from_str("a = 1\nb = 1.5\nc = 'x'").unwrap();
+
+        assert!(is_match!(
+            toml.add_to("a", Value::Integer(-3)),
+            Ok(Value::Integer(-2))
+        ));
+        assert_eq!(
+            Ok(Value::Float(2.5)),
+            toml.add_to("b", Value::Integer(1)).map_err(|_| ())
+        );
+        assert_eq!(
+            Ok(Value::Float(3.0)),
+            toml.add_to("b", Value::Float(0.5)).map_err(|_| ())
+        );
+
+        let res = toml.add_to("a", Value::Integer(::std::i64::MAX));
+        assert!(is_match!(res, Ok(Value::Integer(_))));
+        let res = toml.add_to("a", Value::Integer(3));
+        assert!(is_match!(res, Err(Error::OutOfRange(..))));
+
+        let res = toml.add_to("a", Value::Float(0.5));
+        assert!(is_match!(res, Err(Error::TypeError("Integer", "Float"))));
+        let res = toml.add_to("c", Value::Integer(1));
+        assert!(is_match!(res, Err(Error::TypeError(_, "String"))));
+        let res = toml.add_to("x", Value::Integer(1));
+        assert!(is_match!(res, Err(Error::NotAvailable(_))));
+
+        let expected: Value = toml_from_str("a = 9223372036854775805\nb = 3.0\nc = 'x'").unwrap();
+        assert_eq!(expected, toml);
+    }
+}
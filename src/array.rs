@@ -0,0 +1,339 @@
+/// The Toml Array extensions
+use std::cmp::Ordering;
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::read::TomlValueReadExt;
+use crate::util::name_of_val;
+
+/// Extension functions for modifying the array at a path in a toml::Value document
+///
+/// All functions fail with `Error::NotAvailable` if there is no value at the queried path and
+/// with `Error::TypeError` if the value is not an array.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::array::TomlValueArrayExt;
+///
+/// let mut toml : toml::Value = toml::from_str("[build]\njobs = [ 1 ]").unwrap();
+///
+/// toml.push("build.jobs", toml::Value::Integer(2)).unwrap();
+/// toml.insert_at("build.jobs", 0, toml::Value::Integer(0)).unwrap();
+/// assert_eq!(Some(toml::Value::Integer(2)), toml.pop("build.jobs").unwrap());
+/// assert_eq!(toml::Value::Integer(0), toml.remove_at("build.jobs", 0).unwrap());
+///
+/// assert_eq!(toml, toml::from_str("[build]\njobs = [ 1 ]").unwrap());
+/// ```
+pub trait TomlValueArrayExt {
+    /// Append `value` to the array at `query`
+    fn push(&mut self, query: &str, value: Value) -> Result<()>;
+
+    /// Remove the last element of the array at `query`
+    ///
+    /// Returns `Ok(None)` if the array is empty.
+    fn pop(&mut self, query: &str) -> Result<Option<Value>>;
+
+    /// Insert `value` at `idx` into the array at `query`, shifting all following elements
+    ///
+    /// Fails with `Error::ArrayIndexOutOfBounds` if `idx` is larger than the length of the array.
+    fn insert_at(&mut self, query: &str, idx: usize, value: Value) -> Result<()>;
+
+    /// Remove the element at `idx` from the array at `query`, shifting all following elements
+    ///
+    /// Fails with `Error::ArrayIndexOutOfBounds` if there is no element at `idx`.
+    fn remove_at(&mut self, query: &str, idx: usize) -> Result<Value>;
+
+    /// Sort the array at `query`
+    ///
+    /// Numbers are ordered by their value (integers and floats alike), strings and datetimes
+    /// lexicographically, `false` before `true` and arrays element by element. Values of
+    /// different types are ordered booleans, numbers, strings, datetimes, arrays, tables. Tables
+    /// count as equal, the sort is stable.
+    fn sort_array(&mut self, query: &str, order: SortOrder) -> Result<()>;
+
+    /// Sort the array of tables at `query` in ascending order of the values at `key` in the
+    /// tables, like `sort_array()` does
+    ///
+    /// `key` is a query, tables without a value at `key` come last. Fails with
+    /// `Error::TypeError` if an element is not a table.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::array::TomlValueArrayExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[[bin]]\nname = 'b'\n[[bin]]\nname = 'a'").unwrap();
+    /// toml.sort_array_by_key("bin", "name").unwrap();
+    ///
+    /// assert_eq!(toml, toml::from_str("[[bin]]\nname = 'a'\n[[bin]]\nname = 'b'").unwrap());
+    /// ```
+    fn sort_array_by_key(&mut self, query: &str, key: &str) -> Result<()>;
+
+    /// Remove the elements of the array at `query` which are equal to an earlier element,
+    /// returning the number of removed elements
+    fn dedup_array(&mut self, query: &str) -> Result<usize>;
+}
+
+/// The order of `TomlValueArrayExt::sort_array()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest value first
+    Ascending,
+
+    /// Largest value first
+    Descending,
+}
+
+impl Default for SortOrder {
+    fn default() -> SortOrder {
+        SortOrder::Ascending
+    }
+}
+
+impl TomlValueArrayExt for Value {
+    fn push(&mut self, query: &str, value: Value) -> Result<()> {
+        array_mut(self, query).map(|ary| ary.push(value))
+    }
+
+    fn pop(&mut self, query: &str) -> Result<Option<Value>> {
+        array_mut(self, query).map(|ary| ary.pop())
+    }
+
+    fn insert_at(&mut self, query: &str, idx: usize, value: Value) -> Result<()> {
+        let ary = array_mut(self, query)?;
+        if idx > ary.len() {
+            return Err(Error::ArrayIndexOutOfBounds(idx, ary.len()));
+        }
+
+        ary.insert(idx, value);
+        Ok(())
+    }
+
+    fn remove_at(&mut self, query: &str, idx: usize) -> Result<Value> {
+        let ary = array_mut(self, query)?;
+        if idx >= ary.len() {
+            return Err(Error::ArrayIndexOutOfBounds(idx, ary.len()));
+        }
+
+        Ok(ary.remove(idx))
+    }
+
+    fn sort_array(&mut self, query: &str, order: SortOrder) -> Result<()> {
+        let ary = array_mut(self, query)?;
+        match order {
+            SortOrder::Ascending => ary.sort_by(compare),
+            SortOrder::Descending => ary.sort_by(|a, b| compare(b, a)),
+        }
+        Ok(())
+    }
+
+    fn sort_array_by_key(&mut self, query: &str, key: &str) -> Result<()> {
+        let ary = array_mut(self, query)?;
+        if let Some(elem) = ary.iter().find(|elem| !elem.is_table()) {
+            return Err(Error::TypeError("Table", name_of_val(elem)));
+        }
+
+        sort_by_key(ary, key)
+    }
+
+    fn dedup_array(&mut self, query: &str) -> Result<usize> {
+        let ary = array_mut(self, query)?;
+        let len = ary.len();
+        let mut unique: Vec<Value> = Vec::with_capacity(len);
+        for elem in ary.drain(..) {
+            if !unique.contains(&elem) {
+                unique.push(elem);
+            }
+        }
+
+        *ary = unique;
+        Ok(len - ary.len())
+    }
+}
+
+/// Sort `ary` in ascending order of the values at `key` in its elements, elements without a
+/// value at `key` last
+pub(crate) fn sort_by_key(ary: &mut Vec<Value>, key: &str) -> Result<()> {
+    let keys = ary
+        .iter()
+        .map(|elem| elem.read(key).map(|value| value.cloned()))
+        .collect::<Result<Vec<_>>>()?;
+    let mut keyed = keys.into_iter().zip(ary.drain(..)).collect::<Vec<_>>();
+
+    keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => compare(a, b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    ary.extend(keyed.into_iter().map(|(_, elem)| elem));
+    Ok(())
+}
+
+/// The order of values for `TomlValueArrayExt::sort_array()`
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match *value {
+            Value::Boolean(_) => 0,
+            Value::Integer(_) | Value::Float(_) => 1,
+            Value::String(_) => 2,
+            Value::Datetime(_) => 3,
+            Value::Array(_) => 4,
+            Value::Table(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (&Value::Boolean(a), &Value::Boolean(b)) => a.cmp(&b),
+        (&Value::Integer(a), &Value::Integer(b)) => a.cmp(&b),
+        (&Value::Integer(a), &Value::Float(b)) => compare_floats(a as f64, b),
+        (&Value::Float(a), &Value::Integer(b)) => compare_floats(a, b as f64),
+        (&Value::Float(a), &Value::Float(b)) => compare_floats(a, b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Datetime(a), Value::Datetime(b)) => a.to_string().cmp(&b.to_string()),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| compare(a, b))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Floats in ascending order, NaN counts as equal to everything
+fn compare_floats(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+fn array_mut<'doc>(doc: &'doc mut Value, query: &str) -> Result<&'doc mut Vec<Value>> {
+    match doc.read_mut(query)? {
+        Some(Value::Array(ref mut ary)) => Ok(ary),
+        Some(other) => Err(Error::TypeError("Array", name_of_val(other))),
+        None => Err(Error::NotAvailable(String::from(query))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut toml: Value = toml_from_str("a = { b = [ 1 ] }").unwrap();
+
+        assert!(toml.push("a.b", Value::Integer(2)).is_ok());
+        let expected: Value = toml_from_str("a = { b = [ 1, 2 ] }").unwrap();
+        assert_eq!(expected, toml);
+
+        assert!(is_match!(toml.pop("a.b"), Ok(Some(Value::Integer(2)))));
+        assert!(is_match!(toml.pop("a.b"), Ok(Some(Value::Integer(1)))));
+        assert!(is_match!(toml.pop("a.b"), Ok(None)));
+    }
+
+    #[test]
+    fn test_insert_at() {
+        let mut toml: Value = toml_from_str("a = [ 1, 3 ]").unwrap();
+
+        assert!(toml.insert_at("a", 1, Value::Integer(2)).is_ok());
+        assert!(toml.insert_at("a", 3, Value::Integer(4)).is_ok());
+
+        let res = toml.insert_at("a", 5, Value::Integer(6));
+        assert!(res.is_err());
+        assert!(is_match!(
+            res.unwrap_err(),
+            Error::ArrayIndexOutOfBounds(5, 4)
+        ));
+
+        let expected: Value = toml_from_str("a = [ 1, 2, 3, 4 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_remove_at() {
+        let mut toml: Value = toml_from_str("a = [ 1, 2, 3 ]").unwrap();
+
+        assert!(is_match!(toml.remove_at("a", 0), Ok(Value::Integer(1))));
+
+        let res = toml.remove_at("a", 2);
+        assert!(res.is_err());
+        assert!(is_match!(
+            res.unwrap_err(),
+            Error::ArrayIndexOutOfBounds(2, 2)
+        ));
+
+        let expected: Value = toml_from_str("a = [ 2, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_sort_array() {
+        let mut toml: Value = toml_from_str("a = [ 3, 1.5, 2, -1 ]\nb = [ 'b', 'a' ]").unwrap();
+
+        assert!(toml.sort_array("a", SortOrder::Ascending).is_ok());
+        assert!(toml.sort_array("b", SortOrder::Descending).is_ok());
+
+        let expected: Value = toml_from_str("a = [ -1, 1.5, 2, 3 ]\nb = [ 'b', 'a' ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_sort_array_by_key() {
+        let mut toml: Value = toml_from_str(
+            r#"
+            bins = [ { name = "b" }, { path = "x" }, { name = "a" } ]
+            mixed = [ { name = "b" }, 1 ]
+            "#,
+        )
+        .unwrap();
+
+        assert!(toml.sort_array_by_key("bins", "name").is_ok());
+        let res = toml.sort_array_by_key("mixed", "name");
+        assert!(is_match!(res, Err(Error::TypeError("Table", "Integer"))));
+
+        let expected: Value = toml_from_str(
+            r#"
+            bins = [ { name = "a" }, { name = "b" }, { path = "x" } ]
+            mixed = [ { name = "b" }, 1 ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_dedup_array() {
+        let mut toml: Value = toml_from_str("a = [ 1, 2, 1, 3, 2 ]").unwrap();
+
+        assert!(is_match!(toml.dedup_array("a"), Ok(2)));
+
+        let expected: Value = toml_from_str("a = [ 1, 2, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_missing_array_is_error() {
+        let mut toml: Value = toml_from_str("[a]").unwrap();
+
+        let res = toml.push("a.b", Value::Integer(1));
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::NotAvailable(_)));
+    }
+
+    #[test]
+    fn test_non_array_is_error() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+
+        let res = toml.pop("a.b");
+        assert!(res.is_err());
+        assert!(is_match!(
+            res.unwrap_err(),
+            Error::TypeError("Array", "Integer")
+        ));
+    }
+
+}
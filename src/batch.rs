@@ -0,0 +1,324 @@
+/// The Toml Batch extensions
+use toml::Value;
+
+use crate::copy::TomlValueCopyExt;
+use crate::delete::TomlValueDeleteExt;
+use crate::error::Result;
+use crate::insert::TomlValueInsertExt;
+use crate::mv::TomlValueMoveExt;
+use crate::query::Query;
+use crate::rename::TomlValueRenameExt;
+use crate::set::TomlValueSetExt;
+use crate::transaction::{atomically, Transaction};
+use crate::upsert::{TomlValueUpsertExt, Upserted};
+
+/// An operation on a document, which can be applied as part of a batch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// See `TomlValueSetExt::set`
+    Set { query: String, value: Value },
+
+    /// See `TomlValueInsertExt::insert`
+    Insert { query: String, value: Value },
+
+    /// See `TomlValueUpsertExt::upsert`
+    Upsert { query: String, value: Value },
+
+    /// See `TomlValueDeleteExt::delete`
+    Delete { query: String },
+
+    /// See `TomlValueDeleteExt::delete_recursive`
+    DeleteRecursive { query: String },
+
+    /// See `TomlValueRenameExt::rename`
+    Rename { query: String, new_key: String },
+
+    /// See `TomlValueMoveExt::mv`
+    Move { from: String, to: String },
+
+    /// See `TomlValueCopyExt::copy`
+    Copy { from: String, to: String },
+}
+
+/// The outcome of an operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The operation replaced this value
+    Replaced(Value),
+
+    /// The operation removed this value
+    Removed(Value),
+
+    /// The operation neither replaced nor removed a value
+    Done,
+}
+
+impl Outcome {
+    fn replaced(old: Option<Value>) -> Outcome {
+        old.map(Outcome::Replaced).unwrap_or(Outcome::Done)
+    }
+
+    fn removed(old: Option<Value>) -> Outcome {
+        old.map(Outcome::Removed).unwrap_or(Outcome::Done)
+    }
+}
+
+pub trait TomlValueBatchExt {
+    /// Extension function for applying several operations to the current toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// The operations are applied in order. Either all of them are applied or none: if one of
+    /// them fails, the document is restored to its state before the batch.
+    ///
+    /// # Return value
+    ///
+    /// The outcomes of the operations, in the order of the operations, or the error of the
+    /// first operation which failed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::batch::{Operation, Outcome, TomlValueBatchExt};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[server]\nhostname = \"a\"").unwrap();
+    ///
+    /// let outcomes = toml.apply(vec![
+    ///     Operation::Rename { query: "server.hostname".into(), new_key: "host".into() },
+    ///     Operation::Insert { query: "server.port".into(), value: toml::Value::Integer(80) },
+    /// ]);
+    /// assert_eq!(outcomes.unwrap(), vec![Outcome::Done, Outcome::Done]);
+    ///
+    /// let outcomes = toml.apply(vec![
+    ///     Operation::Delete { query: "server.port".into() },
+    ///     Operation::Delete { query: "server.missing.key".into() },
+    /// ]);
+    /// assert!(outcomes.is_err());
+    /// assert_eq!(toml, toml::from_str("[server]\nhost = \"a\"\nport = 80").unwrap());
+    /// ```
+    fn apply(&mut self, ops: Vec<Operation>) -> Result<Vec<Outcome>>;
+
+    /// Extension function for setting several values in the current toml::Value document, like
+    /// `TomlValueSetExt::set` does
+    ///
+    /// # Semantics
+    ///
+    /// All queries are parsed before the document is changed. The values are set in order, a
+    /// value can be set in a table or array set by an earlier one. If one of them cannot be set,
+    /// the values set before it are restored, so the document is either changed by all of them or
+    /// left as it is.
+    ///
+    /// # Return value
+    ///
+    /// The replaced values, in the order of `values`, or the error of the first query which
+    /// failed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::batch::TomlValueBatchExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[server]\nhost = 'a'\nport = 80").unwrap();
+    ///
+    /// let res = toml.set_many(vec![
+    ///     ("server.host", toml::Value::from("b")),
+    ///     ("server.tls.port", toml::Value::Integer(443)),
+    /// ]);
+    /// assert!(res.is_err());
+    /// assert_eq!(toml, toml::from_str("[server]\nhost = 'a'\nport = 80").unwrap());
+    /// ```
+    fn set_many(&mut self, values: Vec<(&str, Value)>) -> Result<Vec<Option<Value>>>;
+}
+
+impl TomlValueBatchExt for Value {
+    fn apply(&mut self, ops: Vec<Operation>) -> Result<Vec<Outcome>> {
+        atomically(self, |tx| {
+            ops.into_iter().map(|op| apply_operation(tx, op)).collect()
+        })
+    }
+
+    fn set_many(&mut self, values: Vec<(&str, Value)>) -> Result<Vec<Option<Value>>> {
+        let values = values
+            .into_iter()
+            .map(|(query, value)| Query::parse(query).map(|query| (query, value)))
+            .collect::<Result<Vec<_>>>()?;
+
+        atomically(self, |tx| {
+            values
+                .into_iter()
+                .map(|(query, value)| tx.set_query(&query, value))
+                .collect()
+        })
+    }
+}
+
+fn apply_operation(doc: &mut Transaction, op: Operation) -> Result<Outcome> {
+    match op {
+        Operation::Set { query, value } => doc.set(&query, value).map(Outcome::replaced),
+        Operation::Insert { query, value } => doc.insert(&query, value).map(Outcome::replaced),
+        Operation::Upsert { query, value } => {
+            doc.upsert(&query, value).map(|upserted| match upserted {
+                Upserted::Replaced(old) => Outcome::Replaced(old),
+                Upserted::Inserted => Outcome::Done,
+            })
+        }
+        Operation::Delete { query } => doc.delete(&query).map(Outcome::removed),
+        Operation::DeleteRecursive { query } => doc.delete_recursive(&query).map(Outcome::removed),
+        Operation::Rename { query, new_key } => {
+            let query = Query::parse(&query)?;
+            doc.rename_query(&query, &new_key, false)
+                .map(Outcome::replaced)
+        }
+        Operation::Move { from, to } => doc.mv(&from, &to).map(Outcome::replaced),
+        Operation::Copy { from, to } => doc.copy(&from, &to).map(Outcome::replaced),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_apply() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [a]
+        b = 1
+        c = [ 1, 2 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.apply(vec![
+            Operation::Set {
+                query: String::from("a.b"),
+                value: Value::Integer(2),
+            },
+            Operation::Insert {
+                query: String::from("x.y"),
+                value: Value::Integer(3),
+            },
+            Operation::Upsert {
+                query: String::from("x.y"),
+                value: Value::Integer(4),
+            },
+            Operation::DeleteRecursive {
+                query: String::from("a.c"),
+            },
+            Operation::Copy {
+                from: String::from("a.b"),
+                to: String::from("x.z"),
+            },
+            Operation::Move {
+                from: String::from("x"),
+                to: String::from("a.x"),
+            },
+            Operation::Rename {
+                query: String::from("a.b"),
+                new_key: String::from("d"),
+            },
+            Operation::Delete {
+                query: String::from("a.x.y"),
+            },
+        ]);
+
+        assert!(res.is_ok());
+        assert_eq!(
+            vec![
+                Outcome::Replaced(Value::Integer(1)),
+                Outcome::Done,
+                Outcome::Replaced(Value::Integer(3)),
+                Outcome::Removed(Value::Array(vec![Value::Integer(1), Value::Integer(2)])),
+                Outcome::Done,
+                Outcome::Done,
+                Outcome::Done,
+                Outcome::Removed(Value::Integer(4)),
+            ],
+            res.unwrap()
+        );
+
+        let expected: Value = toml_from_str(
+            r#"
+        [a]
+        d = 2
+
+        [a.x]
+        z = 2
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_apply_rolls_back_on_failure() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.apply(vec![
+            Operation::Set {
+                query: String::from("a"),
+                value: Value::Integer(2),
+            },
+            Operation::Insert {
+                query: String::from("b.c"),
+                value: Value::Integer(3),
+            },
+            Operation::Rename {
+                query: String::from("b"),
+                new_key: String::from("a"),
+            },
+        ]);
+
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::KeyExists(_)));
+
+        let expected: Value = toml_from_str("a = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_many() {
+        let mut toml: Value = toml_from_str("a = 1\nb = [ 1 ]").unwrap();
+
+        let res = toml.set_many(vec![
+            ("a", Value::Integer(2)),
+            ("b", Value::Array(vec![])),
+            ("b.[+]", Value::Integer(3)),
+        ]);
+        assert_eq!(
+            vec![
+                Some(Value::Integer(1)),
+                Some(Value::Array(vec![Value::Integer(1)])),
+                None
+            ],
+            res.unwrap()
+        );
+
+        let res = toml.set_many(vec![("a", Value::Integer(4)), ("c.d", Value::Integer(5))]);
+        assert!(res.is_err());
+        let res = toml.set_many(vec![("a", Value::Integer(4)), ("", Value::Integer(5))]);
+        assert!(is_match!(res, Err(Error::EmptyQueryError)));
+
+        let expected: Value = toml_from_str("a = 2\nb = [ 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_apply_nothing() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.apply(vec![]);
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_empty());
+    }
+
+}
@@ -0,0 +1,224 @@
+//! `tomlq`, reading and editing TOML documents on the command line
+//!
+//! ```text
+//! tomlq get server.port config.toml
+//! tomlq set server.port 8080 config.toml --in-place
+//! cat config.toml | tomlq delete server.host
+//! ```
+//!
+//! Documents are read from the file given as last argument, or from stdin. Changed documents are
+//! written to stdout, or back to the file with `--in-place`.
+extern crate clap;
+extern crate failure;
+extern crate toml;
+extern crate toml_query;
+
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use failure::{err_msg, Error};
+use toml::Value;
+
+use toml_query::delete::TomlValueDeleteExt;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::merge::{ArrayMerge, MergeStrategy, TomlValueMergeExt};
+use toml_query::read::TomlValueReadExt;
+use toml_query::set::TomlValueSetExt;
+
+fn main() {
+    let matches = app().get_matches();
+
+    if let Err(e) = run(&matches) {
+        eprintln!("tomlq: {}", e);
+        process::exit(1);
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    let query = || {
+        Arg::with_name("QUERY")
+            .required(true)
+            .help("The query, like 'server.listeners.[0].port'")
+    };
+    let value = || {
+        Arg::with_name("VALUE")
+            .required(true)
+            .help("The value, in TOML syntax ('8080', '\"x\"', '[ 1, 2 ]'), other text is a string")
+    };
+    let file = || Arg::with_name("FILE").help("The document, stdin if it is missing or '-'");
+    let in_place = || {
+        Arg::with_name("in-place")
+            .long("in-place")
+            .short("i")
+            .requires("FILE")
+            .help("Write the changed document back to FILE instead of stdout")
+    };
+
+    App::new("tomlq")
+        .about("Read and edit TOML documents with toml-query queries")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("get")
+                .about("Print the value a query refers to")
+                .arg(query())
+                .arg(file()),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about("Set an existing value")
+                .arg(query())
+                .arg(value())
+                .arg(file())
+                .arg(in_place()),
+        )
+        .subcommand(
+            SubCommand::with_name("insert")
+                .about("Insert a value, creating the missing tables and arrays")
+                .arg(query())
+                .arg(value())
+                .arg(file())
+                .arg(in_place()),
+        )
+        .subcommand(
+            SubCommand::with_name("delete")
+                .about("Delete a value")
+                .arg(query())
+                .arg(file())
+                .arg(in_place()),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Merge another document into the document")
+                .arg(
+                    Arg::with_name("OTHER")
+                        .required(true)
+                        .help("The document to merge, its values take precedence"),
+                )
+                .arg(file())
+                .arg(in_place())
+                .arg(
+                    Arg::with_name("arrays")
+                        .long("arrays")
+                        .takes_value(true)
+                        .possible_values(&["replace", "append", "union"])
+                        .default_value("replace")
+                        .help("How arrays which are in both documents are merged"),
+                ),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let (command, args) = match matches.subcommand() {
+        (command, Some(args)) => (command, args),
+        _ => unreachable!("clap requires a subcommand"),
+    };
+
+    let file = args.value_of("FILE").filter(|f| *f != "-");
+    let mut doc = read_document(file)?;
+    let query = args.value_of("QUERY").unwrap_or_default();
+    let value = args.value_of("VALUE").map(parse_value);
+
+    match command {
+        "get" => {
+            let value = doc
+                .read(query)?
+                .ok_or_else(|| err_msg(format!("No value at '{}'", query)))?;
+            print!("{}", render(value)?);
+            return Ok(());
+        }
+        "set" => {
+            doc.set(query, value.unwrap())?; // safe because clap requires a value
+        }
+        "insert" => {
+            doc.insert(query, value.unwrap())?; // safe because clap requires a value
+        }
+        "delete" => {
+            doc.delete(query)?
+                .ok_or_else(|| err_msg(format!("No value at '{}'", query)))?;
+        }
+        "merge" => {
+            let other = read_document(args.value_of("OTHER"))?;
+            let arrays = match args.value_of("arrays") {
+                Some("append") => ArrayMerge::Append,
+                Some("union") => ArrayMerge::Union,
+                _ => ArrayMerge::Replace,
+            };
+            let strategy = MergeStrategy {
+                arrays,
+                ..MergeStrategy::default()
+            };
+            doc.merge(other, strategy);
+        }
+        _ => unreachable!("clap only accepts the known subcommands"),
+    }
+
+    let rendered = render(&doc)?;
+    match file {
+        Some(file) if args.is_present("in-place") => fs::write(file, rendered)?,
+        _ => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Read and parse the document from `file`, or from stdin
+fn read_document(file: Option<&str>) -> Result<Value, Error> {
+    let mut content = String::new();
+    match file {
+        Some(file) => content = fs::read_to_string(file)?,
+        None => {
+            io::stdin().read_to_string(&mut content)?;
+        }
+    };
+
+    Ok(toml::from_str(&content)?)
+}
+
+/// Parse a value from the command line, text which is no TOML value is a string
+fn parse_value(value: &str) -> Value {
+    let parsed = match toml::from_str(&format!("value = {}", value)) {
+        Ok(Value::Table(mut doc)) if doc.len() == 1 => doc.remove("value"),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(String::from(value)))
+}
+
+/// Render tables as TOML documents and other values in TOML syntax, on a line of their own
+fn render(value: &Value) -> Result<String, Error> {
+    match *value {
+        Value::Table(_) => Ok(toml::to_string(value)?),
+        _ => Ok(format!("{}\n", value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(Value::Integer(8080), parse_value("8080"));
+        assert_eq!(Value::String(String::from("x")), parse_value("\"x\""));
+        assert_eq!(
+            Value::String(String::from("localhost")),
+            parse_value("localhost")
+        );
+        assert_eq!(
+            Value::Array(vec![Value::Integer(1), Value::Boolean(true)]),
+            parse_value("[ 1, true ]")
+        );
+        assert_eq!(
+            Value::String(String::from("1\nx = 2")),
+            parse_value("1\nx = 2")
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let doc: Value = toml::from_str("[a]\nb = 'x'").unwrap();
+        assert_eq!("[a]\nb = \"x\"\n", render(&doc).unwrap());
+        assert_eq!("\"x\"\n", render(&doc["a"]["b"]).unwrap());
+    }
+
+}
@@ -0,0 +1,234 @@
+/// Caching the resolution of queries
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use toml::Value;
+
+use crate::error::Result;
+use crate::query::Query;
+use crate::tokenizer::Token;
+
+/// The number of queries a `CachedDocument::new()` remembers
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// A document which remembers how the queries read from it were resolved
+///
+/// The first `read()` of a query parses it, resolves its `^` segments and remembers the keys and
+/// indices of the path it resolved to. Later reads of the same query string still walk the
+/// document, but only look up the remembered keys and indices, without parsing the query or
+/// resolving it again.
+///
+/// At most `capacity` queries are remembered, when another one is read then, the remembered
+/// ones are forgotten. Any mutable access to the document forgets all remembered queries, so
+/// reads always see the current document.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::cache::CachedDocument;
+///
+/// let mut doc = CachedDocument::new(toml::from_str("[server]\nport = 80").unwrap());
+/// assert_eq!(doc.read("server.port").unwrap(), Some(&toml::Value::Integer(80)));
+///
+/// *doc.document_mut().get_mut("server").unwrap().get_mut("port").unwrap() = 8080.into();
+/// assert_eq!(doc.read("server.port").unwrap(), Some(&toml::Value::Integer(8080)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedDocument {
+    doc: Value,
+    capacity: usize,
+    resolved: RefCell<HashMap<String, Vec<Step>>>,
+}
+
+/// A step of a remembered path
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl CachedDocument {
+    /// Wrap a document, nothing is cached yet
+    ///
+    /// At most `DEFAULT_CACHE_CAPACITY` queries are remembered.
+    pub fn new(doc: Value) -> CachedDocument {
+        CachedDocument::with_capacity(doc, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Wrap a document, remembering at most `capacity` queries
+    pub fn with_capacity(doc: Value, capacity: usize) -> CachedDocument {
+        CachedDocument {
+            doc,
+            capacity,
+            resolved: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The document
+    pub fn document(&self) -> &Value {
+        &self.doc
+    }
+
+    /// The document, mutably
+    ///
+    /// This clears the cache.
+    pub fn document_mut(&mut self) -> &mut Value {
+        self.resolved.get_mut().clear();
+        &mut self.doc
+    }
+
+    /// Unwrap the document
+    pub fn into_inner(self) -> Value {
+        self.doc
+    }
+
+    /// Read a value, as with `TomlValueReadExt::read`
+    ///
+    /// Queries which fail are not cached, they are parsed and resolved again on the next read.
+    pub fn read(&self, query: &str) -> Result<Option<&Value>> {
+        use crate::resolver::in_query;
+        use crate::resolver::non_mut_resolver::resolve;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        if let Some(path) = self.resolved.borrow().get(query) {
+            return Ok(walk(&self.doc, path));
+        }
+
+        let parsed = Query::parse(query)?;
+        let tokens = resolve_parents(&self.doc, parsed.tokens())
+            .map_err(|e| in_query(&self.doc, &parsed, e))?;
+
+        let value = match tokens {
+            Some(ref tokens) => resolve(&self.doc, tokens, false, parsed.max_depth())
+                .map_err(|e| in_query(&self.doc, &parsed, e))?,
+            None => Some(&self.doc),
+        };
+
+        // Paths of keys and indices are the only ones which resolve to a single value
+        let path = tokens
+            .unwrap_or_default()
+            .iter()
+            .map(|token| match *token {
+                Token::Identifier { ref ident, .. } => Some(Step::Key(ident.to_string())),
+                Token::Index { idx, .. } => Some(Step::Index(idx)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(path) = path {
+            let mut resolved = self.resolved.borrow_mut();
+            if resolved.len() >= self.capacity {
+                resolved.clear();
+            }
+            if self.capacity > 0 {
+                resolved.insert(query.to_owned(), path);
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// Look up the remembered `path` in `doc`
+///
+/// The document did not change since the path was resolved, so only the last step can be
+/// missing.
+fn walk<'doc>(doc: &'doc Value, path: &[Step]) -> Option<&'doc Value> {
+    path.iter().try_fold(doc, |cur, step| match *step {
+        Step::Key(ref key) => cur.get(key.as_str()),
+        Step::Index(idx) => cur.get(idx),
+    })
+}
+
+impl From<Value> for CachedDocument {
+    fn from(doc: Value) -> CachedDocument {
+        CachedDocument::new(doc)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_read_is_cached() {
+        let doc = CachedDocument::new(toml_from_str("[a]\nb = 1\nc = [ 2 ]").unwrap());
+
+        assert_eq!(doc.read("a.b").unwrap(), Some(&Value::Integer(1)));
+        assert_eq!(doc.read("a.b.^.c.[0]").unwrap(), Some(&Value::Integer(2)));
+        assert_eq!(doc.read("a.x").unwrap(), None);
+        assert_eq!(doc.read("a.^").unwrap(), Some(doc.document()));
+        assert_eq!(doc.resolved.borrow().len(), 4);
+        assert_eq!(
+            doc.resolved.borrow()["a.b.^.c.[0]"],
+            vec![
+                Step::Key(String::from("a")),
+                Step::Key(String::from("c")),
+                Step::Index(0)
+            ]
+        );
+
+        assert_eq!(doc.read("a.b").unwrap(), Some(&Value::Integer(1)));
+        assert_eq!(doc.read("a.b.^.c.[0]").unwrap(), Some(&Value::Integer(2)));
+        assert_eq!(doc.read("a.x").unwrap(), None);
+        assert_eq!(doc.read("a.^").unwrap(), Some(doc.document()));
+        assert_eq!(doc.resolved.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_failed_read_is_not_cached() {
+        let doc = CachedDocument::new(toml_from_str("[a]\nb = 1").unwrap());
+
+        assert!(doc.read("a.b.c").is_err());
+        assert!(doc.read("a.*").is_err());
+        assert!(doc.resolved.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let doc = CachedDocument::with_capacity(toml_from_str("a = 1\nb = 2\nc = 3").unwrap(), 2);
+
+        assert_eq!(doc.read("a").unwrap(), Some(&Value::Integer(1)));
+        assert_eq!(doc.read("b").unwrap(), Some(&Value::Integer(2)));
+        assert_eq!(doc.resolved.borrow().len(), 2);
+
+        assert_eq!(doc.read("c").unwrap(), Some(&Value::Integer(3)));
+        assert_eq!(doc.resolved.borrow().len(), 1);
+        assert!(doc.resolved.borrow().contains_key("c"));
+
+        let doc = CachedDocument::with_capacity(toml_from_str("a = 1").unwrap(), 0);
+        assert_eq!(doc.read("a").unwrap(), Some(&Value::Integer(1)));
+        assert!(doc.resolved.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_failed_read_has_error_context() {
+        let doc = CachedDocument::new(toml_from_str("[a]\nb = 1").unwrap());
+
+        let err = doc.read("a.b.c").unwrap_err();
+        assert!(is_match!(
+            err,
+            Error::InQuery { ref query, segment: 2, .. } if query == "a.b.c"
+        ));
+    }
+
+    #[test]
+    fn test_mutation_clears_cache() {
+        let mut doc = CachedDocument::new(toml_from_str("[a]\nb = 1").unwrap());
+        assert_eq!(doc.read("a.b").unwrap(), Some(&Value::Integer(1)));
+
+        *doc.document_mut() = toml_from_str("a = [ 1 ]").unwrap();
+        assert!(doc.resolved.borrow().is_empty());
+
+        let result = doc.read("a.b");
+        assert!(result.is_err());
+        assert!(is_match!(
+            *result.unwrap_err().kind(),
+            Error::NoIdentifierInArray(_)
+        ));
+    }
+
+}
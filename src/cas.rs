@@ -0,0 +1,117 @@
+/// The Toml Compare-and-swap extensions
+use std::mem;
+
+use toml::Value;
+
+use crate::error::Result;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+
+/// What a compare-and-swap operation did
+#[derive(Debug, Clone, PartialEq)]
+pub enum CasOutcome {
+    /// The current value was the expected one and was replaced, the old value is returned
+    Swapped(Value),
+
+    /// The current value was another one (or there was none) and is left as it is, the current
+    /// value is returned
+    Mismatch(Option<Value>),
+}
+
+impl CasOutcome {
+    /// Whether the value was replaced
+    pub fn is_swapped(&self) -> bool {
+        is_match!(*self, CasOutcome::Swapped(_))
+    }
+}
+
+pub trait TomlValueCasExt {
+    /// Extension function for replacing a value in the current toml::Value document, if it
+    /// equals `expected`
+    ///
+    /// # Semantics
+    ///
+    /// The value is only replaced if there is a value at the queried path and it equals
+    /// `expected`. A missing value never equals `expected`, nothing is inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::cas::{CasOutcome, TomlValueCasExt};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("version = 1").unwrap();
+    ///
+    /// let res = toml.compare_and_swap("version", &1.into(), 2.into()).unwrap();
+    /// assert_eq!(CasOutcome::Swapped(1.into()), res);
+    ///
+    /// let res = toml.compare_and_swap("version", &1.into(), 3.into()).unwrap();
+    /// assert_eq!(CasOutcome::Mismatch(Some(2.into())), res);
+    /// ```
+    fn compare_and_swap(
+        &mut self,
+        query: &str,
+        expected: &Value,
+        new: Value,
+    ) -> Result<CasOutcome> {
+        self.compare_and_swap_query(&Query::parse(query)?, expected, new)
+    }
+
+    /// Extension function for replacing a value in the current toml::Value document using a
+    /// pre-compiled query, if it equals `expected`
+    ///
+    /// See documentation of `TomlValueCasExt::compare_and_swap`
+    fn compare_and_swap_query(
+        &mut self,
+        query: &Query,
+        expected: &Value,
+        new: Value,
+    ) -> Result<CasOutcome>;
+}
+
+impl TomlValueCasExt for Value {
+    fn compare_and_swap_query(
+        &mut self,
+        query: &Query,
+        expected: &Value,
+        new: Value,
+    ) -> Result<CasOutcome> {
+        match self.read_mut_query(query)? {
+            Some(current) if *current == *expected => {
+                Ok(CasOutcome::Swapped(mem::replace(current, new)))
+            }
+            current => Ok(CasOutcome::Mismatch(current.cloned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_compare_and_swap() {
+        let mut toml: Value = toml_from_str("[a]\nb = [ 1, 2 ]").unwrap();
+
+        let res = toml.compare_and_swap("a.b.[1]", &Value::Integer(2), Value::Integer(3));
+        assert!(is_match!(res, Ok(CasOutcome::Swapped(Value::Integer(2)))));
+
+        let res = toml.compare_and_swap("a.b.[1]", &Value::Integer(2), Value::Integer(4));
+        assert!(is_match!(
+            res,
+            Ok(CasOutcome::Mismatch(Some(Value::Integer(3))))
+        ));
+
+        let res = toml.compare_and_swap("a.c", &Value::Integer(2), Value::Integer(4));
+        assert!(is_match!(res, Ok(CasOutcome::Mismatch(None))));
+
+        assert!(toml
+            .compare_and_swap("a.b.c", &Value::Integer(2), Value::Integer(4))
+            .is_err());
+        assert_eq!(toml, toml_from_str("[a]\nb = [ 1, 3 ]").unwrap());
+    }
+
+}
@@ -0,0 +1,172 @@
+/// The Toml Copy extensions
+use toml::Value;
+
+use crate::error::Result;
+use crate::mv::MoveOptions;
+use crate::query::Query;
+use crate::transaction::atomically;
+
+pub trait TomlValueCopyExt {
+    /// Extension function for copying a value from one path to another in the current
+    /// toml::Value document, using the default `MoveOptions`
+    ///
+    /// See documentation of `TomlValueCopyExt::copy_with_options`
+    fn copy(&mut self, from: &str, to: &str) -> Result<Option<Value>> {
+        self.copy_with_options(from, to, MoveOptions::default())
+    }
+
+    /// Extension function for copying a value from one path to another in the current
+    /// toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// The value at `from` (including everything it contains) is cloned and put at `to`, the
+    /// same way `TomlValueMoveExt::mv_with_options` does. If it fails, the document is left
+    /// unchanged.
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(None)` if the value was copied
+    /// * `Ok(Some(old_value))` if the value was copied and replaced `old_value`
+    /// * On failure, `Err(e)` is returned:
+    ///     * If there is no value at `from`: `Error::NotAvailable`
+    ///     * If there is a value at `to` and `options.overwrite` is not set: `Error::KeyExists`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::copy::TomlValueCopyExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[defaults]\nport = 80").unwrap();
+    ///
+    /// assert!(toml.copy("defaults", "servers.a").is_ok());
+    /// assert_eq!(toml.get("servers").and_then(|s| s.get("a")), toml.get("defaults"));
+    /// ```
+    fn copy_with_options(
+        &mut self,
+        from: &str,
+        to: &str,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        self.copy_query(&Query::parse(from)?, &Query::parse(to)?, options)
+    }
+
+    /// Extension function for copying a value from one path to another in the current
+    /// toml::Value document using pre-compiled queries
+    ///
+    /// See documentation of `TomlValueCopyExt::copy_with_options`
+    fn copy_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>>;
+}
+
+impl TomlValueCopyExt for Value {
+    fn copy_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        // Inserting may create intermediate tables before it fails
+        atomically(self, |tx| tx.copy_query(from, to, options))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_copy() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [defaults]
+        port = 80
+        hosts = [ "a" ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.copy("defaults", "servers.[+]");
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str(
+            r#"
+        [defaults]
+        port = 80
+        hosts = [ "a" ]
+
+        [[servers]]
+        port = 80
+        hosts = [ "a" ]
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_copy_is_deep() {
+        let mut toml: Value = toml_from_str("[a]\nb = { c = 1 }").unwrap();
+
+        assert!(toml.copy("a", "x").is_ok());
+        let res = toml.copy_with_options(
+            "a.b.c",
+            "x.b.c",
+            MoveOptions {
+                overwrite: true,
+                ..MoveOptions::default()
+            },
+        );
+        assert!(res.is_ok());
+
+        assert!(toml.copy("x.b.c", "x.b.d").is_ok());
+
+        let expected: Value =
+            toml_from_str("[a]\nb = { c = 1 }\n[x]\nb = { c = 1, d = 1 }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_copy_missing_source_is_error() {
+        let mut toml: Value = toml_from_str("[a]").unwrap();
+
+        let res = toml.copy("a.b", "c");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::NotAvailable(_)));
+    }
+
+    #[test]
+    fn test_copy_existing_destination_is_error() {
+        let mut toml: Value = toml_from_str("a = 1\nb = 2").unwrap();
+
+        let res = toml.copy("a", "b");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::KeyExists(_)));
+
+        let expected: Value = toml_from_str("a = 1\nb = 2").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_copy_rolls_back_on_failure() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.copy("a", "x.y.[3]");
+        assert!(res.is_err());
+
+        let expected: Value = toml_from_str("a = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -0,0 +1,167 @@
+/// Building queries step by step while accessing a document
+use toml::Value;
+
+use crate::delete::TomlValueDeleteExt;
+use crate::error::Result;
+use crate::query::{Query, QueryBuilder};
+use crate::read::TomlValueReadExt;
+use crate::set::TomlValueSetExt;
+
+pub trait TomlValueCursorExt {
+    /// Start a path for reading at the key `key`
+    ///
+    /// Keys are used as they are, so paths with keys from variables need no formatting or
+    /// quoting.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::cursor::TomlValueCursorExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("[[listeners]]\nport = 80").unwrap();
+    /// let port = toml.at("listeners").idx(0).at("port").read().unwrap();
+    ///
+    /// assert_eq!(port, Some(&toml::Value::Integer(80)));
+    /// ```
+    fn at(&self, key: &str) -> Cursor<&Value>;
+
+    /// Start a path for reading and changing at the key `key`
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::cursor::TomlValueCursorExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[[listeners]]\nport = 80").unwrap();
+    /// let old = toml.at_mut("listeners").idx(0).at("port").set(8080.into()).unwrap();
+    ///
+    /// assert_eq!(old, Some(toml::Value::Integer(80)));
+    /// ```
+    fn at_mut(&mut self, key: &str) -> Cursor<&mut Value>;
+}
+
+impl TomlValueCursorExt for Value {
+    fn at(&self, key: &str) -> Cursor<&Value> {
+        Cursor::new(self, key)
+    }
+
+    fn at_mut(&mut self, key: &str) -> Cursor<&mut Value> {
+        Cursor::new(self, key)
+    }
+}
+
+/// A path into a document, which is executed by `read()`, `set()` or `delete()`
+#[derive(Debug)]
+pub struct Cursor<D> {
+    doc: D,
+    query: QueryBuilder,
+}
+
+impl<D> Cursor<D> {
+    fn new(doc: D, key: &str) -> Cursor<D> {
+        Cursor {
+            doc,
+            query: QueryBuilder::new().key(key),
+        }
+    }
+
+    /// Go to the key `key` of the current table
+    pub fn at(mut self, key: &str) -> Cursor<D> {
+        self.query = self.query.key(key);
+        self
+    }
+
+    /// Go to the element `idx` of the current array
+    pub fn idx(mut self, idx: usize) -> Cursor<D> {
+        self.query = self.query.index(idx);
+        self
+    }
+
+    /// The query the cursor executes
+    pub fn query(&self) -> Query<'static> {
+        // safe because the builder always has a key
+        self.query.clone().build().unwrap()
+    }
+}
+
+impl<'doc> Cursor<&'doc Value> {
+    /// Read the value at the path, see `TomlValueReadExt::read_query()`
+    pub fn read(self) -> Result<Option<&'doc Value>> {
+        self.doc.read_query(&self.query())
+    }
+}
+
+impl<'doc> Cursor<&'doc mut Value> {
+    /// Read the value at the path, see `TomlValueReadExt::read_query()`
+    pub fn read(self) -> Result<Option<&'doc Value>> {
+        let query = self.query();
+        self.doc.read_query(&query)
+    }
+
+    /// Read the value at the path mutably, see `TomlValueReadExt::read_mut_query()`
+    pub fn read_mut(self) -> Result<Option<&'doc mut Value>> {
+        let query = self.query();
+        self.doc.read_mut_query(&query)
+    }
+
+    /// Set the value at the path, see `TomlValueSetExt::set_query()`
+    pub fn set(self, value: Value) -> Result<Option<Value>> {
+        let query = self.query();
+        self.doc.set_query(&query, value)
+    }
+
+    /// Delete the value at the path, see `TomlValueDeleteExt::delete_query()`
+    pub fn delete(self) -> Result<Option<Value>> {
+        let query = self.query();
+        self.doc.delete_query(&query)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_cursor_read() {
+        let toml: Value = toml_from_str("[server]\n\"a.b\" = [ 1, 2 ]").unwrap();
+
+        let value = toml.at("server").at("a.b").idx(1).read();
+        assert!(is_match!(value, Ok(Some(&Value::Integer(2)))));
+
+        let value = toml.at("server").at("x").read();
+        assert!(is_match!(value, Ok(None)));
+
+        assert_eq!(
+            Query::parse(r#"server."a.b".[1]"#).unwrap(),
+            toml.at("server").at("a.b").idx(1).query()
+        );
+    }
+
+    #[test]
+    fn test_cursor_set_and_delete() {
+        let mut toml: Value = toml_from_str("[server]\nport = 80\nhost = 'x'").unwrap();
+
+        let old = toml.at_mut("server").at("port").set(Value::Integer(8080));
+        assert!(is_match!(old, Ok(Some(Value::Integer(80)))));
+
+        let old = toml.at_mut("server").at("host").delete();
+        assert!(is_match!(old, Ok(Some(Value::String(_)))));
+
+        let result = toml
+            .at_mut("server")
+            .at("port")
+            .idx(0)
+            .set(Value::Integer(1));
+        assert!(is_match!(
+            *result.unwrap_err().kind(),
+            Error::QueryingValueAsArray(0)
+        ));
+
+        assert_eq!(toml, toml_from_str("[server]\nport = 8080").unwrap());
+    }
+
+}
@@ -2,9 +2,30 @@
 use toml::Value;
 
 use crate::error::{Error, Result};
+use crate::query::{Query, DEFAULT_MAX_DEPTH};
+use crate::resolver::in_query;
 use crate::tokenizer::tokenize_with_seperator;
 use crate::tokenizer::Token;
 
+/// How an element is removed from an array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Shift all following elements to the left, like `Vec::remove`, so the order of the array
+    /// is kept
+    Shift,
+
+    /// Replace the element with the last element of the array, like `Vec::swap_remove`
+    ///
+    /// This is faster for large arrays, but changes the order of the array.
+    Swap,
+}
+
+impl Default for DeleteMode {
+    fn default() -> DeleteMode {
+        DeleteMode::Shift
+    }
+}
+
 pub trait TomlValueDeleteExt {
     /// Extension function for deleting a value in the current toml::Value document
     /// using a custom seperator.
@@ -19,6 +40,9 @@ pub trait TomlValueDeleteExt {
     ///
     /// does _not_ work.
     ///
+    /// Deleting an array element shifts all following elements to the left, so the order of the
+    /// array is kept. See `TomlValueDeleteExt::delete_with_mode` for a faster alternative.
+    ///
     /// # Return value
     ///
     /// If the delete operation worked correctly, `Ok(Option<Value>)` is returned.
@@ -30,165 +54,612 @@ pub trait TomlValueDeleteExt {
     /// If the delete operation actually removed something from the toml document, this value is
     /// returned as `Ok(Some(Value))`.
     ///
+    /// If the last token of the query is an array slice (for example `"array.[1:3]"`), all
+    /// elements in the range are removed and returned as `Ok(Some(Value::Array(_)))`.
+    ///
     /// On failure, `Err(e)` is returned
     ///
-    fn delete_with_seperator(&mut self, query: &str, sep: char) -> Result<Option<Value>>;
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Value>>;
 
     /// Extension function for inserting a value from the current toml::Value document
     ///
     /// See documentation of `TomlValueinsertExt::insert_with_seperator`
     fn delete(&mut self, query: &str) -> Result<Option<Value>> {
-        self.delete_with_seperator(query, '.')
+        self.delete_with_seperator(query, ".")
+    }
+
+    /// Extension function for deleting a value in the current toml::Value document using a
+    /// pre-compiled query
+    ///
+    /// See documentation of `TomlValueDeleteExt::delete_with_seperator`
+    fn delete_query(&mut self, query: &Query) -> Result<Option<Value>> {
+        self.delete_query_with_mode(query, DeleteMode::Shift)
     }
+
+    /// Extension function for deleting a value in the current toml::Value document, with `mode`
+    /// deciding how an element is removed from an array
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::delete::{DeleteMode, TomlValueDeleteExt};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("a = [ 1, 2, 3, 4 ]").unwrap();
+    ///
+    /// assert!(toml.delete_with_mode("a.[0]", DeleteMode::Swap).is_ok());
+    /// assert_eq!(toml, toml::from_str("a = [ 4, 2, 3 ]").unwrap());
+    /// ```
+    ///
+    /// Slices (`"a.[1:3]"`) always keep the order of the remaining elements.
+    fn delete_with_mode(&mut self, query: &str, mode: DeleteMode) -> Result<Option<Value>> {
+        self.delete_query_with_mode(&Query::parse(query)?, mode)
+    }
+
+    /// Extension function for deleting a value in the current toml::Value document using a
+    /// pre-compiled query and a `DeleteMode`
+    ///
+    /// See documentation of `TomlValueDeleteExt::delete_with_mode`
+    fn delete_query_with_mode(&mut self, query: &Query, mode: DeleteMode) -> Result<Option<Value>>;
+
+    /// Extension function for deleting all values matching a query in the current toml::Value
+    /// document using a custom seperator.
+    ///
+    /// The query may contain tokens which match more than one value, see
+    /// `TomlValueReadExt::read_all_with_seperator`. For example, `"*.secret"` deletes the key
+    /// `secret` from all tables in the document root, `"**.secret"` from all tables in the
+    /// document.
+    ///
+    /// # Semantics
+    ///
+    /// Like `delete_recursive()`, matched tables and arrays are removed including everything they
+    /// contain, so `"bins.[?(@.name == \"a\")]"` removes whole array elements. A match inside
+    /// of another match is removed together with it and not returned on its own.
+    ///
+    /// # Return value
+    ///
+    /// The removed values are returned together with their paths in the document (before the
+    /// removal), in document order.
+    fn delete_all_with_seperator(&mut self, query: &str, sep: &str)
+        -> Result<Vec<(String, Value)>>;
+
+    /// Extension function for deleting all values matching a query in the current toml::Value
+    /// document
+    ///
+    /// See documentation of `TomlValueDeleteExt::delete_all_with_seperator`
+    fn delete_all(&mut self, query: &str) -> Result<Vec<(String, Value)>> {
+        self.delete_all_with_seperator(query, ".")
+    }
+
+    /// Extension function for deleting a value including everything it contains
+    ///
+    /// Other than `TomlValueDeleteExt::delete`, non-empty tables and arrays are deleted as well.
+    /// The removed subtree is returned, `Ok(None)` if there is no value at the queried path.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::delete::TomlValueDeleteExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[a.b]\nc = 1").unwrap();
+    ///
+    /// let removed = toml.delete_recursive("a.b").unwrap();
+    /// assert_eq!(removed, Some(toml::from_str("c = 1").unwrap()));
+    /// assert_eq!(toml, toml::from_str("[a]").unwrap());
+    /// ```
+    fn delete_recursive(&mut self, query: &str) -> Result<Option<Value>> {
+        self.delete_recursive_query(&Query::parse(query)?, false)
+    }
+
+    /// Extension function for deleting a value including everything it contains, and the tables
+    /// which become empty by that
+    ///
+    /// Deleting `a.b.c` from `a = { b = { c = 1 } }` leaves an empty document. Tables inside
+    /// arrays are not removed.
+    ///
+    /// See documentation of `TomlValueDeleteExt::delete_recursive`
+    fn delete_recursive_and_prune(&mut self, query: &str) -> Result<Option<Value>> {
+        self.delete_recursive_query(&Query::parse(query)?, true)
+    }
+
+    /// Extension function for deleting a value including everything it contains using a
+    /// pre-compiled query
+    ///
+    /// If `prune_empty_parents` is true, the tables which become empty are removed as well, see
+    /// `TomlValueDeleteExt::delete_recursive_and_prune`.
+    fn delete_recursive_query(
+        &mut self,
+        query: &Query,
+        prune_empty_parents: bool,
+    ) -> Result<Option<Value>>;
 }
 
 impl TomlValueDeleteExt for Value {
-    fn delete_with_seperator(&mut self, query: &str, sep: char) -> Result<Option<Value>> {
-        use crate::resolver::mut_resolver::resolve;
-        use std::ops::Index;
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Value>> {
+        self.delete_query(&Query::parse_with_seperator(query, sep)?)
+    }
 
-        let mut tokens = tokenize_with_seperator(query, sep)?;
-        let last_token = tokens.pop_last();
-
-        /// Check whether a structure (Table/Array) is empty. If the Value has not these types,
-        /// the default value is returned
-        #[inline]
-        fn is_empty(val: Option<&Value>, default: bool) -> bool {
-            val.map(|v| match v {
-                &Value::Table(ref tab) => tab.is_empty(),
-                &Value::Array(ref arr) => arr.is_empty(),
-                _ => default,
+    fn delete_query_with_mode(&mut self, query: &Query, mode: DeleteMode) -> Result<Option<Value>> {
+        delete_impl(self, query, mode).map_err(|e| in_query(self, query, e))
+    }
+
+    fn delete_all_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, Value)>> {
+        use crate::resolver::non_mut_multi_resolver::resolve_all;
+
+        let tokens = tokenize_with_seperator(query, sep)?;
+        let mut matches = Vec::new();
+        resolve_all(self, &tokens, sep, "", DEFAULT_MAX_DEPTH, &mut matches)?;
+
+        // The document itself cannot be deleted, and matches inside of other matches are removed
+        // with them
+        let paths = matches
+            .iter()
+            .map(|(path, _)| path)
+            .filter(|path| !path.is_empty())
+            .filter(|path| {
+                !matches.iter().any(|(other, _)| {
+                    !other.is_empty()
+                        && path.len() > other.len() + sep.len()
+                        && path.starts_with(other.as_str())
+                        && path[other.len()..].starts_with(sep)
+                })
             })
-            .unwrap_or(default)
+            .cloned()
+            .collect::<Vec<String>>();
+
+        // Delete back to front, so the paths of array elements stay valid
+        let mut removed = Vec::with_capacity(paths.len());
+        for path in paths.into_iter().rev() {
+            let value = {
+                let query = Query::parse_with_seperator(&path, sep)?;
+                self.delete_recursive_query(&query, false)?
+            };
+
+            if let Some(value) = value {
+                removed.push((path, value));
+            }
         }
 
-        #[inline]
-        fn is_table(val: Option<&Value>) -> bool {
-            val.map(|v| is_match!(v, &Value::Table(_))).unwrap_or(false)
+        removed.reverse();
+        Ok(removed)
+    }
+
+    fn delete_recursive_query(
+        &mut self,
+        query: &Query,
+        prune_empty_parents: bool,
+    ) -> Result<Option<Value>> {
+        use crate::resolver::mut_resolver::resolve;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        let mut segments = resolve_parents(self, query.tokens())?
+            .ok_or(Error::EmptyQueryError)?
+            .into_owned();
+        let last = segments.pop().unwrap(); // safe because resolve_parents() yields no empty list
+
+        let removed = {
+            let parent = resolve(self, &segments, true, query.max_depth())?.unwrap(); // safe because of resolve() guarantees
+            remove_child(parent, last)?
+        };
+
+        if removed.is_some() && prune_empty_parents {
+            prune_empty_tables(self, segments, query.max_depth())?;
         }
 
-        #[inline]
-        fn is_array(val: Option<&Value>) -> bool {
-            val.map(|v| is_match!(v, &Value::Array(_))).unwrap_or(false)
+        Ok(removed)
+    }
+}
+
+fn delete_impl(doc: &mut Value, query: &Query, mode: DeleteMode) -> Result<Option<Value>> {
+    use crate::resolver::mut_resolver::resolve;
+    use crate::resolver::parent_resolver::resolve_parents;
+    use std::ops::Index;
+
+    let mut tokens = resolve_parents(doc, query.tokens())?
+        .ok_or(Error::EmptyQueryError)?
+        .into_owned();
+    let last_token = tokens.pop().unwrap(); // safe because resolve_parents() yields no empty list
+
+    if let Token::Append = last_token {
+        return Err(Error::AppendNotSupported);
+    }
+
+    /// Check whether a structure (Table/Array) is empty. If the Value has not these types,
+    /// the default value is returned
+    #[inline]
+    fn is_empty(val: Option<&Value>, default: bool) -> bool {
+        val.map(|v| match v {
+            &Value::Table(ref tab) => tab.is_empty(),
+            &Value::Array(ref arr) => arr.is_empty(),
+            _ => default,
+        })
+        .unwrap_or(default)
+    }
+
+    #[inline]
+    fn is_table(val: Option<&Value>) -> bool {
+        val.map(|v| is_match!(v, &Value::Table(_))).unwrap_or(false)
+    }
+
+    #[inline]
+    fn is_array(val: Option<&Value>) -> bool {
+        val.map(|v| is_match!(v, &Value::Array(_))).unwrap_or(false)
+    }
+
+    #[inline]
+    fn name_of_val(val: Option<&Value>) -> &'static str {
+        val.map(crate::util::name_of_val).unwrap_or("None")
+    }
+
+    #[inline]
+    fn remove(arr: &mut Vec<Value>, idx: usize, mode: DeleteMode) -> Value {
+        match mode {
+            DeleteMode::Shift => arr.remove(idx),
+            DeleteMode::Swap => arr.swap_remove(idx),
         }
+    }
 
-        #[inline]
-        fn name_of_val(val: Option<&Value>) -> &'static str {
-            val.map(crate::util::name_of_val).unwrap_or("None")
+    /// Remove the elements from `start` to `end` from the array, returning them as Array.
+    /// Same as for single elements, non-empty structures are not removed.
+    fn delete_slice(
+        arr: &mut Vec<Value>,
+        start: Option<usize>,
+        end: Option<usize>,
+    ) -> Result<Option<Value>> {
+        let start = start.unwrap_or(0);
+        let end = end.unwrap_or(arr.len());
+        if end > arr.len() || start > end {
+            return Err(Error::ArrayIndexOutOfBounds(end.max(start), arr.len()));
         }
 
-        if last_token.is_none() {
-            match self {
-                &mut Value::Table(ref mut tab) => match tokens {
-                    Token::Identifier { ident, .. } => {
-                        if is_empty(tab.get(&ident), true) {
-                            Ok(tab.remove(&ident))
+        for elem in arr[start..end].iter() {
+            if is_table(Some(elem)) && !is_empty(Some(elem), true) {
+                return Err(Error::CannotDeleteNonEmptyTable(None));
+            } else if is_array(Some(elem)) && !is_empty(Some(elem), true) {
+                return Err(Error::CannotDeleteNonEmptyArray(None));
+            }
+        }
+
+        Ok(Some(Value::Array(arr.drain(start..end).collect())))
+    }
+
+    if tokens.is_empty() {
+        match doc {
+            &mut Value::Table(ref mut tab) => match last_token {
+                Token::Identifier { ident, .. } => {
+                    if is_empty(tab.get(&*ident), true) {
+                        Ok(tab.remove(&*ident))
+                    } else {
+                        if is_table(tab.get(&*ident)) {
+                            Err(Error::CannotDeleteNonEmptyTable(Some(ident.to_string())))
+                        } else if is_array(tab.get(&*ident)) {
+                            Err(Error::CannotDeleteNonEmptyArray(Some(ident.to_string())))
                         } else {
-                            if is_table(tab.get(&ident)) {
-                                Err(Error::CannotDeleteNonEmptyTable(Some(ident.clone())))
-                            } else if is_array(tab.get(&ident)) {
-                                Err(Error::CannotDeleteNonEmptyArray(Some(ident.clone())))
-                            } else {
-                                let act = name_of_val(tab.get(&ident));
-                                let tbl = "table";
-                                Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
-                            }
+                            let act = name_of_val(tab.get(&*ident));
+                            let tbl = "table";
+                            Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
                         }
                     }
-                    _ => Ok(None),
-                },
-                &mut Value::Array(ref mut arr) => match tokens {
-                    Token::Identifier { ident, .. } => Err(Error::NoIdentifierInArray(ident)),
-                    Token::Index { idx, .. } => {
-                        if is_empty(Some(arr.index(idx)), true) {
-                            Ok(Some(arr.remove(idx)))
+                }
+                Token::Index { .. } => Ok(None),
+                Token::Slice { start, .. } => Err(Error::NoIndexInTable(start.unwrap_or(0))),
+                _ => Err(Error::MultiValueQuery),
+            },
+            &mut Value::Array(ref mut arr) => match last_token {
+                Token::Identifier { ident, .. } => {
+                    Err(Error::NoIdentifierInArray(ident.into_owned()))
+                }
+                Token::Index { idx, .. } => {
+                    if is_empty(Some(arr.index(idx)), true) {
+                        Ok(Some(remove(arr, idx, mode)))
+                    } else {
+                        if is_table(Some(arr.index(idx))) {
+                            Err(Error::CannotDeleteNonEmptyTable(None))
+                        } else if is_array(Some(arr.index(idx))) {
+                            Err(Error::CannotDeleteNonEmptyArray(None))
                         } else {
-                            if is_table(Some(arr.index(idx))) {
-                                Err(Error::CannotDeleteNonEmptyTable(None))
-                            } else if is_array(Some(arr.index(idx))) {
-                                Err(Error::CannotDeleteNonEmptyArray(None))
-                            } else {
-                                let act = name_of_val(Some(arr.index(idx)));
-                                let tbl = "table";
-                                Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
-                            }
+                            let act = name_of_val(Some(arr.index(idx)));
+                            let tbl = "table";
+                            Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
                         }
                     }
-                },
-                _ => {
-                    let kind = match tokens {
-                        Token::Identifier { ident, .. } => Error::QueryingValueAsTable(ident),
-                        Token::Index { idx, .. } => Error::QueryingValueAsArray(idx),
-                    };
-                    Err(Error::from(kind))
                 }
+                Token::Slice { start, end, .. } => delete_slice(arr, start, end),
+                _ => Err(Error::MultiValueQuery),
+            },
+            _ => {
+                let kind = match last_token {
+                    Token::Identifier { ident, .. } => {
+                        Error::QueryingValueAsTable(ident.into_owned())
+                    }
+                    Token::Index { idx, .. } => Error::QueryingValueAsArray(idx),
+                    Token::Slice { start, .. } => Error::QueryingValueAsArray(start.unwrap_or(0)),
+                    _ => Error::MultiValueQuery,
+                };
+                Err(kind)
             }
-        } else {
-            let val = resolve(self, &tokens, true)?.unwrap(); // safe because of resolve() guarantees
-            let last_token = last_token.unwrap();
-            match val {
-                &mut Value::Table(ref mut tab) => match *last_token {
-                    Token::Identifier { ref ident, .. } => {
-                        if is_empty(tab.get(ident), true) {
-                            Ok(tab.remove(ident))
+        }
+    } else {
+        let val = resolve(doc, &tokens, true, query.max_depth())?.unwrap(); // safe because of resolve() guarantees
+        match val {
+            &mut Value::Table(ref mut tab) => match last_token {
+                Token::Identifier { ref ident, .. } => {
+                    if is_empty(tab.get(&**ident), true) {
+                        Ok(tab.remove(&**ident))
+                    } else {
+                        if is_table(tab.get(&**ident)) {
+                            Err(Error::CannotDeleteNonEmptyTable(Some(ident.to_string())))
+                        } else if is_array(tab.get(&**ident)) {
+                            Err(Error::CannotDeleteNonEmptyArray(Some(ident.to_string())))
                         } else {
-                            if is_table(tab.get(ident)) {
-                                Err(Error::CannotDeleteNonEmptyTable(Some(ident.clone())))
-                            } else if is_array(tab.get(ident)) {
-                                Err(Error::CannotDeleteNonEmptyArray(Some(ident.clone())))
-                            } else {
-                                let act = name_of_val(tab.get(ident));
-                                let tbl = "table";
-                                Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
-                            }
+                            let act = name_of_val(tab.get(&**ident));
+                            let tbl = "table";
+                            Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
                         }
                     }
-                    Token::Index { idx, .. } => Err(Error::NoIndexInTable(idx)),
-                },
-                &mut Value::Array(ref mut arr) => match *last_token {
-                    Token::Identifier { ident, .. } => Err(Error::NoIdentifierInArray(ident)),
-                    Token::Index { idx, .. } => {
-                        if idx > arr.len() {
-                            return Err(Error::ArrayIndexOutOfBounds(idx, arr.len()));
-                        }
-                        if is_empty(Some(&arr.index(idx)), true) {
-                            Ok(Some(arr.remove(idx)))
+                }
+                Token::Index { idx, .. } => Err(Error::NoIndexInTable(idx)),
+                Token::Slice { start, .. } => Err(Error::NoIndexInTable(start.unwrap_or(0))),
+                _ => Err(Error::MultiValueQuery),
+            },
+            &mut Value::Array(ref mut arr) => match last_token {
+                Token::Identifier { ident, .. } => {
+                    Err(Error::NoIdentifierInArray(ident.into_owned()))
+                }
+                Token::Index { idx, .. } => {
+                    if idx >= arr.len() {
+                        return Err(Error::ArrayIndexOutOfBounds(idx, arr.len()));
+                    }
+                    if is_empty(Some(arr.index(idx)), true) {
+                        Ok(Some(remove(arr, idx, mode)))
+                    } else {
+                        if is_table(Some(arr.index(idx))) {
+                            Err(Error::CannotDeleteNonEmptyTable(None))
+                        } else if is_array(Some(arr.index(idx))) {
+                            Err(Error::CannotDeleteNonEmptyArray(None))
                         } else {
-                            if is_table(Some(&arr.index(idx))) {
-                                Err(Error::CannotDeleteNonEmptyTable(None))
-                            } else if is_array(Some(&arr.index(idx))) {
-                                Err(Error::CannotDeleteNonEmptyArray(None))
-                            } else {
-                                let act = name_of_val(Some(arr.index(idx)));
-                                let tbl = "table";
-                                Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
-                            }
+                            let act = name_of_val(Some(arr.index(idx)));
+                            let tbl = "table";
+                            Err(Error::CannotAccessBecauseTypeMismatch(tbl, act))
                         }
                     }
-                },
-                _ => {
-                    let kind = match *last_token {
-                        Token::Identifier { ident, .. } => Error::QueryingValueAsTable(ident),
-                        Token::Index { idx, .. } => Error::QueryingValueAsArray(idx),
-                    };
-                    Err(Error::from(kind))
                 }
+                Token::Slice { start, end, .. } => delete_slice(arr, start, end),
+                _ => Err(Error::MultiValueQuery),
+            },
+            _ => {
+                let kind = match last_token {
+                    Token::Identifier { ident, .. } => {
+                        Error::QueryingValueAsTable(ident.into_owned())
+                    }
+                    Token::Index { idx, .. } => Error::QueryingValueAsArray(idx),
+                    Token::Slice { start, .. } => Error::QueryingValueAsArray(start.unwrap_or(0)),
+                    _ => Error::MultiValueQuery,
+                };
+                Err(kind)
             }
         }
     }
 }
 
+/// Remove the value `token` refers to from `val`, no matter whether it is empty
+fn remove_child(val: &mut Value, token: Token) -> Result<Option<Value>> {
+    match token {
+        Token::Identifier { ident, .. } => match *val {
+            Value::Table(ref mut tab) => Ok(tab.remove(&*ident)),
+            Value::Array(_) => Err(Error::NoIdentifierInArray(ident.into_owned())),
+            _ => Err(Error::QueryingValueAsTable(ident.into_owned())),
+        },
+        Token::Index { idx, .. } => match *val {
+            Value::Array(ref mut arr) if idx < arr.len() => Ok(Some(arr.remove(idx))),
+            Value::Array(ref arr) => Err(Error::ArrayIndexOutOfBounds(idx, arr.len())),
+            Value::Table(_) => Err(Error::NoIndexInTable(idx)),
+            _ => Err(Error::QueryingValueAsArray(idx)),
+        },
+        Token::Slice { start, end, .. } => match *val {
+            Value::Array(ref mut arr) => {
+                let start = start.unwrap_or(0);
+                let end = end.unwrap_or(arr.len());
+                if end > arr.len() || start > end {
+                    return Err(Error::ArrayIndexOutOfBounds(end.max(start), arr.len()));
+                }
+
+                Ok(Some(Value::Array(arr.drain(start..end).collect())))
+            }
+            Value::Table(_) => Err(Error::NoIndexInTable(start.unwrap_or(0))),
+            _ => Err(Error::QueryingValueAsArray(start.unwrap_or(0))),
+        },
+        Token::Append { .. } => Err(Error::AppendNotSupported),
+        _ => Err(Error::MultiValueQuery),
+    }
+}
+
+/// Remove the tables on the path `segments` which are empty, from the innermost one up to the
+/// first one which is not empty
+fn prune_empty_tables(doc: &mut Value, mut segments: Vec<Token>, max_depth: usize) -> Result<()> {
+    use crate::resolver::mut_resolver::resolve;
+
+    while let Some(last) = segments.pop() {
+        let ident = match last {
+            Token::Identifier { ident, .. } => ident,
+            _ => break, // Tables in arrays are kept, removing them would shift the array
+        };
+
+        let parent = resolve(doc, &segments, true, max_depth)?.unwrap(); // safe because of resolve() guarantees
+
+        match *parent {
+            Value::Table(ref mut tab) => {
+                let is_empty = match tab.get(&*ident) {
+                    Some(Value::Table(ref t)) => t.is_empty(),
+                    _ => false,
+                };
+
+                if !is_empty {
+                    break;
+                }
+                tab.remove(&*ident);
+            }
+            _ => break,
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use toml::from_str as toml_from_str;
     use toml::Value;
 
+    #[test]
+    fn test_delete_recursive() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [a]
+        x = 1
+
+        [a.b]
+        c = [ 1, 2 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_recursive("a.b");
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("c = [ 1, 2 ]").unwrap();
+        assert_eq!(Some(expected), res.unwrap());
+
+        let expected: Value = toml_from_str("a = { x = 1 }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_recursive_missing_is_none() {
+        let mut toml: Value = toml_from_str("[a]").unwrap();
+
+        let res = toml.delete_recursive("a.b");
+        assert!(is_match!(res, Ok(None)));
+    }
+
+    #[test]
+    fn test_delete_recursive_array_element() {
+        let mut toml: Value = toml_from_str("array = [ [ 1 ], [ 2 ], [ 3 ] ]").unwrap();
+
+        let res = toml.delete_recursive("array.[1]");
+        assert!(res.is_ok());
+        assert_eq!(Some(Value::Array(vec![Value::Integer(2)])), res.unwrap());
+
+        let res = toml.delete_recursive("array.[2]");
+        assert!(res.is_err());
+        assert!(is_match!(
+            res.unwrap_err(),
+            Error::ArrayIndexOutOfBounds(2, 2)
+        ));
+
+        let expected: Value = toml_from_str("array = [ [ 1 ], [ 3 ] ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_recursive_and_prune() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [a]
+        x = 1
+
+        [a.b.c]
+        d = { e = 1 }
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_recursive_and_prune("a.b.c.d");
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_some());
+
+        let expected: Value = toml_from_str("a = { x = 1 }").unwrap();
+        assert_eq!(expected, toml);
+
+        let res = toml.delete_recursive_and_prune("a.x");
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_some());
+
+        let expected: Value = toml_from_str("").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_recursive_and_prune_keeps_tables_in_arrays() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        a = { b = 1 }
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_recursive_and_prune("bins.[0].a.b");
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("bins = [ {} ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_keeps_order() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2, 3, 4 ]").unwrap();
+
+        let res = toml.delete("array.[0]");
+        assert!(is_match!(res, Ok(Some(Value::Integer(1)))));
+
+        let expected: Value = toml_from_str("array = [ 2, 3, 4 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_with_mode_swap() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2, 3, 4 ]").unwrap();
+
+        let res = toml.delete_with_mode("array.[1]", DeleteMode::Swap);
+        assert!(is_match!(res, Ok(Some(Value::Integer(2)))));
+
+        let expected: Value = toml_from_str("array = [ 1, 4, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+
+        let res = toml.delete_with_mode("array.[0:2]", DeleteMode::Swap);
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("array = [ 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_query() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2 ]").unwrap();
+        let query = Query::parse_with_seperator("array/[0]", "/").unwrap();
+
+        assert!(is_match!(
+            toml.delete_query(&query),
+            Ok(Some(Value::Integer(1)))
+        ));
+        assert!(is_match!(
+            toml.delete_query(&query),
+            Ok(Some(Value::Integer(2)))
+        ));
+    }
+
     #[test]
     fn test_delete_from_empty_document() {
         let mut toml: Value = toml_from_str("").unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("a"), '.');
+        let res = toml.delete_with_seperator(&String::from("a"), ".");
 
         assert!(res.is_ok());
 
@@ -205,7 +676,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table.a"), '.');
+        let res = toml.delete_with_seperator(&String::from("table.a"), ".");
 
         assert!(res.is_ok());
 
@@ -222,7 +693,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("value"), '.');
+        let res = toml.delete_with_seperator(&String::from("value"), ".");
 
         assert!(res.is_ok());
 
@@ -241,7 +712,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("value"), '.');
+        let res = toml.delete_with_seperator(&String::from("value"), ".");
 
         assert!(res.is_ok());
 
@@ -265,7 +736,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("value"), '.');
+        let res = toml.delete_with_seperator(&String::from("value"), ".");
 
         assert!(res.is_ok());
 
@@ -288,7 +759,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("value"), '.');
+        let res = toml.delete_with_seperator(&String::from("value"), ".");
 
         assert!(res.is_ok());
 
@@ -316,7 +787,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table"), '.');
+        let res = toml.delete_with_seperator(&String::from("table"), ".");
 
         assert!(res.is_ok());
 
@@ -339,7 +810,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table"), '.');
+        let res = toml.delete_with_seperator(&String::from("table"), ".");
 
         assert!(res.is_ok());
 
@@ -367,7 +838,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array"), '.');
+        let res = toml.delete_with_seperator(&String::from("array"), ".");
 
         assert!(res.is_ok());
 
@@ -390,7 +861,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array"), '.');
+        let res = toml.delete_with_seperator(&String::from("array"), ".");
 
         assert!(res.is_ok());
 
@@ -419,7 +890,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table"), '.');
+        let res = toml.delete_with_seperator(&String::from("table"), ".");
 
         assert!(res.is_err());
 
@@ -436,7 +907,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array"), '.');
+        let res = toml.delete_with_seperator(&String::from("array"), ".");
 
         assert!(res.is_err());
 
@@ -454,7 +925,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table.int"), '.');
+        let res = toml.delete_with_seperator(&String::from("table.int"), ".");
 
         assert!(res.is_ok());
 
@@ -472,7 +943,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table.array"), '.');
+        let res = toml.delete_with_seperator(&String::from("table.array"), ".");
 
         assert!(res.is_ok());
 
@@ -490,7 +961,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table.array.[0]"), '.');
+        let res = toml.delete_with_seperator(&String::from("table.array.[0]"), ".");
 
         assert!(res.is_ok());
 
@@ -507,7 +978,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array.[0]"), '.');
+        let res = toml.delete_with_seperator(&String::from("array.[0]"), ".");
 
         assert!(res.is_ok());
 
@@ -524,7 +995,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array.[0].table.int"), '.');
+        let res = toml.delete_with_seperator(&String::from("array.[0].table.int"), ".");
 
         assert!(res.is_ok());
 
@@ -544,7 +1015,7 @@ mod test {
         .unwrap();
 
         let ary = toml.read_mut(&String::from("array")).unwrap().unwrap();
-        let res = ary.delete_with_seperator(&String::from("[0]"), '.');
+        let res = ary.delete_with_seperator(&String::from("[0]"), ".");
 
         assert!(res.is_ok());
 
@@ -564,12 +1035,27 @@ mod test {
         .unwrap();
 
         let ary = toml.read_mut(&String::from("array.[0]")).unwrap().unwrap();
-        let res = ary.delete_with_seperator(&String::from("nonexist"), '.');
+        let res = ary.delete_with_seperator(&String::from("nonexist"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::QueryingValueAsTable(_)));
+        assert!(is_match!(*res.kind(), Error::QueryingValueAsTable(_)));
+    }
+
+    #[test]
+    fn test_delete_error_context() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+
+        let err = toml.delete("a.^.a.x.y").unwrap_err();
+        assert!(is_match!(
+            *err.kind(),
+            Error::IdentifierNotFoundInDocument(_, _)
+        ));
+        assert!(is_match!(
+            err,
+            Error::InQuery { ref resolved, segment: 3, .. } if resolved == "a.^.a"
+        ));
     }
 
     #[test]
@@ -584,12 +1070,12 @@ mod test {
         .unwrap();
 
         let ary = toml.read_mut(&String::from("array")).unwrap().unwrap();
-        let res = ary.delete_with_seperator(&String::from("[0]"), '.');
+        let res = ary.delete_with_seperator(&String::from("[0]"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::QueryingValueAsArray(_)));
+        assert!(is_match!(*res.kind(), Error::QueryingValueAsArray(_)));
     }
 
     #[test]
@@ -601,12 +1087,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table.another.[0]"), '.');
+        let res = toml.delete_with_seperator(&String::from("table.another.[0]"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::NoIndexInTable(0)));
+        assert!(is_match!(*res.kind(), Error::NoIndexInTable(0)));
     }
 
     #[test]
@@ -618,12 +1104,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("table.another.nonexist"), '.');
+        let res = toml.delete_with_seperator(&String::from("table.another.nonexist"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::NoIdentifierInArray(_)));
+        assert!(is_match!(*res.kind(), Error::NoIdentifierInArray(_)));
     }
 
     #[test]
@@ -635,12 +1121,30 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array.[22]"), '.');
+        let res = toml.delete_with_seperator(&String::from("array.[22]"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::ArrayIndexOutOfBounds(22, 3)));
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(22, 3)));
+    }
+
+    #[test]
+    fn test_delete_array_idx_at_len() {
+        let mut toml: Value = toml_from_str("array = [ 1 ]").unwrap();
+
+        let res = toml.delete("array.[1]");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::ArrayIndexOutOfBounds(1, 1)
+        ));
+        let res = toml.delete_with_mode("array.[1]", DeleteMode::Swap);
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::ArrayIndexOutOfBounds(1, 1)
+        ));
+
+        assert_eq!(toml, toml_from_str("array = [ 1 ]").unwrap());
     }
 
     #[test]
@@ -652,7 +1156,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array.[1]"), '.');
+        let res = toml.delete_with_seperator(&String::from("array.[1]"), ".");
 
         assert!(res.is_err());
 
@@ -669,7 +1173,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("array.[1]"), '.');
+        let res = toml.delete_with_seperator(&String::from("array.[1]"), ".");
 
         assert!(res.is_err());
 
@@ -689,7 +1193,7 @@ mod test {
         .unwrap();
 
         let ary = toml.read_mut(&String::from("array")).unwrap().unwrap();
-        let res = ary.delete_with_seperator(&String::from("[1]"), '.');
+        let res = ary.delete_with_seperator(&String::from("[1]"), ".");
 
         assert!(res.is_err());
 
@@ -706,12 +1210,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("val.foo"), '.');
+        let res = toml.delete_with_seperator(&String::from("val.foo"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::QueryingValueAsTable(_)));
+        assert!(is_match!(*res.kind(), Error::QueryingValueAsTable(_)));
     }
 
     #[test]
@@ -723,12 +1227,388 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.delete_with_seperator(&String::from("val.[0]"), '.');
+        let res = toml.delete_with_seperator(&String::from("val.[0]"), ".");
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::QueryingValueAsArray(0)));
+        assert!(is_match!(*res.kind(), Error::QueryingValueAsArray(0)));
+    }
+
+    #[test]
+    fn test_delete_slice_from_array() {
+        use std::ops::Index;
+
+        let mut toml: Value = toml_from_str(
+            r#"
+        array = [ 0, 1, 2, 3, 4 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_with_seperator(&String::from("array.[1:3]"), ".");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(
+            Some(Value::Array(vec![Value::Integer(1), Value::Integer(2)])),
+            res
+        );
+
+        let array = toml.index("array");
+        assert_eq!(
+            &Value::Array(vec![
+                Value::Integer(0),
+                Value::Integer(3),
+                Value::Integer(4)
+            ]),
+            array
+        );
+    }
+
+    #[test]
+    fn test_delete_open_slice_from_top_level_array() {
+        let mut toml = Value::Array(vec![
+            Value::Integer(0),
+            Value::Integer(1),
+            Value::Integer(2),
+        ]);
+
+        let res = toml.delete_with_seperator(&String::from("[1:]"), ".");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(
+            Some(Value::Array(vec![Value::Integer(1), Value::Integer(2)])),
+            res
+        );
+        assert_eq!(Value::Array(vec![Value::Integer(0)]), toml);
+    }
+
+    #[test]
+    fn test_delete_slice_out_of_bounds() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        array = [ 0, 1 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_with_seperator(&String::from("array.[1:5]"), ".");
+
+        assert!(res.is_err());
+
+        let res = res.unwrap_err();
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(5, 2)));
+    }
+
+    #[test]
+    fn test_delete_slice_with_non_empty_table() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[array]]
+        [[array]]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_with_seperator(&String::from("array.[:]"), ".");
+
+        assert!(res.is_err());
+
+        let res = res.unwrap_err();
+        assert!(is_match!(res, Error::CannotDeleteNonEmptyTable(None)));
+    }
+
+    #[test]
+    fn test_delete_all_index_union() {
+        use std::ops::Index;
+
+        let mut toml: Value = toml_from_str(
+            r#"
+        array = [ 0, 1, 2, 3, 4, 5 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all_with_seperator(&String::from("array.[4,0,2]"), ".");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(
+            vec![
+                (String::from("array.[0]"), Value::Integer(0)),
+                (String::from("array.[2]"), Value::Integer(2)),
+                (String::from("array.[4]"), Value::Integer(4)),
+            ],
+            res
+        );
+
+        let array = toml.index("array");
+        assert_eq!(
+            &Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(3),
+                Value::Integer(5)
+            ]),
+            array
+        );
+    }
+
+    #[test]
+    fn test_delete_all_index_union_in_array_of_tables() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "a"
+        path = "a"
+
+        [[bins]]
+        name = "b"
+        path = "b"
+
+        [[bins]]
+        name = "c"
+        path = "c"
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all(&String::from("bins.[0,2].path"));
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(2, res.len());
+        assert_eq!("bins.[0].path", res[0].0);
+        assert_eq!("bins.[2].path", res[1].0);
+
+        let expected: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "a"
+
+        [[bins]]
+        name = "b"
+        path = "b"
+
+        [[bins]]
+        name = "c"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_all_non_empty_tables() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[array]]
+        [[array]]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all(&String::from("array.[0,1]"));
+
+        assert!(res.is_ok());
+        assert_eq!(2, res.unwrap().len());
+
+        let array = toml.as_table().unwrap().get("array").unwrap();
+        assert!(array.as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_all_index_union_of_tables() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[a]]
+        n = 0
+
+        [[a]]
+        n = 1
+
+        [[a]]
+        n = 2
+
+        [[a]]
+        n = 3
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all("a.[0,2]");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(
+            vec![
+                (String::from("a.[0]"), toml_from_str("n = 0").unwrap()),
+                (String::from("a.[2]"), toml_from_str("n = 2").unwrap()),
+            ],
+            res
+        );
+
+        let expected: Value = toml_from_str(
+            r#"
+        [[a]]
+        n = 1
+
+        [[a]]
+        n = 3
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_all_filter_of_tables() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[a]]
+        n = 1
+        name = "x"
+
+        [[a]]
+        n = 2
+        name = "y"
+
+        [[a]]
+        n = 1
+        name = "z"
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all("a.[?(@.n == 1)]");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(2, res.len());
+        assert_eq!("a.[0]", res[0].0);
+        assert_eq!("a.[2]", res[1].0);
+
+        let expected: Value = toml_from_str(
+            r#"
+        [[a]]
+        n = 2
+        name = "y"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_all_nested_matches() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [a]
+        x = 1
+
+        [a.t]
+        x = 2
+
+        [b]
+        x = 3
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all("**.{t,x}");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(3, res.len());
+        assert!(res.iter().all(|(path, _)| path != "a.t.x"));
+
+        let expected: Value = toml_from_str("[a]\n[b]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_all_wildcard() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        secret = "a"
+
+        [db]
+        user = "b"
+        secret = "c"
+
+        [cache]
+        secret = "d"
+
+        [cache.replica]
+        secret = "e"
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all("*.secret");
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+        assert_eq!(
+            vec![
+                (
+                    String::from("cache.secret"),
+                    Value::String(String::from("d"))
+                ),
+                (String::from("db.secret"), Value::String(String::from("c"))),
+            ],
+            res
+        );
+
+        let expected: Value = toml_from_str(
+            r#"
+        secret = "a"
+
+        [db]
+        user = "b"
+
+        [cache.replica]
+        secret = "e"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_delete_all_recursive_descent() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        secret = "a"
+
+        [db]
+        user = "b"
+        secret = "c"
+
+        [[db.replicas]]
+        secret = "d"
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.delete_all("**.secret");
+
+        assert!(res.is_ok());
+        assert_eq!(3, res.unwrap().len());
+
+        let expected: Value = toml_from_str(
+            r#"
+        [db]
+        user = "b"
+
+        [[db.replicas]]
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
     }
 
 }
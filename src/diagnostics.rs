@@ -0,0 +1,136 @@
+/// Diagnostics for errors in queries, for error reports with `miette`
+use std::error::Error as StdError;
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+
+use crate::error::Error;
+
+/// An `Error` which implements `miette::Diagnostic`
+///
+/// `Error` is a `failure::Fail`, which cannot implement `std::error::Error` and so not
+/// `Diagnostic` itself. Errors in the query (`Error::QuerySyntax` and `Error::InQuery`) are
+/// reported with the query as source code and labels over the part of it which fails.
+///
+/// ```rust,ignore
+/// let err = toml.read("server.[0]").unwrap_err();
+/// let report = miette::Report::new(QueryDiagnostic::from(err));
+/// ```
+#[derive(Debug)]
+pub struct QueryDiagnostic(pub Error);
+
+impl From<Error> for QueryDiagnostic {
+    fn from(error: Error) -> QueryDiagnostic {
+        QueryDiagnostic(error)
+    }
+}
+
+impl fmt::Display for QueryDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Error::QuerySyntax { .. } | Error::InQuery { .. } => write!(f, "{}", self.0.kind()),
+            ref error => write!(f, "{}", error),
+        }
+    }
+}
+
+impl StdError for QueryDiagnostic {}
+
+impl Diagnostic for QueryDiagnostic {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        match self.0 {
+            Error::QuerySyntax { ref query, .. } | Error::InQuery { ref query, .. } => Some(query),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let labels = match self.0 {
+            Error::QuerySyntax { offset, len, .. } => {
+                vec![LabeledSpan::new(Some(String::from("here")), offset, len)]
+            }
+
+            Error::InQuery {
+                ref query,
+                ref resolved,
+                ref seperator,
+                ..
+            } => {
+                // The failing segment follows the resolved part and a seperator
+                let start = if resolved.is_empty() {
+                    0
+                } else {
+                    resolved.len() + seperator.len()
+                };
+
+                let mut labels = Vec::new();
+                if start > 0 {
+                    let label = Some(String::from("resolved"));
+                    labels.push(LabeledSpan::new(label, 0, resolved.len()));
+                }
+                let label = Some(String::from("cannot be resolved"));
+                labels.push(LabeledSpan::new(label, start, query.len() - start));
+                labels
+            }
+
+            _ => return None,
+        };
+
+        Some(Box::new(labels.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::read::TomlValueReadExt;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    fn labels(error: Error) -> Vec<(Option<String>, usize, usize)> {
+        QueryDiagnostic::from(error)
+            .labels()
+            .map(|labels| {
+                labels
+                    .map(|l| (l.label().map(String::from), l.offset(), l.len()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_syntax_error_labels() {
+        let toml: Value = toml_from_str("").unwrap();
+
+        let diagnostic = QueryDiagnostic::from(toml.read("a.[x]").unwrap_err());
+        assert!(diagnostic.source_code().is_some());
+        assert_eq!(
+            vec![(Some(String::from("here")), 3, 1)],
+            labels(diagnostic.0)
+        );
+    }
+
+    #[test]
+    fn test_resolver_error_labels() {
+        let toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let err = toml.read("a.b.c").unwrap_err();
+
+        assert_eq!(
+            vec![
+                (Some(String::from("resolved")), 0, 3),
+                (Some(String::from("cannot be resolved")), 4, 1),
+            ],
+            labels(err)
+        );
+    }
+
+    #[test]
+    fn test_other_errors_have_no_labels() {
+        assert!(labels(Error::NoLayers).is_empty());
+        assert_eq!(
+            "There are no layers",
+            QueryDiagnostic(Error::NoLayers).to_string()
+        );
+    }
+
+}
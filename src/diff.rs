@@ -0,0 +1,472 @@
+/// The Toml Diff extensions
+use toml::Value;
+
+use crate::delete::TomlValueDeleteExt;
+use crate::error::{Error, Result};
+use crate::insert::TomlValueInsertExt;
+use crate::project::TomlValueProjectExt;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::set::TomlValueSetExt;
+use crate::tokenizer::quote_identifier;
+use crate::transaction::{atomically, Transaction};
+use crate::util::child_path;
+
+/// A difference between two toml::Value documents
+///
+/// The path is a query for the value in the documents, like the paths returned by
+/// `TomlValueReadExt::read_all`. The path of the document root is the empty string.
+///
+/// With the `typed` feature, the operations can be serialized, for applying them to a document
+/// elsewhere with `TomlValueDiffExt::apply_patch`. An operation is serialized as a table with
+/// the key `op` (`"added"`, `"removed"` or `"changed"`) and its fields.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "typed", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "typed", serde(tag = "op", rename_all = "lowercase"))]
+pub enum DiffOp {
+    /// The value at `path` exists only in the other document
+    Added { path: String, value: Value },
+
+    /// The value at `path` exists only in the current document
+    Removed { path: String, value: Value },
+
+    /// The value at `path` is `old` in the current document and `new` in the other one
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+pub trait TomlValueDiffExt {
+    /// Extension function for computing the differences between the current toml::Value
+    /// document and `other`
+    ///
+    /// # Semantics
+    ///
+    /// Tables are compared key by key and arrays element by element, so only the innermost
+    /// values which differ are reported. Values of different types are reported as changed.
+    ///
+    /// Within a table, removed and changed keys come first (in the order of the current
+    /// document), then the added ones. Within an array, the changed elements come first, then the
+    /// added ones in ascending or the removed ones in descending order of their index. That way,
+    /// the paths are valid when the operations are applied one after another.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::diff::{DiffOp, TomlValueDiffExt};
+    ///
+    /// let old : toml::Value = toml::from_str("[server]\nport = 80\nhost = \"a\"").unwrap();
+    /// let new : toml::Value = toml::from_str("[server]\nport = 8080\ntls = true").unwrap();
+    ///
+    /// assert_eq!(old.diff(&new), vec![
+    ///     DiffOp::Removed {
+    ///         path: String::from("server.host"),
+    ///         value: toml::Value::String(String::from("a")),
+    ///     },
+    ///     DiffOp::Changed {
+    ///         path: String::from("server.port"),
+    ///         old: toml::Value::Integer(80),
+    ///         new: toml::Value::Integer(8080),
+    ///     },
+    ///     DiffOp::Added {
+    ///         path: String::from("server.tls"),
+    ///         value: toml::Value::Boolean(true),
+    ///     },
+    /// ]);
+    /// ```
+    fn diff(&self, other: &Value) -> Vec<DiffOp>;
+
+    /// Extension function for applying the output of `TomlValueDiffExt::diff` to the current
+    /// toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// The operations are applied in order. Before an operation is applied, the document is
+    /// checked: an added value must not exist yet, a removed or changed value must be equal to
+    /// the one in the operation. If a patch is applied to the document it was computed for, this
+    /// always holds.
+    ///
+    /// Either all operations are applied or none: if one of them fails, the document is restored
+    /// to its state before the patch.
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(())` if the patch was applied
+    /// * On failure, `Err(e)` is returned:
+    ///     * If the document does not match an operation: `Error::PatchConflict`
+    ///     * If a path cannot be parsed or the value cannot be put at it, the error of the
+    ///       failing query
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::diff::TomlValueDiffExt;
+    ///
+    /// let old : toml::Value = toml::from_str("[server]\nport = 80\nhost = \"a\"").unwrap();
+    /// let new : toml::Value = toml::from_str("[server]\nport = 8080\ntls = true").unwrap();
+    ///
+    /// let mut doc = old.clone();
+    /// assert!(doc.apply_patch(old.diff(&new)).is_ok());
+    /// assert_eq!(doc, new);
+    /// ```
+    fn apply_patch(&mut self, patch: Vec<DiffOp>) -> Result<()>;
+
+    /// Extension function for comparing the current toml::Value document to `other`, leaving out
+    /// the values at the paths `ignore` in both of them
+    ///
+    /// Paths may match several values or none, see `TomlValueProjectExt::without`. A value
+    /// which is only in one of the documents is ignored as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::diff::TomlValueDiffExt;
+    ///
+    /// let ours : toml::Value = toml::from_str("name = 'a'\n[meta]\nupdated = 1").unwrap();
+    /// let theirs : toml::Value = toml::from_str("name = 'a'\n[meta]\nupdated = 2").unwrap();
+    ///
+    /// assert!(ours.equals_ignoring(&theirs, &["meta.updated"]).unwrap());
+    /// assert!(!ours.equals_ignoring(&theirs, &[]).unwrap());
+    /// ```
+    fn equals_ignoring(&self, other: &Value, ignore: &[&str]) -> Result<bool>;
+}
+
+impl TomlValueDiffExt for Value {
+    fn diff(&self, other: &Value) -> Vec<DiffOp> {
+        let mut ops = Vec::new();
+        diff_values(self, other, "", &mut ops);
+        ops
+    }
+
+    fn apply_patch(&mut self, patch: Vec<DiffOp>) -> Result<()> {
+        atomically(self, |tx| {
+            patch.into_iter().try_for_each(|op| apply_op(tx, op))
+        })
+    }
+
+    fn equals_ignoring(&self, other: &Value, ignore: &[&str]) -> Result<bool> {
+        Ok(self.without(ignore)? == other.without(ignore)?)
+    }
+}
+
+fn diff_values(ours: &Value, theirs: &Value, path: &str, ops: &mut Vec<DiffOp>) {
+    match (ours, theirs) {
+        (Value::Table(ours), Value::Table(theirs)) => {
+            for (key, our_value) in ours {
+                let path = child_path(path, &quote_identifier(key, "."), ".");
+                match theirs.get(key) {
+                    Some(their_value) => diff_values(our_value, their_value, &path, ops),
+                    None => ops.push(DiffOp::Removed {
+                        path,
+                        value: our_value.clone(),
+                    }),
+                }
+            }
+
+            for (key, their_value) in theirs {
+                if !ours.contains_key(key) {
+                    ops.push(DiffOp::Added {
+                        path: child_path(path, &quote_identifier(key, "."), "."),
+                        value: their_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(ours), Value::Array(theirs)) => {
+            let index_path = |idx: usize| child_path(path, &format!("[{}]", idx), ".");
+
+            for (idx, (our_value, their_value)) in ours.iter().zip(theirs.iter()).enumerate() {
+                diff_values(our_value, their_value, &index_path(idx), ops);
+            }
+
+            for (idx, their_value) in theirs.iter().enumerate().skip(ours.len()) {
+                ops.push(DiffOp::Added {
+                    path: index_path(idx),
+                    value: their_value.clone(),
+                });
+            }
+
+            for (idx, our_value) in ours.iter().enumerate().skip(theirs.len()).rev() {
+                ops.push(DiffOp::Removed {
+                    path: index_path(idx),
+                    value: our_value.clone(),
+                });
+            }
+        }
+        _ => {
+            if ours != theirs {
+                ops.push(DiffOp::Changed {
+                    path: path.to_owned(),
+                    old: ours.clone(),
+                    new: theirs.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn apply_op(doc: &mut Transaction, op: DiffOp) -> Result<()> {
+    match op {
+        DiffOp::Added { path, value } => {
+            let query = Query::parse(&path)?;
+            if current(doc, &query).is_some() {
+                return Err(Error::PatchConflict(path.clone()));
+            }
+
+            doc.insert_query(&query, value).map(|_| ())
+        }
+        DiffOp::Removed { path, value } => {
+            let query = Query::parse(&path)?;
+            if current(doc, &query) != Some(&value) {
+                return Err(Error::PatchConflict(path.clone()));
+            }
+
+            doc.delete_recursive_query(&query, false).map(|_| ())
+        }
+        DiffOp::Changed {
+            ref path, ref old, ..
+        } if path.is_empty() && **doc != *old => Err(Error::PatchConflict(path.clone())),
+        DiffOp::Changed { path, new, .. } if path.is_empty() => {
+            doc.replace_document(new);
+            Ok(())
+        }
+        DiffOp::Changed { path, old, new } => {
+            let query = Query::parse(&path)?;
+            if current(doc, &query) != Some(&old) {
+                return Err(Error::PatchConflict(path.clone()));
+            }
+
+            doc.set_query(&query, new).map(|_| ())
+        }
+    }
+}
+
+/// The value at `query`, if the document has one
+///
+/// A document which cannot contain a value at `query` (for example because the query indexes a
+/// table) has none.
+fn current<'doc>(doc: &'doc Value, query: &Query) -> Option<&'doc Value> {
+    doc.read_query(query).ok().and_then(|found| found)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_diff_equal_documents() {
+        let toml: Value = toml_from_str("a = { b = [ 1, { c = 2 } ] }").unwrap();
+        assert!(toml.diff(&toml.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_equals_ignoring() {
+        let ours: Value = toml_from_str("a = 1\nb = [ { t = 1, c = 2 } ]").unwrap();
+        let theirs: Value = toml_from_str("a = 1\nb = [ { t = 2, c = 2 } ]\nt = 3").unwrap();
+
+        assert!(is_match!(
+            ours.equals_ignoring(&theirs, &["**.t"]),
+            Ok(true)
+        ));
+        assert!(is_match!(
+            ours.equals_ignoring(&theirs, &["b.*.t"]),
+            Ok(false)
+        ));
+        assert!(ours.equals_ignoring(&theirs, &["a.x"]).is_err());
+    }
+
+    #[test]
+    fn test_diff_nested() {
+        let ours: Value = toml_from_str("a = { b = { c = 1, d = 2 } }").unwrap();
+        let theirs: Value = toml_from_str("a = { b = { c = 1, d = 3 } }").unwrap();
+
+        assert_eq!(
+            vec![DiffOp::Changed {
+                path: String::from("a.b.d"),
+                old: Value::Integer(2),
+                new: Value::Integer(3),
+            }],
+            ours.diff(&theirs)
+        );
+    }
+
+    #[test]
+    fn test_diff_arrays() {
+        let ours: Value = toml_from_str("a = [ 1, 2, 3, 4 ]\nb = [ 1 ]").unwrap();
+        let theirs: Value = toml_from_str("a = [ 1, 5 ]\nb = [ 1, 2, 3 ]").unwrap();
+
+        assert_eq!(
+            vec![
+                DiffOp::Changed {
+                    path: String::from("a.[1]"),
+                    old: Value::Integer(2),
+                    new: Value::Integer(5),
+                },
+                DiffOp::Removed {
+                    path: String::from("a.[3]"),
+                    value: Value::Integer(4),
+                },
+                DiffOp::Removed {
+                    path: String::from("a.[2]"),
+                    value: Value::Integer(3),
+                },
+                DiffOp::Added {
+                    path: String::from("b.[1]"),
+                    value: Value::Integer(2),
+                },
+                DiffOp::Added {
+                    path: String::from("b.[2]"),
+                    value: Value::Integer(3),
+                },
+            ],
+            ours.diff(&theirs)
+        );
+    }
+
+    #[test]
+    fn test_diff_type_change() {
+        let ours: Value = toml_from_str("a = { b = 1 }").unwrap();
+        let theirs: Value = toml_from_str("a = [ 1 ]").unwrap();
+
+        assert_eq!(
+            vec![DiffOp::Changed {
+                path: String::from("a"),
+                old: toml_from_str("b = 1").unwrap(),
+                new: Value::Array(vec![Value::Integer(1)]),
+            }],
+            ours.diff(&theirs)
+        );
+    }
+
+    #[test]
+    fn test_diff_quotes_paths() {
+        let ours: Value = toml_from_str("").unwrap();
+        let theirs: Value = toml_from_str(r#""a.b" = 1"#).unwrap();
+
+        assert_eq!(
+            vec![DiffOp::Added {
+                path: String::from(r#""a.b""#),
+                value: Value::Integer(1),
+            }],
+            ours.diff(&theirs)
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_round_trip() {
+        let ours: Value = toml_from_str(
+            r#"
+        a = [ 1, 2, { b = 3 } ]
+        c = [ 4 ]
+
+        [d]
+        e = "e"
+        f = { g = 1 }
+        "#,
+        )
+        .unwrap();
+        let theirs: Value = toml_from_str(
+            r#"
+        a = [ 1 ]
+        c = [ 4, 5, { h = 6 } ]
+        i = 7
+
+        [d]
+        e = [ "e" ]
+        "#,
+        )
+        .unwrap();
+
+        let mut toml = ours.clone();
+        assert!(toml.apply_patch(ours.diff(&theirs)).is_ok());
+        assert_eq!(theirs, toml);
+
+        let mut toml = theirs.clone();
+        assert!(toml.apply_patch(theirs.diff(&ours)).is_ok());
+        assert_eq!(ours, toml);
+    }
+
+    #[test]
+    fn test_apply_patch_to_root() {
+        let mut toml = Value::Integer(1);
+        let patch = toml.diff(&Value::Integer(2));
+
+        assert!(toml.apply_patch(patch.clone()).is_ok());
+        assert_eq!(Value::Integer(2), toml);
+
+        let res = toml.apply_patch(patch);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::PatchConflict(_)));
+    }
+
+    #[test]
+    fn test_apply_patch_conflict_leaves_document_unchanged() {
+        let ours: Value = toml_from_str("a = 1\nb = 2").unwrap();
+        let theirs: Value = toml_from_str("a = 2\nb = 3").unwrap();
+        let patch = ours.diff(&theirs);
+
+        let mut toml: Value = toml_from_str("a = 1\nb = 4").unwrap();
+        let res = toml.apply_patch(patch);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::PatchConflict(ref path) if path == "b"));
+
+        let expected: Value = toml_from_str("a = 1\nb = 4").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_apply_patch_added_value_exists() {
+        let patch = vec![DiffOp::Added {
+            path: String::from("a"),
+            value: Value::Integer(1),
+        }];
+
+        let mut toml: Value = toml_from_str("a = 2").unwrap();
+        let res = toml.apply_patch(patch);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::PatchConflict(_)));
+    }
+
+    #[cfg(feature = "typed")]
+    #[test]
+    fn test_serialize_patch() {
+        let ours: Value = toml_from_str("a = 1\nb = [ 2 ]").unwrap();
+        let theirs: Value = toml_from_str("a = 2\nb = [ 2, { c = 3 } ]").unwrap();
+        let patch = ours.diff(&theirs);
+
+        let serialized = Value::try_from(&patch).unwrap();
+        let expected: Value = toml_from_str(
+            r#"
+        [[patch]]
+        op = "changed"
+        path = "a"
+        old = 1
+        new = 2
+
+        [[patch]]
+        op = "added"
+        path = "b.[1]"
+        value = { c = 3 }
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected.get("patch"), Some(&serialized));
+
+        let deserialized: Vec<DiffOp> = serialized.try_into().unwrap();
+        assert_eq!(patch, deserialized);
+    }
+
+}
@@ -0,0 +1,443 @@
+/// The document abstraction the resolvers work on
+///
+/// Implementing `DocumentLike` for a tree of values (like `toml::Value` or `serde_json::Value`)
+/// makes it possible to resolve queries in it.
+use std::mem;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::resolver::non_mut_resolver::{not_a_table, not_an_array};
+use crate::tokenizer::Token;
+use crate::util::name_of_val;
+use toml::map::Map;
+use toml::Value;
+
+/// A tree of values which consists of tables (with string keys), arrays and scalar values
+pub trait DocumentLike: Sized {
+    /// The value for `key`, if this is a table which contains `key`
+    fn get_key(&self, key: &str) -> Option<&Self>;
+
+    /// The value for `key`, if this is a table which contains `key`
+    fn get_key_mut(&mut self, key: &str) -> Option<&mut Self>;
+
+    /// The element at `idx`, if this is an array which is long enough
+    fn get_index(&self, idx: usize) -> Option<&Self>;
+
+    /// The element at `idx`, if this is an array which is long enough
+    fn get_index_mut(&mut self, idx: usize) -> Option<&mut Self>;
+
+    /// Whether this is a table
+    fn is_table(&self) -> bool;
+
+    /// The keys, if this is a table
+    fn keys(&self) -> Vec<&str>;
+
+    /// The number of elements, if this is an array
+    fn array_len(&self) -> Option<usize>;
+
+    /// An empty table
+    fn new_table() -> Self;
+
+    /// An empty array
+    fn new_array() -> Self;
+
+    /// Insert `value` for `key`, if this is a table, returning the value it replaced
+    fn insert_key(&mut self, key: &str, value: Self) -> Option<Self>;
+
+    /// Remove the value for `key`, if this is a table which contains `key`
+    fn remove_key(&mut self, key: &str) -> Option<Self>;
+
+    /// Insert `value` at `idx`, if this is an array
+    ///
+    /// `idx` is at most the length of the array.
+    fn insert_index(&mut self, idx: usize, value: Self);
+
+    /// Remove the element at `idx`, if this is an array which is long enough
+    fn remove_index(&mut self, idx: usize) -> Option<Self>;
+
+    /// The name of the type of the value, as used in errors
+    fn type_name(&self) -> &'static str;
+
+    /// The value as a TOML value, if it is a string, number, boolean or datetime
+    ///
+    /// Filters (`[?(@.port == 80)]`) compare these with their literals.
+    fn to_scalar(&self) -> Option<Value>;
+}
+
+impl DocumentLike for Value {
+    fn get_key(&self, key: &str) -> Option<&Value> {
+        self.as_table().and_then(|t| t.get(key))
+    }
+
+    fn get_key_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_table_mut().and_then(|t| t.get_mut(key))
+    }
+
+    fn get_index(&self, idx: usize) -> Option<&Value> {
+        self.as_array().and_then(|a| a.get(idx))
+    }
+
+    fn get_index_mut(&mut self, idx: usize) -> Option<&mut Value> {
+        self.as_array_mut().and_then(|a| a.get_mut(idx))
+    }
+
+    fn is_table(&self) -> bool {
+        Value::is_table(self)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.as_table()
+            .map(|t| t.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    fn array_len(&self) -> Option<usize> {
+        self.as_array().map(Vec::len)
+    }
+
+    fn new_table() -> Value {
+        Value::Table(Map::new())
+    }
+
+    fn new_array() -> Value {
+        Value::Array(vec![])
+    }
+
+    fn insert_key(&mut self, key: &str, value: Value) -> Option<Value> {
+        self.as_table_mut()
+            .and_then(|t| t.insert(key.to_owned(), value))
+    }
+
+    fn remove_key(&mut self, key: &str) -> Option<Value> {
+        self.as_table_mut().and_then(|t| t.remove(key))
+    }
+
+    fn insert_index(&mut self, idx: usize, value: Value) {
+        if let Some(a) = self.as_array_mut() {
+            a.insert(idx, value)
+        }
+    }
+
+    fn remove_index(&mut self, idx: usize) -> Option<Value> {
+        self.as_array_mut()
+            .filter(|a| idx < a.len())
+            .map(|a| a.remove(idx))
+    }
+
+    fn type_name(&self) -> &'static str {
+        name_of_val(self)
+    }
+
+    fn to_scalar(&self) -> Option<Value> {
+        match *self {
+            Value::Table(_) | Value::Array(_) => None,
+            ref value => Some(value.clone()),
+        }
+    }
+}
+
+/// Reading from documents other than `toml::Value`
+///
+/// The queries are the same as for `TomlValueReadExt`. Like there, queries which match more than
+/// one value can only be used with `read_all()`.
+pub trait DocumentReadExt: DocumentLike {
+    /// Extension function for reading a value from the current document using a custom
+    /// seperator
+    ///
+    /// See documentation of `TomlValueReadExt::read_with_seperator`
+    fn read_with_seperator(&self, query: &str, sep: &str) -> Result<Option<&Self>> {
+        self.read_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    /// Extension function for reading a value from the current document
+    fn read(&self, query: &str) -> Result<Option<&Self>> {
+        self.read_with_seperator(query, ".")
+    }
+
+    /// Extension function for reading a value from the current document using a pre-compiled
+    /// query
+    fn read_query(&self, query: &Query) -> Result<Option<&Self>> {
+        use crate::resolver::in_query;
+        use crate::resolver::non_mut_resolver::resolve;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        resolve_parents(self, query.tokens())
+            .and_then(|tokens| match tokens {
+                Some(tokens) => resolve(self, &tokens, false, query.max_depth()),
+                None => Ok(Some(self)),
+            })
+            .map_err(|e| in_query(self, query, e))
+    }
+
+    /// Extension function for reading a value mutably from the current document using a custom
+    /// seperator
+    fn read_mut_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<&mut Self>> {
+        self.read_mut_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    /// Extension function for reading a value mutably from the current document
+    fn read_mut(&mut self, query: &str) -> Result<Option<&mut Self>> {
+        self.read_mut_with_seperator(query, ".")
+    }
+
+    /// Extension function for reading a value mutably from the current document using a
+    /// pre-compiled query
+    fn read_mut_query(&mut self, query: &Query) -> Result<Option<&mut Self>> {
+        use crate::resolver::in_query;
+        use crate::resolver::mut_resolver::resolve_in_query;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        match resolve_parents(&*self, query.tokens()) {
+            Ok(Some(tokens)) => resolve_in_query(self, query, &tokens, false, query.max_depth()),
+            Ok(None) => Ok(Some(self)),
+            Err(e) => Err(in_query(&*self, query, e)),
+        }
+    }
+
+    /// Extension function for reading all values matching a query from the current document
+    /// using a custom seperator
+    ///
+    /// See documentation of `TomlValueReadExt::read_all_with_seperator`
+    fn read_all_with_seperator(&self, query: &str, sep: &str) -> Result<Vec<(String, &Self)>> {
+        use crate::resolver::non_mut_multi_resolver::resolve_all;
+
+        let query = Query::parse_with_seperator(query, sep)?;
+        let mut results = Vec::new();
+        resolve_all(
+            self,
+            query.tokens(),
+            sep,
+            "",
+            query.max_depth(),
+            &mut results,
+        )?;
+        Ok(results)
+    }
+
+    /// Extension function for reading all values matching a query from the current document
+    ///
+    /// See documentation of `TomlValueReadExt::read_all_with_seperator`
+    fn read_all(&self, query: &str) -> Result<Vec<(String, &Self)>> {
+        self.read_all_with_seperator(query, ".")
+    }
+}
+
+/// Setting values in documents other than `toml::Value`
+///
+/// See documentation of `TomlValueSetExt`
+pub trait DocumentSetExt: DocumentLike {
+    /// Extension function for setting a value in the current document using a custom seperator
+    ///
+    /// See documentation of `TomlValueSetExt::set_with_seperator`
+    fn set_with_seperator(&mut self, query: &str, sep: &str, value: Self) -> Result<Option<Self>> {
+        self.set_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    /// Extension function for setting a value in the current document
+    fn set(&mut self, query: &str, value: Self) -> Result<Option<Self>> {
+        self.set_with_seperator(query, ".", value)
+    }
+
+    /// Extension function for setting a value in the current document using a pre-compiled
+    /// query
+    fn set_query(&mut self, query: &Query, value: Self) -> Result<Option<Self>> {
+        use crate::resolver::in_query;
+
+        set_impl(self, query, value).map_err(|e| in_query(self, query, e))
+    }
+}
+
+/// Inserting values into documents other than `toml::Value`
+///
+/// See documentation of `TomlValueInsertExt`, indices beyond the end of an array are an error.
+pub trait DocumentInsertExt: DocumentLike {
+    /// Extension function for inserting a value into the current document using a custom
+    /// seperator
+    ///
+    /// See documentation of `TomlValueInsertExt::insert_with_seperator`
+    fn insert_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Self,
+    ) -> Result<Option<Self>> {
+        self.insert_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    /// Extension function for inserting a value into the current document
+    fn insert(&mut self, query: &str, value: Self) -> Result<Option<Self>> {
+        self.insert_with_seperator(query, ".", value)
+    }
+
+    /// Extension function for inserting a value into the current document using a pre-compiled
+    /// query
+    fn insert_query(&mut self, query: &Query, value: Self) -> Result<Option<Self>> {
+        use crate::resolver::in_query;
+
+        insert_impl(self, query, value).map_err(|e| in_query(self, query, e))
+    }
+}
+
+/// Deleting values from documents other than `toml::Value`
+///
+/// See documentation of `TomlValueDeleteExt`, array elements are always removed by shifting the
+/// following elements.
+pub trait DocumentDeleteExt: DocumentLike {
+    /// Extension function for deleting a value from the current document using a custom
+    /// seperator
+    ///
+    /// See documentation of `TomlValueDeleteExt::delete_with_seperator`
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Self>> {
+        self.delete_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    /// Extension function for deleting a value from the current document
+    fn delete(&mut self, query: &str) -> Result<Option<Self>> {
+        self.delete_with_seperator(query, ".")
+    }
+
+    /// Extension function for deleting a value from the current document using a pre-compiled
+    /// query
+    fn delete_query(&mut self, query: &Query) -> Result<Option<Self>> {
+        use crate::resolver::in_query;
+
+        delete_impl(self, query).map_err(|e| in_query(self, query, e))
+    }
+}
+
+/// The parent of the value `query` refers to, the token which refers to the value in it and
+/// whether the parent was created
+fn resolve_last<'doc, 'q, D: DocumentLike>(
+    doc: &'doc mut D,
+    query: &'q Query,
+    create: bool,
+) -> Result<(&'doc mut D, Token<'q>, bool)> {
+    use crate::resolver::mut_creating_resolver::resolve as resolve_creating;
+    use crate::resolver::mut_resolver::resolve;
+    use crate::resolver::non_mut_resolver::resolve as resolve_existing;
+    use crate::resolver::parent_resolver::resolve_parents;
+
+    let mut tokens = resolve_parents(doc, query.tokens())?
+        .ok_or(Error::EmptyQueryError)?
+        .into_owned();
+    let last = tokens.pop().unwrap(); // safe because resolve_parents() yields no empty list
+
+    let created = create
+        && is_match!(
+            resolve_existing(&*doc, &tokens, false, query.max_depth()),
+            Ok(None)
+        );
+    let parent = if create {
+        resolve_creating(doc, &tokens, query.max_depth())?
+    } else {
+        resolve(doc, &tokens, true, query.max_depth())?.unwrap() // safe because of resolve() guarantees
+    };
+
+    Ok((parent, last, created))
+}
+
+fn set_impl<D: DocumentLike>(doc: &mut D, query: &Query, value: D) -> Result<Option<D>> {
+    let (parent, last, _) = resolve_last(doc, query, false)?;
+
+    match last {
+        Token::Identifier { ref ident } if parent.is_table() => Ok(parent.insert_key(ident, value)),
+        Token::Identifier { ref ident } => Err(not_a_table(parent, ident)),
+
+        Token::Index { idx } => match parent.array_len() {
+            Some(len) if idx < len => {
+                let old = parent.get_index_mut(idx).unwrap(); // safe because of the length
+                Ok(Some(mem::replace(old, value)))
+            }
+            Some(len) if idx == len => {
+                parent.insert_index(idx, value);
+                Ok(None)
+            }
+            Some(len) => Err(Error::ArrayIndexOutOfBounds(idx, len)),
+            None => Err(not_an_array(parent, idx)),
+        },
+
+        Token::Append => match parent.array_len() {
+            Some(len) => {
+                parent.insert_index(len, value);
+                Ok(None)
+            }
+            None => Err(Error::AppendToNonArray(parent.type_name())),
+        },
+
+        _ => Err(Error::MultiValueQuery),
+    }
+}
+
+fn insert_impl<D: DocumentLike>(doc: &mut D, query: &Query, value: D) -> Result<Option<D>> {
+    let (parent, last, created) = resolve_last(doc, query, true)?;
+
+    // The resolver creates a table, but the last token needs an array
+    if created && is_match!(last, Token::Index { .. } | Token::Append) {
+        *parent = D::new_array();
+    }
+
+    match last {
+        Token::Identifier { ref ident } if parent.is_table() => Ok(parent.insert_key(ident, value)),
+        Token::Identifier { ref ident } => Err(not_a_table(parent, ident)),
+
+        Token::Index { idx } => match parent.array_len() {
+            Some(len) if idx <= len => {
+                parent.insert_index(idx, value);
+                Ok(None)
+            }
+            Some(len) => Err(Error::ArrayIndexOutOfBounds(idx, len)),
+            None => Err(not_an_array(parent, idx)),
+        },
+
+        Token::Append => match parent.array_len() {
+            Some(len) => {
+                parent.insert_index(len, value);
+                Ok(None)
+            }
+            None => Err(Error::AppendToNonArray(parent.type_name())),
+        },
+
+        _ => Err(Error::MultiValueQuery),
+    }
+}
+
+fn delete_impl<D: DocumentLike>(doc: &mut D, query: &Query) -> Result<Option<D>> {
+    let (parent, last, _) = resolve_last(doc, query, false)?;
+
+    match last {
+        Token::Identifier { ref ident } if parent.is_table() => {
+            match parent
+                .get_key(ident)
+                .map(|value| non_empty_error(value, Some(ident)))
+            {
+                Some(Some(e)) => Err(e),
+                _ => Ok(parent.remove_key(ident)),
+            }
+        }
+        Token::Identifier { ref ident } => Err(not_a_table(parent, ident)),
+
+        Token::Index { idx } => match parent.array_len() {
+            Some(len) if idx < len => match non_empty_error(parent.get_index(idx).unwrap(), None) {
+                Some(e) => Err(e),
+                None => Ok(parent.remove_index(idx)),
+            },
+            Some(len) => Err(Error::ArrayIndexOutOfBounds(idx, len)),
+            None => Err(not_an_array(parent, idx)),
+        },
+
+        Token::Append => Err(Error::AppendNotSupported),
+
+        _ => Err(Error::MultiValueQuery),
+    }
+}
+
+/// The error for deleting `value` if it is a non-empty table or array
+fn non_empty_error<D: DocumentLike>(value: &D, ident: Option<&str>) -> Option<Error> {
+    if value.is_table() && !value.keys().is_empty() {
+        Some(Error::CannotDeleteNonEmptyTable(ident.map(String::from)))
+    } else if value.array_len().map_or(false, |len| len > 0) {
+        Some(Error::CannotDeleteNonEmptyArray(ident.map(String::from)))
+    } else {
+        None
+    }
+}
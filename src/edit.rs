@@ -0,0 +1,654 @@
+/// The Toml Edit extensions
+use toml::Value;
+use toml_edit::{Array, ArrayOfTables, Document, InlineTable, Item, Table};
+
+use crate::delete::{DeleteMode, TomlValueDeleteExt};
+use crate::error::Result;
+use crate::insert::{InsertPolicy, TomlValueInsertExt};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::resolver::parent_resolver::resolve_parents;
+use crate::set::TomlValueSetExt;
+use crate::tokenizer::Token;
+
+/// Reading from a `toml_edit::Document`
+///
+/// A `toml_edit::Document` does not contain `toml::Value`s which could be borrowed, so the
+/// values are returned owned.
+pub trait TomlEditReadExt {
+    /// Extension function for reading a value from the current toml_edit::Document using a
+    /// custom seperator
+    ///
+    /// See documentation of `TomlValueReadExt::read_with_seperator`
+    fn read_value_with_seperator(&self, query: &str, sep: &str) -> Result<Option<Value>> {
+        self.read_value_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    /// Extension function for reading a value from the current toml_edit::Document
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_edit;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::edit::TomlEditReadExt;
+    ///
+    /// let doc = "[server]\nport = 0x1F90 # 8080\n".parse::<toml_edit::Document>().unwrap();
+    ///
+    /// let port = doc.read_value("server.port").unwrap();
+    /// assert_eq!(port, Some(toml::Value::Integer(8080)));
+    /// ```
+    fn read_value(&self, query: &str) -> Result<Option<Value>> {
+        self.read_value_with_seperator(query, ".")
+    }
+
+    /// Extension function for reading a value from the current toml_edit::Document using a
+    /// pre-compiled query
+    fn read_value_query(&self, query: &Query) -> Result<Option<Value>>;
+}
+
+impl TomlEditReadExt for Document {
+    fn read_value_query(&self, query: &Query) -> Result<Option<Value>> {
+        to_value(self).read_query(query).map(|value| value.cloned())
+    }
+}
+
+/// The modifications are done on a `toml::Value` copy of the document, the changes are then
+/// written back into the `toml_edit::Document`. Everything which did not change (including
+/// comments, whitespace, the order of keys and the representation of values) stays as it is.
+/// Elements which are deleted from or inserted into arrays are removed from or inserted into
+/// the document, so the comments of the other elements stay with them.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_edit;
+/// extern crate toml_query;
+///
+/// use toml_query::set::TomlValueSetExt;
+///
+/// let mut doc = r#"
+/// [server] # The server
+/// host = 'localhost' # no TLS
+/// port = 80
+/// "#.parse::<toml_edit::Document>().unwrap();
+///
+/// doc.set("server.port", toml::Value::Integer(8080)).unwrap();
+///
+/// assert_eq!(doc.to_string(), r#"
+/// [server] # The server
+/// host = 'localhost' # no TLS
+/// port = 8080
+/// "#);
+/// ```
+impl TomlValueSetExt for Document {
+    fn set_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        edit(self, |doc| {
+            Ok((doc.set_with_seperator(query, sep, value)?, vec![]))
+        })
+    }
+
+    fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        edit(self, |doc| Ok((doc.set_query(query, value)?, vec![])))
+    }
+
+    fn set_query_strict(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        edit(self, |doc| {
+            Ok((doc.set_query_strict(query, value)?, vec![]))
+        })
+    }
+
+    fn set_query_checked(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        edit(self, |doc| {
+            Ok((doc.set_query_checked(query, value)?, vec![]))
+        })
+    }
+}
+
+/// See the `TomlValueSetExt` implementation for `toml_edit::Document`
+impl TomlValueInsertExt for Document {
+    fn insert_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        self.insert_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    fn insert_query_with_policy(
+        &mut self,
+        query: &Query,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>> {
+        edit(self, |doc| {
+            let changes = resolved_path(doc, query)
+                .map(|path| Change::Insert(path, value.clone()))
+                .into_iter()
+                .collect();
+            Ok((doc.insert_query_with_policy(query, value, policy)?, changes))
+        })
+    }
+}
+
+/// See the `TomlValueSetExt` implementation for `toml_edit::Document`
+impl TomlValueDeleteExt for Document {
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Value>> {
+        self.delete_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn delete_query_with_mode(&mut self, query: &Query, mode: DeleteMode) -> Result<Option<Value>> {
+        edit(self, |doc| {
+            let path = resolved_path(doc, query);
+            let removed = doc.delete_query_with_mode(query, mode)?;
+            let changes = path
+                .filter(|_| removed.is_some())
+                .map(|path| Change::Remove(path, mode))
+                .into_iter()
+                .collect();
+            Ok((removed, changes))
+        })
+    }
+
+    fn delete_all_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, Value)>> {
+        edit(self, |doc| {
+            let removed = doc.delete_all_with_seperator(query, sep)?;
+
+            // The values were removed back to front
+            let mut changes = Vec::with_capacity(removed.len());
+            for (path, _) in removed.iter().rev() {
+                let path = Query::parse_with_seperator(path, sep)?.tokens().to_vec();
+                let path = path.into_iter().map(Token::into_owned).collect();
+                changes.push(Change::Remove(path, DeleteMode::Shift));
+            }
+
+            Ok((removed, changes))
+        })
+    }
+
+    fn delete_recursive_query(
+        &mut self,
+        query: &Query,
+        prune_empty_parents: bool,
+    ) -> Result<Option<Value>> {
+        edit(self, |doc| {
+            let path = resolved_path(doc, query);
+            let removed = doc.delete_recursive_query(query, prune_empty_parents)?;
+            let changes = path
+                .filter(|_| removed.is_some())
+                .map(|path| Change::Remove(path, DeleteMode::Shift))
+                .into_iter()
+                .collect();
+            Ok((removed, changes))
+        })
+    }
+}
+
+/// A value which was removed or inserted by an edit, identified by its path in the document
+///
+/// Removing and inserting values changes the positions of the following elements of an array,
+/// so these changes are applied to the items of the `toml_edit::Document` themselves before the
+/// values are synced. This way the comments and formatting stay with the elements they belong to.
+enum Change {
+    /// The value at the path was removed, array elements are removed with the `DeleteMode`
+    Remove(Vec<Token<'static>>, DeleteMode),
+
+    /// The value was inserted at the path
+    Insert(Vec<Token<'static>>, Value),
+}
+
+/// The path `query` refers to in `doc`, with all `^` tokens resolved
+fn resolved_path(doc: &Value, query: &Query) -> Option<Vec<Token<'static>>> {
+    match resolve_parents(doc, query.tokens()) {
+        Ok(Some(tokens)) => Some(tokens.iter().cloned().map(Token::into_owned).collect()),
+        _ => None,
+    }
+}
+
+/// Run `f` on a `toml::Value` copy of `doc` and write the changes back into `doc`
+///
+/// `f` returns the values it removed or inserted, which are applied to the items of `doc` before
+/// everything else is synced. If `f` fails, `doc` is not touched.
+fn edit<T, F>(doc: &mut Document, f: F) -> Result<T>
+where
+    F: FnOnce(&mut Value) -> Result<(T, Vec<Change>)>,
+{
+    let mut value = to_value(doc);
+    let (result, changes) = f(&mut value)?;
+
+    for change in changes {
+        apply_change(doc.as_item_mut(), change);
+    }
+
+    if let Value::Table(ref table) = value {
+        sync_table(doc.as_table_mut(), table);
+    }
+
+    Ok(result)
+}
+
+/// Remove or insert the item of `change`, if its parent exists in `doc`
+///
+/// Everything which cannot be applied here (like tables created on the way) is left to the sync.
+fn apply_change(doc: &mut Item, change: Change) {
+    let (path, last) = match change {
+        Change::Remove(ref path, _) | Change::Insert(ref path, _) => match path.split_last() {
+            Some((last, path)) => (path, last),
+            None => return,
+        },
+    };
+
+    let parent = path.iter().try_fold(doc, |item, token| match *token {
+        Token::Identifier { ref ident } => item.get_mut(&**ident),
+        Token::Index { idx } => item.get_mut(idx),
+        _ => None,
+    });
+
+    match (parent, last, &change) {
+        (Some(parent), Token::Identifier { ident }, Change::Remove(..)) => {
+            if let Some(table) = parent.as_table_like_mut() {
+                table.remove(ident);
+            }
+        }
+        (Some(&mut Item::ArrayOfTables(ref mut tables)), last, change) => {
+            let mut elements = tables.iter().cloned().collect::<Vec<_>>();
+            change_elements(&mut elements, last, change, |value| match *value {
+                Value::Table(ref table) => Some(value_to_table(table)),
+                _ => None,
+            });
+
+            tables.clear();
+            for table in elements {
+                tables.push(table);
+            }
+        }
+        (Some(&mut Item::Value(toml_edit::Value::Array(ref mut array))), last, change) => {
+            let mut elements = array.iter().cloned().collect::<Vec<_>>();
+            change_elements(&mut elements, last, change, |value| {
+                Some(value_to_edit_value(value).decorated(" ", ""))
+            });
+
+            array.clear();
+            for value in elements {
+                array.push_formatted(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove or insert the element of `change` in `elements`, `convert` creates inserted elements
+fn change_elements<E, C>(elements: &mut Vec<E>, last: &Token, change: &Change, convert: C)
+where
+    C: FnOnce(&Value) -> Option<E>,
+{
+    match (last, change) {
+        (&Token::Index { idx }, &Change::Remove(_, DeleteMode::Shift)) if idx < elements.len() => {
+            elements.remove(idx);
+        }
+        (&Token::Index { idx }, &Change::Remove(_, DeleteMode::Swap)) if idx < elements.len() => {
+            elements.swap_remove(idx);
+        }
+        (&Token::Slice { start, end }, &Change::Remove(..)) => {
+            let start = start.unwrap_or(0);
+            let end = end.unwrap_or(elements.len());
+            if start <= end && end <= elements.len() {
+                elements.drain(start..end);
+            }
+        }
+        (&Token::Index { idx }, Change::Insert(_, value)) if idx <= elements.len() => {
+            if let Some(element) = convert(value) {
+                elements.insert(idx, element);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn to_value(doc: &Document) -> Value {
+    table_to_value(doc.as_table())
+}
+
+fn table_to_value(table: &Table) -> Value {
+    Value::Table(
+        table
+            .iter()
+            .filter_map(|(key, item)| item_to_value(item).map(|value| (key.to_string(), value)))
+            .collect(),
+    )
+}
+
+fn item_to_value(item: &Item) -> Option<Value> {
+    match *item {
+        Item::None => None,
+        Item::Value(ref value) => Some(edit_value_to_value(value)),
+        Item::Table(ref table) => Some(table_to_value(table)),
+        Item::ArrayOfTables(ref tables) => {
+            Some(Value::Array(tables.iter().map(table_to_value).collect()))
+        }
+    }
+}
+
+fn edit_value_to_value(value: &toml_edit::Value) -> Value {
+    match *value {
+        toml_edit::Value::String(ref s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(ref i) => Value::Integer(*i.value()),
+        toml_edit::Value::Float(ref f) => Value::Float(*f.value()),
+        toml_edit::Value::Boolean(ref b) => Value::Boolean(*b.value()),
+        toml_edit::Value::Datetime(ref dt) => Value::Datetime(
+            dt.value()
+                .to_string()
+                .parse()
+                .expect("toml_edit datetimes are valid toml datetimes"),
+        ),
+        toml_edit::Value::Array(ref array) => {
+            Value::Array(array.iter().map(edit_value_to_value).collect())
+        }
+        toml_edit::Value::InlineTable(ref table) => Value::Table(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_string(), edit_value_to_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Convert a value for a position where a table header (`[a]`, `[[a]]`) can be used
+fn value_to_item(value: &Value) -> Item {
+    match *value {
+        Value::Table(ref table) => Item::Table(value_to_table(table)),
+        Value::Array(ref array) if !array.is_empty() && array.iter().all(Value::is_table) => {
+            let mut tables = ArrayOfTables::new();
+            for value in array {
+                if let Value::Table(ref table) = *value {
+                    tables.push(value_to_table(table));
+                }
+            }
+            Item::ArrayOfTables(tables)
+        }
+        ref value => Item::Value(value_to_edit_value(value)),
+    }
+}
+
+fn value_to_table(table: &toml::value::Table) -> Table {
+    let mut result = Table::new();
+    for (key, value) in table {
+        result.insert(key, value_to_item(value));
+    }
+    result.set_implicit(!table.is_empty() && table.values().all(Value::is_table));
+    result
+}
+
+/// Convert a value for a position where only inline values can be used
+fn value_to_edit_value(value: &Value) -> toml_edit::Value {
+    match *value {
+        Value::String(ref s) => toml_edit::Value::from(s.as_str()),
+        Value::Integer(i) => toml_edit::Value::from(i),
+        Value::Float(f) => toml_edit::Value::from(f),
+        Value::Boolean(b) => toml_edit::Value::from(b),
+        Value::Datetime(ref dt) => toml_edit::Value::from(
+            dt.to_string()
+                .parse::<toml_edit::Datetime>()
+                .expect("toml datetimes are valid toml_edit datetimes"),
+        ),
+        Value::Array(ref array) => {
+            toml_edit::Value::Array(array.iter().map(value_to_edit_value).collect::<Array>())
+        }
+        Value::Table(ref table) => toml_edit::Value::InlineTable(
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), value_to_edit_value(value)))
+                .collect::<InlineTable>(),
+        ),
+    }
+}
+
+fn sync_table(target: &mut Table, source: &toml::value::Table) {
+    let removed = target
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !source.contains_key(key))
+        .collect::<Vec<_>>();
+    for key in removed {
+        target.remove(&key);
+    }
+
+    for (key, value) in source {
+        match target.get_mut(key) {
+            Some(item) => sync_item(item, value),
+            None => {
+                target.insert(key, value_to_item(value));
+            }
+        }
+    }
+}
+
+fn sync_item(target: &mut Item, source: &Value) {
+    match (target, source) {
+        (Item::Table(table), Value::Table(source)) => sync_table(table, source),
+        (Item::ArrayOfTables(tables), Value::Array(source))
+            if source.iter().all(Value::is_table) =>
+        {
+            while tables.len() > source.len() {
+                tables.remove(tables.len() - 1);
+            }
+            for (idx, value) in source.iter().enumerate() {
+                if let Value::Table(ref source) = *value {
+                    match tables.get_mut(idx) {
+                        Some(table) => sync_table(table, source),
+                        None => tables.push(value_to_table(source)),
+                    }
+                }
+            }
+        }
+        (Item::Value(value), source) => sync_value(value, source),
+        (target, source) => *target = value_to_item(source),
+    }
+}
+
+fn sync_value(target: &mut toml_edit::Value, source: &Value) {
+    match (target, source) {
+        (toml_edit::Value::InlineTable(table), Value::Table(source)) => {
+            let removed = table
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .filter(|key| !source.contains_key(key))
+                .collect::<Vec<_>>();
+            for key in removed {
+                table.remove(&key);
+            }
+
+            for (key, value) in source {
+                match table.get_mut(key) {
+                    Some(target) => sync_value(target, value),
+                    None => {
+                        table.insert(key, value_to_edit_value(value));
+                    }
+                }
+            }
+        }
+        (toml_edit::Value::Array(array), Value::Array(source)) => {
+            while array.len() > source.len() {
+                array.remove(array.len() - 1);
+            }
+            for (idx, value) in source.iter().enumerate() {
+                match array.get_mut(idx) {
+                    Some(target) => sync_value(target, value),
+                    None => array.push(value_to_edit_value(value)),
+                }
+            }
+        }
+        (target, source) => {
+            if edit_value_to_value(target) != *source {
+                let decor = target.decor().clone();
+                *target = value_to_edit_value(source);
+                *target.decor_mut() = decor;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+
+    const DOC: &str = r#"
+# The server
+[server]
+host = 'localhost' # no TLS
+port = 0x50
+aliases = [ "a", "b" ] # sorted
+
+[[bin]]
+name = "first"
+
+[[bin]]
+name = "second"
+"#;
+
+    fn doc() -> Document {
+        DOC.parse().unwrap()
+    }
+
+    #[test]
+    fn test_read_value() {
+        let doc = doc();
+        assert_eq!(
+            Some(Value::Integer(80)),
+            doc.read_value("server.port").unwrap()
+        );
+        assert_eq!(
+            Some(Value::String(String::from("second"))),
+            doc.read_value("bin.[1].name").unwrap()
+        );
+        assert_eq!(None, doc.read_value("server.missing").unwrap());
+    }
+
+    #[test]
+    fn test_set_preserves_formatting() {
+        let mut doc = doc();
+        let old = doc.set("server.host", Value::String(String::from("example.com")));
+        assert_eq!(Some(Value::String(String::from("localhost"))), old.unwrap());
+
+        let old = doc.set("server.aliases.[1]", Value::String(String::from("c")));
+        assert_eq!(Some(Value::String(String::from("b"))), old.unwrap());
+
+        let expected = DOC
+            .replace("'localhost'", "\"example.com\"")
+            .replace(r#""b" ]"#, r#""c" ]"#);
+        assert_eq!(expected, doc.to_string());
+    }
+
+    #[test]
+    fn test_insert_appends_keys() {
+        let mut doc = doc();
+        assert!(doc
+            .insert("server.tls", Value::Boolean(true))
+            .unwrap()
+            .is_none());
+        assert!(doc
+            .insert("client.retries", Value::Integer(3))
+            .unwrap()
+            .is_none());
+
+        let expected =
+            DOC.replace("# sorted\n", "# sorted\ntls = true\n") + "\n[client]\nretries = 3\n";
+        assert_eq!(expected, doc.to_string());
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut doc = doc();
+        assert_eq!(Some(Value::Integer(80)), doc.delete("server.port").unwrap());
+        assert!(doc.delete_recursive("bin.[0]").unwrap().is_some());
+
+        let expected = DOC
+            .replace("port = 0x50\n", "")
+            .replace("[[bin]]\nname = \"first\"\n\n", "");
+        assert_eq!(expected, doc.to_string());
+    }
+
+    #[test]
+    fn test_array_changes_keep_comments_with_elements() {
+        let doc = r#"
+ports = [
+    # http
+    80,
+    # https
+    443,
+    # alt
+    8080,
+]
+
+# first bin
+[[bin]]
+name = "first" # keep-first
+
+# second bin
+[[bin]]
+name = "second"
+
+# third bin
+[[bin]]
+name = "third"
+"#;
+        let mut doc = doc.parse::<Document>().unwrap();
+
+        assert!(doc.delete("ports.[1]").unwrap().is_some());
+        assert!(doc
+            .insert("ports.[1]", Value::Integer(8443))
+            .unwrap()
+            .is_none());
+        assert!(doc.delete_recursive("bin.[1]").unwrap().is_some());
+
+        let mut table = toml::value::Table::new();
+        table.insert(String::from("name"), Value::String(String::from("new")));
+        assert!(doc
+            .insert("bin.[1]", Value::Table(table))
+            .unwrap()
+            .is_none());
+
+        assert!(doc.delete_recursive("bin.[0]").unwrap().is_some());
+
+        let expected = r#"
+ports = [
+    # http
+    80, 8443,
+    # alt
+    8080,
+]
+
+[[bin]]
+name = "new"
+
+# third bin
+[[bin]]
+name = "third"
+"#;
+        assert_eq!(expected, doc.to_string());
+    }
+
+    #[test]
+    fn test_failed_edit_leaves_document_unchanged() {
+        let mut doc = doc();
+        let res = doc.delete("server");
+        assert!(is_match!(
+            res.unwrap_err(),
+            Error::CannotDeleteNonEmptyTable(_)
+        ));
+        assert_eq!(DOC, doc.to_string());
+    }
+
+}
@@ -0,0 +1,234 @@
+/// The Toml Environment extensions
+use std::env;
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::flatten::sort_key;
+use crate::query::{Query, QueryBuilder};
+use crate::transaction::atomically;
+use crate::upsert::TomlValueUpsertExt;
+
+pub trait TomlValueEnvExt {
+    /// Extension function for overlaying the environment variables starting with `prefix`
+    /// followed by `separator` onto the current toml::Value document
+    ///
+    /// See documentation of `TomlValueEnvExt::overlay_vars`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::env::TomlValueEnvExt;
+    /// use toml_query::read::TomlValueReadExt;
+    ///
+    /// let mut config : toml::Value = toml::from_str("[server]\nport = 80").unwrap();
+    ///
+    /// std::env::set_var("MYAPP__SERVER__PORT", "8080");
+    /// config.overlay_env("MYAPP", "__").unwrap();
+    ///
+    /// assert_eq!(config.read("server.port").unwrap(), Some(&toml::Value::Integer(8080)));
+    /// ```
+    fn overlay_env(&mut self, prefix: &str, separator: &str) -> Result<Vec<Query<'static>>> {
+        // Variables which are not valid unicode cannot be put into the document
+        let vars = env::vars_os().filter_map(|(name, value)| {
+            match (name.into_string(), value.into_string()) {
+                (Ok(name), Ok(value)) => Some((name, value)),
+                _ => None,
+            }
+        });
+        self.overlay_vars(vars, prefix, separator)
+    }
+
+    /// Extension function for overlaying variables (like the environment variables) onto the
+    /// current toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// Only variables whose name starts with `prefix` followed by `separator` are used. The rest
+    /// of the name is split at `separator` into the path of the value: segments which are numbers
+    /// are array indices, other segments are keys, in lowercase. `APP__SERVERS__0__HOST` with
+    /// prefix `APP` and separator `__` sets `servers.[0].host`.
+    ///
+    /// The values are parsed as TOML values (`8080`, `true`, `[ 1, 2 ]`, `"quoted"`), values which
+    /// are not valid TOML are used as strings.
+    ///
+    /// The values are written as with `TomlValueUpsertExt::upsert`: existing values are replaced,
+    /// missing ones are inserted with the tables and arrays leading to them. They are written in
+    /// the order of their paths, array indices in numerical order. If one of them cannot be
+    /// written, the document is left unchanged.
+    ///
+    /// # Return value
+    ///
+    /// The queries of the values which were set, in the order they were set.
+    fn overlay_vars<I>(
+        &mut self,
+        vars: I,
+        prefix: &str,
+        separator: &str,
+    ) -> Result<Vec<Query<'static>>>
+    where
+        I: IntoIterator<Item = (String, String)>;
+}
+
+impl TomlValueEnvExt for Value {
+    fn overlay_vars<I>(
+        &mut self,
+        vars: I,
+        prefix: &str,
+        separator: &str,
+    ) -> Result<Vec<Query<'static>>>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let start = format!("{}{}", prefix, separator);
+        let mut vars = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(&start))
+            .map(|(name, raw)| {
+                let query = var_query(&name, &name[start.len()..], separator)?;
+                Ok((sort_key(&query), name, query, raw))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // "APP__A__10" comes before "APP__A__2" by name, but has to be written after it
+        vars.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+        atomically(self, |tx| {
+            vars.into_iter()
+                .map(|(_, _, query, raw)| {
+                    tx.upsert_query(&query, parse_value(raw))?;
+                    Ok(query)
+                })
+                .collect()
+        })
+    }
+}
+
+fn var_query(name: &str, path: &str, separator: &str) -> Result<Query<'static>> {
+    let mut builder = QueryBuilder::new();
+    for segment in path.split(separator) {
+        if segment.is_empty() {
+            return Err(Error::InvalidEnvVar(name.to_owned()));
+        }
+
+        builder = match segment.parse::<usize>() {
+            Ok(idx) => builder.index(idx),
+            Err(_) => builder.key(segment.to_lowercase()),
+        };
+    }
+    builder.build()
+}
+
+fn parse_value(raw: String) -> Value {
+    toml::from_str::<Value>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut doc| doc.as_table_mut().and_then(|t| t.remove("value")))
+        .unwrap_or(Value::String(raw))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_overlay_vars() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [server]
+        host = "localhost"
+        port = 80
+
+        [[upstream]]
+        host = "a"
+        "#,
+        )
+        .unwrap();
+
+        let queries = toml
+            .overlay_vars(
+                vars(&[
+                    ("APP__SERVER__PORT", "8080"),
+                    ("APP__SERVER__TLS", "true"),
+                    ("APP__UPSTREAM__0__HOST", "b"),
+                    ("APP__LEVELS", "[ 1, 2 ]"),
+                    ("APP__NAME", "\"quoted\""),
+                    ("OTHER__SERVER__HOST", "ignored"),
+                    ("APP_SERVER", "ignored"),
+                ]),
+                "APP",
+                "__",
+            )
+            .unwrap();
+
+        let queries = queries.iter().map(|q| q.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "levels",
+                "name",
+                "server.port",
+                "server.tls",
+                "upstream.[0].host"
+            ],
+            queries
+        );
+
+        let expected: Value = toml_from_str(
+            r#"
+        levels = [ 1, 2 ]
+        name = "quoted"
+
+        [server]
+        host = "localhost"
+        port = 8080
+        tls = true
+
+        [[upstream]]
+        host = "b"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_overlay_vars_is_atomic() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.overlay_vars(vars(&[("X_A", "2"), ("X_B__", "3")]), "X", "_");
+        assert!(is_match!(res.unwrap_err(), Error::InvalidEnvVar(ref name) if name == "X_B__"));
+
+        let res = toml.overlay_vars(vars(&[("X_A", "2"), ("X_A_B", "3")]), "X", "_");
+        assert!(res.is_err());
+
+        assert_eq!(toml_from_str::<Value>("a = 1").unwrap(), toml);
+    }
+
+    #[test]
+    fn test_overlay_vars_indices() {
+        let mut toml: Value = toml_from_str("list = [ 0, 1, 2, 3, 4, 5, 6, 7, 8 ]").unwrap();
+
+        let overlay = vars(&[
+            ("APP__LIST__10", "10"),
+            ("APP__LIST__9", "9"),
+            ("APP__LIST__0", "5"),
+        ]);
+        let queries = toml.overlay_vars(overlay, "APP", "__").unwrap();
+
+        let queries = queries.iter().map(|q| q.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["list.[0]", "list.[9]", "list.[10]"], queries);
+
+        let expected: Value = toml_from_str("list = [ 5, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -1,7 +1,31 @@
 /// Error types
+use std::fmt;
 
 pub type Result<T> = ::std::result::Result<T, Error>;
 
+/// The existing key which is most similar to a missing one, if there is a similar one
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Suggestion(pub Option<String>);
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(ref key) => write!(f, ", did you mean '{}'?", key),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The paths of values which refer to each other, the last one refers to the first one again
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cycle(pub Vec<String>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.join(" -> "))
+    }
+}
+
 #[derive(Debug, Fail)]
 pub enum Error {
     #[cfg(feature = "typed")]
@@ -12,6 +36,31 @@ pub enum Error {
     #[fail(display = "{}", _0)]
     TomlDeserialize(#[cause] ::toml::de::Error),
 
+    #[cfg(feature = "typed")]
+    #[fail(display = "Deserializing '{}' failed: {}", _0, _1)]
+    TomlDeserializeAt(String, #[cause] ::toml::de::Error),
+
+    #[cfg(feature = "regex")]
+    #[fail(display = "{}", _0)]
+    Regex(#[cause] ::regex::Error),
+
+    // Errors for documents in files
+    #[fail(display = "Accessing '{}' failed: {}", _0, _1)]
+    Io(String, #[cause] ::std::io::Error),
+
+    #[fail(display = "The file '{}' is not valid UTF-8", _0)]
+    InvalidEncoding(String),
+
+    #[fail(display = "Parsing the document '{}' failed: {}", _0, _1)]
+    ParseDocument(String, #[cause] ::toml::de::Error),
+
+    #[fail(display = "Serializing the document '{}' failed: {}", _0, _1)]
+    SerializeDocument(String, #[cause] ::toml::ser::Error),
+
+    #[cfg(feature = "watch")]
+    #[fail(display = "Watching '{}' failed: {}", _0, _1)]
+    Watch(String, #[cause] ::notify::Error),
+
     // Errors for tokenizer
     #[fail(display = "Parsing the query '{}' failed", _0)]
     QueryParsingError(String),
@@ -22,6 +71,9 @@ pub enum Error {
     #[fail(display = "The passed query has an empty identifier")]
     EmptyIdentifier,
 
+    #[fail(display = "The seperator of a query must not be empty")]
+    EmptySeperator,
+
     #[fail(display = "The passed query tries to access an array but does not specify the index")]
     ArrayAccessWithoutIndex,
 
@@ -30,9 +82,44 @@ pub enum Error {
     )]
     ArrayAccessWithInvalidIndex,
 
+    #[fail(display = "Invalid query '{}' at offset {}: {}", query, offset, error)]
+    QuerySyntax {
+        query: String,
+        offset: usize,
+        len: usize,
+        error: Box<Error>,
+    },
+
+    #[fail(display = "The segment '{}' cannot be part of a query pattern", _0)]
+    UnsupportedPatternSegment(String),
+
     // Errors for Resolver
-    #[fail(display = "The identfier '{}' is not present in the document", _0)]
-    IdentifierNotFoundInDocument(String),
+    #[fail(display = "The query may match multiple values, which is not supported here")]
+    MultiValueQuery,
+
+    #[fail(display = "The query goes up beyond the root of the document")]
+    ParentOfRoot,
+
+    #[fail(display = "The append token '[+]' can only be used for inserting or setting values")]
+    AppendNotSupported,
+
+    #[fail(display = "Got an append query but have {}", _0)]
+    AppendToNonArray(&'static str),
+
+    #[fail(display = "The query goes deeper than the limit of {} levels", _0)]
+    DepthLimitExceeded(usize),
+
+    #[fail(
+        display = "The identfier '{}' is not present in the document{}",
+        _0, _1
+    )]
+    IdentifierNotFoundInDocument(String, Suggestion),
+
+    #[fail(
+        display = "The identifier '{}' matches several keys when ignoring case: {:?}",
+        _0, _1
+    )]
+    AmbiguousKey(String, Vec<String>),
 
     #[fail(display = "Got an index query '[{}]' but have table", _0)]
     NoIndexInTable(usize),
@@ -46,6 +133,21 @@ pub enum Error {
     #[fail(display = "Got an index query '{}' but have value", _0)]
     QueryingValueAsArray(usize),
 
+    #[fail(
+        display = "Resolving '{}' failed at segment {} after '{}': {}",
+        query, segment, resolved, error
+    )]
+    InQuery {
+        query: String,
+        resolved: String,
+        seperator: String,
+        segment: usize,
+        error: Box<Error>,
+    },
+
+    #[fail(display = "Got a filter query but have {}", _0)]
+    FilterOnNonArray(&'static str),
+
     #[fail(display = "Cannot delete table '{:?}' which is not empty", _0)]
     CannotDeleteNonEmptyTable(Option<String>),
 
@@ -55,12 +157,97 @@ pub enum Error {
     #[fail(display = "Cannot access {} because expected {}", _0, _1)]
     CannotAccessBecauseTypeMismatch(&'static str, &'static str),
 
-    #[fail(display = "Cannot delete in array at {}, array has length {}", _0, _1)]
+    #[fail(display = "Cannot access array at {}, array has length {}", _0, _1)]
     ArrayIndexOutOfBounds(usize, usize),
 
+    #[fail(display = "Only keys of tables can be renamed")]
+    RenameNonIdentifier,
+
+    #[fail(display = "The key '{}' exists already", _0)]
+    KeyExists(String),
+
+    #[fail(display = "The value at '{}' does not match the patch", _0)]
+    PatchConflict(String),
+
+    #[fail(display = "Invalid patch: {}", _0)]
+    InvalidPatch(String),
+
+    #[fail(display = "'{}' is not a valid JSON Pointer", _0)]
+    InvalidJsonPointer(String),
+
+    #[fail(display = "Environment variable '{}' does not name a path", _0)]
+    InvalidEnvVar(String),
+
+    #[fail(display = "There is no layer '{}'", _0)]
+    UnknownLayer(String),
+
+    #[fail(display = "There are no layers")]
+    NoLayers,
+
     #[fail(display = "Type Error. Requested {}, but got {}", _0, _1)]
     TypeError(&'static str, &'static str),
 
+    #[fail(
+        display = "The value is a {}, it cannot be replaced by a {}",
+        expected, found
+    )]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[fail(display = "Value {} is out of range for {}", _0, _1)]
+    OutOfRange(String, &'static str),
+
     #[fail(display = "Value at '{}' not there", _0)]
     NotAvailable(String),
+
+    // Errors for interpolation
+    #[fail(
+        display = "The placeholder '${{{}}}' in '{}' refers to no value",
+        _0, _1
+    )]
+    UnresolvedPlaceholder(String, String),
+
+    #[fail(
+        display = "The placeholder '${{{}}}' in '{}' refers to a {}, which cannot be part of a string",
+        _0, _1, _2
+    )]
+    PlaceholderTypeMismatch(String, String, &'static str),
+
+    #[fail(
+        display = "The string at '{}' has a placeholder without closing '}}'",
+        _0
+    )]
+    UnterminatedPlaceholder(String),
+
+    #[fail(display = "The placeholders refer to each other: {}", _0)]
+    InterpolationCycle(Cycle),
+
+    // Errors for references
+    #[fail(display = "The reference to '{}' at '{}' refers to no value", _0, _1)]
+    UnresolvedRef(String, String),
+
+    #[fail(display = "The references refer to each other: {}", _0)]
+    RefCycle(Cycle),
+}
+
+impl Error {
+    /// The error without the context of `Error::InQuery` or `Error::QuerySyntax`
+    pub fn kind(&self) -> &Error {
+        match *self {
+            Error::InQuery { ref error, .. } | Error::QuerySyntax { ref error, .. } => error,
+            ref error => error,
+        }
+    }
+
+    /// The byte offset and length of the malformed part of the query, for `Error::QuerySyntax`
+    ///
+    /// The column of the part is `query[..offset].chars().count()`.
+    pub fn span(&self) -> Option<(usize, usize)> {
+        match *self {
+            Error::QuerySyntax { offset, len, .. } => Some((offset, len)),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,347 @@
+/// Reading and editing documents in files
+///
+/// Files are read as UTF-8, with or without a byte order mark. Edited documents are written to a
+/// temporary file next to the original one, which then replaces the original, so readers never see
+/// a half-written document. The byte order mark and `\r\n` line endings of the original file are
+/// kept.
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+
+const BOM: &str = "\u{feff}";
+
+/// The number of temporary files created by this process, which makes their names unique
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+pub trait TomlFileExt: Sized {
+    /// Read and parse the document in the file at `path`
+    fn read_document<P: AsRef<Path>>(path: P) -> Result<Self>;
+
+    /// Read the document in the file at `path`, change it with `f` and write it back
+    ///
+    /// Nothing is written if `f` fails or leaves the document as it is. Formatting and comments of
+    /// the file are lost if the document is written.
+    ///
+    /// ```rust,no_run
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::file::TomlFileExt;
+    /// use toml_query::set::TomlValueSetExt;
+    ///
+    /// let old = toml::Value::edit_document("Cargo.toml", |doc| {
+    ///     doc.set("package.version", toml::Value::from("0.2.0"))
+    /// }).unwrap();
+    /// ```
+    fn edit_document<P, F, T>(path: P, f: F) -> Result<T>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut Self) -> Result<T>;
+}
+
+impl TomlFileExt for Value {
+    fn read_document<P: AsRef<Path>>(path: P) -> Result<Value> {
+        let path = path.as_ref();
+        let content = read_content(path)?;
+        parse(path, &content)
+    }
+
+    fn edit_document<P, F, T>(path: P, f: F) -> Result<T>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut Value) -> Result<T>,
+    {
+        let path = path.as_ref();
+        let content = read_content(path)?;
+        let original = parse(path, &content)?;
+
+        let mut doc = original.clone();
+        let result = f(&mut doc)?;
+        if doc == original {
+            return Ok(result);
+        }
+
+        let rendered = render(path, &content, &doc)?;
+        write_atomically(path, rendered.as_bytes())?;
+        Ok(result)
+    }
+}
+
+/// Read and parse the document in the file at `path` without blocking the runtime
+///
+/// The async counterpart of `TomlFileExt::read_document()`.
+#[cfg(feature = "async")]
+pub async fn read_document_async<P: AsRef<Path>>(path: P) -> Result<Value> {
+    let path = path.as_ref();
+    let content = read_content_async(path).await?;
+    parse(path, &content)
+}
+
+/// Read the document in the file at `path`, change it with `f` and write it back without blocking
+/// the runtime
+///
+/// The async counterpart of `TomlFileExt::edit_document()`, `f` itself is not async.
+///
+/// ```rust,no_run
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::file::edit_document_async;
+/// use toml_query::set::TomlValueSetExt;
+///
+/// async fn bump_version() -> toml_query::error::Result<()> {
+///     edit_document_async("Cargo.toml", |doc| {
+///         doc.set("package.version", toml::Value::from("0.2.0")).map(|_| ())
+///     })
+///     .await
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub async fn edit_document_async<P, F, T>(path: P, f: F) -> Result<T>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut Value) -> Result<T>,
+{
+    let path = path.as_ref();
+    let content = read_content_async(path).await?;
+    let original = parse(path, &content)?;
+
+    let mut doc = original.clone();
+    let result = f(&mut doc)?;
+    if doc == original {
+        return Ok(result);
+    }
+
+    let rendered = render(path, &content, &doc)?;
+    write_atomically_async(path, rendered.as_bytes()).await?;
+    Ok(result)
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn io_error(path: &Path, error: io::Error) -> Error {
+    Error::Io(display(path), error)
+}
+
+fn read_content(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).map_err(|e| io_error(path, e))?;
+    String::from_utf8(bytes).map_err(|_| Error::InvalidEncoding(display(path)))
+}
+
+fn parse(path: &Path, content: &str) -> Result<Value> {
+    let content = content.trim_start_matches(BOM);
+    toml::from_str(content).map_err(|e| Error::ParseDocument(display(path), e))
+}
+
+/// Serialize `doc` with the byte order mark and line endings of `content`, the original file
+fn render(path: &Path, content: &str, doc: &Value) -> Result<String> {
+    let mut rendered =
+        toml::to_string(doc).map_err(|e| Error::SerializeDocument(display(path), e))?;
+    if content.contains("\r\n") {
+        rendered = rendered.replace('\n', "\r\n");
+    }
+    if content.starts_with(BOM) {
+        rendered.insert_str(0, BOM);
+    }
+    Ok(rendered)
+}
+
+/// Write `content` to a temporary file in the directory of `path` and rename it to `path`
+fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let (tmp, file) = create_temp(path).map_err(|e| io_error(path, e))?;
+
+    let write = |mut file: fs::File| -> io::Result<()> {
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::set_permissions(&tmp, fs::metadata(path)?.permissions())?;
+        fs::rename(&tmp, path)
+    };
+
+    write(file).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        io_error(path, e)
+    })
+}
+
+#[cfg(feature = "async")]
+async fn read_content_async(path: &Path) -> Result<String> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| io_error(path, e))?;
+    String::from_utf8(bytes).map_err(|_| Error::InvalidEncoding(display(path)))
+}
+
+#[cfg(feature = "async")]
+async fn write_atomically_async(path: &Path, content: &[u8]) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let (tmp, mut file) = loop {
+        let tmp = temp_path(path);
+        let created = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+            .await;
+
+        match created {
+            Ok(file) => break (tmp, file),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(io_error(path, e)),
+        }
+    };
+
+    let write = async {
+        file.write_all(content).await?;
+        file.sync_all().await?;
+        let permissions = tokio::fs::metadata(path).await?.permissions();
+        tokio::fs::set_permissions(&tmp, permissions).await?;
+        tokio::fs::rename(&tmp, path).await
+    };
+
+    match write.await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            Err(io_error(path, e))
+        }
+    }
+}
+
+/// Create a new temporary file in the directory of `path`
+///
+/// Files which already exist are never reused, so concurrent writers of the same document do not
+/// write to the same temporary file.
+fn create_temp(path: &Path) -> io::Result<(PathBuf, fs::File)> {
+    loop {
+        let tmp = temp_path(path);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+        {
+            Ok(file) => return Ok((tmp, file)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// A name for a temporary file next to `path`, which differs on every call
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let n = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.{}.{}.tmp", name, process::id(), n))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::set::TomlValueSetExt;
+
+    fn test_file(name: &str, content: &[u8]) -> PathBuf {
+        let path =
+            ::std::env::temp_dir().join(format!("toml-query-{}-{}.toml", name, process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn temp_files(path: &Path) -> Vec<PathBuf> {
+        let prefix = format!(".{}.", path.file_name().unwrap().to_string_lossy());
+        fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| {
+                p.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with(&prefix)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_document() {
+        let path = test_file("read", b"\xef\xbb\xbf[a]\nb = 1\n");
+        let doc = Value::read_document(&path).unwrap();
+        assert_eq!(Value::Integer(1), doc["a"]["b"]);
+
+        fs::write(&path, b"a = \xff").unwrap();
+        let result = Value::read_document(&path);
+        assert!(is_match!(result, Err(Error::InvalidEncoding(_))));
+
+        fs::write(&path, b"a = ").unwrap();
+        let result = Value::read_document(&path);
+        assert!(is_match!(result, Err(Error::ParseDocument(..))));
+
+        fs::remove_file(&path).unwrap();
+        let result = Value::read_document(&path);
+        assert!(is_match!(result, Err(Error::Io(..))));
+    }
+
+    #[test]
+    fn test_edit_document() {
+        let path = test_file("edit", b"# comment\r\n[a]\r\nb = 1\r\n");
+
+        let old = Value::edit_document(&path, |doc| doc.set("a.b", Value::Integer(2))).unwrap();
+        assert_eq!(Some(Value::Integer(1)), old);
+        assert_eq!("[a]\r\nb = 2\r\n", fs::read_to_string(&path).unwrap());
+
+        let result = Value::edit_document(&path, |doc| doc.set("a.b.c", Value::Integer(3)));
+        assert!(result.is_err());
+        assert_eq!("[a]\r\nb = 2\r\n", fs::read_to_string(&path).unwrap());
+        assert!(temp_files(&path).is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_create_temp_is_unique() {
+        let path = test_file("temp", b"");
+
+        // The name the next temporary file would get
+        let n = TEMP_FILES.load(Ordering::Relaxed);
+        let taken = path.with_file_name(format!(
+            ".toml-query-temp-{}.toml.{}.{}.tmp",
+            process::id(),
+            process::id(),
+            n
+        ));
+        fs::write(&taken, b"taken").unwrap();
+
+        let (first, _) = create_temp(&path).unwrap();
+        let (second, _) = create_temp(&path).unwrap();
+        assert!(first != taken && second != taken && first != second);
+        assert_eq!("taken", fs::read_to_string(&taken).unwrap());
+
+        for tmp in &[taken, first, second] {
+            fs::remove_file(tmp).unwrap();
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_edit_document_async() {
+        let path = test_file("edit-async", b"[a]\nb = 1\n");
+
+        let old = edit_document_async(&path, |doc| doc.set("a.b", Value::Integer(2))).await;
+        assert!(is_match!(old, Ok(Some(Value::Integer(1)))));
+
+        let doc = read_document_async(&path).await.unwrap();
+        assert_eq!(Value::Integer(2), doc["a"]["b"]);
+
+        fs::remove_file(&path).unwrap();
+        let result = read_document_async(&path).await;
+        assert!(is_match!(result, Err(Error::Io(..))));
+    }
+
+}
@@ -0,0 +1,143 @@
+/// The Toml Flatten extensions
+use std::collections::BTreeMap;
+
+use toml::Value;
+
+use crate::error::Result;
+use crate::insert::TomlValueInsertExt;
+use crate::query::Query;
+use crate::tokenizer::Token;
+use crate::walk::TomlValueWalkExt;
+
+pub trait TomlValueFlattenExt {
+    /// Extension function for flattening the current toml::Value document into a map from the
+    /// paths of its scalar values (see `TomlValueWalkExt::leaf_paths`) to the values
+    ///
+    /// Empty tables and arrays have no scalar values, so they are not part of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::flatten::{unflatten, TomlValueFlattenExt};
+    ///
+    /// let toml : toml::Value = toml::from_str("[server]\nhost = 'a'\nports = [ 80 ]").unwrap();
+    ///
+    /// let flat = toml.flatten();
+    /// assert_eq!(flat["server.host"], toml::Value::String(String::from("a")));
+    /// assert_eq!(flat["server.ports.[0]"], toml::Value::Integer(80));
+    ///
+    /// assert_eq!(unflatten(flat).unwrap(), toml);
+    /// ```
+    fn flatten(&self) -> BTreeMap<String, Value>;
+}
+
+impl TomlValueFlattenExt for Value {
+    fn flatten(&self) -> BTreeMap<String, Value> {
+        self.walk()
+            .filter(|(_, value)| !value.is_table() && !value.is_array())
+            .map(|(query, value)| (query.to_string(), value.clone()))
+            .collect()
+    }
+}
+
+/// Build a document from a map of paths to values, as returned by `TomlValueFlattenExt::flatten`
+///
+/// Intermediate tables and arrays are created as necessary. The indices of an array must not
+/// have gaps, `a.[1]` without `a.[0]` is an `ArrayIndexOutOfBounds` error.
+pub fn unflatten(map: BTreeMap<String, Value>) -> Result<Value> {
+    let mut entries = map
+        .into_iter()
+        .map(|(path, value)| {
+            Query::parse(&path).map(|query| (sort_key(&query), query.into_owned(), value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // "a.[10]" comes before "a.[2]" in the map, but has to be inserted after it
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut doc = Value::Table(Default::default());
+    for (_, query, value) in entries {
+        doc.insert_query(&query, value)?;
+    }
+    Ok(doc)
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Segment {
+    Index(usize),
+    Other(String),
+}
+
+/// The key to sort paths by, with array indices in numerical order
+pub(crate) fn sort_key(query: &Query) -> Vec<Segment> {
+    query
+        .tokens()
+        .iter()
+        .map(|token| match *token {
+            Token::Index { idx, .. } => Segment::Index(idx),
+            Token::Identifier { ref ident, .. } => Segment::Other(ident.to_string()),
+            // `[+]`, keeping the order of the map
+            _ => Segment::Other(String::new()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_flatten() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = 1
+        b = []
+
+        [[c]]
+        "d.e" = true
+        "#,
+        )
+        .unwrap();
+
+        let flat = toml.flatten();
+        let keys = flat.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(vec!["a", r#"c.[0]."d.e""#], keys);
+        assert_eq!(Some(&Value::Boolean(true)), flat.get(r#"c.[0]."d.e""#));
+    }
+
+    #[test]
+    fn test_unflatten_roundtrip() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = [ [ 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11 ] ]
+
+        [[b]]
+        "c.d" = "e"
+
+        [[b]]
+        f = { g = 1.5 }
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(toml, unflatten(toml.flatten()).unwrap());
+    }
+
+    #[test]
+    fn test_unflatten_errors() {
+        let mut map = BTreeMap::new();
+        map.insert(String::from("a.[1]"), Value::Integer(1));
+        let err = unflatten(map).unwrap_err();
+        assert!(is_match!(*err.kind(), Error::ArrayIndexOutOfBounds(1, 0)));
+
+        let mut map = BTreeMap::new();
+        map.insert(String::from("a..b"), Value::Integer(1));
+        assert!(unflatten(map).is_err());
+    }
+
+}
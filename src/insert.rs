@@ -5,8 +5,29 @@ use serde::Serialize;
 use toml::Value;
 
 use crate::error::{Error, Result};
-use crate::tokenizer::tokenize_with_seperator;
+use crate::query::Query;
+use crate::resolver::in_query;
 use crate::tokenizer::Token;
+use crate::util::name_of_val;
+
+/// What happens if a value is inserted at an array index beyond the end of the array
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertPolicy {
+    /// Return `Error::ArrayIndexOutOfBounds`
+    Error,
+
+    /// Push the value to the end of the array, ignoring the index
+    Push,
+
+    /// Fill the gap up to the index with clones of the value, then insert at the index
+    PadWith(Value),
+}
+
+impl Default for InsertPolicy {
+    fn default() -> InsertPolicy {
+        InsertPolicy::Error
+    }
+}
 
 pub trait TomlValueInsertExt {
     /// Extension function for inserting a value in the current toml::Value document
@@ -19,21 +40,16 @@ pub trait TomlValueInsertExt {
     /// The function automatically creates intermediate data structures based on the query string.
     /// That means, if the query string is `"a.b.c.[0]"`, but only a table `"a"` exists in the
     /// document, the function automatically creates a table `"b"` inside `"a"` and `"c"` inside
-    /// `"b"`, and an array in `"c"`. The array index is ignored if the array is created.
-    ///
-    /// If an Array exists, but the specified index is larger than the last index, the array will
-    /// be expanded by one element: If the array has a length of 3, but the query string specifies
-    /// that the element should be put at 1000, the function ignores the large index and simply
-    /// appends the value to the index.
+    /// `"b"`, and an array in `"c"`. As the array is empty then, the index has to be `0`.
     ///
     /// If a Value is inserted into an Array, the array indexes are shifted. Semantically this is
-    /// the same as doing a `array.insert(4, _)` (see the standard library).
+    /// the same as doing a `array.insert(4, _)` (see the standard library). If the specified index
+    /// is larger than the length of the array, an error is returned (see
+    /// `TomlValueInsertExt::insert_with_policy` for alternatives).
     ///
-    /// ## Known Bugs
-    ///
-    /// The current implementation does _not_ create intermediate Arrays as described above.
-    /// This is a known bug. So queries like "foo.bar.[0].baz" (or any query which has an array
-    /// element) will fail with an error rather than work.
+    /// The `[+]` token appends to an array without the need to know its length: `"jobs.[+]"`
+    /// pushes the value to the array `"jobs"`. In the middle of a query, it pushes a new Table
+    /// (or Array) to be filled, so `"jobs.[+].name"` appends a table with the key `"name"`.
     ///
     /// # Return value
     ///
@@ -52,7 +68,7 @@ pub trait TomlValueInsertExt {
     ///
     /// let mut toml : toml::Value = toml::from_str("").unwrap();
     /// let query = "foo.bar";
-    /// let sep = '.';
+    /// let sep = ".";
     /// let val = toml::Value::Integer(1);
     ///
     /// let res = toml_query::insert::TomlValueInsertExt::insert_with_seperator(&mut toml, query, sep, val);
@@ -61,26 +77,28 @@ pub trait TomlValueInsertExt {
     /// assert!(res.is_none());
     /// ```
     ///
-    /// The following example shows a failing `insert_with_seperator()` call on an empty toml
-    /// document. The Query does contain an array token, which does not yet work.
+    /// The following example shows an `insert_with_seperator()` call on an empty toml document,
+    /// which creates an array. The Value is inserted as `"foo = [1]"` in the document.
     ///
-    /// ```rust,should_panic
+    /// ```rust
     /// extern crate toml;
     /// extern crate toml_query;
     ///
     /// let mut toml : toml::Value = toml::from_str("").unwrap();
     /// let query = "foo.[0]";
-    /// let sep = '.';
+    /// let sep = ".";
     /// let val = toml::Value::Integer(1);
     ///
     /// let res = toml_query::insert::TomlValueInsertExt::insert_with_seperator(&mut toml, query, sep, val);
-    /// assert!(res.is_ok()); // panics
+    /// assert!(res.is_ok());
+    /// let res = res.unwrap();
+    /// assert!(res.is_none());
     /// ```
     ///
     fn insert_with_seperator(
         &mut self,
         query: &str,
-        sep: char,
+        sep: &str,
         value: Value,
     ) -> Result<Option<Value>>;
 
@@ -88,9 +106,55 @@ pub trait TomlValueInsertExt {
     ///
     /// See documentation of `TomlValueinsertExt::insert_with_seperator`
     fn insert(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
-        self.insert_with_seperator(query, '.', value)
+        self.insert_with_seperator(query, ".", value)
+    }
+
+    /// Extension function for inserting a value in the current toml::Value document using a
+    /// pre-compiled query
+    ///
+    /// See documentation of `TomlValueInsertExt::insert_with_seperator`
+    fn insert_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        self.insert_query_with_policy(query, value, InsertPolicy::Error)
+    }
+
+    /// Extension function for inserting a value in the current toml::Value document, with
+    /// `policy` deciding what happens if the last index of the query is beyond the end of the
+    /// array
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::insert::{InsertPolicy, TomlValueInsertExt};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("a = [ 1 ]").unwrap();
+    /// let pad = InsertPolicy::PadWith(toml::Value::Integer(0));
+    ///
+    /// assert!(toml.insert_with_policy("a.[3]", toml::Value::Integer(4), pad).is_ok());
+    /// assert_eq!(toml, toml::from_str("a = [ 1, 0, 0, 4 ]").unwrap());
+    /// ```
+    ///
+    /// Indices in the middle of the query are not affected by the policy.
+    fn insert_with_policy(
+        &mut self,
+        query: &str,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>> {
+        self.insert_query_with_policy(&Query::parse(query)?, value, policy)
     }
 
+    /// Extension function for inserting a value in the current toml::Value document using a
+    /// pre-compiled query and an `InsertPolicy`
+    ///
+    /// See documentation of `TomlValueInsertExt::insert_with_policy`
+    fn insert_query_with_policy(
+        &mut self,
+        query: &Query,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>>;
+
     /// A convenience method for inserting any arbitrary serializable value.
     #[cfg(feature = "typed")]
     fn insert_serialized<S: Serialize>(&mut self, query: &str, value: S) -> Result<Option<Value>> {
@@ -103,36 +167,85 @@ impl TomlValueInsertExt for Value {
     fn insert_with_seperator(
         &mut self,
         query: &str,
-        sep: char,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        self.insert_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    fn insert_query_with_policy(
+        &mut self,
+        query: &Query,
         value: Value,
+        policy: InsertPolicy,
     ) -> Result<Option<Value>> {
-        use crate::resolver::mut_creating_resolver::resolve;
-
-        let mut tokens = tokenize_with_seperator(query, sep)?;
-        let (val, last) = match tokens.pop_last() {
-            None => (self, Box::new(tokens)),
-            Some(last) => (resolve(self, &tokens)?, last),
-        };
-
-        match *last {
-            Token::Identifier { ident, .. } => match val {
-                &mut Value::Table(ref mut t) => Ok(t.insert(ident, value)),
-                _ => Err(Error::NoIdentifierInArray(ident.clone())),
-            },
-
-            Token::Index { idx, .. } => match val {
-                &mut Value::Array(ref mut a) => {
-                    if a.len() > idx {
-                        a.insert(idx, value);
+        insert_impl(self, query, value, policy).map_err(|e| in_query(self, query, e))
+    }
+}
+
+fn insert_impl(
+    doc: &mut Value,
+    query: &Query,
+    value: Value,
+    policy: InsertPolicy,
+) -> Result<Option<Value>> {
+    use crate::resolver::mut_creating_resolver::resolve;
+    use crate::resolver::non_mut_resolver::resolve as resolve_existing;
+    use crate::resolver::parent_resolver::resolve_parents;
+
+    let mut tokens = resolve_parents(doc, query.tokens())?
+        .ok_or(Error::EmptyQueryError)?
+        .into_owned();
+    let last = tokens.pop().unwrap(); // safe because resolve_parents() yields no empty list
+    let created = is_match!(
+        resolve_existing(doc, &tokens, false, query.max_depth()),
+        Ok(None)
+    );
+    let val = resolve(doc, &tokens, query.max_depth())?;
+
+    // The resolver creates a Table, but the last token needs an Array
+    if created && is_match!(last, Token::Index { .. } | Token::Append) {
+        *val = Value::Array(vec![]);
+    }
+
+    match last {
+        Token::Identifier { ident, .. } => match val {
+            &mut Value::Table(ref mut t) => Ok(t.insert(ident.into_owned(), value)),
+            _ => Err(Error::NoIdentifierInArray(ident.into_owned())),
+        },
+
+        Token::Index { idx, .. } => match val {
+            &mut Value::Array(ref mut a) => {
+                if a.len() >= idx {
+                    a.insert(idx, value);
+                    return Ok(None);
+                }
+
+                match policy {
+                    InsertPolicy::Error => Err(Error::ArrayIndexOutOfBounds(idx, a.len())),
+                    InsertPolicy::Push => {
+                        a.push(value);
                         Ok(None)
-                    } else {
+                    }
+                    InsertPolicy::PadWith(pad) => {
+                        a.resize(idx, pad);
                         a.push(value);
                         Ok(None)
                     }
                 }
-                _ => Err(Error::NoIndexInTable(idx)),
-            },
-        }
+            }
+            _ => Err(Error::NoIndexInTable(idx)),
+        },
+
+        Token::Append { .. } => match val {
+            &mut Value::Array(ref mut a) => {
+                a.push(value);
+                Ok(None)
+            }
+            _ => Err(Error::AppendToNonArray(name_of_val(val))),
+        },
+
+        _ => Err(Error::MultiValueQuery),
     }
 }
 
@@ -182,7 +295,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("table.a"), '.', Value::Integer(1));
+        let res = toml.insert_with_seperator(&String::from("table.a"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -227,7 +340,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("array.[0]"), '.', Value::Integer(1));
+        let res = toml.insert_with_seperator(&String::from("array.[0]"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -265,7 +378,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("a.b.c.d"), '.', Value::Integer(1));
+        let res = toml.insert_with_seperator(&String::from("a.b.c.d"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -331,12 +444,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("table.a"), '.', Value::Integer(1));
+        let res = toml.insert_with_seperator(&String::from("table.a"), ".", Value::Integer(1));
 
         assert!(res.is_err());
 
         let err = res.unwrap_err();
-        assert!(is_match!(err, Error::NoIdentifierInArray(_)));
+        assert!(is_match!(*err.kind(), Error::NoIdentifierInArray(_)));
     }
 
     #[test]
@@ -348,12 +461,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("table.[0]"), '.', Value::Integer(1));
+        let res = toml.insert_with_seperator(&String::from("table.[0]"), ".", Value::Integer(1));
 
         assert!(res.is_err());
 
         let err = res.unwrap_err();
-        assert!(is_match!(err, Error::NoIndexInTable(_)));
+        assert!(is_match!(*err.kind(), Error::NoIndexInTable(_)));
     }
 
     #[test]
@@ -367,7 +480,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("array.[2]"), '.', Value::Integer(6));
+        let res = toml.insert_with_seperator(&String::from("array.[2]"), ".", Value::Integer(6));
 
         assert!(res.is_ok());
 
@@ -409,7 +522,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.insert_with_seperator(&String::from("table.a"), '.', Value::Integer(1));
+        let res = toml.insert_with_seperator(&String::from("table.a"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -443,4 +556,191 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_insert_with_seperator_append() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        jobs = [ 1, 2 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.insert_with_seperator(&String::from("jobs.[+]"), ".", Value::Integer(3));
+
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str("jobs = [ 1, 2, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_insert_query() {
+        let mut toml: Value = toml_from_str("").unwrap();
+        let query = Query::parse("jobs.[+]").unwrap();
+
+        for i in 0..3 {
+            let res = toml.insert_query(&query, Value::Integer(i));
+            assert!(res.is_ok());
+            assert!(res.unwrap().is_none());
+        }
+
+        let expected: Value = toml_from_str("jobs = [ 0, 1, 2 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_insert_with_seperator_append_table() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[jobs]]
+        name = "a"
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.insert_with_seperator(
+            &String::from("jobs.[+].name"),
+            ".",
+            Value::String(String::from("b")),
+        );
+
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str(
+            r#"
+        [[jobs]]
+        name = "a"
+
+        [[jobs]]
+        name = "b"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_insert_with_seperator_append_to_nonexisting_array() {
+        let mut toml: Value = toml_from_str("").unwrap();
+
+        let res =
+            toml.insert_with_seperator(&String::from("a.jobs.[+].[+]"), ".", Value::Integer(1));
+
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str("a = { jobs = [ [ 1 ] ] }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_insert_with_seperator_append_to_table() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [jobs]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.insert_with_seperator(&String::from("jobs.[+]"), ".", Value::Integer(1));
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+
+        assert!(is_match!(res, Error::AppendToNonArray("Table")));
+    }
+
+    #[test]
+    fn test_insert_with_seperator_index_out_of_bounds() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        array = [ 1 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.insert_with_seperator(&String::from("array.[2]"), ".", Value::Integer(2));
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(2, 1)));
+
+        let res = toml.insert_with_seperator(&String::from("array.[5].a"), ".", Value::Integer(2));
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(5, 1)));
+    }
+
+    #[test]
+    fn test_insert_with_policy_error() {
+        let mut toml: Value = toml_from_str("array = [ 1 ]").unwrap();
+
+        let res = toml.insert_with_policy("array.[2]", Value::Integer(2), InsertPolicy::Error);
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(2, 1)));
+    }
+
+    #[test]
+    fn test_insert_with_policy_push() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2 ]").unwrap();
+
+        let res = toml.insert_with_policy("array.[5]", Value::Integer(3), InsertPolicy::Push);
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let res = toml.insert_with_policy("array.[0]", Value::Integer(0), InsertPolicy::Push);
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str("array = [ 0, 1, 2, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_insert_with_policy_pad_with() {
+        let mut toml: Value = toml_from_str("").unwrap();
+        let pad = InsertPolicy::PadWith(Value::Integer(0));
+
+        let res = toml.insert_with_policy("a.array.[2]", Value::Integer(3), pad);
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str("a = { array = [ 0, 0, 3 ] }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_insert_with_policy_does_not_apply_in_the_middle() {
+        let mut toml: Value = toml_from_str("array = [ ]").unwrap();
+
+        let res = toml.insert_with_policy("array.[3].a", Value::Integer(1), InsertPolicy::Push);
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(3, 0)));
+    }
+
+    #[test]
+    fn test_insert_below_value_is_error() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.insert("a.b.c", Value::Integer(1));
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::QueryingValueAsTable(_)
+        ));
+        let res = toml.insert("a.[0].c", Value::Integer(1));
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::QueryingValueAsArray(0)
+        ));
+
+        assert_eq!(toml, toml_from_str("a = 1").unwrap());
+    }
+
 }
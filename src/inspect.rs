@@ -0,0 +1,223 @@
+/// The Toml Inspect extensions
+use std::fmt;
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::read::TomlValueReadExt;
+use crate::util::name_of_val;
+
+/// The type of a toml::Value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Datetime,
+    Array,
+    Table,
+}
+
+impl ValueType {
+    /// The type of `value`
+    pub fn of(value: &Value) -> ValueType {
+        match *value {
+            Value::String(_) => ValueType::String,
+            Value::Integer(_) => ValueType::Integer,
+            Value::Float(_) => ValueType::Float,
+            Value::Boolean(_) => ValueType::Boolean,
+            Value::Datetime(_) => ValueType::Datetime,
+            Value::Array(_) => ValueType::Array,
+            Value::Table(_) => ValueType::Table,
+        }
+    }
+
+    /// The name of the type, as used in `Error::TypeError`
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::String => "String",
+            ValueType::Integer => "Integer",
+            ValueType::Float => "Float",
+            ValueType::Boolean => "Boolean",
+            ValueType::Datetime => "Datetime",
+            ValueType::Array => "Array",
+            ValueType::Table => "Table",
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The keys of a table, or the number of elements of an array
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Keys {
+    /// The keys of a table, in document order
+    Table(Vec<String>),
+
+    /// The number of elements of an array, its indices are `0..n`
+    Array(usize),
+}
+
+impl Keys {
+    /// The number of keys or indices
+    pub fn len(&self) -> usize {
+        match *self {
+            Keys::Table(ref keys) => keys.len(),
+            Keys::Array(len) => len,
+        }
+    }
+
+    /// Whether there are no keys or indices
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub trait TomlValueInspectExt<'doc>: TomlValueReadExt<'doc> {
+    /// Extension function for listing the keys of the table (or the indices of the array) at a
+    /// path in the current toml::Value document
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(None)` if there is no value at the queried path
+    /// * `Err(Error::TypeError(_, _))` if the value is neither a table nor an array
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::inspect::{Keys, TomlValueInspectExt};
+    ///
+    /// let toml : toml::Value = toml::from_str("[server]\nhost = 'a'\nports = [ 80, 443 ]").unwrap();
+    ///
+    /// let keys = vec![String::from("host"), String::from("ports")];
+    /// assert_eq!(toml.keys("server").unwrap(), Some(Keys::Table(keys)));
+    /// assert_eq!(toml.keys("server.ports").unwrap(), Some(Keys::Array(2)));
+    /// assert!(toml.keys("server.host").is_err());
+    /// ```
+    fn keys(&'doc self, query: &str) -> Result<Option<Keys>> {
+        match self.read(query)? {
+            None => Ok(None),
+            Some(Value::Table(table)) => Ok(Some(Keys::Table(table.keys().cloned().collect()))),
+            Some(Value::Array(array)) => Ok(Some(Keys::Array(array.len()))),
+            Some(value) => Err(Error::TypeError("Table or Array", name_of_val(value))),
+        }
+    }
+
+    /// Extension function for getting the number of values in the table or array at a path in
+    /// the current toml::Value document
+    ///
+    /// See documentation of `TomlValueInspectExt::keys`
+    fn len(&'doc self, query: &str) -> Result<Option<usize>> {
+        self.keys(query).map(|keys| keys.map(|keys| keys.len()))
+    }
+
+    /// Extension function for checking whether there is a value at a path in the current
+    /// toml::Value document
+    ///
+    /// Missing tables or indices on the way to the value are not an error. Type mismatches (like
+    /// querying `a.b` if `a` is an integer) are, as with `TomlValueReadExt::read`.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::inspect::{TomlValueInspectExt, ValueType};
+    ///
+    /// let toml : toml::Value = toml::from_str("[server]\nport = 80").unwrap();
+    ///
+    /// assert!(toml.exists("server.port").unwrap());
+    /// assert!(!toml.exists("client.port").unwrap());
+    /// assert_eq!(toml.type_of("server.port").unwrap(), Some(ValueType::Integer));
+    /// assert_eq!(toml.type_of("server.host").unwrap(), None);
+    /// ```
+    fn exists(&'doc self, query: &str) -> Result<bool> {
+        self.read(query).map(|value| value.is_some())
+    }
+
+    /// Extension function for getting the type of the value at a path in the current toml::Value
+    /// document, `Ok(None)` if there is no value
+    ///
+    /// See documentation of `TomlValueInspectExt::exists`
+    fn type_of(&'doc self, query: &str) -> Result<Option<ValueType>> {
+        self.read(query).map(|value| value.map(ValueType::of))
+    }
+}
+
+impl<'doc, T> TomlValueInspectExt<'doc> for T where T: TomlValueReadExt<'doc> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    fn doc() -> Value {
+        toml_from_str(
+            r#"
+        a = 1
+        b = []
+
+        [c]
+        e = 1
+        d = 2
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_keys() {
+        let toml = doc();
+
+        let keys = vec![String::from("a"), String::from("b"), String::from("c")];
+        assert_eq!(Some(Keys::Table(keys)), toml.keys("c.^").unwrap());
+        assert_eq!(Some(Keys::Array(0)), toml.keys("b").unwrap());
+        assert_eq!(None, toml.keys("x").unwrap());
+
+        let err = toml.keys("a").unwrap_err();
+        assert!(is_match!(
+            err,
+            Error::TypeError("Table or Array", "Integer")
+        ));
+    }
+
+    #[test]
+    fn test_exists() {
+        let toml = doc();
+
+        assert!(toml.exists("a").unwrap());
+        assert!(toml.exists("c.d.^").unwrap());
+        assert!(!toml.exists("b.[0]").unwrap());
+        assert!(!toml.exists("x.y.z").unwrap());
+        assert!(toml.exists("a.b").is_err());
+    }
+
+    #[test]
+    fn test_type_of() {
+        let toml = doc();
+
+        assert_eq!(Some(ValueType::Integer), toml.type_of("a").unwrap());
+        assert_eq!(Some(ValueType::Array), toml.type_of("b").unwrap());
+        assert_eq!(Some(ValueType::Table), toml.type_of("c").unwrap());
+        assert_eq!(None, toml.type_of("c.x").unwrap());
+        assert_eq!("Table", ValueType::Table.to_string());
+    }
+
+    #[test]
+    fn test_len() {
+        let toml = doc();
+
+        assert_eq!(Some(2), toml.len("c").unwrap());
+        assert_eq!(Some(0), toml.len("b").unwrap());
+        assert_eq!(None, toml.len("c.x").unwrap());
+        assert!(toml.len("c.d").is_err());
+    }
+
+}
@@ -0,0 +1,228 @@
+/// Substituting placeholders in the strings of a document
+use std::collections::HashMap;
+
+use toml::Value;
+
+use crate::error::{Cycle, Error, Result};
+use crate::read::TomlValueReadExt;
+use crate::tokenizer::quote_identifier;
+use crate::util::{child_path, name_of_val, path_contains};
+
+pub trait TomlValueInterpolateExt {
+    /// Extension function for substituting the `${query}` placeholders in the strings of the
+    /// current toml::Value document with the values the queries refer to
+    ///
+    /// # Semantics
+    ///
+    /// A string which consists of a single placeholder is replaced by the value, whatever its
+    /// type. Otherwise the value must be a string, number, boolean or datetime, it is written
+    /// into the string. `$${` is a literal `${`. Queries end at the first `}`.
+    ///
+    /// Placeholders in the values which are referred to are substituted first. Placeholders which
+    /// refer to their own string (directly, over other placeholders or by referring to a table or
+    /// array which contains the string) are an `Error::InterpolationCycle`.
+    ///
+    /// The document is left as it is if substituting a placeholder fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::interpolate::TomlValueInterpolateExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str(r#"
+    /// [server]
+    /// host = "example.com"
+    /// port = 8080
+    /// url = "https://${server.host}:${server.port}/"
+    /// [client]
+    /// port = "${server.port}"
+    /// "#).unwrap();
+    ///
+    /// toml.interpolate().unwrap();
+    ///
+    /// assert_eq!(toml["server"]["url"].as_str(), Some("https://example.com:8080/"));
+    /// assert_eq!(toml["client"]["port"].as_integer(), Some(8080));
+    /// ```
+    fn interpolate(&mut self) -> Result<()>;
+}
+
+impl TomlValueInterpolateExt for Value {
+    fn interpolate(&mut self) -> Result<()> {
+        let interpolated = {
+            let mut interpolator = Interpolator {
+                doc: &*self,
+                stack: Vec::new(),
+                done: HashMap::new(),
+            };
+            interpolator.value(&*self, "")?
+        };
+
+        *self = interpolated;
+        Ok(())
+    }
+}
+
+struct Interpolator<'doc> {
+    doc: &'doc Value,
+
+    /// The paths of the strings which are being interpolated
+    stack: Vec<String>,
+
+    /// The interpolated strings, by their path
+    done: HashMap<String, Value>,
+}
+
+impl<'doc> Interpolator<'doc> {
+    fn value(&mut self, value: &Value, path: &str) -> Result<Value> {
+        match *value {
+            Value::String(ref s) => self.string(s, path),
+            Value::Table(ref table) => table
+                .iter()
+                .map(|(key, value)| {
+                    let path = child_path(path, &quote_identifier(key, "."), ".");
+                    self.value(value, &path).map(|value| (key.clone(), value))
+                })
+                .collect::<Result<_>>()
+                .map(Value::Table),
+            Value::Array(ref array) => array
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| {
+                    self.value(value, &child_path(path, &format!("[{}]", idx), "."))
+                })
+                .collect::<Result<_>>()
+                .map(Value::Array),
+            ref value => Ok(value.clone()),
+        }
+    }
+
+    fn string(&mut self, s: &str, path: &str) -> Result<Value> {
+        if !s.contains("${") {
+            return Ok(Value::String(s.to_owned()));
+        }
+        if let Some(value) = self.done.get(path) {
+            return Ok(value.clone());
+        }
+
+        self.stack.push(path.to_owned());
+        let interpolated = self.substitute(s, path);
+        self.stack.pop();
+
+        let interpolated = interpolated?;
+        self.done.insert(path.to_owned(), interpolated.clone());
+        Ok(interpolated)
+    }
+
+    fn substitute(&mut self, s: &str, path: &str) -> Result<Value> {
+        if s.starts_with("${") && s.find('}') == Some(s.len() - 1) {
+            return self.reference(&s[2..s.len() - 1], path);
+        }
+
+        let mut result = String::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("${") {
+            if rest[..start].ends_with('$') {
+                result.push_str(&rest[..start - 1]);
+                result.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+
+            result.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| Error::UnterminatedPlaceholder(path.to_owned()))?;
+
+            let query = &rest[start + 2..start + end];
+            match self.reference(query, path)? {
+                Value::String(ref s) => result.push_str(s),
+                ref value @ Value::Table(_) | ref value @ Value::Array(_) => {
+                    return Err(Error::PlaceholderTypeMismatch(
+                        query.to_owned(),
+                        path.to_owned(),
+                        name_of_val(value),
+                    ));
+                }
+                ref value => result.push_str(&value.to_string()),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        result.push_str(rest);
+
+        Ok(Value::String(result))
+    }
+
+    /// The interpolated value `query` refers to, from the placeholder in the string at `path`
+    fn reference(&mut self, query: &str, path: &str) -> Result<Value> {
+        let mut values = self.doc.read_all(query)?;
+        if values.len() > 1 {
+            return Err(Error::MultiValueQuery);
+        }
+        let (target, value) = values
+            .pop()
+            .ok_or_else(|| Error::UnresolvedPlaceholder(query.to_owned(), path.to_owned()))?;
+
+        if let Some(start) = self.stack.iter().position(|s| path_contains(&target, s)) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(target);
+            return Err(Error::InterpolationCycle(Cycle(cycle)));
+        }
+
+        self.value(value, &target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_interpolate() {
+        let mut toml: Value = toml_from_str(
+            r#"
+            name = "x"
+            ports = [ 80, "${port}" ]
+            port = 8080
+            greeting = "${message}!"
+            message = "hello ${name}, $${name} is ${ports.[1]}"
+            "#,
+        )
+        .unwrap();
+
+        toml.interpolate().unwrap();
+        assert_eq!(Value::Integer(8080), toml["ports"][1]);
+        assert_eq!(
+            Value::String(String::from("hello x, ${name} is 8080!")),
+            toml["greeting"]
+        );
+    }
+
+    #[test]
+    fn test_interpolate_errors() {
+        let interpolate = |doc: &str| {
+            let mut toml: Value = toml_from_str(doc).unwrap();
+            toml.interpolate().unwrap_err()
+        };
+
+        match interpolate("a = '${b}'\nb = '${c}'\nc = 'x ${a}'") {
+            Error::InterpolationCycle(cycle) => assert_eq!("a -> b -> c -> a", cycle.to_string()),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+        match interpolate("[a]\nb = '${a}'") {
+            Error::InterpolationCycle(cycle) => assert_eq!("a.b -> a", cycle.to_string()),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+
+        let error = interpolate("a = '${x}'");
+        assert!(is_match!(error, Error::UnresolvedPlaceholder(..)));
+        let error = interpolate("a = 'x ${b}'\nb = [ 1 ]");
+        assert!(is_match!(error, Error::PlaceholderTypeMismatch(..)));
+        let error = interpolate("a = 'x ${b'");
+        assert!(is_match!(error, Error::UnterminatedPlaceholder(_)));
+    }
+
+}
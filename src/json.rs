@@ -0,0 +1,288 @@
+/// The query extensions for serde_json::Value
+///
+/// JSON objects are tables, so the same query strings work for TOML and JSON documents:
+///
+/// ```rust
+/// extern crate serde_json;
+/// extern crate toml_query;
+///
+/// use toml_query::document::DocumentReadExt;
+///
+/// let json : serde_json::Value = serde_json::from_str(r#"
+///     { "servers": [ { "host": "a" }, { "host": "b" } ] }
+/// "#).unwrap();
+///
+/// let host = json.read("servers.[1].host").unwrap();
+/// assert_eq!(host, Some(&serde_json::Value::from("b")));
+/// ```
+///
+/// Values are set, inserted and deleted with `DocumentSetExt`, `DocumentInsertExt` and
+/// `DocumentDeleteExt`:
+///
+/// ```rust
+/// extern crate serde_json;
+/// extern crate toml_query;
+///
+/// use toml_query::document::{DocumentDeleteExt, DocumentInsertExt};
+///
+/// let mut json : serde_json::Value = serde_json::from_str(r#"{ "servers": [] }"#).unwrap();
+///
+/// json.insert("servers.[0].host", serde_json::Value::from("a")).unwrap();
+/// json.delete("servers.[0].host").unwrap();
+/// assert_eq!(json, serde_json::from_str::<serde_json::Value>(r#"{ "servers": [ {} ] }"#).unwrap());
+/// ```
+use serde_json::{Map, Value};
+
+use crate::document::{
+    DocumentDeleteExt, DocumentInsertExt, DocumentLike, DocumentReadExt, DocumentSetExt,
+};
+
+impl DocumentLike for Value {
+    fn get_key(&self, key: &str) -> Option<&Value> {
+        self.as_object().and_then(|o| o.get(key))
+    }
+
+    fn get_key_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_object_mut().and_then(|o| o.get_mut(key))
+    }
+
+    fn get_index(&self, idx: usize) -> Option<&Value> {
+        self.as_array().and_then(|a| a.get(idx))
+    }
+
+    fn get_index_mut(&mut self, idx: usize) -> Option<&mut Value> {
+        self.as_array_mut().and_then(|a| a.get_mut(idx))
+    }
+
+    fn is_table(&self) -> bool {
+        self.is_object()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.as_object()
+            .map(|o| o.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    fn array_len(&self) -> Option<usize> {
+        self.as_array().map(Vec::len)
+    }
+
+    fn new_table() -> Value {
+        Value::Object(Map::new())
+    }
+
+    fn new_array() -> Value {
+        Value::Array(vec![])
+    }
+
+    fn insert_key(&mut self, key: &str, value: Value) -> Option<Value> {
+        self.as_object_mut()
+            .and_then(|o| o.insert(key.to_owned(), value))
+    }
+
+    fn remove_key(&mut self, key: &str) -> Option<Value> {
+        self.as_object_mut().and_then(|o| o.remove(key))
+    }
+
+    fn insert_index(&mut self, idx: usize, value: Value) {
+        if let Some(a) = self.as_array_mut() {
+            a.insert(idx, value)
+        }
+    }
+
+    fn remove_index(&mut self, idx: usize) -> Option<Value> {
+        self.as_array_mut()
+            .filter(|a| idx < a.len())
+            .map(|a| a.remove(idx))
+    }
+
+    fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Null => "Null",
+            Value::Bool(_) => "Boolean",
+            Value::Number(ref n) if n.is_f64() => "Float",
+            Value::Number(_) => "Integer",
+            Value::String(_) => "String",
+            Value::Array(_) => "Array",
+            Value::Object(_) => "Table",
+        }
+    }
+
+    fn to_scalar(&self) -> Option<toml::Value> {
+        match *self {
+            Value::Bool(b) => Some(toml::Value::Boolean(b)),
+            Value::Number(ref n) => n
+                .as_i64()
+                .map(toml::Value::Integer)
+                .or_else(|| n.as_f64().map(toml::Value::Float)),
+            Value::String(ref s) => Some(toml::Value::String(s.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl DocumentReadExt for Value {}
+
+impl DocumentSetExt for Value {}
+
+impl DocumentInsertExt for Value {}
+
+impl DocumentDeleteExt for Value {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use serde_json::from_str as json_from_str;
+
+    fn doc() -> Value {
+        json_from_str(r#"{ "a": { "b": [ 1, { "c": true } ] }, "d": null }"#).unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let json = doc();
+
+        assert_eq!(Some(&Value::from(1)), json.read("a.b.[0]").unwrap());
+        assert_eq!(Some(&Value::from(true)), json.read("a.b.[1].c").unwrap());
+        assert_eq!(Some(&Value::Null), json.read("d").unwrap());
+        assert_eq!(None, json.read("a.e").unwrap());
+        assert_eq!(Some(&json["a"]), json.read("a.b.^").unwrap());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let json = doc();
+
+        let res = json.read("a.b.c");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::NoIdentifierInArray(_)
+        ));
+
+        let res = json.read("a.[0]");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::NoIndexInTable(0)
+        ));
+
+        let res = json.read("d.e");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::QueryingValueAsTable(_)
+        ));
+
+        let res = json.read("a.*");
+        assert!(is_match!(res.unwrap_err(), Error::MultiValueQuery));
+    }
+
+    #[test]
+    fn test_read_mut() {
+        let mut json = doc();
+
+        *json.read_mut("a.b.[1].c").unwrap().unwrap() = Value::from("set");
+        assert_eq!(Value::from("set"), json["a"]["b"][1]["c"]);
+    }
+
+    #[test]
+    fn test_read_all() {
+        let json: Value = json_from_str(
+            r#"{ "bins": [ { "n": 1, "x": "a" }, { "n": 2, "x": "b" }, { "n": 1, "x": "c" } ] }"#,
+        )
+        .unwrap();
+
+        let res = json.read_all("bins.[?(@.n == 1)].x").unwrap();
+        assert_eq!(
+            vec![
+                (String::from("bins.[0].x"), &Value::from("a")),
+                (String::from("bins.[2].x"), &Value::from("c")),
+            ],
+            res
+        );
+
+        let res = json.read_all("**.x").unwrap();
+        assert_eq!(3, res.len());
+
+        let res = json.read_all("bins.[0].x.y");
+        assert!(is_match!(res, Err(Error::QueryingValueAsTable(_))));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut json = doc();
+
+        assert_eq!(
+            Some(Value::from(1)),
+            json.set("a.b.[0]", Value::from(2)).unwrap()
+        );
+        assert_eq!(None, json.set("a.b.[2]", Value::from(3)).unwrap());
+        assert_eq!(None, json.set("a.e", Value::from("e")).unwrap());
+
+        let expected: Value =
+            json_from_str(r#"{ "a": { "b": [ 2, { "c": true }, 3 ], "e": "e" }, "d": null }"#)
+                .unwrap();
+        assert_eq!(expected, json);
+
+        let res = json.set("a.x.y", Value::from(1));
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::IdentifierNotFoundInDocument(_, _)
+        ));
+
+        let res = json.set("d.[+]", Value::from(1));
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::AppendToNonArray("Null")
+        ));
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut json = doc();
+
+        assert_eq!(None, json.insert("a.b.[0]", Value::from(0)).unwrap());
+        assert_eq!(None, json.insert("x.y.[0].z", Value::from(true)).unwrap());
+        assert_eq!(None, json.insert("a.l.[+]", Value::from(1)).unwrap());
+
+        let expected: Value = json_from_str(
+            r#"{
+                "a": { "b": [ 0, 1, { "c": true } ], "l": [ 1 ] },
+                "d": null,
+                "x": { "y": [ { "z": true } ] }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(expected, json);
+
+        let res = json.insert("a.b.[5]", Value::from(5));
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::ArrayIndexOutOfBounds(5, 3)
+        ));
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut json = doc();
+
+        let res = json.delete("a.b");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::CannotDeleteNonEmptyArray(_)
+        ));
+
+        assert_eq!(Some(Value::from(1)), json.delete("a.b.[0]").unwrap());
+        assert_eq!(Some(Value::from(true)), json.delete("a.b.[0].c").unwrap());
+        assert_eq!(
+            Some(json_from_str("{}").unwrap()),
+            json.delete("a.b.[0]").unwrap()
+        );
+        assert_eq!(Some(Value::Null), json.delete("d").unwrap());
+        assert_eq!(None, json.delete("e").unwrap());
+
+        let expected: Value = json_from_str(r#"{ "a": { "b": [] } }"#).unwrap();
+        assert_eq!(expected, json);
+    }
+
+}
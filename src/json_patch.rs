@@ -0,0 +1,445 @@
+/// The Toml JSON Patch extensions
+use toml::Value;
+
+use crate::delete::TomlValueDeleteExt;
+use crate::error::{Error, Result};
+use crate::insert::TomlValueInsertExt;
+use crate::json_pointer::{pointer_query, TomlValueJsonPointerExt};
+use crate::set::TomlValueSetExt;
+use crate::transaction::{atomically, Transaction};
+
+/// An operation of a JSON Patch (RFC 6902)
+///
+/// The paths are JSON Pointers (RFC 6901), like `"/servers/0/host"`. Whether a segment is an
+/// array index or a key depends on the value it is applied to, `-` refers to the position after
+/// the last element of an array.
+///
+/// With the `typed` feature, the operations can be (de)serialized in the format of RFC 6902, for
+/// example from a JSON document.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "typed", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "typed", serde(tag = "op", rename_all = "lowercase"))]
+pub enum PatchOperation {
+    /// Add `value` at `path`, replacing a value in a table or shifting the elements of an array
+    Add { path: String, value: Value },
+
+    /// Remove the value at `path`
+    Remove { path: String },
+
+    /// Replace the value at `path` with `value`
+    Replace { path: String, value: Value },
+
+    /// Remove the value at `from` and add it at `path`
+    Move { from: String, path: String },
+
+    /// Add a copy of the value at `from` at `path`
+    Copy { from: String, path: String },
+
+    /// Check that the value at `path` is equal to `value`
+    Test { path: String, value: Value },
+}
+
+/// Parse a JSON Patch document, given as an array of tables
+///
+/// Fails with `Error::InvalidPatch` if the document is not a valid JSON Patch.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::json_patch::{parse_json_patch, PatchOperation};
+///
+/// let doc : toml::Value = toml::from_str(r#"
+///     [[patch]]
+///     op = "remove"
+///     path = "/a/b"
+/// "#).unwrap();
+///
+/// let patch = parse_json_patch(doc.get("patch").unwrap()).unwrap();
+/// assert_eq!(patch, vec![PatchOperation::Remove { path: String::from("/a/b") }]);
+/// ```
+pub fn parse_json_patch(patch: &Value) -> Result<Vec<PatchOperation>> {
+    match *patch {
+        Value::Array(ref ops) => ops.iter().map(parse_operation).collect(),
+        _ => Err(Error::InvalidPatch(String::from(
+            "a patch must be an array of operations",
+        ))),
+    }
+}
+
+fn parse_operation(op: &Value) -> Result<PatchOperation> {
+    let member = |name: &str| {
+        op.get(name)
+            .ok_or_else(|| Error::InvalidPatch(format!("missing member '{}'", name)))
+    };
+    let string = |name: &str| {
+        member(name)?
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| Error::InvalidPatch(format!("member '{}' is not a string", name)))
+    };
+
+    let path = string("path")?;
+    match string("op")?.as_str() {
+        "add" => Ok(PatchOperation::Add {
+            path,
+            value: member("value")?.clone(),
+        }),
+        "remove" => Ok(PatchOperation::Remove { path }),
+        "replace" => Ok(PatchOperation::Replace {
+            path,
+            value: member("value")?.clone(),
+        }),
+        "move" => Ok(PatchOperation::Move {
+            from: string("from")?,
+            path,
+        }),
+        "copy" => Ok(PatchOperation::Copy {
+            from: string("from")?,
+            path,
+        }),
+        "test" => Ok(PatchOperation::Test {
+            path,
+            value: member("value")?.clone(),
+        }),
+        other => Err(Error::InvalidPatch(format!(
+            "unknown operation '{}'",
+            other
+        ))),
+    }
+}
+
+pub trait TomlValueJsonPatchExt {
+    /// Extension function for applying a JSON Patch (RFC 6902) to the current toml::Value
+    /// document
+    ///
+    /// # Semantics
+    ///
+    /// The operations are applied in order, as described in RFC 6902. Either all operations are
+    /// applied or none: if one of them fails, the document is restored to its state before the
+    /// patch.
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(())` if the patch was applied
+    /// * On failure, `Err(e)` is returned:
+    ///     * If a path is not a valid JSON Pointer: `Error::InvalidJsonPointer`
+    ///     * If the parent of an added value does not exist, or there is no value to remove,
+    ///       replace, move or copy: `Error::NotAvailable`
+    ///     * If a `test` operation fails: `Error::PatchConflict`
+    ///     * Otherwise the error of the failing query
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::json_patch::{PatchOperation, TomlValueJsonPatchExt};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("servers = [ \"a\" ]").unwrap();
+    ///
+    /// let res = toml.apply_json_patch(vec![
+    ///     PatchOperation::Test {
+    ///         path: String::from("/servers/0"),
+    ///         value: toml::Value::String(String::from("a")),
+    ///     },
+    ///     PatchOperation::Add {
+    ///         path: String::from("/servers/-"),
+    ///         value: toml::Value::String(String::from("b")),
+    ///     },
+    /// ]);
+    /// assert!(res.is_ok());
+    /// assert_eq!(toml, toml::from_str("servers = [ \"a\", \"b\" ]").unwrap());
+    /// ```
+    fn apply_json_patch(&mut self, patch: Vec<PatchOperation>) -> Result<()>;
+}
+
+impl TomlValueJsonPatchExt for Value {
+    fn apply_json_patch(&mut self, patch: Vec<PatchOperation>) -> Result<()> {
+        atomically(self, |tx| {
+            patch.into_iter().try_for_each(|op| apply_op(tx, op))
+        })
+    }
+}
+
+fn apply_op(doc: &mut Transaction, op: PatchOperation) -> Result<()> {
+    match op {
+        PatchOperation::Add { path, value } => add(doc, &path, value),
+        PatchOperation::Remove { path } => remove(doc, &path).map(|_| ()),
+        PatchOperation::Replace { path, value } => {
+            if doc.read_jp(&path)?.is_none() {
+                return Err(Error::NotAvailable(path));
+            }
+
+            match pointer_query(doc, &path)? {
+                None => doc.replace_document(value),
+                Some(query) => {
+                    doc.set_query(&query, value)?;
+                }
+            }
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if from == path {
+                return Ok(());
+            }
+
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(Error::InvalidPatch(format!(
+                    "cannot move '{}' into itself",
+                    from
+                )));
+            }
+
+            let value = remove(doc, &from)?;
+            add(doc, &path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = doc
+                .read_jp(&from)?
+                .cloned()
+                .ok_or(Error::NotAvailable(from))?;
+            add(doc, &path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            if doc.read_jp(&path)? != Some(&value) {
+                return Err(Error::PatchConflict(path));
+            }
+
+            Ok(())
+        }
+    }
+}
+
+fn add(doc: &mut Transaction, path: &str, value: Value) -> Result<()> {
+    let parent = &path[..path.rfind('/').unwrap_or(0)];
+    if doc.read_jp(parent)?.is_none() {
+        return Err(Error::NotAvailable(String::from(parent)));
+    }
+
+    match pointer_query(doc, path)? {
+        None => doc.replace_document(value),
+        Some(query) => {
+            doc.insert_query(&query, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Transaction, path: &str) -> Result<Value> {
+    let query = pointer_query(doc, path)?.ok_or(Error::EmptyQueryError)?;
+    doc.delete_recursive_query(&query, false)?
+        .ok_or_else(|| Error::NotAvailable(String::from(path)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    fn patch(doc: &str) -> Vec<PatchOperation> {
+        let doc: Value = toml_from_str(doc).unwrap();
+        parse_json_patch(doc.get("patch").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_apply_json_patch() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        a = [ 1, 2 ]
+
+        [b]
+        c = "c"
+        "d/e" = 1
+        "f~g" = 2
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.apply_json_patch(patch(
+            r#"
+        [[patch]]
+        op = "add"
+        path = "/a/1"
+        value = 3
+
+        [[patch]]
+        op = "add"
+        path = "/a/-"
+        value = 4
+
+        [[patch]]
+        op = "remove"
+        path = "/a/0"
+
+        [[patch]]
+        op = "replace"
+        path = "/b/d~1e"
+        value = 5
+
+        [[patch]]
+        op = "move"
+        from = "/b/f~0g"
+        path = "/b/h"
+
+        [[patch]]
+        op = "copy"
+        from = "/b/c"
+        path = "/i"
+
+        [[patch]]
+        op = "test"
+        path = "/a"
+        value = [ 3, 2, 4 ]
+        "#,
+        ));
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str(
+            r#"
+        a = [ 3, 2, 4 ]
+        i = "c"
+
+        [b]
+        c = "c"
+        "d/e" = 5
+        h = 2
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_numeric_keys_in_tables() {
+        let mut toml: Value = toml_from_str(r#"a = { "0" = 1 }"#).unwrap();
+
+        let res = toml.apply_json_patch(vec![PatchOperation::Replace {
+            path: String::from("/a/0"),
+            value: Value::Integer(2),
+        }]);
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str(r#"a = { "0" = 2 }"#).unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_replace_root() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+        let value: Value = toml_from_str("b = 2").unwrap();
+
+        let res = toml.apply_json_patch(vec![PatchOperation::Replace {
+            path: String::from(""),
+            value: value.clone(),
+        }]);
+        assert!(res.is_ok());
+        assert_eq!(value, toml);
+    }
+
+    #[test]
+    fn test_failing_patch_leaves_document_unchanged() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.apply_json_patch(vec![
+            PatchOperation::Add {
+                path: String::from("/b"),
+                value: Value::Integer(2),
+            },
+            PatchOperation::Test {
+                path: String::from("/a"),
+                value: Value::Integer(2),
+            },
+        ]);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::PatchConflict(_)));
+
+        let expected: Value = toml_from_str("a = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_add_requires_parent() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.apply_json_patch(vec![PatchOperation::Add {
+            path: String::from("/b/c"),
+            value: Value::Integer(2),
+        }]);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::NotAvailable(_)));
+    }
+
+    #[test]
+    fn test_remove_missing_value() {
+        let mut toml: Value = toml_from_str("a = [ 1 ]").unwrap();
+
+        let res = toml.apply_json_patch(vec![PatchOperation::Remove {
+            path: String::from("/b"),
+        }]);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::NotAvailable(_)));
+    }
+
+    #[test]
+    fn test_move_into_itself() {
+        let mut toml: Value = toml_from_str("a = { b = 1 }").unwrap();
+
+        let res = toml.apply_json_patch(vec![PatchOperation::Move {
+            from: String::from("/a"),
+            path: String::from("/a/c"),
+        }]);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::InvalidPatch(_)));
+    }
+
+    #[test]
+    fn test_invalid_pointer() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        for pointer in &["a", "/a~2"] {
+            let res = toml.apply_json_patch(vec![PatchOperation::Remove {
+                path: String::from(*pointer),
+            }]);
+            assert!(res.is_err());
+            assert!(is_match!(res.unwrap_err(), Error::InvalidJsonPointer(_)));
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_patch() {
+        let doc: Value = toml_from_str(
+            r#"
+        [[patch]]
+        op = "add"
+        path = "/a"
+        "#,
+        )
+        .unwrap();
+
+        let res = parse_json_patch(doc.get("patch").unwrap());
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::InvalidPatch(_)));
+    }
+
+    #[cfg(feature = "typed")]
+    #[test]
+    fn test_deserialize_patch() {
+        let doc: Value = toml_from_str(
+            r#"
+        [[patch]]
+        op = "copy"
+        from = "/a"
+        path = "/b"
+        "#,
+        )
+        .unwrap();
+
+        let deserialized: Vec<PatchOperation> =
+            doc.get("patch").unwrap().clone().try_into().unwrap();
+        assert_eq!(patch(&doc.to_string()), deserialized);
+    }
+
+}
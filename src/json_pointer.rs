@@ -0,0 +1,174 @@
+/// The Toml JSON Pointer extensions
+use std::borrow::Cow;
+use std::mem;
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::set::TomlValueSetExt;
+use crate::tokenizer::{tokenize_json_pointer, Token};
+
+/// Extension functions for using JSON Pointers (RFC 6901) as queries
+///
+/// A JSON Pointer like `"/servers/0/host"` consists of segments, each prefixed with `/`. In a
+/// segment, `~1` stands for `/` and `~0` for `~`. The empty pointer refers to the whole document.
+///
+/// Whether a segment like `0` is an array index or a table key is decided by the value it is
+/// applied to, `-` refers to the position after the last element of an array.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::json_pointer::TomlValueJsonPointerExt;
+///
+/// let mut toml : toml::Value = toml::from_str(r#"
+///     servers = [ { host = "a" } ]
+///     ports = { "0" = 80 }
+/// "#).unwrap();
+///
+/// let host = toml.read_jp("/servers/0/host").unwrap();
+/// assert_eq!(host, Some(&toml::Value::String(String::from("a"))));
+///
+/// let old = toml.set_jp("/ports/0", toml::Value::Integer(8080)).unwrap();
+/// assert_eq!(old, Some(toml::Value::Integer(80)));
+/// ```
+pub trait TomlValueJsonPointerExt {
+    /// Extension function for reading the value `pointer` refers to
+    ///
+    /// Behaves like `TomlValueReadExt::read`, the `-` segment refers to no value.
+    fn read_jp(&self, pointer: &str) -> Result<Option<&Value>>;
+
+    /// Extension function for setting the value `pointer` refers to
+    ///
+    /// Behaves like `TomlValueSetExt::set`, the `-` segment appends to an array. Setting the
+    /// empty pointer replaces the whole document.
+    fn set_jp(&mut self, pointer: &str, value: Value) -> Result<Option<Value>>;
+}
+
+impl TomlValueJsonPointerExt for Value {
+    fn read_jp(&self, pointer: &str) -> Result<Option<&Value>> {
+        match pointer_query(self, pointer)? {
+            None => Ok(Some(self)),
+            Some(query) => match self.read_query(&query) {
+                Err(Error::AppendNotSupported) => Ok(None),
+                other => other,
+            },
+        }
+    }
+
+    fn set_jp(&mut self, pointer: &str, value: Value) -> Result<Option<Value>> {
+        match pointer_query(self, pointer)? {
+            None => Ok(Some(mem::replace(self, value))),
+            Some(query) => self.set_query(&query, value),
+        }
+    }
+}
+
+/// Translate a JSON Pointer to a query for `doc`
+///
+/// Index and `[+]` tokens which are applied to a table are turned into identifiers. Returns
+/// `Ok(None)` for the pointer to the whole document.
+pub(crate) fn pointer_query(doc: &Value, pointer: &str) -> Result<Option<Query<'static>>> {
+    let tokens = match tokenize_json_pointer(pointer) {
+        Ok(tokens) => tokens,
+        Err(Error::EmptyQueryError) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut cur = Some(doc);
+    let tokens = tokens
+        .into_iter()
+        .map(|token| {
+            let token = match (token, cur) {
+                (Token::Index { idx, .. }, Some(&Value::Table(_))) => Token::Identifier {
+                    ident: Cow::Owned(idx.to_string()),
+                },
+                (Token::Append, Some(&Value::Table(_))) => Token::Identifier {
+                    ident: Cow::Borrowed("-"),
+                },
+                (token, _) => token,
+            };
+
+            cur = match token {
+                Token::Identifier { ref ident, .. } => cur.and_then(|val| val.get(&**ident)),
+                Token::Index { idx, .. } => cur.and_then(|val| val.get(idx)),
+                _ => None,
+            };
+
+            token
+        })
+        .collect();
+
+    Ok(Some(Query::from_tokens(tokens, ".")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_read_jp() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = [ 1, { b = 2 } ]
+        "c/d" = { "~" = 3, "-" = 4, "0" = 5 }
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(Some(&toml), toml.read_jp("").unwrap());
+        assert_eq!(Some(&Value::Integer(2)), toml.read_jp("/a/1/b").unwrap());
+        assert_eq!(Some(&Value::Integer(3)), toml.read_jp("/c~1d/~0").unwrap());
+        assert_eq!(Some(&Value::Integer(4)), toml.read_jp("/c~1d/-").unwrap());
+        assert_eq!(Some(&Value::Integer(5)), toml.read_jp("/c~1d/0").unwrap());
+        assert_eq!(None, toml.read_jp("/a/-").unwrap());
+        assert_eq!(None, toml.read_jp("/a/2").unwrap());
+        assert_eq!(None, toml.read_jp("/e").unwrap());
+    }
+
+    #[test]
+    fn test_read_jp_identifier_in_array() {
+        let toml: Value = toml_from_str("a = [ 1 ]").unwrap();
+
+        let res = toml.read_jp("/a/01");
+        assert!(res.is_err());
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::NoIdentifierInArray(_)
+        ));
+    }
+
+    #[test]
+    fn test_set_jp() {
+        let mut toml: Value = toml_from_str("a = [ 1 ]\nb = { \"1\" = 2 }").unwrap();
+
+        assert!(is_match!(
+            toml.set_jp("/a/0", Value::Integer(3)),
+            Ok(Some(Value::Integer(1)))
+        ));
+        assert!(is_match!(toml.set_jp("/a/-", Value::Integer(4)), Ok(None)));
+        assert!(is_match!(
+            toml.set_jp("/b/1", Value::Integer(5)),
+            Ok(Some(Value::Integer(2)))
+        ));
+
+        let expected: Value = toml_from_str("a = [ 3, 4 ]\nb = { \"1\" = 5 }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_jp_root() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.set_jp("", Value::Integer(2));
+        assert!(res.is_ok());
+        assert_eq!(Some(toml_from_str("a = 1").unwrap()), res.unwrap());
+        assert_eq!(Value::Integer(2), toml);
+    }
+
+}
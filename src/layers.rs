@@ -0,0 +1,215 @@
+/// Layered documents
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::merge::{MergeStrategy, TomlValueMergeExt};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::upsert::{TomlValueUpsertExt, Upserted};
+
+/// A stack of named documents, like the defaults, system, user and override configuration files
+/// of a program
+///
+/// Layers which are pushed later have a higher priority: reading a value returns it from the
+/// topmost layer which contains it. Values are written to one designated layer, the topmost one
+/// unless another one is chosen with `Layers::write_to`.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::layers::Layers;
+///
+/// let mut config = Layers::new();
+/// config.push("defaults", toml::from_str("[server]\nhost = 'localhost'\nport = 80").unwrap());
+/// config.push("user", toml::from_str("[server]\nport = 8080").unwrap());
+///
+/// assert_eq!(config.read("server.port").unwrap(), Some(&toml::Value::Integer(8080)));
+/// assert_eq!(config.read("server.host").unwrap(), Some(&toml::Value::from("localhost")));
+///
+/// config.set("server.tls", toml::Value::Boolean(true)).unwrap();
+/// assert!(config.layer("user").unwrap().get("server").unwrap().get("tls").is_some());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layers {
+    layers: Vec<(String, Value)>,
+    write: Option<usize>,
+}
+
+impl Layers {
+    /// An empty stack of layers
+    pub fn new() -> Layers {
+        Layers::default()
+    }
+
+    /// Push a document as the topmost layer
+    pub fn push<S: Into<String>>(&mut self, name: S, doc: Value) {
+        self.layers.push((name.into(), doc));
+    }
+
+    /// The names of the layers, from the lowest to the highest priority
+    pub fn names(&self) -> Vec<&str> {
+        self.layers.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// The document of a layer
+    pub fn layer(&self, name: &str) -> Option<&Value> {
+        self.position(name).map(|idx| &self.layers[idx].1)
+    }
+
+    /// The document of a layer, mutably
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.position(name).map(move |idx| &mut self.layers[idx].1)
+    }
+
+    /// Write values to the layer `name` from now on
+    ///
+    /// Returns `Err(Error::UnknownLayer(_))` if there is no layer with this name.
+    pub fn write_to(&mut self, name: &str) -> Result<()> {
+        let idx = self
+            .position(name)
+            .ok_or_else(|| Error::UnknownLayer(name.to_owned()))?;
+        self.write = Some(idx);
+        Ok(())
+    }
+
+    /// Read a value from the topmost layer which contains it
+    ///
+    /// Errors (like type mismatches) in a layer are returned, lower layers are not consulted
+    /// then.
+    pub fn read(&self, query: &str) -> Result<Option<&Value>> {
+        self.read_query(&Query::parse(query)?)
+    }
+
+    /// Read a value from the topmost layer which contains it using a pre-compiled query
+    pub fn read_query(&self, query: &Query) -> Result<Option<&Value>> {
+        self.read_query_with_origin(query)
+            .map(|found| found.map(|(_, value)| value))
+    }
+
+    /// Read a value together with the name of the layer it comes from
+    pub fn read_with_origin(&self, query: &str) -> Result<Option<(&str, &Value)>> {
+        self.read_query_with_origin(&Query::parse(query)?)
+    }
+
+    /// Read a value together with the name of the layer it comes from using a pre-compiled query
+    pub fn read_query_with_origin(&self, query: &Query) -> Result<Option<(&str, &Value)>> {
+        for (name, doc) in self.layers.iter().rev() {
+            if let Some(value) = doc.read_query(query)? {
+                return Ok(Some((name.as_str(), value)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Write a value to the write layer, as with `TomlValueUpsertExt::upsert`
+    ///
+    /// An existing value is replaced and returned, a missing one is inserted together with the
+    /// tables and arrays leading to it. Returns `Err(Error::NoLayers)` if there are no layers.
+    pub fn set(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
+        self.set_query(&Query::parse(query)?, value)
+    }
+
+    /// Write a value to the write layer using a pre-compiled query
+    pub fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        let idx = match self.write {
+            Some(idx) => idx,
+            None => self.layers.len().checked_sub(1).ok_or(Error::NoLayers)?,
+        };
+        match self.layers[idx].1.upsert_query(query, value)? {
+            Upserted::Replaced(old) => Ok(Some(old)),
+            Upserted::Inserted => Ok(None),
+        }
+    }
+
+    /// The document which results from merging all layers, from the lowest to the highest
+    /// priority, with `strategy`
+    pub fn merged(&self, strategy: MergeStrategy) -> Value {
+        let mut result = Value::Table(Default::default());
+        for (_, doc) in &self.layers {
+            result.merge(doc.clone(), strategy);
+        }
+        result
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|(n, _)| n == name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    fn layers() -> Layers {
+        let mut layers = Layers::new();
+        layers.push(
+            "defaults",
+            toml_from_str("a = 1\nb = 1\n[c]\nd = 1").unwrap(),
+        );
+        layers.push("system", toml_from_str("b = 2\n[c]\ne = 2").unwrap());
+        layers.push("user", toml_from_str("[c]\nd = 3").unwrap());
+        layers
+    }
+
+    #[test]
+    fn test_read() {
+        let layers = layers();
+
+        assert_eq!(Some(&Value::Integer(1)), layers.read("a").unwrap());
+        assert_eq!(Some(&Value::Integer(2)), layers.read("b").unwrap());
+        assert_eq!(Some(&Value::Integer(3)), layers.read("c.d").unwrap());
+        assert_eq!(Some(&Value::Integer(2)), layers.read("c.e").unwrap());
+        assert_eq!(None, layers.read("x").unwrap());
+        assert!(layers.read("b.x").is_err());
+
+        let (origin, _) = layers.read_with_origin("c.e").unwrap().unwrap();
+        assert_eq!("system", origin);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut layers = layers();
+
+        assert_eq!(None, layers.set("a", Value::Integer(3)).unwrap());
+        assert_eq!(Some(&Value::Integer(3)), layers.read("a").unwrap());
+        assert!(layers.layer("user").unwrap().get("a").is_some());
+
+        let old = layers.set("a", Value::Integer(4)).unwrap();
+        assert_eq!(Some(Value::Integer(3)), old);
+        assert_eq!(Some(&Value::Integer(4)), layers.read("a").unwrap());
+
+        layers
+            .set("l", Value::Array(vec![Value::Integer(1)]))
+            .unwrap();
+        let old = layers.set("l.[0]", Value::Integer(2)).unwrap();
+        assert_eq!(Some(Value::Integer(1)), old);
+        assert_eq!(
+            Some(&Value::Array(vec![Value::Integer(2)])),
+            layers.read("l").unwrap()
+        );
+
+        layers.write_to("defaults").unwrap();
+        layers.set("b", Value::Integer(3)).unwrap();
+        assert_eq!(Some(&Value::Integer(2)), layers.read("b").unwrap());
+        assert_eq!(
+            Some(&Value::Integer(3)),
+            layers.layer("defaults").unwrap().get("b")
+        );
+
+        let err = layers.write_to("missing").unwrap_err();
+        assert!(is_match!(err, Error::UnknownLayer(_)));
+
+        let err = Layers::new().set("a", Value::Integer(1)).unwrap_err();
+        assert!(is_match!(err, Error::NoLayers));
+    }
+
+    #[test]
+    fn test_merged() {
+        let expected: Value = toml_from_str("a = 1\nb = 2\n[c]\nd = 3\ne = 2").unwrap();
+        assert_eq!(expected, layers().merged(MergeStrategy::default()));
+        assert_eq!(vec!["defaults", "system", "user"], layers().names());
+    }
+
+}
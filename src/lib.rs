@@ -11,13 +11,14 @@
 #[macro_use]
 extern crate is_match;
 #[macro_use]
-extern crate lazy_static;
-#[macro_use]
 extern crate failure_derive;
 extern crate failure;
-extern crate regex;
+extern crate smallvec;
 extern crate toml;
 
+#[cfg(feature = "regex")]
+extern crate regex;
+
 #[cfg(feature = "log")]
 #[macro_use]
 extern crate log;
@@ -25,6 +26,30 @@ extern crate log;
 #[cfg(feature = "typed")]
 extern crate serde;
 
+#[cfg(feature = "typed")]
+extern crate serde_path_to_error;
+
+#[cfg(feature = "edit")]
+extern crate toml_edit;
+
+#[cfg(feature = "json")]
+extern crate serde_json;
+
+#[cfg(feature = "yaml")]
+extern crate serde_yaml;
+
+#[cfg(feature = "diagnostics")]
+extern crate miette;
+
+#[cfg(feature = "async")]
+extern crate tokio;
+
+#[cfg(feature = "watch")]
+extern crate notify;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
 #[cfg(all(test, feature = "typed"))]
 #[macro_use]
 extern crate serde_derive;
@@ -44,15 +69,71 @@ extern crate toml_query_derive;
 #[doc(hidden)]
 pub use toml_query_derive::*;
 
+#[cfg(feature = "macros")]
+pub use toml_query_derive::toml_path;
+
+pub mod arith;
+pub mod array;
+pub mod batch;
+pub mod cache;
+pub mod cas;
+pub mod copy;
+pub mod cursor;
 pub mod delete;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod diff;
+pub mod document;
+#[cfg(feature = "edit")]
+pub mod edit;
+pub mod env;
 pub mod error;
+pub mod file;
+pub mod flatten;
 pub mod insert;
+pub mod inspect;
+pub mod interpolate;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod json_patch;
+pub mod json_pointer;
+pub mod layers;
+pub mod map;
+pub mod merge;
+pub mod mv;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod path;
+pub mod prelude;
+pub mod project;
+pub mod query;
 pub mod read;
+pub mod redact;
+pub mod refs;
+pub mod rename;
+pub mod resolve;
+pub mod retain;
+pub mod scalar;
+pub mod schema;
+pub mod scope;
 pub mod set;
+pub mod sort;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transaction;
+pub mod transform;
+pub mod upsert;
 mod util;
 pub mod value;
+pub mod visitor;
+pub mod walk;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
 // private modules
 
+mod predicate;
 mod resolver;
 mod tokenizer;
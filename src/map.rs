@@ -0,0 +1,370 @@
+/// Reading from and changing a `toml::map::Map`
+///
+/// Many crates keep the top-level table of a document as a `Map`, the extension traits work on it
+/// like on a `Value::Table`. Only a `^` cannot go back up to the map itself, as it is no `Value`
+/// which could be returned.
+///
+/// `Map::insert()` takes precedence over `TomlValueInsertExt::insert()`, which has to be called as
+/// `TomlValueInsertExt::insert(&mut map, query, value)`.
+use std::mem;
+
+use toml::map::Map;
+use toml::Value;
+
+use crate::delete::{DeleteMode, TomlValueDeleteExt};
+use crate::error::{Error, Result};
+use crate::insert::{InsertPolicy, TomlValueInsertExt};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::resolver::check_depth;
+use crate::set::TomlValueSetExt;
+use crate::tokenizer::{quote_identifier, Token};
+
+type Table = Map<String, Value>;
+
+impl<'doc> TomlValueReadExt<'doc> for Table {
+    fn read_with_seperator(&'doc self, query: &str, sep: &str) -> Result<Option<&'doc Value>> {
+        self.read_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn read_mut_with_seperator(
+        &'doc mut self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Option<&'doc mut Value>> {
+        self.read_mut_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn read_query(&'doc self, query: &Query) -> Result<Option<&'doc Value>> {
+        let (key, rest) = split_key(query)?;
+
+        match self.get(key) {
+            Some(value) => value.read_query(&rest).map_err(|e| in_map(query, key, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn read_mut_query(&'doc mut self, query: &Query) -> Result<Option<&'doc mut Value>> {
+        let (key, rest) = split_key(query)?;
+
+        match self.get_mut(key) {
+            Some(value) => value
+                .read_mut_query(&rest)
+                .map_err(|e| in_map(query, key, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn read_all_with_seperator(
+        &'doc self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, &'doc Value)>> {
+        self.read_all_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn read_all_query(&'doc self, query: &Query) -> Result<Vec<(String, &'doc Value)>> {
+        let (tokens, sep) = (query.tokens(), query.seperator());
+        let mut results = Vec::new();
+        resolve_all(self, tokens, sep, true, query.max_depth(), &mut results)?;
+        Ok(results)
+    }
+}
+
+impl TomlValueSetExt for Table {
+    fn set_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.set_with_seperator(query, sep, value))
+    }
+
+    fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.set_query(query, value))
+    }
+
+    fn set_query_strict(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.set_query_strict(query, value))
+    }
+
+    fn set_query_checked(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.set_query_checked(query, value))
+    }
+}
+
+impl TomlValueInsertExt for Table {
+    fn insert_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.insert_with_seperator(query, sep, value))
+    }
+
+    fn insert_query_with_policy(
+        &mut self,
+        query: &Query,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>> {
+        with_table(self, |doc| {
+            doc.insert_query_with_policy(query, value, policy)
+        })
+    }
+}
+
+impl TomlValueDeleteExt for Table {
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.delete_with_seperator(query, sep))
+    }
+
+    fn delete_query_with_mode(&mut self, query: &Query, mode: DeleteMode) -> Result<Option<Value>> {
+        with_table(self, |doc| doc.delete_query_with_mode(query, mode))
+    }
+
+    fn delete_all_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, Value)>> {
+        with_table(self, |doc| doc.delete_all_with_seperator(query, sep))
+    }
+
+    fn delete_recursive_query(
+        &mut self,
+        query: &Query,
+        prune_empty_parents: bool,
+    ) -> Result<Option<Value>> {
+        with_table(self, |doc| {
+            doc.delete_recursive_query(query, prune_empty_parents)
+        })
+    }
+}
+
+/// Run `f` on the map as `Value::Table`, without copying it
+fn with_table<T, F>(map: &mut Table, f: F) -> T
+where
+    F: FnOnce(&mut Value) -> T,
+{
+    let mut doc = Value::Table(mem::replace(map, Map::new()));
+    let result = f(&mut doc);
+
+    match doc {
+        Value::Table(table) => *map = table,
+        _ => unreachable!("No query refers to the document itself"),
+    }
+    result
+}
+
+/// Split a query which matches a single value into its first key and the query for the rest
+fn split_key<'q, 'a>(query: &'a Query<'q>) -> Result<(&'a str, Query<'a>)> {
+    check_depth(query.tokens(), query.max_depth())?;
+
+    match query.tokens().split_first() {
+        Some((Token::Identifier { ident }, rest)) => {
+            let rest = Query::from_tokens(rest.iter().cloned().collect(), query.seperator())
+                .with_max_depth(query.max_depth() - 1);
+            Ok((ident, rest))
+        }
+        Some((Token::Index { idx }, _)) => Err(in_map_root(query, Error::NoIndexInTable(*idx))),
+        Some((Token::Parent, _)) => Err(Error::ParentOfRoot),
+        Some((Token::Append, _)) => Err(Error::AppendNotSupported),
+        _ => Err(Error::MultiValueQuery),
+    }
+}
+
+/// Moves the context of an error about the rest of `query` after `key` to `query` itself
+fn in_map(query: &Query, key: &str, error: Error) -> Error {
+    match error {
+        Error::InQuery {
+            resolved,
+            seperator,
+            segment,
+            error,
+            ..
+        } => {
+            let key = quote_identifier(key, &seperator);
+            let resolved = if resolved.is_empty() {
+                key
+            } else {
+                format!("{}{}{}", key, seperator, resolved)
+            };
+
+            Error::InQuery {
+                query: query.to_string(),
+                resolved,
+                seperator,
+                segment: segment + 1,
+                error,
+            }
+        }
+        Error::DepthLimitExceeded(_) => Error::DepthLimitExceeded(query.max_depth()),
+        error => error,
+    }
+}
+
+/// The context of an error about the first segment of `query`
+fn in_map_root(query: &Query, error: Error) -> Error {
+    Error::InQuery {
+        query: query.to_string(),
+        resolved: String::new(),
+        seperator: String::from(query.seperator()),
+        segment: 0,
+        error: Box::new(error),
+    }
+}
+
+/// Resolve a query which may match more than one value in the map, like `resolve_all()` does for
+/// a `Value`
+fn resolve_all<'doc>(
+    map: &'doc Table,
+    tokens: &[Token],
+    sep: &str,
+    strict: bool,
+    max_depth: usize,
+    results: &mut Vec<(String, &'doc Value)>,
+) -> Result<()> {
+    use crate::resolver::non_mut_multi_resolver::resolve_all_from;
+
+    check_depth(tokens, max_depth)?;
+
+    // The map itself is no value which could be a match
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+
+    if let Token::RecursiveDescent = *token {
+        // `**` matching zero levels
+        resolve_all(map, rest, sep, false, max_depth, results)?;
+    }
+
+    let mut resolve_entry = |key: &str, value: &'doc Value, tokens: &[Token], strict| {
+        let path = quote_identifier(key, sep);
+        resolve_all_from(value, tokens, sep, &path, strict, max_depth - 1, results).map_err(|e| {
+            match e {
+                Error::DepthLimitExceeded(_) => Error::DepthLimitExceeded(max_depth),
+                e => e,
+            }
+        })
+    };
+
+    match *token {
+        Token::Identifier { ref ident } => match map.get(&**ident) {
+            Some(value) => resolve_entry(ident, value, rest, strict),
+            None => Ok(()),
+        },
+
+        Token::Wildcard => map
+            .iter()
+            .try_for_each(|(key, value)| resolve_entry(key, value, rest, false)),
+
+        // `**` matching one or more levels
+        Token::RecursiveDescent => map
+            .iter()
+            .try_for_each(|(key, value)| resolve_entry(key, value, tokens, false)),
+
+        Token::KeyUnion { ref idents } => idents
+            .iter()
+            .filter_map(|ident| map.get(ident).map(|value| (ident, value)))
+            .try_for_each(|(key, value)| resolve_entry(key, value, rest, false)),
+
+        #[cfg(feature = "regex")]
+        Token::KeyRegex { ref regex } => map
+            .iter()
+            .filter(|&(key, _)| regex.is_match(key))
+            .try_for_each(|(key, value)| resolve_entry(key, value, rest, false)),
+
+        Token::Append => Err(Error::AppendNotSupported),
+        Token::Parent if strict => Err(Error::ParentOfRoot),
+        Token::Index { idx } if strict => Err(Error::NoIndexInTable(idx)),
+        Token::Slice { start, .. } if strict => Err(Error::NoIndexInTable(start.unwrap_or(0))),
+        Token::IndexUnion { ref idxs } if strict => Err(Error::NoIndexInTable(idxs[0])),
+        Token::Filter { .. } if strict => Err(Error::FilterOnNonArray("table")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_read() {
+        let mut map: Table = toml_from_str("[a]\nb = [ 1, 2 ]").unwrap();
+
+        assert!(is_match!(map.read("a.b.[1]"), Ok(Some(&Value::Integer(2)))));
+        assert!(is_match!(map.read("a.c"), Ok(None)));
+        assert!(is_match!(map.read("x.c"), Ok(None)));
+
+        *map.read_mut("a.b.[0]").unwrap().unwrap() = Value::Integer(3);
+        assert_eq!(map, toml_from_str("[a]\nb = [ 3, 2 ]").unwrap());
+    }
+
+    #[test]
+    fn test_read_error_context() {
+        let map: Table = toml_from_str("[a]\nb = 1").unwrap();
+
+        match map.read("a.b.c").unwrap_err() {
+            Error::InQuery {
+                query,
+                resolved,
+                segment,
+                error,
+                ..
+            } => {
+                assert_eq!("a.b.c", query);
+                assert_eq!("a.b", resolved);
+                assert_eq!(2, segment);
+                assert!(is_match!(*error, Error::QueryingValueAsTable(_)));
+            }
+            error => panic!("Unexpected error: {:?}", error),
+        }
+
+        let result = map.read("[0]");
+        assert!(is_match!(result, Err(Error::InQuery { segment: 0, .. })));
+        assert!(is_match!(map.read("a.^"), Err(Error::ParentOfRoot)));
+    }
+
+    #[test]
+    fn test_read_all() {
+        let map: Table = toml_from_str("x = 1\n[a]\nx = 2\n[b]\ny = 3").unwrap();
+
+        let paths = |query| {
+            map.read_all(query)
+                .unwrap()
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec!["x", "a.x"], paths("**.x"));
+        assert_eq!(vec!["a.x"], paths("*.x"));
+        assert_eq!(vec!["b.y"], paths("{b,c}.y"));
+        assert_eq!(vec!["a"], paths("a.x.^"));
+        assert!(map.read_all("[0]").is_err());
+    }
+
+    #[test]
+    fn test_set_insert_delete() {
+        let mut map: Table = toml_from_str("[a]\nb = 1").unwrap();
+
+        assert!(is_match!(
+            map.set("a.b", Value::Integer(2)),
+            Ok(Some(Value::Integer(1)))
+        ));
+        assert!(is_match!(
+            TomlValueInsertExt::insert(&mut map, "c.d", Value::Integer(3)),
+            Ok(None)
+        ));
+        assert!(is_match!(map.delete("a.b"), Ok(Some(Value::Integer(2)))));
+        assert!(map.set("a.b.c", Value::Integer(4)).is_err());
+
+        assert_eq!(map, toml_from_str("[a]\n[c]\nd = 3").unwrap());
+    }
+
+}
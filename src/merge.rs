@@ -0,0 +1,381 @@
+/// The Toml Merge extensions
+use toml::Value;
+
+/// How arrays are merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The array of the other document replaces the array of the current one
+    Replace,
+
+    /// The elements of the other array are appended
+    Append,
+
+    /// The elements of the other array which the current array does not contain are appended
+    Union,
+}
+
+impl Default for ArrayMerge {
+    fn default() -> ArrayMerge {
+        ArrayMerge::Replace
+    }
+}
+
+/// How scalar values are merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarMerge {
+    /// The value of the current document is kept
+    Ours,
+
+    /// The value of the other document replaces the value of the current one
+    Theirs,
+}
+
+impl Default for ScalarMerge {
+    fn default() -> ScalarMerge {
+        ScalarMerge::Theirs
+    }
+}
+
+/// How two documents are merged
+///
+/// Tables are always merged key by key. Values which are only in one of the documents are kept.
+/// Values of different types are merged like scalars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeStrategy {
+    /// How arrays are merged
+    pub arrays: ArrayMerge,
+
+    /// How scalar values are merged
+    pub scalars: ScalarMerge,
+}
+
+/// The value which removes a key in `TomlValueMergeExt::merge_patch`, as TOML has no `null`
+pub const MERGE_PATCH_NULL: &str = "__null__";
+
+pub trait TomlValueMergeExt {
+    /// Extension function for merging another toml::Value document into the current one
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::merge::{ArrayMerge, MergeStrategy, TomlValueMergeExt};
+    ///
+    /// let mut config : toml::Value = toml::from_str(r#"
+    ///     [server]
+    ///     host = "localhost"
+    ///     port = 80
+    ///     plugins = [ "log" ]
+    /// "#).unwrap();
+    ///
+    /// let user : toml::Value = toml::from_str(r#"
+    ///     [server]
+    ///     port = 8080
+    ///     plugins = [ "log", "auth" ]
+    /// "#).unwrap();
+    ///
+    /// let strategy = MergeStrategy { arrays: ArrayMerge::Union, ..MergeStrategy::default() };
+    /// config.merge(user, strategy);
+    ///
+    /// assert_eq!(config, toml::from_str(r#"
+    ///     [server]
+    ///     host = "localhost"
+    ///     port = 8080
+    ///     plugins = [ "log", "auth" ]
+    /// "#).unwrap());
+    /// ```
+    fn merge(&mut self, other: Value, strategy: MergeStrategy);
+
+    /// Extension function for applying a JSON Merge Patch (RFC 7386) to the current toml::Value
+    /// document
+    ///
+    /// The string `MERGE_PATCH_NULL` takes the place of `null`, see
+    /// `TomlValueMergeExt::merge_patch_with_null` for using another value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::merge::TomlValueMergeExt;
+    ///
+    /// let mut config : toml::Value = toml::from_str(r#"
+    ///     [server]
+    ///     host = "localhost"
+    ///     port = 80
+    ///     plugins = [ "log" ]
+    /// "#).unwrap();
+    ///
+    /// let patch : toml::Value = toml::from_str(r#"
+    ///     [server]
+    ///     host = "__null__"
+    ///     plugins = [ "auth" ]
+    /// "#).unwrap();
+    ///
+    /// config.merge_patch(patch);
+    ///
+    /// assert_eq!(config, toml::from_str(r#"
+    ///     [server]
+    ///     port = 80
+    ///     plugins = [ "auth" ]
+    /// "#).unwrap());
+    /// ```
+    fn merge_patch(&mut self, patch: Value) {
+        self.merge_patch_with_null(patch, &Value::String(String::from(MERGE_PATCH_NULL)))
+    }
+
+    /// Extension function for applying a JSON Merge Patch (RFC 7386) to the current toml::Value
+    /// document, with `null` taking the place of JSON's `null`
+    ///
+    /// # Semantics
+    ///
+    /// If the patch is a table, it is applied key by key: keys whose value is `null` are removed,
+    /// the others are patched recursively. If the current value is not a table, it is replaced by
+    /// an empty table first. Any other patch (including arrays) replaces the current value.
+    fn merge_patch_with_null(&mut self, patch: Value, null: &Value);
+}
+
+impl TomlValueMergeExt for Value {
+    fn merge(&mut self, other: Value, strategy: MergeStrategy) {
+        match (self, other) {
+            (&mut Value::Table(ref mut ours), Value::Table(theirs)) => {
+                for (key, value) in theirs {
+                    match ours.get_mut(&key) {
+                        Some(our_value) => our_value.merge(value, strategy),
+                        None => {
+                            ours.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (&mut Value::Array(ref mut ours), Value::Array(theirs)) => match strategy.arrays {
+                ArrayMerge::Replace => *ours = theirs,
+                ArrayMerge::Append => ours.extend(theirs),
+                ArrayMerge::Union => {
+                    for value in theirs {
+                        if !ours.contains(&value) {
+                            ours.push(value);
+                        }
+                    }
+                }
+            },
+            (ours, theirs) => {
+                if strategy.scalars == ScalarMerge::Theirs {
+                    *ours = theirs;
+                }
+            }
+        }
+    }
+
+    fn merge_patch_with_null(&mut self, patch: Value, null: &Value) {
+        let patch = match patch {
+            Value::Table(patch) => patch,
+            patch => {
+                *self = patch;
+                return;
+            }
+        };
+
+        if !self.is_table() {
+            *self = Value::Table(Default::default());
+        }
+
+        if let Value::Table(ref mut target) = *self {
+            for (key, value) in patch {
+                if value == *null {
+                    target.remove(&key);
+                } else {
+                    target
+                        .entry(key)
+                        .or_insert_with(|| Value::Table(Default::default()))
+                        .merge_patch_with_null(value, null);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    fn ours() -> Value {
+        toml_from_str(
+            r#"
+        a = 1
+        b = [ 1, 2 ]
+
+        [c]
+        d = "ours"
+        e = { f = 1 }
+        "#,
+        )
+        .unwrap()
+    }
+
+    fn theirs() -> Value {
+        toml_from_str(
+            r#"
+        a = 2
+        b = [ 2, 3 ]
+
+        [c]
+        d = "theirs"
+        e = { g = 2 }
+        h = true
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merge_default() {
+        let mut toml = ours();
+        toml.merge(theirs(), MergeStrategy::default());
+
+        let expected: Value = toml_from_str(
+            r#"
+        a = 2
+        b = [ 2, 3 ]
+
+        [c]
+        d = "theirs"
+        e = { f = 1, g = 2 }
+        h = true
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_merge_ours() {
+        let mut toml = ours();
+        let strategy = MergeStrategy {
+            arrays: ArrayMerge::Append,
+            scalars: ScalarMerge::Ours,
+        };
+        toml.merge(theirs(), strategy);
+
+        let expected: Value = toml_from_str(
+            r#"
+        a = 1
+        b = [ 1, 2, 2, 3 ]
+
+        [c]
+        d = "ours"
+        e = { f = 1, g = 2 }
+        h = true
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_merge_array_union() {
+        let mut toml = ours();
+        let strategy = MergeStrategy {
+            arrays: ArrayMerge::Union,
+            ..MergeStrategy::default()
+        };
+        toml.merge(theirs(), strategy);
+
+        let expected = Value::Array(vec![
+            Value::Integer(1),
+            Value::Integer(2),
+            Value::Integer(3),
+        ]);
+        assert_eq!(Some(&expected), toml.get("b"));
+    }
+
+    #[test]
+    fn test_merge_type_mismatch() {
+        let mut toml: Value = toml_from_str("a = { b = 1 }\nc = [ 1 ]").unwrap();
+        let other: Value = toml_from_str("a = 1\nc = { d = 1 }").unwrap();
+
+        let mut merged = toml.clone();
+        let strategy = MergeStrategy {
+            scalars: ScalarMerge::Ours,
+            ..MergeStrategy::default()
+        };
+        merged.merge(other.clone(), strategy);
+        assert_eq!(toml, merged);
+
+        toml.merge(other.clone(), MergeStrategy::default());
+        assert_eq!(other, toml);
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        // The example of RFC 7386, section 3
+        let mut toml: Value = toml_from_str(
+            r#"
+        title = "Goodbye!"
+        tags = [ "example", "sample" ]
+        content = "This will be unchanged"
+
+        [author]
+        givenName = "John"
+        familyName = "Doe"
+        "#,
+        )
+        .unwrap();
+
+        let patch: Value = toml_from_str(
+            r#"
+        title = "Hello!"
+        phoneNumber = "+01-123-456-7890"
+        tags = [ "example" ]
+
+        [author]
+        familyName = "__null__"
+        "#,
+        )
+        .unwrap();
+
+        toml.merge_patch(patch);
+
+        let expected: Value = toml_from_str(
+            r#"
+        title = "Hello!"
+        tags = [ "example" ]
+        content = "This will be unchanged"
+        phoneNumber = "+01-123-456-7890"
+
+        [author]
+        givenName = "John"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_merge_patch_replaces_non_tables() {
+        let mut toml: Value = toml_from_str("a = [ 1 ]\nb = 1").unwrap();
+        let patch: Value = toml_from_str("a = { c = 2, d = \"__null__\" }\nb = [ 2 ]").unwrap();
+
+        toml.merge_patch(patch);
+
+        let expected: Value = toml_from_str("a = { c = 2 }\nb = [ 2 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_merge_patch_with_null() {
+        let mut toml: Value = toml_from_str("a = 1\nb = \"__null__\"").unwrap();
+        let patch: Value = toml_from_str("a = false\nc = \"__null__\"").unwrap();
+
+        toml.merge_patch_with_null(patch, &Value::Boolean(false));
+
+        let expected: Value = toml_from_str("b = \"__null__\"\nc = \"__null__\"").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
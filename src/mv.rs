@@ -0,0 +1,251 @@
+/// The Toml Move extensions
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::insert::TomlValueInsertExt;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::set::TomlValueSetExt;
+use crate::transaction::atomically;
+
+/// Options for moving (or copying) a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveOptions {
+    /// Create missing tables and arrays on the way to the destination, like
+    /// `TomlValueInsertExt::insert` does. Otherwise they have to exist, like for
+    /// `TomlValueSetExt::set`.
+    pub create_intermediates: bool,
+
+    /// Replace the value at the destination if there is one. Otherwise an existing value at the
+    /// destination is an error.
+    pub overwrite: bool,
+}
+
+impl Default for MoveOptions {
+    fn default() -> MoveOptions {
+        MoveOptions {
+            create_intermediates: true,
+            overwrite: false,
+        }
+    }
+}
+
+pub trait TomlValueMoveExt {
+    /// Extension function for moving a value from one path to another in the current
+    /// toml::Value document, using the default `MoveOptions`
+    ///
+    /// See documentation of `TomlValueMoveExt::mv_with_options`
+    fn mv(&mut self, from: &str, to: &str) -> Result<Option<Value>> {
+        self.mv_with_options(from, to, MoveOptions::default())
+    }
+
+    /// Extension function for moving a value from one path to another in the current
+    /// toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// The value at `from` is removed (including everything it contains, see
+    /// `TomlValueDeleteExt::delete_recursive`) and put at `to`. A value at `to` is only replaced
+    /// if `options.overwrite` is set, use the `[+]` token to append to an array.
+    ///
+    /// The move is atomic: if any part of it fails, the document is left unchanged.
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(None)` if the value was moved
+    /// * `Ok(Some(old_value))` if the value was moved and replaced `old_value`
+    /// * On failure, `Err(e)` is returned:
+    ///     * If there is no value at `from`: `Error::NotAvailable`
+    ///     * If there is a value at `to` and `options.overwrite` is not set: `Error::KeyExists`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::mv::TomlValueMoveExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[old]\nport = 80").unwrap();
+    ///
+    /// assert!(toml.mv("old.port", "new.server.port").is_ok());
+    /// assert_eq!(toml, toml::from_str("[old]\n[new.server]\nport = 80").unwrap());
+    /// ```
+    fn mv_with_options(
+        &mut self,
+        from: &str,
+        to: &str,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        self.mv_query(&Query::parse(from)?, &Query::parse(to)?, options)
+    }
+
+    /// Extension function for moving a value from one path to another in the current
+    /// toml::Value document using pre-compiled queries
+    ///
+    /// See documentation of `TomlValueMoveExt::mv_with_options`
+    fn mv_query(&mut self, from: &Query, to: &Query, options: MoveOptions)
+        -> Result<Option<Value>>;
+}
+
+impl TomlValueMoveExt for Value {
+    fn mv_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        // Inserting may create intermediate tables before it fails
+        atomically(self, |tx| tx.mv_query(from, to, options))
+    }
+}
+
+/// Put `value` at `to`, as described by `options`
+pub(crate) fn put(
+    doc: &mut Value,
+    to: &Query,
+    value: Value,
+    options: MoveOptions,
+) -> Result<Option<Value>> {
+    let exists = match doc.read_query(to) {
+        Ok(found) => found.is_some(),
+        // The appended element never exists
+        Err(Error::AppendNotSupported) => false,
+        Err(e) => return Err(e),
+    };
+
+    if exists {
+        if !options.overwrite {
+            return Err(Error::KeyExists(to.to_string()));
+        }
+
+        doc.set_query(to, value)
+    } else if options.create_intermediates {
+        doc.insert_query(to, value)
+    } else {
+        doc.set_query(to, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_mv() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [a]
+        b = { c = 1 }
+        d = 2
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.mv("a.b", "x.y");
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str(
+            r#"
+        [a]
+        d = 2
+
+        [x.y]
+        c = 1
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_mv_between_arrays() {
+        let mut toml: Value = toml_from_str("a = [ 1, 2 ]\nb = [ 3 ]").unwrap();
+
+        let res = toml.mv("a.[0]", "b.[0]");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::KeyExists(_)));
+
+        let res = toml.mv("a.[0]", "b.[1]");
+        assert!(res.is_ok());
+
+        let res = toml.mv("a.[0]", "b.[+]");
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("a = []\nb = [ 3, 1, 2 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_mv_missing_source_is_error() {
+        let mut toml: Value = toml_from_str("[a]").unwrap();
+
+        let res = toml.mv("a.b", "c");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::NotAvailable(_)));
+    }
+
+    #[test]
+    fn test_mv_existing_destination() {
+        let mut toml: Value = toml_from_str("a = 1\nb = 2").unwrap();
+
+        let res = toml.mv("a", "b");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::KeyExists(_)));
+
+        let expected: Value = toml_from_str("a = 1\nb = 2").unwrap();
+        assert_eq!(expected, toml);
+
+        let options = MoveOptions {
+            overwrite: true,
+            ..MoveOptions::default()
+        };
+        let res = toml.mv_with_options("a", "b", options);
+        assert!(res.is_ok());
+        assert_eq!(Some(Value::Integer(2)), res.unwrap());
+
+        let expected: Value = toml_from_str("b = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_mv_without_creating_intermediates() {
+        let mut toml: Value = toml_from_str("a = 1\n[b]").unwrap();
+        let options = MoveOptions {
+            create_intermediates: false,
+            ..MoveOptions::default()
+        };
+
+        let res = toml.mv_with_options("a", "c.d", options);
+        assert!(res.is_err());
+
+        let expected: Value = toml_from_str("a = 1\n[b]").unwrap();
+        assert_eq!(expected, toml);
+
+        let res = toml.mv_with_options("a", "b.d", options);
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("[b]\nd = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_mv_rolls_back_on_failure() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        // creates the table `x` before failing on the out of bounds index
+        let res = toml.mv("a", "x.y.[3]");
+        assert!(res.is_err());
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::ArrayIndexOutOfBounds(3, 0)
+        ));
+
+        let expected: Value = toml_from_str("a = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
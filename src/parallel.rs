@@ -0,0 +1,66 @@
+/// Querying many documents in parallel
+use rayon::prelude::*;
+use toml::Value;
+
+use crate::error::Result;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+
+/// Run all `queries` on each of `docs`, spreading the documents over the rayon thread pool
+///
+/// The result has one entry per document, in the order of `docs`. An entry holds the values of
+/// the queries, in the order of `queries`, as `TomlValueReadExt::read_query` returns them, or
+/// the error of the first query which failed on that document.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::parallel::query_all_docs;
+/// use toml_query::query::Query;
+///
+/// let docs = vec![
+///     toml::from_str::<toml::Value>("[package]\nname = 'a'\nedition = '2018'").unwrap(),
+///     toml::from_str::<toml::Value>("[package]\nname = 'b'").unwrap(),
+/// ];
+/// let queries = vec![
+///     Query::parse("package.name").unwrap(),
+///     Query::parse("package.edition").unwrap(),
+/// ];
+///
+/// let results = query_all_docs(&docs, &queries);
+/// assert_eq!(results[1].as_ref().unwrap()[1], None);
+/// ```
+pub fn query_all_docs<'doc>(
+    docs: &'doc [Value],
+    queries: &[Query],
+) -> Vec<Result<Vec<Option<&'doc Value>>>> {
+    docs.par_iter()
+        .map(|doc| queries.iter().map(|query| doc.read_query(query)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_query_all_docs() {
+        let docs: Vec<Value> = (0..64)
+            .map(|i| toml_from_str(&format!("a = {}\nb = [ {} ]", i, i)).unwrap())
+            .chain(Some(toml_from_str("a = 'x'\nb = 1").unwrap()))
+            .collect();
+        let queries = vec![Query::parse("a").unwrap(), Query::parse("b.[0]").unwrap()];
+
+        let results = query_all_docs(&docs, &queries);
+
+        assert_eq!(65, results.len());
+        for (i, result) in results[..64].iter().enumerate() {
+            let value = Value::Integer(i as i64);
+            assert_eq!(&vec![Some(&value), Some(&value)], result.as_ref().unwrap());
+        }
+        assert!(results[64].is_err());
+    }
+
+}
@@ -0,0 +1,87 @@
+/// Indexing documents with queries
+use std::ops::{Index, IndexMut};
+
+use toml::Value;
+
+use crate::read::TomlValueReadExt;
+
+/// A query for indexing a document with `doc[path!("a.b.c")]`
+///
+/// Indexing panics if the query is invalid or the value does not exist, which makes it handy for
+/// tests and examples. Use `read()` and `read_mut()` to handle these cases.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate toml_query;
+/// extern crate toml;
+///
+/// # fn main() {
+/// let mut toml : toml::Value = toml::from_str("[server]\nport = 80").unwrap();
+/// assert_eq!(toml[path!("server.port")], toml::Value::Integer(80));
+///
+/// toml[path!("server.port")] = toml::Value::Integer(8080);
+/// assert_eq!(toml["server"]["port"], toml::Value::Integer(8080));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Path<'a>(pub &'a str);
+
+/// Wrap a query in a `Path`, for indexing a document with it
+#[macro_export]
+macro_rules! path {
+    ($query:expr) => {
+        $crate::path::Path($query)
+    };
+}
+
+impl<'a> Index<Path<'a>> for Value {
+    type Output = Value;
+
+    fn index(&self, path: Path<'a>) -> &Value {
+        match self.read(path.0) {
+            Ok(Some(value)) => value,
+            Ok(None) => panic!("No value at '{}'", path.0),
+            Err(e) => panic!("Cannot index with '{}': {}", path.0, e),
+        }
+    }
+}
+
+impl<'a> IndexMut<Path<'a>> for Value {
+    fn index_mut(&mut self, path: Path<'a>) -> &mut Value {
+        match self.read_mut(path.0) {
+            Ok(Some(value)) => value,
+            Ok(None) => panic!("No value at '{}'", path.0),
+            Err(e) => panic!("Cannot index with '{}': {}", path.0, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_index() {
+        let mut toml: Value = toml_from_str("[a]\nb = [ 1, 2 ]").unwrap();
+        assert_eq!(Value::Integer(2), toml[path!("a.b.[1]")]);
+
+        toml[path!("a.b.[0]")] = Value::Integer(3);
+        assert_eq!(Value::Integer(3), toml["a"]["b"][0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No value at 'a.c'")]
+    fn test_index_missing() {
+        let toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let _ = &toml[path!("a.c")];
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot index with 'a..b'")]
+    fn test_index_invalid_query() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let _ = &mut toml[path!("a..b")];
+    }
+
+}
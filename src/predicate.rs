@@ -0,0 +1,284 @@
+/// Filter predicates for array elements, as used by the `[?(...)]` token
+///
+/// The expression language is a small subset of the JSONPath filter expressions:
+///
+/// ```text
+/// expr       := and_expr ( "||" and_expr )*
+/// and_expr   := comparison ( "&&" comparison )*
+/// comparison := "(" expr ")" | path [ operator literal ]
+/// path       := "@" ( "." key )*
+/// operator   := "==" | "!=" | "<" | "<=" | ">" | ">="
+/// literal    := string | integer | float | "true" | "false"
+/// ```
+///
+/// A path without operator tests whether the path exists in the element.
+use std::cmp::Ordering;
+use std::fmt;
+
+use toml::Value;
+use toml_query_tokenizer::predicate::{self, Expr, Literal};
+use toml_query_tokenizer::ErrorKind;
+
+use crate::document::DocumentLike;
+use crate::error::{Error, Result};
+use crate::tokenizer::quote;
+
+pub use toml_query_tokenizer::predicate::Comparison;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// `@.a.b`
+    Exists(Vec<String>),
+
+    /// `@.a.b == "value"`
+    Compare(Vec<String>, Comparison, Value),
+
+    And(Box<Predicate>, Box<Predicate>),
+
+    Or(Box<Predicate>, Box<Predicate>),
+}
+
+impl Predicate {
+    /// Parse a predicate expression (the part between `[?(` and `)]`)
+    pub fn parse(expr: &str) -> Result<Predicate> {
+        trace!("Predicate::parse({:?})", expr);
+        predicate::parse(expr)
+            .map(Predicate::from)
+            .map_err(|kind| match kind {
+                ErrorKind::EmptyIdentifier => Error::EmptyIdentifier,
+                _ => Error::QueryParsingError(String::from(expr)),
+            })
+    }
+
+    /// Check whether the predicate holds for an element
+    pub fn matches<D: DocumentLike>(&self, element: &D) -> bool {
+        match *self {
+            Predicate::Exists(ref path) => lookup(element, path).is_some(),
+            Predicate::Compare(ref path, cmp, ref literal) => lookup(element, path)
+                .map(|value| compare(value.to_scalar().as_ref(), cmp, literal))
+                .unwrap_or(false),
+            Predicate::And(ref a, ref b) => a.matches(element) && b.matches(element),
+            Predicate::Or(ref a, ref b) => a.matches(element) || b.matches(element),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Predicate::Exists(ref path) => write_path(f, path),
+            Predicate::Compare(ref path, cmp, ref literal) => {
+                write_path(f, path)?;
+                write!(f, " {} ", cmp)?;
+                match *literal {
+                    Value::String(ref s) => write!(f, "{}", quote(s)),
+                    Value::Float(x) => write!(f, "{:?}", x),
+                    ref other => write!(f, "{}", other),
+                }
+            }
+            Predicate::And(ref a, ref b) => {
+                write_operand(f, a, |p| is_match!(*p, Predicate::Or(..)))?;
+                write!(f, " && ")?;
+                write_operand(f, b, |p| {
+                    is_match!(*p, Predicate::Or(..) | Predicate::And(..))
+                })
+            }
+            Predicate::Or(ref a, ref b) => {
+                write!(f, "{} || ", a)?;
+                write_operand(f, b, |p| is_match!(*p, Predicate::Or(..)))
+            }
+        }
+    }
+}
+
+fn write_path(f: &mut fmt::Formatter, path: &[String]) -> fmt::Result {
+    write!(f, "@")?;
+    for key in path {
+        if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            write!(f, ".{}", key)?;
+        } else {
+            write!(f, ".{}", quote(key))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write an operand of `&&` or `||`, in parentheses if `needs_parens` holds for it
+fn write_operand<F>(f: &mut fmt::Formatter, operand: &Predicate, needs_parens: F) -> fmt::Result
+where
+    F: Fn(&Predicate) -> bool,
+{
+    if needs_parens(operand) {
+        write!(f, "({})", operand)
+    } else {
+        write!(f, "{}", operand)
+    }
+}
+
+fn lookup<'a, D: DocumentLike>(element: &'a D, path: &[String]) -> Option<&'a D> {
+    path.iter()
+        .try_fold(element, |value, key| value.get_key(key))
+}
+
+/// Compare `value` with `literal`, values which are no scalars are unequal to all literals
+fn compare(value: Option<&Value>, cmp: Comparison, literal: &Value) -> bool {
+    let ordering = match (value, literal) {
+        (Some(Value::Integer(a)), Value::Integer(b)) => a.partial_cmp(b),
+        (Some(Value::Integer(a)), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Some(Value::Float(a)), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Some(Value::Float(a)), Value::Float(b)) => a.partial_cmp(b),
+        (Some(Value::String(a)), Value::String(b)) => a.partial_cmp(b),
+        (Some(Value::Boolean(a)), Value::Boolean(b)) => a.partial_cmp(b),
+        _ => None,
+    };
+
+    match (ordering, cmp) {
+        (None, Comparison::NotEq) => true,
+        (None, _) => false,
+        (Some(o), Comparison::Eq) => o == Ordering::Equal,
+        (Some(o), Comparison::NotEq) => o != Ordering::Equal,
+        (Some(o), Comparison::Less) => o == Ordering::Less,
+        (Some(o), Comparison::LessEq) => o != Ordering::Greater,
+        (Some(o), Comparison::Greater) => o == Ordering::Greater,
+        (Some(o), Comparison::GreaterEq) => o != Ordering::Less,
+    }
+}
+
+/// Convert the expression `toml_query_tokenizer` parsed
+impl From<Expr> for Predicate {
+    fn from(expr: Expr) -> Predicate {
+        match expr {
+            Expr::Exists(path) => Predicate::Exists(path),
+            Expr::Compare(path, cmp, literal) => {
+                let literal = match literal {
+                    Literal::String(s) => Value::String(s),
+                    Literal::Integer(i) => Value::Integer(i),
+                    Literal::Float(x) => Value::Float(x),
+                    Literal::Boolean(b) => Value::Boolean(b),
+                };
+                Predicate::Compare(path, cmp, literal)
+            }
+            Expr::And(a, b) => Predicate::And(Box::new((*a).into()), Box::new((*b).into())),
+            Expr::Or(a, b) => Predicate::Or(Box::new((*a).into()), Box::new((*b).into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| String::from(*k)).collect()
+    }
+
+    #[test]
+    fn test_parse_comparison() {
+        let p = Predicate::parse(r#"@.name == "server""#);
+        assert!(p.is_ok());
+
+        let expected = Predicate::Compare(
+            path(&["name"]),
+            Comparison::Eq,
+            Value::String(String::from("server")),
+        );
+        assert_eq!(expected, p.unwrap());
+    }
+
+    #[test]
+    fn test_parse_exists() {
+        let p = Predicate::parse("@.a.'b.c'");
+        assert!(p.is_ok());
+        assert_eq!(Predicate::Exists(path(&["a", "b.c"])), p.unwrap());
+    }
+
+    #[test]
+    fn test_parse_operators_and_literals() {
+        let p = Predicate::parse("@ <= 1.5 || (@.x>=-3 && @.y != false)");
+        assert!(p.is_ok());
+
+        let expected = Predicate::Or(
+            Box::new(Predicate::Compare(
+                path(&[]),
+                Comparison::LessEq,
+                Value::Float(1.5),
+            )),
+            Box::new(Predicate::And(
+                Box::new(Predicate::Compare(
+                    path(&["x"]),
+                    Comparison::GreaterEq,
+                    Value::Integer(-3),
+                )),
+                Box::new(Predicate::Compare(
+                    path(&["y"]),
+                    Comparison::NotEq,
+                    Value::Boolean(false),
+                )),
+            )),
+        );
+        assert_eq!(expected, p.unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for expr in &[
+            "",
+            "name == 1",
+            "@.name ==",
+            "@.name == 'x",
+            "(@.a",
+            "@.a 1",
+            "@.",
+        ] {
+            assert!(
+                Predicate::parse(expr).is_err(),
+                "{:?} should not parse",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let p =
+            Predicate::parse("(@.a || @.b == 'x\"') && (@.c < -1 || @.'d e' != 2.5 && @)").unwrap();
+        let expected = r#"(@.a || @.b == 'x"') && (@.c < -1 || @."d e" != 2.5 && @)"#;
+
+        assert_eq!(expected, p.to_string());
+        assert_eq!(p, Predicate::parse(expected).unwrap());
+
+        let p = Predicate::parse("@.a || (@.b || @.c)").unwrap();
+        assert_eq!("@.a || (@.b || @.c)", p.to_string());
+    }
+
+    #[test]
+    fn test_matches() {
+        let element: Value = toml_from_str(
+            r#"
+        name = "server"
+        port = 8080
+
+        [opts]
+        debug = true
+        "#,
+        )
+        .unwrap();
+
+        let matches = |expr: &str| Predicate::parse(expr).unwrap().matches(&element);
+
+        assert!(matches(r#"@.name == "server""#));
+        assert!(!matches(r#"@.name == "client""#));
+        assert!(matches("@.port > 80 && @.port < 9000.0"));
+        assert!(matches("@.opts.debug"));
+        assert!(!matches("@.opts.verbose"));
+        assert!(matches("@.missing == 1 || @.opts.debug == true"));
+        assert!(!matches("@.name > 1"));
+        assert!(matches("@.name != 1"));
+    }
+
+}
@@ -0,0 +1,60 @@
+/// # Prelude
+///
+/// `use toml_query::prelude::*;` brings the extension traits for reading, setting, inserting and
+/// deleting values into scope.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::prelude::*;
+///
+/// let mut toml : toml::Value = toml::from_str("[a]\nb = 1").unwrap();
+/// toml.set("a.b", toml::Value::Integer(2)).unwrap();
+/// toml.insert("a.c", toml::Value::Integer(3)).unwrap();
+/// toml.delete("a.b").unwrap();
+///
+/// assert_eq!(toml.read_int("a.c").unwrap(), Some(3));
+/// ```
+///
+pub use crate::delete::TomlValueDeleteExt;
+pub use crate::insert::TomlValueInsertExt;
+pub use crate::read::{TomlValueReadExt, TomlValueReadTypeExt};
+pub use crate::set::TomlValueSetExt;
+
+/// All of the traits for reading, setting, inserting and deleting values
+///
+/// Implemented for every type which implements them, so it can be used as a single bound:
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::prelude::*;
+///
+/// fn bump_version<'doc, D: TomlQueryExt<'doc>>(doc: &'doc mut D) {
+///     doc.set("package.version", toml::Value::from("0.2.0")).unwrap();
+/// }
+///
+/// let mut toml : toml::Value = toml::from_str("[package]\nversion = '0.1.0'").unwrap();
+/// bump_version(&mut toml);
+///
+/// assert_eq!(toml.read_string("package.version").unwrap(), Some(String::from("0.2.0")));
+/// ```
+pub trait TomlQueryExt<'doc>:
+    TomlValueReadExt<'doc>
+    + TomlValueReadTypeExt<'doc>
+    + TomlValueSetExt
+    + TomlValueInsertExt
+    + TomlValueDeleteExt
+{
+}
+
+impl<'doc, T> TomlQueryExt<'doc> for T where
+    T: TomlValueReadExt<'doc>
+        + TomlValueReadTypeExt<'doc>
+        + TomlValueSetExt
+        + TomlValueInsertExt
+        + TomlValueDeleteExt
+{
+}
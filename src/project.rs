@@ -0,0 +1,292 @@
+/// The Toml Projection extensions
+use toml::map::Map;
+use toml::Value;
+
+use crate::error::Result;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::tokenizer::Token;
+
+pub trait TomlValueProjectExt {
+    /// Extension function for copying the values at `paths` in the current toml::Value document
+    /// into a new document
+    ///
+    /// # Semantics
+    ///
+    /// The new document has the tables and arrays leading to the selected values, each of them
+    /// with only the selected entries. Selected array elements keep their order, but not their
+    /// indices, so selecting `a.[2]` yields an array with one element. Values which are selected
+    /// several times (also as part of a selected table or array) are copied once.
+    ///
+    /// Paths may match several values (like `servers.*.name`) or none, as with
+    /// `TomlValueReadExt::read_all`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::project::TomlValueProjectExt;
+    ///
+    /// let toml : toml::Value = toml::from_str(r#"
+    /// [server]
+    /// host = "example.com"
+    /// password = "secret"
+    /// [[server.listeners]]
+    /// port = 80
+    /// tls = false
+    /// "#).unwrap();
+    ///
+    /// let public = toml.project(&["server.host", "server.listeners.*.port"]).unwrap();
+    ///
+    /// let expected : toml::Value = toml::from_str(r#"
+    /// [server]
+    /// host = "example.com"
+    /// [[server.listeners]]
+    /// port = 80
+    /// "#).unwrap();
+    /// assert_eq!(public, expected);
+    /// ```
+    fn project(&self, paths: &[&str]) -> Result<Value>;
+
+    /// Extension function for copying the current toml::Value document without the values at
+    /// `paths`
+    ///
+    /// Paths may match several values or none, as with `project()`. Tables and arrays which
+    /// become empty are kept, see `prune_empty()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::project::TomlValueProjectExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("[server]\nhost = 'a'\n[secrets]\nkey = 'x'").unwrap();
+    ///
+    /// let mut public = toml.without(&["secrets.*"]).unwrap();
+    /// public.prune_empty();
+    ///
+    /// assert_eq!(public, toml::from_str("[server]\nhost = 'a'").unwrap());
+    /// ```
+    fn without(&self, paths: &[&str]) -> Result<Value>;
+
+    /// Remove the empty tables and arrays from the current toml::Value document, including the
+    /// ones which only contained empty tables and arrays, returning how many were removed
+    ///
+    /// The document itself is kept, even if it becomes empty.
+    fn prune_empty(&mut self) -> usize;
+}
+
+impl TomlValueProjectExt for Value {
+    fn project(&self, paths: &[&str]) -> Result<Value> {
+        let selected = select(self, paths)?;
+        let selected = selected.iter().map(Query::tokens).collect::<Vec<_>>();
+
+        Ok(match project(self, &selected) {
+            table @ Value::Table(_) => table,
+            _ => Value::Table(Map::new()),
+        })
+    }
+
+    fn without(&self, paths: &[&str]) -> Result<Value> {
+        let selected = select(self, paths)?;
+        let selected = selected.iter().map(Query::tokens).collect::<Vec<_>>();
+
+        Ok(without(self, &selected).unwrap_or_else(|| Value::Table(Map::new())))
+    }
+
+    fn prune_empty(&mut self) -> usize {
+        let mut removed = 0;
+        match *self {
+            Value::Table(ref mut table) => {
+                let keys = table.keys().cloned().collect::<Vec<_>>();
+                for key in keys {
+                    let child = table.get_mut(&key).unwrap(); // safe, the key is in the table
+                    removed += child.prune_empty();
+                    if is_empty(child) {
+                        table.remove(&key);
+                        removed += 1;
+                    }
+                }
+            }
+            Value::Array(ref mut array) => {
+                for child in array.iter_mut() {
+                    removed += child.prune_empty();
+                }
+                let len = array.len();
+                array.retain(|child| !is_empty(child));
+                removed += len - array.len();
+            }
+            _ => {}
+        }
+        removed
+    }
+}
+
+/// The concrete paths of the values at `paths` in `doc`
+fn select(doc: &Value, paths: &[&str]) -> Result<Vec<Query<'static>>> {
+    let mut selected = Vec::new();
+    for path in paths {
+        for (path, _) in doc.read_all(path)? {
+            selected.push(Query::parse(&path)?.into_owned());
+        }
+    }
+    Ok(selected)
+}
+
+/// The paths below the child at `step`, from the paths `selected` of its parent
+fn below<'p, 'q>(selected: &[&'p [Token<'q>]], step: &Token) -> Vec<&'p [Token<'q>]> {
+    selected
+        .iter()
+        .filter(|path| path[0] == *step)
+        .map(|path| &path[1..])
+        .collect()
+}
+
+/// The parts of `value` at the (relative) paths `selected`, which consist of keys and indices
+fn project(value: &Value, selected: &[&[Token]]) -> Value {
+    if selected.iter().any(|path| path.is_empty()) {
+        return value.clone();
+    }
+
+    match *value {
+        Value::Table(ref table) => Value::Table(
+            table
+                .iter()
+                .filter_map(|(key, child)| {
+                    let below = below(selected, &key_token(key));
+                    if below.is_empty() {
+                        None
+                    } else {
+                        Some((key.clone(), project(child, &below)))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(ref array) => Value::Array(
+            array
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, child)| {
+                    let below = below(selected, &Token::Index { idx });
+                    if below.is_empty() {
+                        None
+                    } else {
+                        Some(project(child, &below))
+                    }
+                })
+                .collect(),
+        ),
+        ref value => value.clone(),
+    }
+}
+
+/// `value` without the parts at the (relative) paths `selected`, `None` if it is selected itself
+fn without(value: &Value, selected: &[&[Token]]) -> Option<Value> {
+    if selected.iter().any(|path| path.is_empty()) {
+        return None;
+    }
+    if selected.is_empty() {
+        return Some(value.clone());
+    }
+
+    match *value {
+        Value::Table(ref table) => Some(Value::Table(
+            table
+                .iter()
+                .filter_map(|(key, child)| {
+                    let below = below(selected, &key_token(key));
+                    without(child, &below).map(|child| (key.clone(), child))
+                })
+                .collect(),
+        )),
+        Value::Array(ref array) => Some(Value::Array(
+            array
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, child)| without(child, &below(selected, &Token::Index { idx })))
+                .collect(),
+        )),
+        ref value => Some(value.clone()),
+    }
+}
+
+fn key_token(key: &str) -> Token<'_> {
+    Token::Identifier { ident: key.into() }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match *value {
+        Value::Table(ref table) => table.is_empty(),
+        Value::Array(ref array) => array.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_project() {
+        let toml: Value = toml_from_str(
+            r#"
+            x = 1
+            [a]
+            b = [ 1, 2, 3 ]
+            c = { d = 1, e = 2 }
+            f = 3
+            "#,
+        )
+        .unwrap();
+
+        let projection = toml
+            .project(&["a.b.[2]", "a.c", "a.c.d", "a.z", "y.*"])
+            .unwrap();
+
+        let expected: Value = toml_from_str("[a]\nb = [ 3 ]\nc = { d = 1, e = 2 }").unwrap();
+        assert_eq!(expected, projection);
+
+        let projection = toml.project(&[]).unwrap();
+        assert_eq!(Value::Table(Map::new()), projection);
+        assert!(toml.project(&["x.y"]).is_err());
+    }
+
+    #[test]
+    fn test_without() {
+        let toml: Value = toml_from_str(
+            r#"
+            x = 1
+            [a]
+            b = [ 1, 2, 3 ]
+            c = { d = 1 }
+            "#,
+        )
+        .unwrap();
+
+        let copy = toml
+            .without(&["a.b.[0]", "a.b.[2]", "a.c.d", "y.*"])
+            .unwrap();
+
+        let expected: Value = toml_from_str("x = 1\n[a]\nb = [ 2 ]\nc = {}").unwrap();
+        assert_eq!(expected, copy);
+        assert!(toml.without(&["x.y"]).is_err());
+    }
+
+    #[test]
+    fn test_prune_empty() {
+        let mut toml: Value =
+            toml_from_str("a = [ [], [ {} ], 1 ]\n[b]\nc = {}\nd = { e = [] }\n[f]\ng = 0")
+                .unwrap();
+
+        assert_eq!(7, toml.prune_empty());
+
+        let expected: Value = toml_from_str("a = [ 1 ]\n[f]\ng = 0").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -0,0 +1,979 @@
+/// # Pre-compiled queries
+///
+/// A `Query` holds an already tokenized query, so it can be executed repeatedly (via
+/// `read_query()`, `set_query()`, `insert_query()` and `delete_query()`) without parsing the
+/// query string again.
+///
+/// Queries can also be built from their segments with a `QueryBuilder`, so keys from untrusted
+/// sources do not need to be escaped.
+///
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::tokenizer::{
+    quote_identifier, quote_identifier_with_escape, render_token, tokenize, Token, Tokens,
+};
+
+/// The number of levels a query may descend into a document, unless configured otherwise with
+/// `Query::with_max_depth()`
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// The escape character of queries, unless configured otherwise with `Query::parse_with_escape()`
+pub const DEFAULT_ESCAPE: char = '\\';
+
+/// A parsed query
+///
+/// The identifiers of a parsed query borrow from the query string, `Query::into_owned()` makes a
+/// query which can be kept around independently of it.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::query::Query;
+/// use toml_query::read::TomlValueReadExt;
+///
+/// let query = Query::parse("a.b").unwrap();
+///
+/// for doc in &["a = { b = 1 }", "a = { b = 2 }"] {
+///     let toml : toml::Value = toml::from_str(doc).unwrap();
+///     assert!(toml.read_query(&query).unwrap().is_some());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<'a> {
+    tokens: Tokens<'a>,
+    sep: Cow<'a, str>,
+    escape: Option<char>,
+    max_depth: usize,
+}
+
+impl<'a> Query<'a> {
+    /// Parse a query using `.` as seperator
+    pub fn parse(query: &'a str) -> Result<Query<'a>> {
+        Query::parse_with_seperator(query, ".")
+    }
+
+    /// Parse a query using a custom seperator
+    pub fn parse_with_seperator(query: &'a str, sep: &'a str) -> Result<Query<'a>> {
+        Query::parse_with_escape(query, sep, Some(DEFAULT_ESCAPE))
+    }
+
+    /// Parse a query using a custom seperator and escape character
+    ///
+    /// The character after the escape character is taken literally, so with `\` as escape
+    /// character, `a\.b.c` refers to the key `c` in the table `a.b`. `None` disables escaping.
+    pub fn parse_with_escape(
+        query: &'a str,
+        sep: &'a str,
+        escape: Option<char>,
+    ) -> Result<Query<'a>> {
+        tokenize(query, sep, escape).map(|tokens| Query {
+            escape,
+            ..Query::from_tokens(tokens, sep)
+        })
+    }
+
+    /// The seperator the query was parsed with
+    pub fn seperator(&self) -> &str {
+        &self.sep
+    }
+
+    /// The escape character the query was parsed with
+    pub fn escape(&self) -> Option<char> {
+        self.escape
+    }
+
+    /// Limit the number of levels the query may descend into a document
+    ///
+    /// Resolving a query which is longer, or which matches values nested deeper than the limit
+    /// (with `**`), fails with `Error::DepthLimitExceeded`. This protects against queries from
+    /// untrusted sources which would exhaust the stack. The default is `DEFAULT_MAX_DEPTH`.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Query<'a> {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Use the identifiers which consist of digits only as array indices
+    ///
+    /// This way `bin.0.name` is the same as `bin.[0].name`, like in queries of JSONPath-like
+    /// tools. This is not the default, as `0` could as well be the key of a table. All such
+    /// identifiers are converted, regardless of whether they were quoted or escaped.
+    pub fn with_numeric_indices(mut self) -> Query<'a> {
+        for token in self.tokens.iter_mut() {
+            let idx = match *token {
+                Token::Identifier { ref ident } if ident.bytes().all(|b| b.is_ascii_digit()) => {
+                    ident.parse().ok()
+                }
+                _ => None,
+            };
+
+            if let Some(idx) = idx {
+                *token = Token::Index { idx };
+            }
+        }
+        self
+    }
+
+    /// The number of levels the query may descend into a document
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Copy the parts of the query which borrow from the query string
+    pub fn into_owned(self) -> Query<'static> {
+        Query {
+            tokens: self.tokens.into_iter().map(Token::into_owned).collect(),
+            sep: Cow::Owned(self.sep.into_owned()),
+            escape: self.escape,
+            max_depth: self.max_depth,
+        }
+    }
+
+    /// The number of segments of the query
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether the query has no segments, which only happens after removing all of them
+    ///
+    /// A query without segments refers to the document itself.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The segments of the query, in order
+    ///
+    /// ```rust
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::query::{Query, Segment};
+    ///
+    /// let query = Query::parse("bin.[0].*").unwrap();
+    /// let segments = query.segments().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(segments, vec![Segment::Key("bin"), Segment::Index(0), Segment::Wildcard]);
+    /// ```
+    pub fn segments(&self) -> impl DoubleEndedIterator<Item = Segment<'_>> + ExactSizeIterator {
+        self.tokens.iter().map(Segment::of)
+    }
+
+    /// The segment at position `idx`, if the query is long enough
+    pub fn segment(&self, idx: usize) -> Option<Segment<'_>> {
+        self.tokens.get(idx).map(Segment::of)
+    }
+
+    /// Add the key `key` at the end of the query
+    pub fn push_key<S: Into<String>>(&mut self, key: S) {
+        self.tokens.push(Token::Identifier {
+            ident: Cow::Owned(key.into()),
+        });
+    }
+
+    /// Add the array index `idx` at the end of the query
+    pub fn push_index(&mut self, idx: usize) {
+        self.tokens.push(Token::Index { idx });
+    }
+
+    /// Remove the last segment of the query, returning whether there was one
+    pub fn pop(&mut self) -> bool {
+        self.tokens.pop().is_some()
+    }
+
+    /// Keep only the first `len` segments of the query
+    pub fn truncate(&mut self, len: usize) {
+        self.tokens.truncate(len);
+    }
+
+    /// The query without its last segment, if it has more than one
+    pub fn parent(&self) -> Option<Query<'a>> {
+        if self.tokens.len() < 2 {
+            return None;
+        }
+
+        let mut parent = self.clone();
+        parent.pop();
+        Some(parent)
+    }
+
+    /// Whether the segments of `prefix` are the first segments of this query
+    pub fn starts_with(&self, prefix: &Query) -> bool {
+        self.tokens.starts_with(&prefix.tokens)
+    }
+
+    /// Whether the query refers to a path which falls under `pattern`
+    ///
+    /// Only queries of keys and indices refer to a single path, other queries match no pattern.
+    ///
+    /// ```rust
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::query::{Query, QueryPattern};
+    ///
+    /// let pattern = QueryPattern::parse("servers.*.tls.**").unwrap();
+    ///
+    /// assert!(Query::parse("servers.[0].tls.cert").unwrap().matches(&pattern));
+    /// assert!(!Query::parse("servers.[0].port").unwrap().matches(&pattern));
+    /// ```
+    pub fn matches(&self, pattern: &QueryPattern) -> bool {
+        let concrete = self
+            .tokens
+            .iter()
+            .all(|token| is_match!(*token, Token::Identifier { .. } | Token::Index { .. }));
+
+        concrete && pattern_matches(pattern.query.tokens(), &self.tokens)
+    }
+
+    /// Build a query from the tokens `toml_path!()` parsed at compile time
+    #[cfg(feature = "macros")]
+    #[doc(hidden)]
+    pub fn from_static(tokens: &'static [StaticToken]) -> Query<'static> {
+        let tokens = tokens
+            .iter()
+            .map(|token| match *token {
+                StaticToken::Identifier(ident) => Token::Identifier {
+                    ident: Cow::Borrowed(ident),
+                },
+                StaticToken::Index(idx) => Token::Index { idx },
+                StaticToken::RecursiveDescent => Token::RecursiveDescent,
+                StaticToken::Wildcard => Token::Wildcard,
+                StaticToken::Parent => Token::Parent,
+                StaticToken::Append => Token::Append,
+                StaticToken::Slice(start, end) => Token::Slice { start, end },
+                StaticToken::IndexUnion(idxs) => Token::IndexUnion {
+                    idxs: idxs.to_vec(),
+                },
+                StaticToken::KeyUnion(idents) => Token::KeyUnion {
+                    idents: idents.iter().map(|i| String::from(*i)).collect(),
+                },
+                StaticToken::Filter(expr) => Token::Filter {
+                    predicate: crate::predicate::Predicate::parse(expr)
+                        .expect("toml_path!() checks filters"),
+                },
+                #[cfg(feature = "regex")]
+                StaticToken::KeyRegex(pattern) => Token::KeyRegex {
+                    regex: crate::tokenizer::KeyRegex::new(pattern)
+                        .expect("toml_path!() checks regexes"),
+                },
+            })
+            .collect();
+
+        Query::from_tokens(tokens, ".")
+    }
+
+    pub(crate) fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+
+    pub(crate) fn from_tokens<S: Into<Cow<'a, str>>>(tokens: Tokens<'a>, sep: S) -> Query<'a> {
+        Query {
+            tokens,
+            sep: sep.into(),
+            escape: Some(DEFAULT_ESCAPE),
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+/// A pattern of paths, see `Query::matches()`
+///
+/// Patterns are written like queries, of keys, indices, `*` (any single key or index) and `**`
+/// (any number of keys and indices, including none).
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPattern {
+    query: Query<'static>,
+}
+
+impl QueryPattern {
+    /// Parse a pattern using `.` as seperator
+    ///
+    /// Fails with `Error::UnsupportedPatternSegment` if the pattern contains other segments than
+    /// keys, indices, `*` and `**`.
+    pub fn parse(pattern: &str) -> Result<QueryPattern> {
+        let query = Query::parse(pattern)?.into_owned();
+
+        let unsupported = query.tokens.iter().find(|token| {
+            !is_match!(
+                **token,
+                Token::Identifier { .. }
+                    | Token::Index { .. }
+                    | Token::Wildcard
+                    | Token::RecursiveDescent
+            )
+        });
+        if let Some(token) = unsupported {
+            return Err(Error::UnsupportedPatternSegment(render_token(token, ".")));
+        }
+
+        Ok(QueryPattern { query })
+    }
+}
+
+impl fmt::Display for QueryPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.query.fmt(f)
+    }
+}
+
+impl FromStr for QueryPattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<QueryPattern> {
+        QueryPattern::parse(s)
+    }
+}
+
+/// Whether the keys and indices of `path` match the segments of `pattern`
+///
+/// `matches[j]` is whether the segments of `pattern` from the current one on match `path[j..]`.
+/// It is computed from the last segment to the first, so every pair of a segment and a position
+/// in the path is looked at once, regardless of the number of `**` segments.
+fn pattern_matches(pattern: &[Token], path: &[Token]) -> bool {
+    let mut matches = (0..=path.len())
+        .map(|j| j == path.len())
+        .collect::<Vec<_>>();
+
+    for segment in pattern.iter().rev() {
+        let next = matches;
+        matches = vec![false; path.len() + 1];
+
+        for j in (0..=path.len()).rev() {
+            matches[j] = match *segment {
+                // `**` matches any number of steps, so the rest of the pattern may start here or
+                // at any later step
+                Token::RecursiveDescent => next[j] || (j < path.len() && matches[j + 1]),
+                _ => {
+                    j < path.len()
+                        && (*segment == Token::Wildcard || *segment == path[j])
+                        && next[j + 1]
+                }
+            };
+        }
+    }
+
+    matches[0]
+}
+
+/// A segment of a parsed query, see `Query::segments()`
+///
+/// More kinds of segments may be added in the future, so matching on a `Segment` needs a
+/// wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment<'q> {
+    /// A key of a table, `a` or `"a.b"`
+    Key(&'q str),
+
+    /// An index of an array, `[0]`
+    Index(usize),
+
+    /// `**`, any nesting depth
+    RecursiveDescent,
+
+    /// `*`, all values of a table or array
+    Wildcard,
+
+    /// `^`, the parent of the current value
+    Parent,
+
+    /// `[+]`, the position after the last element of an array
+    Append,
+
+    /// `[start:end]`, a range of array elements
+    Slice {
+        start: Option<usize>,
+        end: Option<usize>,
+    },
+
+    /// `[0,2,5]`, the listed array elements, sorted and deduplicated
+    Indices(&'q [usize]),
+
+    /// `{a,b}`, the listed keys of a table
+    Keys(&'q [String]),
+
+    /// `[?(expr)]`, the array elements matching the filter expression, in its canonical form
+    Filter(String),
+
+    /// `/regex/`, the keys of a table matching the regex
+    KeyRegex(&'q str),
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl<'q> Segment<'q> {
+    fn of(token: &'q Token) -> Segment<'q> {
+        match *token {
+            Token::Identifier { ref ident } => Segment::Key(ident),
+            Token::Index { idx } => Segment::Index(idx),
+            Token::RecursiveDescent => Segment::RecursiveDescent,
+            Token::Wildcard => Segment::Wildcard,
+            Token::Parent => Segment::Parent,
+            Token::Append => Segment::Append,
+            Token::Slice { start, end } => Segment::Slice { start, end },
+            Token::IndexUnion { ref idxs } => Segment::Indices(idxs),
+            Token::KeyUnion { ref idents } => Segment::Keys(idents),
+            Token::Filter { ref predicate } => Segment::Filter(predicate.to_string()),
+            #[cfg(feature = "regex")]
+            Token::KeyRegex { ref regex } => Segment::KeyRegex(regex.as_str()),
+        }
+    }
+}
+
+/// Renders the segment like it is written in a query using `.` as seperator
+impl<'q> fmt::Display for Segment<'q> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Segment::Key(key) => write!(f, "{}", quote_identifier(key, ".")),
+            Segment::Index(idx) => write!(f, "[{}]", idx),
+            Segment::RecursiveDescent => write!(f, "**"),
+            Segment::Wildcard => write!(f, "*"),
+            Segment::Parent => write!(f, "^"),
+            Segment::Append => write!(f, "[+]"),
+            Segment::Slice { start, end } => {
+                let bound = |b: Option<usize>| b.map(|b| b.to_string()).unwrap_or_default();
+                write!(f, "[{}:{}]", bound(start), bound(end))
+            }
+            Segment::Indices(idxs) => {
+                let idxs = idxs.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "[{}]", idxs.join(","))
+            }
+            Segment::Keys(keys) => {
+                let token = Token::KeyUnion {
+                    idents: keys.to_vec(),
+                };
+                write!(f, "{}", render_token(&token, "."))
+            }
+            Segment::Filter(ref expr) => write!(f, "[?({})]", expr),
+            Segment::KeyRegex(regex) => write!(f, "/{}/", regex),
+            Segment::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+/// A token of a query which was parsed at compile time, see `toml_path!()`
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum StaticToken {
+    Identifier(&'static str),
+    Index(usize),
+    RecursiveDescent,
+    Wildcard,
+    Parent,
+    Append,
+    Slice(Option<usize>, Option<usize>),
+    IndexUnion(&'static [usize]),
+    KeyUnion(&'static [&'static str]),
+
+    /// The expression of a filter, which `toml_path!()` checked
+    Filter(&'static str),
+
+    /// The pattern of a regex, which `toml_path!()` checked
+    #[cfg(feature = "regex")]
+    KeyRegex(&'static str),
+}
+
+/// Renders the query in its canonical form, which parses back to the same query
+///
+/// Identifiers are quoted if necessary, so `Query::parse(r#"a."b.c""#)` is rendered as `a."b.c"`.
+impl<'a> fmt::Display for Query<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", self.sep)?;
+            }
+            match *token {
+                Token::Identifier { ref ident } => write!(
+                    f,
+                    "{}",
+                    quote_identifier_with_escape(ident, &self.sep, self.escape)
+                )?,
+                _ => write!(f, "{}", render_token(token, &self.sep))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serializes the query in its canonical form, see `Display`
+///
+/// Queries with another seperator, escape character or depth limit than the default ones are
+/// serialized as a table with the fields `query`, `seperator`, `escape` (empty if escaping is
+/// disabled) and `max_depth`.
+#[cfg(feature = "typed")]
+impl<'a> serde::Serialize for Query<'a> {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        if self.sep == "."
+            && self.escape == Some(DEFAULT_ESCAPE)
+            && self.max_depth == DEFAULT_MAX_DEPTH
+        {
+            return serializer.collect_str(self);
+        }
+
+        let escape = self.escape.map(String::from).unwrap_or_default();
+        let mut query = serializer.serialize_struct("Query", 4)?;
+        query.serialize_field("query", &self.to_string())?;
+        query.serialize_field("seperator", &self.sep)?;
+        query.serialize_field("escape", &escape)?;
+        query.serialize_field("max_depth", &self.max_depth)?;
+        query.end()
+    }
+}
+
+/// A query as it is serialized by `Query::serialize()`
+#[cfg(feature = "typed")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SerializedQuery {
+    Canonical(String),
+    Custom {
+        query: String,
+        seperator: Option<String>,
+        escape: Option<String>,
+        max_depth: Option<usize>,
+    },
+}
+
+/// Deserializes a query from a string, or from a table as written by `Query::serialize()`
+///
+/// Missing fields of the table default to the seperator `.`, the escape character `\` and
+/// `DEFAULT_MAX_DEPTH`.
+#[cfg(feature = "typed")]
+impl<'de> serde::Deserialize<'de> for Query<'static> {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Query<'static>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as DeError;
+
+        let (query, sep, escape, max_depth) = match SerializedQuery::deserialize(deserializer)? {
+            SerializedQuery::Canonical(query) => (query, None, None, None),
+            SerializedQuery::Custom {
+                query,
+                seperator,
+                escape,
+                max_depth,
+            } => (query, seperator, escape, max_depth),
+        };
+
+        let escape = match escape {
+            None => Some(DEFAULT_ESCAPE),
+            Some(escape) => {
+                let mut chars = escape.chars();
+                match (chars.next(), chars.next()) {
+                    (escape, None) => escape,
+                    _ => return Err(D::Error::custom("escape must be a single character")),
+                }
+            }
+        };
+
+        let sep = sep.unwrap_or_else(|| String::from("."));
+        Query::parse_with_escape(&query, &sep, escape)
+            .map(|query| {
+                query
+                    .with_max_depth(max_depth.unwrap_or(DEFAULT_MAX_DEPTH))
+                    .into_owned()
+            })
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Parses a query using `.` as seperator, see `Query::parse()`
+impl FromStr for Query<'static> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Query<'static>> {
+        Query::parse(s).map(Query::into_owned)
+    }
+}
+
+/// Builder for a `Query`
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::query::QueryBuilder;
+/// use toml_query::read::TomlValueReadExt;
+///
+/// let toml : toml::Value = toml::from_str(r#"a = [ { "b.c" = 1 } ]"#).unwrap();
+/// let query = QueryBuilder::new().key("a").index(0).key("b.c").build().unwrap();
+///
+/// assert_eq!(Some(&toml::Value::Integer(1)), toml.read_query(&query).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryBuilder {
+    segments: Tokens<'static>,
+    sep: Cow<'static, str>,
+}
+
+impl QueryBuilder {
+    /// A new builder for a query using `.` as seperator
+    pub fn new() -> QueryBuilder {
+        QueryBuilder {
+            segments: Tokens::new(),
+            sep: Cow::Borrowed("."),
+        }
+    }
+
+    /// A new builder for a query using a custom seperator
+    pub fn with_seperator(sep: &str) -> QueryBuilder {
+        QueryBuilder {
+            segments: Tokens::new(),
+            sep: Cow::Owned(String::from(sep)),
+        }
+    }
+
+    /// Append a key, which is used as is (no quoting or escaping is needed)
+    pub fn key<S: Into<String>>(mut self, key: S) -> QueryBuilder {
+        self.segments.push(Token::Identifier {
+            ident: Cow::Owned(key.into()),
+        });
+        self
+    }
+
+    /// Append an array index
+    pub fn index(mut self, idx: usize) -> QueryBuilder {
+        self.segments.push(Token::Index { idx });
+        self
+    }
+
+    /// Append the `[+]` token
+    pub fn append(mut self) -> QueryBuilder {
+        self.segments.push(Token::Append);
+        self
+    }
+
+    /// Append the `^` token
+    pub fn parent(mut self) -> QueryBuilder {
+        self.segments.push(Token::Parent);
+        self
+    }
+
+    /// Build the query
+    ///
+    /// Fails with `Error::EmptyQueryError` if no segment was added.
+    pub fn build(self) -> Result<Query<'static>> {
+        if self.segments.is_empty() {
+            return Err(Error::EmptyQueryError);
+        }
+
+        Ok(Query::from_tokens(self.segments, self.sep))
+    }
+}
+
+impl Default for QueryBuilder {
+    fn default() -> QueryBuilder {
+        QueryBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::tokenize_with_seperator;
+
+    #[test]
+    fn test_parse() {
+        let query = Query::parse("a.b.[0]");
+        assert!(query.is_ok());
+        let query = query.unwrap();
+
+        assert_eq!(".", query.seperator());
+        assert_eq!(
+            &tokenize_with_seperator("a.b.[0]", ".").unwrap()[..],
+            query.tokens()
+        );
+    }
+
+    #[test]
+    fn test_parse_with_seperator() {
+        let query = Query::parse_with_seperator("a/b.c", "/");
+        assert!(query.is_ok());
+        let query = query.unwrap();
+
+        assert_eq!("/", query.seperator());
+        assert_eq!(
+            &tokenize_with_seperator("a/b.c", "/").unwrap()[..],
+            query.tokens()
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let query = Query::parse("a..b");
+        assert!(query.is_err());
+
+        assert!(is_match!(
+            *query.unwrap_err().kind(),
+            Error::EmptyIdentifier
+        ));
+    }
+
+    #[test]
+    fn test_display_is_canonical() {
+        let queries = [
+            ("a.b.[0]", "a.b.[0]"),
+            (r#"a.'b.c'.d"#, r#"a."b.c".d"#),
+            (r#"'"x".y'"#, r#"'"x".y'"#),
+            (r#""[0]".'**'.'^'.''"#, r#""[0]"."**"."^"."""#),
+            ("**.^.[+]", "**.^.[+]"),
+            (r#"*.'*'.*a"#, r#"*."*".*a"#),
+            ("a.[1:4].[:3].[2:].[:]", "a.[1:4].[:3].[2:].[:]"),
+            ("a.[5, 0,2]", "a.[0,2,5]"),
+            (r#"db.{ host,"a,b" , ' c'}"#, r#"db.{host,"a,b"," c"}"#),
+            (
+                r#"bins.[?(@.name=='x' && (@.a.'b.c' >= 1.0 || @.d))]"#,
+                r#"bins.[?(@.name == "x" && (@.a."b.c" >= 1.0 || @.d))]"#,
+            ),
+        ];
+
+        for &(query, canonical) in queries.iter() {
+            let parsed = Query::parse(query).unwrap();
+            assert_eq!(canonical, parsed.to_string());
+            assert_eq!(parsed, Query::parse(canonical).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_display_with_seperator() {
+        let query = Query::parse_with_seperator(r#"a/'b/c'/d.e"#, "/").unwrap();
+        assert_eq!(r#"a/"b/c"/d.e"#, query.to_string());
+    }
+
+    #[test]
+    fn test_parse_with_escape() {
+        let query = Query::parse_with_escape("a%.b.c", ".", Some('%')).unwrap();
+        assert_eq!(Some('%'), query.escape());
+        let expected = QueryBuilder::new().key("a.b").key("c").build().unwrap();
+        assert_eq!(expected.tokens(), query.tokens());
+
+        let query = Query::parse_with_escape(r"a\.b", ".", None).unwrap();
+        let expected = QueryBuilder::new().key(r"a\").key("b").build().unwrap();
+        assert_eq!(expected.tokens(), query.tokens());
+    }
+
+    #[test]
+    fn test_display_with_escape() {
+        let query = Query::parse_with_escape("'a%b'", ".", Some('%')).unwrap();
+        assert_eq!(r#""a%b""#, query.to_string());
+
+        let query = Query::parse(r"a\.b.c\\d").unwrap();
+        let rendered = query.to_string();
+        assert_eq!(r#""a.b"."c\d""#, rendered);
+        assert_eq!(query, Query::parse(&rendered).unwrap().into_owned());
+    }
+
+    #[test]
+    fn test_with_numeric_indices() {
+        let query = Query::parse("bin.0.name.12.x1").unwrap();
+        let expected = Query::parse("bin.[0].name.[12].x1").unwrap();
+        assert_eq!(expected, query.with_numeric_indices());
+
+        let query = Query::parse("a.0").unwrap();
+        let expected = QueryBuilder::new().key("a").key("0").build().unwrap();
+        assert_eq!(expected, query);
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_from_static() {
+        let query = Query::from_static(&[
+            StaticToken::Identifier("a.b"),
+            StaticToken::Index(0),
+            StaticToken::Slice(None, Some(2)),
+            StaticToken::KeyUnion(&["c", "d"]),
+        ]);
+        assert_eq!(Query::parse(r#""a.b".[0].[:2].{c,d}"#).unwrap(), query);
+    }
+
+    #[cfg(feature = "typed")]
+    #[test]
+    fn test_serde() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Config {
+            default: Query<'static>,
+            custom: Query<'static>,
+        }
+
+        let config = Config {
+            default: Query::parse("a.b.[0]").unwrap().into_owned(),
+            custom: Query::parse_with_escape("a/b", "/", None)
+                .unwrap()
+                .with_max_depth(4)
+                .into_owned(),
+        };
+
+        let serialized = toml::to_string(&config).unwrap();
+        assert_eq!(
+            "default = \"a.b.[0]\"\n\n[custom]\nquery = \"a/b\"\nseperator = \"/\"\nescape = \"\"\nmax_depth = 4\n",
+            serialized
+        );
+        assert_eq!(config, toml::from_str(&serialized).unwrap());
+
+        let config: Config =
+            toml::from_str("default = 'a.b'\ncustom = { query = 'a%.b', escape = '%' }").unwrap();
+        assert_eq!(Query::parse("a.b").unwrap(), config.default);
+        assert_eq!(
+            Query::parse(r"a\.b").unwrap().tokens(),
+            config.custom.tokens()
+        );
+
+        let config = toml::from_str::<Config>("default = 'a..b'\ncustom = 'a'");
+        assert!(config.is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_display_regex() {
+        let query = Query::parse(r"a./^x\.y$/.b").unwrap();
+        assert_eq!(r"a./^x\.y$/.b", query.to_string());
+    }
+
+    #[test]
+    fn test_from_str() {
+        let query = "a.[0]".parse::<Query>();
+        assert!(query.is_ok());
+
+        assert_eq!(Query::parse("a.[0]").unwrap(), query.unwrap());
+    }
+
+    quickcheck! {
+        fn test_display_roundtrip(keys: Vec<String>, idx: usize) -> bool {
+            let query = keys
+                .iter()
+                .fold(QueryBuilder::new().index(idx), |b, k| b.key(k.as_str()))
+                .build()
+                .unwrap();
+
+            let rendered = query.to_string();
+            Query::parse(&rendered).map(|q| q.into_owned() == query).unwrap_or(false)
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let query = QueryBuilder::new()
+            .key("a")
+            .index(3)
+            .append()
+            .parent()
+            .key("b")
+            .build();
+        assert!(query.is_ok());
+
+        assert_eq!(Query::parse("a.[3].[+].^.b").unwrap(), query.unwrap());
+    }
+
+    #[test]
+    fn test_build_does_not_interpret_keys() {
+        let query = QueryBuilder::with_seperator("/")
+            .key("a/b")
+            .key("[0]")
+            .key("**")
+            .key("")
+            .build();
+        assert!(query.is_ok());
+
+        let expected = Query::parse_with_seperator(r#""a/b"/"[0]"/"**"/"""#, "/").unwrap();
+        assert_eq!(expected, query.unwrap());
+    }
+
+    #[test]
+    fn test_build_empty_is_error() {
+        let query = QueryBuilder::new().build();
+        assert!(query.is_err());
+
+        assert!(is_match!(query.unwrap_err(), Error::EmptyQueryError));
+    }
+
+    #[test]
+    fn test_segments() {
+        let query = Query::parse(r#""a.b".[1].**.*.^.[+].[1:].[2,0].{x,y}.[?(@.n == 1)]"#).unwrap();
+
+        assert_eq!(
+            vec![
+                Segment::Key("a.b"),
+                Segment::Index(1),
+                Segment::RecursiveDescent,
+                Segment::Wildcard,
+                Segment::Parent,
+                Segment::Append,
+                Segment::Slice {
+                    start: Some(1),
+                    end: None
+                },
+                Segment::Indices(&[0, 2]),
+                Segment::Keys(&[String::from("x"), String::from("y")]),
+                Segment::Filter(String::from("@.n == 1")),
+            ],
+            query.segments().collect::<Vec<_>>()
+        );
+
+        let rendered = query
+            .segments()
+            .map(|segment| segment.to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+        assert_eq!(query.to_string(), rendered);
+        assert_eq!(Some(Segment::Index(1)), query.segment(1));
+        assert_eq!(None, query.segment(10));
+    }
+
+    #[test]
+    fn test_manipulate_segments() {
+        let mut query = Query::parse("a.b").unwrap();
+
+        query.push_index(2);
+        query.push_key("c.d");
+        assert_eq!(Query::parse(r#"a.b.[2]."c.d""#).unwrap(), query);
+        assert!(query.starts_with(&Query::parse("a.b").unwrap()));
+        assert!(!query.starts_with(&Query::parse("a.c").unwrap()));
+
+        assert!(query.pop());
+        assert_eq!(Query::parse("a.b").unwrap(), query.parent().unwrap());
+        query.truncate(1);
+        assert_eq!(1, query.len());
+        assert!(query.parent().is_none());
+        assert!(query.pop());
+        assert!(query.is_empty());
+        assert!(!query.pop());
+    }
+
+    #[test]
+    fn test_matches() {
+        let matches = |query: &str, pattern: &str| {
+            Query::parse(query)
+                .unwrap()
+                .matches(&QueryPattern::parse(pattern).unwrap())
+        };
+
+        assert!(matches("a.b.[0]", "a.b.[0]"));
+        assert!(matches("a.b.[0]", "a.*.*"));
+        assert!(matches("a.b.[0]", "**"));
+        assert!(matches("a.b.[0]", "a.**.[0]"));
+        assert!(matches("a", "a.**"));
+        assert!(matches("x.a.y.a", "**.a"));
+        assert!(!matches("a.b", "a"));
+        assert!(!matches("a", "a.*"));
+        assert!(!matches("a.b.c", "a.*"));
+        assert!(!matches("a.*", "a.*"));
+
+        // Would take exponential time if the `**` segments were tried one after the other
+        let query = vec!["a"; 40].join(".");
+        let pattern = vec!["**"; 30].join(".");
+        assert!(!matches(&query, &format!("{}.b", pattern)));
+        assert!(matches(&query, &format!("{}.a", pattern)));
+
+        let pattern = QueryPattern::parse("a.[1:]");
+        assert!(is_match!(pattern, Err(Error::UnsupportedPatternSegment(_))));
+    }
+
+}
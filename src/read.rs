@@ -1,40 +1,234 @@
 /// The Toml Read extensions
-
+use std::borrow::Cow;
 #[cfg(feature = "typed")]
 use std::fmt::Debug;
 
 #[cfg(feature = "typed")]
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use toml::value::Datetime;
 use toml::Value;
 
 use crate::error::{Error, Result};
-use crate::tokenizer::tokenize_with_seperator;
+use crate::query::Query;
+#[cfg(feature = "typed")]
+use crate::tokenizer::render_token;
+use crate::tokenizer::Token;
 
 pub trait TomlValueReadExt<'doc> {
     /// Extension function for reading a value from the current toml::Value document
     /// using a custom seperator
-    fn read_with_seperator(&'doc self, query: &str, sep: char) -> Result<Option<&'doc Value>>;
+    fn read_with_seperator(&'doc self, query: &str, sep: &str) -> Result<Option<&'doc Value>>;
 
     /// Extension function for reading a value from the current toml::Value document mutably
     /// using a custom seperator
     fn read_mut_with_seperator(
         &'doc mut self,
         query: &str,
-        sep: char,
+        sep: &str,
     ) -> Result<Option<&'doc mut Value>>;
 
+    /// Extension function for reading all values matching a query from the current toml::Value
+    /// document using a custom seperator
+    ///
+    /// In contrast to `read_with_seperator()`, the query may contain tokens which match more than
+    /// one value, for example `**` which matches any nesting depth: `"**.password"` finds every
+    /// `password` key in the document, `*` matches all values of a table or array one level deep
+    /// (`"*.password"`). Array slices like `"a.[1:4]"`, `"a.[:3]"` or `"a.[2:]"`
+    /// match a range of array elements, unions like `"a.[0,2,5]"` or `"db.{host,port}"` match
+    /// the listed elements or keys. Filters like `"bins.[?(@.name == \"server\")]"` match the
+    /// array elements for which the predicate holds. With the `regex` feature, `"a./^log_.*/"`
+    /// matches all keys of the table `a` which match the regex. A `^` goes up to the parent of
+    /// the current value, a value reached by several matches is only returned once.
+    ///
+    /// # Return value
+    ///
+    /// All matches are returned together with their full path in the document, the segments of
+    /// the path being joined by `sep`. If nothing matches, an empty vector is returned.
+    fn read_all_with_seperator(
+        &'doc self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, &'doc Value)>>;
+
+    /// Extension function for reading a value from the current toml::Value document using a
+    /// pre-compiled query
+    fn read_query(&'doc self, query: &Query) -> Result<Option<&'doc Value>>;
+
+    /// Extension function for reading all values matching a pre-compiled query from the current
+    /// toml::Value document
+    ///
+    /// See documentation of `TomlValueReadExt::read_all_with_seperator`
+    fn read_all_query(&'doc self, query: &Query) -> Result<Vec<(String, &'doc Value)>>;
+
+    /// Extension function for reading a value from the current toml::Value document mutably
+    /// using a pre-compiled query
+    fn read_mut_query(&'doc mut self, query: &Query) -> Result<Option<&'doc mut Value>>;
+
     /// Extension function for reading a value from the current toml::Value document
     fn read(&'doc self, query: &str) -> Result<Option<&'doc Value>> {
-        self.read_with_seperator(query, '.')
+        self.read_with_seperator(query, ".")
     }
 
     /// Extension function for reading a value from the current toml::Value document mutably
+    ///
+    /// The returned reference can be used to modify the value in place, without reading,
+    /// cloning and setting it again:
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::read::TomlValueReadExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[table]\nlist = [ 1 ]").unwrap();
+    ///
+    /// if let Some(&mut toml::Value::Array(ref mut list)) = toml.read_mut("table.list").unwrap() {
+    ///     list.push(toml::Value::Integer(2));
+    /// }
+    ///
+    /// assert_eq!(toml, toml::from_str("[table]\nlist = [ 1, 2 ]").unwrap());
+    /// ```
     fn read_mut(&'doc mut self, query: &str) -> Result<Option<&'doc mut Value>> {
-        self.read_mut_with_seperator(query, '.')
+        self.read_mut_with_seperator(query, ".")
     }
 
+    /// Extension function for reading a clone of a value from the current toml::Value document
+    ///
+    /// The result does not borrow the document, so it can be read from a temporary one:
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::read::TomlValueReadExt;
+    ///
+    /// let port = toml::from_str::<toml::Value>("[server]\nport = 80")
+    ///     .unwrap()
+    ///     .read_owned("server.port")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(port, Some(toml::Value::Integer(80)));
+    /// ```
+    fn read_owned(&'doc self, query: &str) -> Result<Option<Value>> {
+        self.read(query).map(|value| value.cloned())
+    }
+
+    /// Extension function for reading many values from the current toml::Value document at once
+    ///
+    /// The result has one entry per query, in the order of `queries`, as if each was passed to
+    /// `read()`. All queries are parsed before any is resolved, so a malformed query fails the
+    /// call without reading anything.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::read::TomlValueReadExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("[server]\nhost = 'a'\nport = 80").unwrap();
+    ///
+    /// let values = toml.read_many(&["server.host", "server.port", "server.tls"]).unwrap();
+    /// assert_eq!(values[1], Some(&toml::Value::Integer(80)));
+    /// assert_eq!(values[2], None);
+    /// ```
+    fn read_many(&'doc self, queries: &[&str]) -> Result<Vec<Option<&'doc Value>>> {
+        queries
+            .iter()
+            .map(|query| Query::parse(query))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .map(|query| self.read_query(query))
+            .collect()
+    }
+
+    /// Extension function for reading a value from the current toml::Value document, falling back
+    /// to `default` if there is no value at the queried path
+    ///
+    /// Errors (for example querying a key in something which is not a table) are still returned,
+    /// only a missing value is replaced by the fallback.
+    fn read_or(&'doc self, query: &str, default: Value) -> Result<Value> {
+        self.read_or_else(query, move || default)
+    }
+
+    /// Extension function for reading a value from the current toml::Value document, falling back
+    /// to the value returned by `f` if there is no value at the queried path
+    ///
+    /// See documentation of `TomlValueReadExt::read_or`
+    fn read_or_else<F>(&'doc self, query: &str, f: F) -> Result<Value>
+    where
+        F: FnOnce() -> Value,
+    {
+        self.read(query)
+            .map(|value| value.cloned().unwrap_or_else(f))
+    }
+
+    /// Extension function for reading all values matching a query from the current toml::Value
+    /// document
+    ///
+    /// See documentation of `TomlValueReadExt::read_all_with_seperator`
+    fn read_all(&'doc self, query: &str) -> Result<Vec<(String, &'doc Value)>> {
+        self.read_all_with_seperator(query, ".")
+    }
+
+    /// Extension function for reading a value from the current toml::Value document, or the
+    /// values of a query which matches more than one value
+    ///
+    /// A single value is borrowed from the document. The matches of a query which may match more
+    /// than one value (see `TomlValueReadExt::read_all_with_seperator`) are cloned into an array:
+    /// they are not stored next to each other in the document, so they cannot be borrowed as one
+    /// value. Use `TomlValueReadExt::read_all` to borrow them one by one instead. If nothing
+    /// matches, `None` is returned in both cases.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use std::borrow::Cow;
+    /// use toml_query::read::TomlValueReadExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("ports = [ 80, 443, 8080 ]").unwrap();
+    ///
+    /// let port = toml.read_cow("ports.[0]").unwrap();
+    /// assert!(match port { Some(Cow::Borrowed(_)) => true, _ => false });
+    ///
+    /// let ports = toml.read_cow("ports.[1:]").unwrap().unwrap();
+    /// assert_eq!(ports.into_owned(), toml::Value::from(vec![443, 8080]));
+    ///
+    /// assert!(toml.read_cow("ports.[5:]").unwrap().is_none());
+    /// ```
+    fn read_cow(&'doc self, query: &str) -> Result<Option<Cow<'doc, Value>>> {
+        let query = Query::parse(query)?;
+        let single_valued = query.tokens().iter().all(|token| {
+            is_match!(
+                *token,
+                Token::Identifier { .. } | Token::Index { .. } | Token::Parent | Token::Append
+            )
+        });
+        if single_valued {
+            return self
+                .read_query(&query)
+                .map(|value| value.map(Cow::Borrowed));
+        }
+
+        let values = self
+            .read_all_query(&query)?
+            .into_iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Cow::Owned(Value::Array(values))))
+        }
+    }
+
+    /// A convenience method for reading a value and deserializing it, the mirror of
+    /// `TomlValueSetExt::set_serialized`
+    ///
+    /// Returns `Ok(None)` if there is no value at the queried path.
     #[cfg(feature = "typed")]
-    fn read_deserialized<'de, D: Deserialize<'de>>(&'doc self, query: &str) -> Result<Option<D>> {
+    fn read_deserialized<D: DeserializeOwned>(&'doc self, query: &str) -> Result<Option<D>> {
         let raw = self.read(query)?;
 
         match raw {
@@ -46,10 +240,68 @@ pub trait TomlValueReadExt<'doc> {
         }
     }
 
+    /// Like `TomlValueReadExt::read_deserialized`, but a deserialization error reports the path
+    /// of the offending value in the document
+    ///
+    /// The path is the query followed by the location of the value inside the deserialized
+    /// subtree, for example `Error::TomlDeserializeAt("server.ports.[1]", _)` if the second port
+    /// of the `server` table has the wrong type.
     #[cfg(feature = "typed")]
-    fn read_partial<'a, P: Partial<'a>>(&'doc self) -> Result<Option<P::Output>> {
-        self.read_deserialized::<P::Output>(P::LOCATION)
+    fn read_deserialized_with_path<D: DeserializeOwned>(
+        &'doc self,
+        query: &str,
+    ) -> Result<Option<D>> {
+        let query = Query::parse(query)?;
+
+        match self.read_query(&query)? {
+            Some(value) => serde_path_to_error::deserialize(value.clone())
+                .map(Some)
+                .map_err(|e| {
+                    let path = error_path(&query, e.path());
+                    Error::TomlDeserializeAt(path, e.into_inner())
+                }),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the part of the document described by `P`
+    ///
+    /// Deserialization errors report the path of the offending value, see
+    /// `TomlValueReadExt::read_deserialized_with_path`.
+    #[cfg(feature = "typed")]
+    fn read_partial<'a, P: Partial<'a>>(&'doc self) -> Result<Option<P::Output>>
+    where
+        P::Output: DeserializeOwned,
+    {
+        self.read_deserialized_with_path::<P::Output>(P::LOCATION)
+    }
+}
+
+/// Render the query followed by the path serde reports inside the deserialized value
+#[cfg(feature = "typed")]
+fn error_path(query: &Query, path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+
+    let sep = query.seperator();
+    let mut rendered = query.to_string();
+
+    for segment in path.iter() {
+        let token = match *segment {
+            Segment::Seq { index } => Token::Index { idx: index },
+            Segment::Map { ref key } => Token::Identifier {
+                ident: Cow::Borrowed(key),
+            },
+            Segment::Enum { ref variant } => Token::Identifier {
+                ident: Cow::Borrowed(variant),
+            },
+            Segment::Unknown => continue,
+        };
+
+        rendered.push_str(sep);
+        rendered.push_str(&render_token(&token, sep));
     }
+
+    rendered
 }
 
 /// Describes a _part_ of a document
@@ -63,38 +315,340 @@ pub trait Partial<'a> {
 }
 
 impl<'doc> TomlValueReadExt<'doc> for Value {
-    fn read_with_seperator(&'doc self, query: &str, sep: char) -> Result<Option<&'doc Value>> {
-        use crate::resolver::non_mut_resolver::resolve;
-
-        tokenize_with_seperator(query, sep).and_then(move |tokens| resolve(self, &tokens, false))
+    fn read_with_seperator(&'doc self, query: &str, sep: &str) -> Result<Option<&'doc Value>> {
+        self.read_query(&Query::parse_with_seperator(query, sep)?)
     }
 
     fn read_mut_with_seperator(
         &'doc mut self,
         query: &str,
-        sep: char,
+        sep: &str,
     ) -> Result<Option<&'doc mut Value>> {
-        use crate::resolver::mut_resolver::resolve;
+        self.read_mut_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn read_query(&'doc self, query: &Query) -> Result<Option<&'doc Value>> {
+        use crate::resolver::in_query;
+        use crate::resolver::non_mut_resolver::resolve;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        resolve_parents(self, query.tokens())
+            .and_then(|tokens| match tokens {
+                Some(tokens) => resolve(self, &tokens, false, query.max_depth()),
+                None => Ok(Some(self)),
+            })
+            .map_err(|e| in_query(self, query, e))
+    }
+
+    /// Queries of keys and indices only are resolved in the order of their paths, so the values
+    /// along a path prefix which is shared with the previous query are looked up once. Other
+    /// queries, and queries which do not resolve cleanly, are passed to `read_query()`.
+    fn read_many(&'doc self, queries: &[&str]) -> Result<Vec<Option<&'doc Value>>> {
+        let queries = queries
+            .iter()
+            .map(|query| Query::parse(query))
+            .collect::<Result<Vec<_>>>()?;
+        let steps = queries.iter().map(steps_of).collect::<Vec<_>>();
+
+        let mut results = vec![None; queries.len()];
+        let mut order = Vec::with_capacity(queries.len());
+        for (idx, steps) in steps.iter().enumerate() {
+            match *steps {
+                Some(_) => order.push(idx),
+                None => results[idx] = self.read_query(&queries[idx])?,
+            }
+        }
+        order.sort_by(|&a, &b| steps[a].cmp(&steps[b]));
+
+        // `values[i]` is the value at the first `i` steps of `previous`
+        let mut previous: &[Step] = &[];
+        let mut values = vec![self];
+        for idx in order {
+            let current = steps[idx].as_ref().map(|s| &s[..]).unwrap_or_default();
+            let shared = previous
+                .iter()
+                .zip(current)
+                .take_while(|(a, b)| a == b)
+                .count()
+                .min(values.len() - 1);
+            values.truncate(shared + 1);
+
+            let mut unresolved = false;
+            for step in &current[shared..] {
+                let next = match (step, values[values.len() - 1]) {
+                    (Step::Key(key), Value::Table(table)) => table.get(*key),
+                    (Step::Index(idx), Value::Array(array)) => array.get(*idx),
+                    _ => {
+                        unresolved = true;
+                        break;
+                    }
+                };
+                match next {
+                    Some(value) => values.push(value),
+                    None => break,
+                }
+            }
+
+            results[idx] = if unresolved {
+                self.read_query(&queries[idx])?
+            } else if values.len() == current.len() + 1 {
+                Some(values[current.len()])
+            } else {
+                None
+            };
+            previous = current;
+        }
+
+        Ok(results)
+    }
+
+    fn read_mut_query(&'doc mut self, query: &Query) -> Result<Option<&'doc mut Value>> {
+        use crate::resolver::in_query;
+        use crate::resolver::mut_resolver::resolve_in_query;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        match resolve_parents(&*self, query.tokens()) {
+            Ok(Some(tokens)) => resolve_in_query(self, query, &tokens, false, query.max_depth()),
+            Ok(None) => Ok(Some(self)),
+            Err(e) => Err(in_query(&*self, query, e)),
+        }
+    }
+
+    fn read_all_with_seperator(
+        &'doc self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, &'doc Value)>> {
+        self.read_all_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn read_all_query(&'doc self, query: &Query) -> Result<Vec<(String, &'doc Value)>> {
+        use crate::resolver::non_mut_multi_resolver::resolve_all;
+
+        let (tokens, sep) = (query.tokens(), query.seperator());
+        let mut results = Vec::new();
+        resolve_all(self, tokens, sep, "", query.max_depth(), &mut results)?;
+        Ok(results)
+    }
+}
+
+/// A step of a query which consists of keys and indices only, see `read_many()`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Step<'q> {
+    Key(&'q str),
+    Index(usize),
+}
+
+/// The steps of `query`, if it consists of keys and indices only and does not exceed its depth
+/// limit
+fn steps_of<'q>(query: &'q Query) -> Option<Vec<Step<'q>>> {
+    if query.tokens().len() > query.max_depth() {
+        return None;
+    }
+
+    query
+        .tokens()
+        .iter()
+        .map(|token| match *token {
+            Token::Identifier { ref ident } => Some(Step::Key(ident)),
+            Token::Index { idx } => Some(Step::Index(idx)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// How the typed readers of `TomlValueReadTypeExt` treat values of another type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoercionPolicy {
+    /// Values of another type are a `TypeError`
+    Strict,
+
+    /// Values of another type are converted, if they can be converted without losing
+    /// information:
+    ///
+    /// * Strings from integers, floats, booleans and datetimes
+    /// * Integers from strings like `"42"`, floats without fractional part, and booleans (as
+    ///   `0` and `1`)
+    /// * Floats from integers and strings like `"4.2"`
+    /// * Booleans from the integers `0` and `1` and the strings `"true"`, `"false"`, `"yes"`,
+    ///   `"no"`, `"on"`, `"off"`, `"1"` and `"0"` (case insensitive)
+    ///
+    /// Other values are still a `TypeError`.
+    Lenient,
+}
 
-        tokenize_with_seperator(query, sep).and_then(move |tokens| resolve(self, &tokens, false))
+impl Default for CoercionPolicy {
+    fn default() -> CoercionPolicy {
+        CoercionPolicy::Strict
     }
 }
 
 pub trait TomlValueReadTypeExt<'doc>: TomlValueReadExt<'doc> {
-    fn read_string(&'doc self, query: &str) -> Result<Option<String>>;
-    fn read_int(&'doc self, query: &str) -> Result<Option<i64>>;
-    fn read_float(&'doc self, query: &str) -> Result<Option<f64>>;
-    fn read_bool(&'doc self, query: &str) -> Result<Option<bool>>;
+    fn read_string(&'doc self, query: &str) -> Result<Option<String>> {
+        self.read_string_with_policy(query, CoercionPolicy::Strict)
+    }
+
+    fn read_int(&'doc self, query: &str) -> Result<Option<i64>> {
+        self.read_int_with_policy(query, CoercionPolicy::Strict)
+    }
+
+    fn read_float(&'doc self, query: &str) -> Result<Option<f64>> {
+        self.read_float_with_policy(query, CoercionPolicy::Strict)
+    }
+
+    fn read_bool(&'doc self, query: &str) -> Result<Option<bool>> {
+        self.read_bool_with_policy(query, CoercionPolicy::Strict)
+    }
+
+    /// Read a string, with `policy` deciding whether values of other types are converted
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::read::{CoercionPolicy, TomlValueReadTypeExt};
+    ///
+    /// let toml : toml::Value = toml::from_str("port = \"8080\"\nverbose = 1").unwrap();
+    ///
+    /// assert!(toml.read_int("port").is_err());
+    /// assert_eq!(toml.read_int_with_policy("port", CoercionPolicy::Lenient).unwrap(), Some(8080));
+    /// assert_eq!(toml.read_bool_with_policy("verbose", CoercionPolicy::Lenient).unwrap(), Some(true));
+    /// ```
+    fn read_string_with_policy(
+        &'doc self,
+        query: &str,
+        policy: CoercionPolicy,
+    ) -> Result<Option<String>>;
+
+    /// Read an integer, see `TomlValueReadTypeExt::read_string_with_policy`
+    fn read_int_with_policy(&'doc self, query: &str, policy: CoercionPolicy)
+        -> Result<Option<i64>>;
+
+    /// Read a float, see `TomlValueReadTypeExt::read_string_with_policy`
+    fn read_float_with_policy(
+        &'doc self,
+        query: &str,
+        policy: CoercionPolicy,
+    ) -> Result<Option<f64>>;
+
+    /// Read a boolean, see `TomlValueReadTypeExt::read_string_with_policy`
+    fn read_bool_with_policy(
+        &'doc self,
+        query: &str,
+        policy: CoercionPolicy,
+    ) -> Result<Option<bool>>;
+
+    /// Read a value as `V`, see `TryFromValue` for the conversions
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::read::TomlValueReadTypeExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("port = 8080\nratio = 1").unwrap();
+    ///
+    /// assert_eq!(toml.read_as::<u16>("port").unwrap(), Some(8080));
+    /// assert_eq!(toml.read_as::<f32>("ratio").unwrap(), Some(1.0));
+    /// assert!(toml.read_as::<u8>("port").is_err());
+    /// ```
+    fn read_as<V: TryFromValue>(&'doc self, query: &str) -> Result<Option<V>> {
+        match self.read_with_seperator(query, ".")? {
+            Some(value) => V::try_from_value(value).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Conversion of a toml::Value into a Rust type, see `TomlValueReadTypeExt::read_as`
+///
+/// Integers are converted into all integer types, if they are in their range, and into floats.
+/// Floats are converted into `f64` and, if they are in its range, into `f32`. Other types only
+/// convert into the corresponding Rust type, a mismatch is a `TypeError`.
+pub trait TryFromValue: Sized {
+    fn try_from_value(value: &Value) -> Result<Self>;
+}
+
+macro_rules! impl_try_from_value_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl TryFromValue for $t {
+                fn try_from_value(value: &Value) -> Result<Self> {
+                    match *value {
+                        Value::Integer(i)
+                            if i128::from(i) < <$t>::min_value() as i128
+                                || i128::from(i) > <$t>::max_value() as i128 =>
+                        {
+                            Err(Error::OutOfRange(i.to_string(), stringify!($t)))
+                        }
+                        Value::Integer(i) => Ok(i as $t),
+                        ref value => Err(type_error("Integer", value)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_value_for_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl TryFromValue for f64 {
+    fn try_from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::Float(f) => Ok(f),
+            Value::Integer(i) => Ok(i as f64),
+            ref value => Err(type_error("Float", value)),
+        }
+    }
+}
+
+impl TryFromValue for f32 {
+    fn try_from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::Float(f) if f.is_finite() && f.abs() > f64::from(::std::f32::MAX) => {
+                Err(Error::OutOfRange(f.to_string(), "f32"))
+            }
+            Value::Float(f) => Ok(f as f32),
+            Value::Integer(i) => Ok(i as f32),
+            ref value => Err(type_error("Float", value)),
+        }
+    }
+}
+
+impl TryFromValue for bool {
+    fn try_from_value(value: &Value) -> Result<Self> {
+        coerce_bool(value, CoercionPolicy::Strict)
+    }
+}
+
+impl TryFromValue for String {
+    fn try_from_value(value: &Value) -> Result<Self> {
+        coerce_string(value, CoercionPolicy::Strict)
+    }
+}
+
+impl TryFromValue for Datetime {
+    fn try_from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::Datetime(ref d) => Ok(d.clone()),
+            ref value => Err(type_error("Datetime", value)),
+        }
+    }
+}
+
+impl TryFromValue for Value {
+    fn try_from_value(value: &Value) -> Result<Self> {
+        Ok(value.clone())
+    }
 }
 
 macro_rules! make_type_getter {
-    ($fnname:ident, $rettype:ty, $typename:expr, $matcher:pat => $implementation:expr) => {
-        fn $fnname(&'doc self, query: &str) -> Result<Option<$rettype>> {
-            self.read_with_seperator(query, '.').and_then(|o| match o {
-                $matcher => Ok(Some($implementation)),
-                Some(o)  => Err(Error::TypeError($typename, crate::util::name_of_val(&o)).into()),
-                None     => Ok(None),
-            })
+    ($fnname:ident, $rettype:ty, $coerce:ident) => {
+        fn $fnname(&'doc self, query: &str, policy: CoercionPolicy) -> Result<Option<$rettype>> {
+            match self.read_with_seperator(query, ".")? {
+                Some(value) => $coerce(value, policy).map(Some),
+                None => Ok(None),
+            }
         }
     };
 }
@@ -103,10 +657,66 @@ impl<'doc, T> TomlValueReadTypeExt<'doc> for T
 where
     T: TomlValueReadExt<'doc>,
 {
-    make_type_getter!(read_string, String, "String", Some(&Value::String(ref obj)) => obj.clone());
-    make_type_getter!(read_int, i64, "Integer", Some(&Value::Integer(obj)) => obj);
-    make_type_getter!(read_float, f64, "Float", Some(&Value::Float(obj)) => obj);
-    make_type_getter!(read_bool, bool, "Boolean", Some(&Value::Boolean(obj)) => obj);
+    make_type_getter!(read_string_with_policy, String, coerce_string);
+    make_type_getter!(read_int_with_policy, i64, coerce_int);
+    make_type_getter!(read_float_with_policy, f64, coerce_float);
+    make_type_getter!(read_bool_with_policy, bool, coerce_bool);
+}
+
+fn type_error(expected: &'static str, value: &Value) -> Error {
+    Error::TypeError(expected, crate::util::name_of_val(value))
+}
+
+fn coerce_string(value: &Value, policy: CoercionPolicy) -> Result<String> {
+    match (value, policy) {
+        (Value::String(s), _) => Ok(s.clone()),
+        (Value::Integer(i), CoercionPolicy::Lenient) => Ok(i.to_string()),
+        (Value::Float(f), CoercionPolicy::Lenient) => Ok(f.to_string()),
+        (Value::Boolean(b), CoercionPolicy::Lenient) => Ok(b.to_string()),
+        (Value::Datetime(d), CoercionPolicy::Lenient) => Ok(d.to_string()),
+        (value, _) => Err(type_error("String", value)),
+    }
+}
+
+fn coerce_int(value: &Value, policy: CoercionPolicy) -> Result<i64> {
+    match (value, policy) {
+        (Value::Integer(i), _) => Ok(*i),
+        (Value::String(s), CoercionPolicy::Lenient) => {
+            s.trim().parse().map_err(|_| type_error("Integer", value))
+        }
+        (Value::Float(f), CoercionPolicy::Lenient)
+            if f.fract() == 0.0 && *f >= ::std::i64::MIN as f64 && *f < ::std::i64::MAX as f64 =>
+        {
+            Ok(*f as i64)
+        }
+        (Value::Boolean(b), CoercionPolicy::Lenient) => Ok(i64::from(*b)),
+        (value, _) => Err(type_error("Integer", value)),
+    }
+}
+
+fn coerce_float(value: &Value, policy: CoercionPolicy) -> Result<f64> {
+    match (value, policy) {
+        (Value::Float(f), _) => Ok(*f),
+        (Value::Integer(i), CoercionPolicy::Lenient) => Ok(*i as f64),
+        (Value::String(s), CoercionPolicy::Lenient) => {
+            s.trim().parse().map_err(|_| type_error("Float", value))
+        }
+        (value, _) => Err(type_error("Float", value)),
+    }
+}
+
+fn coerce_bool(value: &Value, policy: CoercionPolicy) -> Result<bool> {
+    match (value, policy) {
+        (Value::Boolean(b), _) => Ok(*b),
+        (Value::Integer(0), CoercionPolicy::Lenient) => Ok(false),
+        (Value::Integer(1), CoercionPolicy::Lenient) => Ok(true),
+        (Value::String(s), CoercionPolicy::Lenient) => match s.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(true),
+            "false" | "no" | "off" | "0" => Ok(false),
+            _ => Err(type_error("Boolean", value)),
+        },
+        (value, _) => Err(type_error("Boolean", value)),
+    }
 }
 
 #[cfg(test)]
@@ -118,7 +728,7 @@ mod test {
     fn test_read_empty() {
         let toml: Value = toml_from_str("").unwrap();
 
-        let val = toml.read_with_seperator(&String::from("a"), '.');
+        let val = toml.read_with_seperator(&String::from("a"), ".");
 
         assert!(val.is_ok());
         let val = val.unwrap();
@@ -135,7 +745,7 @@ mod test {
         )
         .unwrap();
 
-        let val = toml.read_with_seperator(&String::from("table"), '.');
+        let val = toml.read_with_seperator(&String::from("table"), ".");
 
         assert!(val.is_ok());
         let val = val.unwrap();
@@ -160,7 +770,7 @@ mod test {
         )
         .unwrap();
 
-        let val = toml.read_with_seperator(&String::from("table.a"), '.');
+        let val = toml.read_with_seperator(&String::from("table.a"), ".");
 
         assert!(val.is_ok());
         let val = val.unwrap();
@@ -180,7 +790,7 @@ mod test {
         )
         .unwrap();
 
-        let val = toml.read_with_seperator(&String::from("table.a"), '.');
+        let val = toml.read_with_seperator(&String::from("table.a"), ".");
         assert!(val.is_ok());
         let val = val.unwrap();
 
@@ -196,11 +806,66 @@ mod test {
         )
         .unwrap();
 
-        let val = toml.read_with_seperator(&String::from("table.[0]"), '.');
+        let val = toml.read_with_seperator(&String::from("table.[0]"), ".");
         assert!(val.is_err());
         let err = val.unwrap_err();
 
-        assert!(is_match!(err, Error::NoIndexInTable(_)));
+        assert!(is_match!(*err.kind(), Error::NoIndexInTable(_)));
+    }
+
+    #[test]
+    fn test_read_error_context() {
+        let toml: Value = toml_from_str("[a]\nb = [ 1 ]").unwrap();
+
+        let err = toml.read("a.b.x").unwrap_err();
+        assert!(is_match!(*err.kind(), Error::NoIdentifierInArray(_)));
+        assert!(is_match!(
+            err,
+            Error::InQuery { ref query, ref resolved, segment: 2, .. }
+                if query == "a.b.x" && resolved == "a.b"
+        ));
+        assert_eq!(
+            "Resolving 'a.b.x' failed at segment 2 after 'a.b': \
+             Got an identifier query 'x' but have array",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_read_mut_error_context() {
+        let mut toml: Value = toml_from_str("[a]\nb = [ 1 ]\nc = 1").unwrap();
+
+        let err = toml.read_mut("a.b.x").unwrap_err();
+        assert!(is_match!(
+            err,
+            Error::InQuery { ref query, ref resolved, segment: 2, .. }
+                if query == "a.b.x" && resolved == "a.b"
+        ));
+
+        let err = toml.read_mut("a.b.^.c.d").unwrap_err();
+        assert!(is_match!(*err.kind(), Error::QueryingValueAsTable(_)));
+        assert!(is_match!(
+            err,
+            Error::InQuery { ref resolved, segment: 4, .. } if resolved == "a.b.^.c"
+        ));
+    }
+
+    #[test]
+    fn test_read_depth_limit() {
+        use crate::query::DEFAULT_MAX_DEPTH;
+
+        let toml: Value = toml_from_str("a = { b = { c = 1 } }").unwrap();
+
+        let query = Query::parse("a.b.c").unwrap().with_max_depth(2);
+        let err = toml.read_query(&query).unwrap_err();
+        assert!(is_match!(err, Error::DepthLimitExceeded(2)));
+
+        let query = query.with_max_depth(3);
+        assert_eq!(Some(&Value::Integer(1)), toml.read_query(&query).unwrap());
+
+        let deep = vec!["a"; 100_000].join(".");
+        let err = toml.read(&deep).unwrap_err();
+        assert!(is_match!(err, Error::DepthLimitExceeded(DEFAULT_MAX_DEPTH)));
     }
 
     ///
@@ -294,7 +959,282 @@ mod test {
         assert!(val.is_err());
         let err = val.unwrap_err();
 
-        assert!(is_match!(err, Error::NoIndexInTable(_)));
+        assert!(is_match!(*err.kind(), Error::NoIndexInTable(_)));
+    }
+
+    #[test]
+    fn test_read_recursive_descent_is_error() {
+        let toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        let val = toml.read(&String::from("**.a"));
+        assert!(val.is_err());
+        let err = val.unwrap_err();
+
+        assert!(is_match!(err, Error::MultiValueQuery));
+    }
+
+    #[test]
+    fn test_read_all() {
+        let toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = 1
+
+        [table.inner]
+        a = 2
+        "#,
+        )
+        .unwrap();
+
+        let val = toml.read_all(&String::from("**.a"));
+        assert!(val.is_ok());
+        let val = val.unwrap();
+
+        assert_eq!(2, val.len());
+        assert_eq!("table.a", val[0].0);
+        assert!(is_match!(val[0].1, &Value::Integer(1)));
+        assert_eq!("table.inner.a", val[1].0);
+        assert!(is_match!(val[1].1, &Value::Integer(2)));
+    }
+
+    #[test]
+    fn test_read_cow() {
+        let toml: Value = toml_from_str("[a]\nb = 1\nc = 2").unwrap();
+
+        let val = toml.read_cow("a.b").unwrap();
+        assert!(is_match!(val, Some(Cow::Borrowed(&Value::Integer(1)))));
+        assert!(is_match!(toml.read_cow("a.x"), Ok(None)));
+
+        let val = toml.read_cow("a.*").unwrap().unwrap();
+        assert_eq!(Value::from(vec![1, 2]), val.into_owned());
+
+        assert!(is_match!(toml.read_cow("x.*"), Ok(None)));
+    }
+
+    #[test]
+    fn test_read_owned() {
+        let toml: Value = toml_from_str("[a]\nb = [ 1 ]").unwrap();
+
+        let val = toml.read_owned("a.b").unwrap();
+        assert_eq!(Some(Value::from(vec![1])), val);
+        assert!(is_match!(toml.read_owned("a.x"), Ok(None)));
+        assert!(toml.read_owned("a.b.c").is_err());
+    }
+
+    #[test]
+    fn test_read_many() {
+        let toml: Value = toml_from_str("[a]\nb = [ 1, { c = 2 } ]\nd = 3").unwrap();
+        let queries = [
+            "a.d",
+            "a.b.[1].c",
+            "a.x.y",
+            "a.b.[0]",
+            "a.b.[1].c.^",
+            "a.b.[5]",
+            "a",
+        ];
+
+        let values = toml.read_many(&queries).unwrap();
+        let expected = queries
+            .iter()
+            .map(|query| toml.read(query).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(expected, values);
+        assert_eq!(Some(&Value::Integer(2)), values[1]);
+
+        assert!(toml.read_many(&["a", "a.d.e"]).is_err());
+        assert!(is_match!(
+            toml.read_many(&["a", ""]),
+            Err(Error::EmptyQueryError)
+        ));
+    }
+
+    #[test]
+    fn test_read_with_multi_char_seperator() {
+        let toml: Value = toml_from_str("[table]\na = [ 1 ]").unwrap();
+
+        let val = toml.read_with_seperator("table->a->[0]", "->");
+        assert!(is_match!(val, Ok(Some(&Value::Integer(1)))));
+    }
+
+    #[test]
+    fn test_read_array_of_tables_with_attached_index() {
+        let toml: Value = toml_from_str("[[bin]]\nname = \"a\"\n[[bin]]\nname = \"b\"").unwrap();
+
+        let val = toml.read("bin[1].name").unwrap();
+        assert_eq!(val, Some(&Value::String(String::from("b"))));
+    }
+
+    #[test]
+    fn test_read_all_with_seperator() {
+        let toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = [ 1 ]
+        "#,
+        )
+        .unwrap();
+
+        let val = toml.read_all_with_seperator(&String::from("table/a/[0]"), "/");
+        assert!(val.is_ok());
+        let val = val.unwrap();
+
+        assert_eq!(1, val.len());
+        assert_eq!("table/a/[0]", val[0].0);
+    }
+
+    #[test]
+    fn test_read_parent() {
+        let toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = 1
+        b = 2
+        "#,
+        )
+        .unwrap();
+
+        let val = toml.read(&String::from("table.a.^.b"));
+        assert!(val.is_ok());
+        let val = val.unwrap();
+
+        assert!(is_match!(val, Some(&Value::Integer(2))));
+
+        let val = toml.read(&String::from("table.^"));
+        assert!(val.is_ok());
+        let val = val.unwrap();
+
+        assert_eq!(Some(&toml), val);
+    }
+
+    #[test]
+    fn test_read_mut_modifies_in_place() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        {
+            let val = toml.read_mut(&String::from("table.a"));
+            assert!(val.is_ok());
+            let val = val.unwrap();
+
+            assert!(val.is_some());
+            *val.unwrap() = Value::Integer(2);
+        }
+
+        assert!(is_match!(
+            toml.read("table.a"),
+            Ok(Some(&Value::Integer(2)))
+        ));
+    }
+
+    #[test]
+    fn test_read_mut_missing_is_none() {
+        let mut toml: Value = toml_from_str("[table]").unwrap();
+
+        let val = toml.read_mut(&String::from("table.a"));
+        assert!(val.is_ok());
+
+        assert!(val.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_mut_type_mismatch_is_error() {
+        let mut toml: Value = toml_from_str("table = 1").unwrap();
+
+        let val = toml.read_mut(&String::from("table.a"));
+        assert!(val.is_err());
+        let err = val.unwrap_err();
+
+        assert!(is_match!(*err.kind(), Error::QueryingValueAsTable(_)));
+    }
+
+    #[test]
+    fn test_read_or() {
+        let toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        let val = toml.read_or("table.a", Value::Integer(2));
+        assert!(is_match!(val, Ok(Value::Integer(1))));
+
+        let val = toml.read_or("table.b", Value::Integer(2));
+        assert!(is_match!(val, Ok(Value::Integer(2))));
+
+        let val = toml.read_or("other.b", Value::Integer(2));
+        assert!(is_match!(val, Ok(Value::Integer(2))));
+    }
+
+    #[test]
+    fn test_read_or_type_mismatch_is_error() {
+        let toml: Value = toml_from_str("table = 1").unwrap();
+
+        let val = toml.read_or("table.a", Value::Integer(2));
+        assert!(val.is_err());
+        let err = val.unwrap_err();
+
+        assert!(is_match!(*err.kind(), Error::QueryingValueAsTable(_)));
+    }
+
+    #[test]
+    fn test_read_or_else() {
+        let toml: Value = toml_from_str("a = [ 1 ]").unwrap();
+
+        let val = toml.read_or_else("a.[0]", || panic!("Must not be called"));
+        assert!(is_match!(val, Ok(Value::Integer(1))));
+
+        let val = toml.read_or_else("a.[1]", || Value::Integer(2));
+        assert!(is_match!(val, Ok(Value::Integer(2))));
+    }
+
+    #[test]
+    fn test_read_query() {
+        let query = Query::parse("table.a").unwrap();
+
+        let toml: Value = toml_from_str("table = { a = 1 }").unwrap();
+        assert!(is_match!(
+            toml.read_query(&query),
+            Ok(Some(&Value::Integer(1)))
+        ));
+
+        let toml: Value = toml_from_str("table = { b = 1 }").unwrap();
+        assert!(is_match!(toml.read_query(&query), Ok(None)));
+    }
+
+    #[test]
+    fn test_read_append_is_error() {
+        let toml: Value = toml_from_str("a = [ 1 ]").unwrap();
+
+        let val = toml.read(&String::from("a.[+]"));
+        assert!(val.is_err());
+        let err = val.unwrap_err();
+
+        assert!(is_match!(err, Error::AppendNotSupported));
+    }
+
+    #[test]
+    fn test_read_parent_of_root_is_error() {
+        let toml: Value = toml_from_str("").unwrap();
+
+        let val = toml.read(&String::from("^.a"));
+        assert!(val.is_err());
+        let err = val.unwrap_err();
+
+        assert!(is_match!(err, Error::ParentOfRoot));
     }
 
 }
@@ -319,6 +1259,95 @@ mod high_level_fn_test {
         assert_eq!(val.unwrap(), 1);
     }
 
+    #[test]
+    fn test_read_strict_type_error() {
+        let toml: Value = toml_from_str("a = \"1\"").unwrap();
+
+        let err = toml.read_int("a").unwrap_err();
+        assert!(is_match!(err, Error::TypeError("Integer", "String")));
+    }
+
+    #[test]
+    fn test_read_lenient() {
+        let toml: Value = toml_from_str(
+            r#"
+        int = "42"
+        float = 2
+        whole = 3.0
+        yes = "Yes"
+        one = 1
+        flag = true
+        "#,
+        )
+        .unwrap();
+        let lenient = CoercionPolicy::Lenient;
+
+        assert_eq!(Some(42), toml.read_int_with_policy("int", lenient).unwrap());
+        assert_eq!(
+            Some(3),
+            toml.read_int_with_policy("whole", lenient).unwrap()
+        );
+        assert_eq!(Some(1), toml.read_int_with_policy("flag", lenient).unwrap());
+        assert_eq!(
+            Some(2.0),
+            toml.read_float_with_policy("float", lenient).unwrap()
+        );
+        assert_eq!(
+            Some(42.0),
+            toml.read_float_with_policy("int", lenient).unwrap()
+        );
+        assert_eq!(
+            Some(true),
+            toml.read_bool_with_policy("yes", lenient).unwrap()
+        );
+        assert_eq!(
+            Some(true),
+            toml.read_bool_with_policy("one", lenient).unwrap()
+        );
+        assert_eq!(
+            Some(String::from("true")),
+            toml.read_string_with_policy("flag", lenient).unwrap()
+        );
+        assert_eq!(None, toml.read_int_with_policy("missing", lenient).unwrap());
+    }
+
+    #[test]
+    fn test_read_as() {
+        let toml: Value = toml_from_str("a = 300\nb = -1\nc = 1.5\nd = \"x\"").unwrap();
+
+        assert_eq!(Some(300u16), toml.read_as("a").unwrap());
+        assert_eq!(Some(300usize), toml.read_as("a").unwrap());
+        assert_eq!(Some(-1i8), toml.read_as("b").unwrap());
+        assert_eq!(Some(300.0f64), toml.read_as("a").unwrap());
+        assert_eq!(Some(1.5f32), toml.read_as("c").unwrap());
+        assert_eq!(Some(String::from("x")), toml.read_as("d").unwrap());
+        assert_eq!(None, toml.read_as::<i64>("e").unwrap());
+
+        let err = toml.read_as::<u8>("a").unwrap_err();
+        assert!(is_match!(err, Error::OutOfRange(ref v, "u8") if v == "300"));
+
+        let err = toml.read_as::<u64>("b").unwrap_err();
+        assert!(is_match!(err, Error::OutOfRange(ref v, "u64") if v == "-1"));
+
+        let err = toml.read_as::<i32>("c").unwrap_err();
+        assert!(is_match!(err, Error::TypeError("Integer", "Float")));
+    }
+
+    #[test]
+    fn test_read_lenient_type_error() {
+        let toml: Value = toml_from_str("a = \"4.2\"\nb = 2\nc = [ 1 ]").unwrap();
+        let lenient = CoercionPolicy::Lenient;
+
+        let err = toml.read_int_with_policy("a", lenient).unwrap_err();
+        assert!(is_match!(err, Error::TypeError("Integer", "String")));
+
+        let err = toml.read_bool_with_policy("b", lenient).unwrap_err();
+        assert!(is_match!(err, Error::TypeError("Boolean", "Integer")));
+
+        let err = toml.read_string_with_policy("c", lenient).unwrap_err();
+        assert!(is_match!(err, Error::TypeError("String", "Array")));
+    }
+
     #[cfg(feature = "typed")]
     #[test]
     fn test_name() {
@@ -363,6 +1392,26 @@ mod high_level_fn_test {
         assert!(true);
     }
 
+    #[cfg(feature = "typed")]
+    #[test]
+    fn test_read_deserialized_missing_is_none() {
+        let toml: Value = toml_from_str("[table]").unwrap();
+
+        let val = toml.read_deserialized::<u32>("table.a");
+        assert!(is_match!(val, Ok(None)));
+    }
+
+    #[cfg(feature = "typed")]
+    #[test]
+    fn test_read_deserialized_wrong_type_is_error() {
+        let toml: Value = toml_from_str("[table]\na = \"b\"").unwrap();
+
+        let val = toml.read_deserialized::<u32>("table.a");
+        assert!(val.is_err());
+
+        assert!(is_match!(val.unwrap_err(), Error::TomlDeserialize(_)));
+    }
+
 }
 
 #[cfg(all(test, feature = "typed"))]
@@ -382,6 +1431,79 @@ mod partial_tests {
         type Output = Self;
     }
 
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Server {
+        pub host: String,
+        pub ports: Vec<u16>,
+    }
+
+    impl<'a> Partial<'a> for Server {
+        const LOCATION: &'static str = "servers.'eu.west'";
+        type Output = Self;
+    }
+
+    #[test]
+    fn test_read_deserialized_with_path() {
+        let toml: Value = toml::from_str(
+            r#"
+        [app.server]
+        host = "localhost"
+        ports = [ 80, 443 ]
+        "#,
+        )
+        .unwrap();
+
+        let server = toml.read_deserialized_with_path::<Server>("app.server");
+        assert!(server.is_ok());
+        let server = server.unwrap().unwrap();
+
+        assert_eq!("localhost", server.host);
+        assert_eq!(vec![80, 443], server.ports);
+
+        let server = toml.read_deserialized_with_path::<Server>("app.nothing");
+        assert!(is_match!(server, Ok(None)));
+    }
+
+    #[test]
+    fn test_read_deserialized_with_path_reports_path() {
+        let toml: Value = toml::from_str(
+            r#"
+        [app.server]
+        host = "localhost"
+        ports = [ 80, "https" ]
+        "#,
+        )
+        .unwrap();
+
+        let server = toml.read_deserialized_with_path::<Server>("app.server");
+        assert!(server.is_err());
+
+        match server.unwrap_err() {
+            Error::TomlDeserializeAt(path, _) => assert_eq!("app.server.ports.[1]", path),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_read_partial_reports_path() {
+        let toml: Value = toml::from_str(
+            r#"
+        [servers."eu.west"]
+        host = 1
+        ports = []
+        "#,
+        )
+        .unwrap();
+
+        let server = toml.read_partial::<Server>();
+        assert!(server.is_err());
+
+        match server.unwrap_err() {
+            Error::TomlDeserializeAt(path, _) => assert_eq!(r#"servers."eu.west".host"#, path),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
     #[test]
     fn test_compiles() {
         let tbl = {
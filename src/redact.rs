@@ -0,0 +1,107 @@
+/// The Toml Redaction extensions
+use toml::Value;
+
+use crate::error::Result;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::util::path_contains;
+
+pub trait TomlValueRedactExt {
+    /// Extension function for replacing the values matching `patterns` in the current
+    /// toml::Value document with `placeholder`
+    ///
+    /// # Semantics
+    ///
+    /// The patterns are queries which may match several values, like `**.password`, see
+    /// `TomlValueReadExt::read_all`. Values inside of a matched table or array are replaced along
+    /// with it and are not reported separately.
+    ///
+    /// # Return value
+    ///
+    /// The paths of the replaced values, in the order in which the patterns matched them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::redact::TomlValueRedactExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str(r#"
+    /// [database]
+    /// user = "app"
+    /// password = "secret"
+    /// [[mirrors]]
+    /// token = "abc"
+    /// "#).unwrap();
+    ///
+    /// let redacted = toml.redact(&["**.password", "mirrors.*.token"], "***".into()).unwrap();
+    ///
+    /// assert_eq!(redacted, vec!["database.password", "mirrors.[0].token"]);
+    /// assert_eq!(toml["database"]["password"].as_str(), Some("***"));
+    /// ```
+    fn redact(&mut self, patterns: &[&str], placeholder: Value) -> Result<Vec<String>>;
+}
+
+impl TomlValueRedactExt for Value {
+    fn redact(&mut self, patterns: &[&str], placeholder: Value) -> Result<Vec<String>> {
+        let mut matched = Vec::new();
+        for pattern in patterns {
+            for (path, _) in self.read_all(pattern)? {
+                if !matched.contains(&path) {
+                    matched.push(path);
+                }
+            }
+        }
+
+        let redacted = matched
+            .iter()
+            .filter(|path| {
+                !matched
+                    .iter()
+                    .any(|outer| outer != *path && path_contains(outer, path))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for path in &redacted {
+            if let Some(value) = self.read_mut_query(&Query::parse(path)?)? {
+                *value = placeholder.clone();
+            }
+        }
+
+        Ok(redacted)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_redact() {
+        let mut toml: Value = toml_from_str(
+            r#"
+            key = "a"
+            [secrets]
+            key = "b"
+            list = [ "c", "d" ]
+            [other]
+            value = 1
+            "#,
+        )
+        .unwrap();
+
+        let redacted = toml
+            .redact(&["secrets.list.*", "**.key", "secrets"], Value::from("-"))
+            .unwrap();
+        assert_eq!(vec!["key", "secrets"], redacted);
+
+        let expected: Value =
+            toml_from_str("key = '-'\nsecrets = '-'\n[other]\nvalue = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
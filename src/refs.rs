@@ -0,0 +1,197 @@
+/// Replacing references to other values of a document
+use std::collections::HashMap;
+
+use toml::map::Map;
+use toml::Value;
+
+use crate::error::{Cycle, Error, Result};
+use crate::read::TomlValueReadExt;
+use crate::tokenizer::quote_identifier;
+use crate::util::{child_path, path_contains};
+
+/// The key of a table which refers to another value
+pub const REF_KEY: &str = "$ref";
+
+pub trait TomlValueRefsExt {
+    /// Extension function for replacing the references in the current toml::Value document with
+    /// clones of the values they refer to
+    ///
+    /// # Semantics
+    ///
+    /// A reference is a table with the single key `$ref`, whose value is a string with the query
+    /// for the value it refers to, like `timeout = { "$ref" = "defaults.timeout" }`. Tables with
+    /// more keys or a `$ref` which is no string are no references.
+    ///
+    /// References in the values which are referred to are replaced as well. References which
+    /// refer to themselves (directly, over other references or by referring to a table or array
+    /// which contains them) are an `Error::RefCycle`.
+    ///
+    /// The document is left as it is if replacing a reference fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::refs::TomlValueRefsExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str(r#"
+    /// [defaults]
+    /// timeout = 30
+    /// [server]
+    /// timeout = { "$ref" = "defaults.timeout" }
+    /// "#).unwrap();
+    ///
+    /// toml.resolve_refs().unwrap();
+    ///
+    /// assert_eq!(toml["server"]["timeout"].as_integer(), Some(30));
+    /// ```
+    fn resolve_refs(&mut self) -> Result<()>;
+}
+
+impl TomlValueRefsExt for Value {
+    fn resolve_refs(&mut self) -> Result<()> {
+        let resolved = {
+            let mut resolver = RefResolver {
+                doc: &*self,
+                stack: Vec::new(),
+                done: HashMap::new(),
+            };
+            resolver.value(&*self, "")?
+        };
+
+        *self = resolved;
+        Ok(())
+    }
+}
+
+struct RefResolver<'doc> {
+    doc: &'doc Value,
+
+    /// The paths of the references which are being replaced
+    stack: Vec<String>,
+
+    /// The replaced references, by their path
+    done: HashMap<String, Value>,
+}
+
+impl<'doc> RefResolver<'doc> {
+    fn value(&mut self, value: &Value, path: &str) -> Result<Value> {
+        match *value {
+            Value::Table(ref table) => match ref_of(table) {
+                Some(query) => self.reference(query, path),
+                None => table
+                    .iter()
+                    .map(|(key, value)| {
+                        let path = child_path(path, &quote_identifier(key, "."), ".");
+                        self.value(value, &path).map(|value| (key.clone(), value))
+                    })
+                    .collect::<Result<_>>()
+                    .map(Value::Table),
+            },
+            Value::Array(ref array) => array
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| {
+                    self.value(value, &child_path(path, &format!("[{}]", idx), "."))
+                })
+                .collect::<Result<_>>()
+                .map(Value::Array),
+            ref value => Ok(value.clone()),
+        }
+    }
+
+    /// The resolved value `query` refers to, from the reference at `path`
+    fn reference(&mut self, query: &str, path: &str) -> Result<Value> {
+        if let Some(value) = self.done.get(path) {
+            return Ok(value.clone());
+        }
+
+        self.stack.push(path.to_owned());
+        let resolved = self.target(query, path);
+        self.stack.pop();
+
+        let resolved = resolved?;
+        self.done.insert(path.to_owned(), resolved.clone());
+        Ok(resolved)
+    }
+
+    fn target(&mut self, query: &str, path: &str) -> Result<Value> {
+        let mut values = self.doc.read_all(query)?;
+        if values.len() > 1 {
+            return Err(Error::MultiValueQuery);
+        }
+        let (target, value) = values
+            .pop()
+            .ok_or_else(|| Error::UnresolvedRef(query.to_owned(), path.to_owned()))?;
+
+        if let Some(start) = self.stack.iter().position(|s| path_contains(&target, s)) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(target);
+            return Err(Error::RefCycle(Cycle(cycle)));
+        }
+
+        self.value(value, &target)
+    }
+}
+
+/// The query of `table`, if it is a reference
+fn ref_of(table: &Map<String, Value>) -> Option<&str> {
+    match table.get(REF_KEY) {
+        Some(Value::String(query)) if table.len() == 1 => Some(query),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_resolve_refs() {
+        let mut toml: Value = toml_from_str(
+            r#"
+            [defaults]
+            timeout = 30
+            retries = { "$ref" = "defaults.timeout" }
+            [[servers]]
+            limits = { "$ref" = "defaults" }
+            [[servers]]
+            limits = { "$ref" = "servers.[0].limits" }
+            other = { "$ref" = 1 }
+            "#,
+        )
+        .unwrap();
+
+        toml.resolve_refs().unwrap();
+
+        let defaults: Value = toml_from_str("timeout = 30\nretries = 30").unwrap();
+        assert_eq!(defaults, toml["defaults"]);
+        assert_eq!(defaults, toml["servers"][0]["limits"]);
+        assert_eq!(defaults, toml["servers"][1]["limits"]);
+        assert_eq!(Value::Integer(1), toml["servers"][1]["other"]["$ref"]);
+    }
+
+    #[test]
+    fn test_resolve_refs_errors() {
+        let resolve_refs = |doc: &str| {
+            let mut toml: Value = toml_from_str(doc).unwrap();
+            toml.resolve_refs().unwrap_err()
+        };
+
+        match resolve_refs("a = { '$ref' = 'b' }\nb = { '$ref' = 'a' }") {
+            Error::RefCycle(cycle) => assert_eq!("a -> b -> a", cycle.to_string()),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+        match resolve_refs("[a]\nb = { '$ref' = 'a' }") {
+            Error::RefCycle(cycle) => assert_eq!("a.b -> a", cycle.to_string()),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+
+        let error = resolve_refs("a = { '$ref' = 'x' }");
+        assert!(is_match!(error, Error::UnresolvedRef(..)));
+    }
+
+}
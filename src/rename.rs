@@ -0,0 +1,204 @@
+/// The Toml Rename extensions
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::tokenizer::Token;
+use crate::util::suggest;
+
+pub trait TomlValueRenameExt {
+    /// Extension function for renaming a key in the current toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// The last segment of the query must be a key, which is renamed to `new_key` within its
+    /// table. The value itself is not touched. The new key is used as is, so it needs no quoting.
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(())` if the key was renamed
+    /// * On failure, `Err(e)` is returned:
+    ///     * If there is no value at the queried path: `Error::IdentifierNotFoundInDocument`
+    ///     * If the table contains `new_key` already: `Error::KeyExists`, see
+    ///       `TomlValueRenameExt::rename_overwriting` for replacing the existing value instead
+    ///     * If the query does not end with a key (for example `"a.[0]"`):
+    ///       `Error::RenameNonIdentifier`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::rename::TomlValueRenameExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[server]\nhostname = \"a\"").unwrap();
+    ///
+    /// assert!(toml.rename("server.hostname", "host").is_ok());
+    /// assert_eq!(toml, toml::from_str("[server]\nhost = \"a\"").unwrap());
+    /// ```
+    fn rename(&mut self, query: &str, new_key: &str) -> Result<()> {
+        self.rename_query(&Query::parse(query)?, new_key, false)
+            .map(|_| ())
+    }
+
+    /// Extension function for renaming a key in the current toml::Value document, replacing the
+    /// value at `new_key` if there is one
+    ///
+    /// The replaced value is returned, see documentation of `TomlValueRenameExt::rename`.
+    fn rename_overwriting(&mut self, query: &str, new_key: &str) -> Result<Option<Value>> {
+        self.rename_query(&Query::parse(query)?, new_key, true)
+    }
+
+    /// Extension function for renaming a key in the current toml::Value document using a
+    /// pre-compiled query
+    ///
+    /// If `overwrite` is false, an existing `new_key` is an error. Otherwise its value is
+    /// replaced and returned.
+    fn rename_query(
+        &mut self,
+        query: &Query,
+        new_key: &str,
+        overwrite: bool,
+    ) -> Result<Option<Value>>;
+}
+
+impl TomlValueRenameExt for Value {
+    fn rename_query(
+        &mut self,
+        query: &Query,
+        new_key: &str,
+        overwrite: bool,
+    ) -> Result<Option<Value>> {
+        use crate::resolver::mut_resolver::resolve;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        let mut tokens = resolve_parents(self, query.tokens())?
+            .ok_or(Error::RenameNonIdentifier)?
+            .into_owned();
+        let last = tokens.pop().unwrap(); // safe because resolve_parents() yields no empty list
+        let val = resolve(self, &tokens, true, query.max_depth())?.unwrap(); // safe because of resolve() guarantees
+
+        let ident = match last {
+            Token::Identifier { ident, .. } => ident,
+            _ => return Err(Error::RenameNonIdentifier),
+        };
+
+        match *val {
+            Value::Table(ref mut t) => {
+                if !t.contains_key(&*ident) {
+                    let suggestion = suggest(&ident, t.keys().map(String::as_str));
+                    return Err(Error::IdentifierNotFoundInDocument(
+                        ident.into_owned(),
+                        suggestion,
+                    ));
+                }
+
+                if ident == new_key {
+                    return Ok(None);
+                }
+
+                if !overwrite && t.contains_key(new_key) {
+                    return Err(Error::KeyExists(String::from(new_key)));
+                }
+
+                let value = t.remove(&*ident).unwrap(); // safe because of the check above
+                Ok(t.insert(String::from(new_key), value))
+            }
+            Value::Array(_) => Err(Error::NoIdentifierInArray(ident.into_owned())),
+            _ => Err(Error::QueryingValueAsTable(ident.into_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_rename() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = [ 1 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.rename("table.a", "b.c");
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str(r#"table = { "b.c" = [ 1 ] }"#).unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_rename_top_level_key() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.rename("a", "b");
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("b = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_rename_to_existing_key_is_error() {
+        let mut toml: Value = toml_from_str("a = 1\nb = 2").unwrap();
+
+        let res = toml.rename("a", "b");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::KeyExists(_)));
+
+        let expected: Value = toml_from_str("a = 1\nb = 2").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_rename_overwriting() {
+        let mut toml: Value = toml_from_str("a = 1\nb = 2").unwrap();
+
+        let res = toml.rename_overwriting("a", "b");
+        assert!(res.is_ok());
+        assert_eq!(Some(Value::Integer(2)), res.unwrap());
+
+        let expected: Value = toml_from_str("b = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_rename_to_same_key() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.rename("a", "a");
+        assert!(res.is_ok());
+
+        let expected: Value = toml_from_str("a = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_rename_missing_key_is_error() {
+        let mut toml: Value = toml_from_str("[table]").unwrap();
+
+        let res = toml.rename("table.a", "b");
+        assert!(res.is_err());
+        assert!(is_match!(
+            res.unwrap_err(),
+            Error::IdentifierNotFoundInDocument(_, _)
+        ));
+    }
+
+    #[test]
+    fn test_rename_index_is_error() {
+        let mut toml: Value = toml_from_str("a = [ 1 ]").unwrap();
+
+        let res = toml.rename("a.[0]", "b");
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err(), Error::RenameNonIdentifier));
+    }
+
+}
@@ -0,0 +1,272 @@
+/// Resolving queries with custom options
+use toml::Value;
+
+use crate::error::Result;
+use crate::query::{Query, DEFAULT_MAX_DEPTH};
+use crate::resolver::in_query;
+use crate::tokenizer::Token;
+
+/// How `resolve_with()` walks the path of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolveOptions {
+    /// Create the tables and arrays which are missing on the path, like `insert()` does. If the
+    /// value itself is missing, it is created as an empty table.
+    pub create_intermediates: bool,
+
+    /// Return `Ok(None)` if the value is missing, instead of
+    /// `Error::IdentifierNotFoundInDocument` or `Error::ArrayIndexOutOfBounds`
+    pub treat_missing_as_none: bool,
+
+    /// The number of levels the query may descend into the document, this is used instead of
+    /// `Query::max_depth()`
+    pub max_depth: usize,
+
+    /// Match the identifiers of the query with the keys of tables regardless of their case
+    ///
+    /// If several keys of a table match an identifier then, this is an
+    /// `Error::AmbiguousKey`. Keys which are created are named as in the query.
+    pub case_insensitive: bool,
+}
+
+impl Default for ResolveOptions {
+    /// The options for reading: nothing is created and missing values are `None`
+    fn default() -> ResolveOptions {
+        ResolveOptions {
+            create_intermediates: false,
+            treat_missing_as_none: true,
+            max_depth: DEFAULT_MAX_DEPTH,
+            case_insensitive: false,
+        }
+    }
+}
+
+/// Resolve `query` in `doc` with the passed options, returning the value it refers to
+///
+/// Only queries which refer to a single value are supported, like for `read()`. `[+]` tokens
+/// are only supported with `create_intermediates`, they append a new element to the array.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::query::Query;
+/// use toml_query::resolve::{resolve_with, ResolveOptions};
+///
+/// let mut toml : toml::Value = toml::from_str("[a]\nb = 1").unwrap();
+/// let query = Query::parse("a.c.d").unwrap();
+///
+/// assert!(resolve_with(&mut toml, &query, ResolveOptions::default()).unwrap().is_none());
+///
+/// let options = ResolveOptions {
+///     create_intermediates: true,
+///     ..ResolveOptions::default()
+/// };
+/// *resolve_with(&mut toml, &query, options).unwrap().unwrap() = toml::Value::Integer(2);
+///
+/// assert_eq!(toml, toml::from_str("[a]\nb = 1\n[a.c]\nd = 2").unwrap());
+/// ```
+pub fn resolve_with<'doc>(
+    doc: &'doc mut Value,
+    query: &Query,
+    options: ResolveOptions,
+) -> Result<Option<&'doc mut Value>> {
+    use crate::resolver::case_resolver::resolve_case;
+    use crate::resolver::parent_resolver::resolve_parents;
+
+    let error_if_not_found = !options.create_intermediates && !options.treat_missing_as_none;
+
+    let parents_resolved = match resolve_parents(&*doc, query.tokens()) {
+        Ok(Some(tokens)) => tokens,
+        Ok(None) => return Ok(Some(doc)),
+        Err(e) => return Err(in_query(&*doc, query, e)),
+    };
+
+    let case_resolved;
+    let tokens = if options.case_insensitive {
+        case_resolved = resolve_case(&*doc, &parents_resolved)?;
+        &case_resolved
+    } else {
+        &parents_resolved
+    };
+
+    // The identifiers of the query may differ from the keys they matched in the document
+    if options.case_insensitive {
+        let query = Query::from_tokens(tokens.iter().cloned().collect(), query.seperator());
+        resolve_tokens(doc, &query, tokens, error_if_not_found, options)
+    } else {
+        resolve_tokens(doc, query, tokens, error_if_not_found, options)
+    }
+}
+
+fn resolve_tokens<'doc>(
+    doc: &'doc mut Value,
+    query: &Query,
+    tokens: &[Token],
+    error_if_not_found: bool,
+    options: ResolveOptions,
+) -> Result<Option<&'doc mut Value>> {
+    use crate::resolver::mut_creating_resolver::resolve_in_query as resolve_creating;
+    use crate::resolver::mut_resolver::resolve_in_query;
+
+    if options.create_intermediates {
+        resolve_creating(doc, query, tokens, options.max_depth).map(Some)
+    } else {
+        resolve_in_query(doc, query, tokens, error_if_not_found, options.max_depth)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_resolve_with_default_options() {
+        let mut toml: Value = toml_from_str("[a]\nb = [ 1 ]").unwrap();
+
+        let query = Query::parse("a.b.[0]").unwrap();
+        let result = resolve_with(&mut toml, &query, ResolveOptions::default());
+        assert!(is_match!(result, Ok(Some(&mut Value::Integer(1)))));
+
+        let query = Query::parse("a.x").unwrap();
+        let result = resolve_with(&mut toml, &query, ResolveOptions::default());
+        assert!(is_match!(result, Ok(None)));
+
+        let query = Query::parse("a.b.x").unwrap();
+        let result = resolve_with(&mut toml, &query, ResolveOptions::default());
+        assert!(result.is_err());
+        assert!(is_match!(
+            *result.unwrap_err().kind(),
+            Error::NoIdentifierInArray(_)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_with_missing_as_error() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let options = ResolveOptions {
+            treat_missing_as_none: false,
+            ..ResolveOptions::default()
+        };
+
+        let query = Query::parse("a.x").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(result.is_err());
+        assert!(is_match!(
+            result.unwrap_err(),
+            Error::InQuery { segment: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_resolve_with_create_intermediates() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let options = ResolveOptions {
+            create_intermediates: true,
+            ..ResolveOptions::default()
+        };
+
+        let query = Query::parse("a.c.[0].d").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(result, Ok(Some(&mut Value::Table(_)))));
+
+        let expected: Value = toml_from_str("[a]\nb = 1\n[[a.c]]\n[a.c.d]").unwrap();
+        assert_eq!(expected, toml);
+
+        let query = Query::parse("a.b.c").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(result.is_err());
+        assert!(is_match!(
+            *result.unwrap_err().kind(),
+            Error::QueryingValueAsTable(_)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_with_create_intermediates_append() {
+        let mut toml: Value = toml_from_str("jobs = [ 1 ]").unwrap();
+        let options = ResolveOptions {
+            create_intermediates: true,
+            ..ResolveOptions::default()
+        };
+
+        let query = Query::parse("jobs.[+]").unwrap();
+        *resolve_with(&mut toml, &query, options).unwrap().unwrap() = Value::Integer(2);
+
+        let query = Query::parse("jobs.[+].name").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(result, Ok(Some(&mut Value::Table(_)))));
+
+        let expected: Value = toml_from_str("jobs = [ 1, 2, { name = {} } ]").unwrap();
+        assert_eq!(expected, toml);
+
+        let query = Query::parse("jobs.[0].[+]").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(
+            *result.unwrap_err().kind(),
+            Error::AppendToNonArray(_)
+        ));
+
+        let query = Query::parse("jobs.[1].x").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(
+            result.unwrap_err(),
+            Error::InQuery { segment: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_resolve_with_case_insensitive() {
+        let mut toml: Value = toml_from_str("[Server]\nPort = 1").unwrap();
+        let options = ResolveOptions {
+            case_insensitive: true,
+            create_intermediates: true,
+            ..ResolveOptions::default()
+        };
+
+        let query = Query::parse("server.port").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(result, Ok(Some(&mut Value::Integer(1)))));
+
+        let query = Query::parse("SERVER.Host").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(result, Ok(Some(&mut Value::Table(_)))));
+
+        let expected: Value = toml_from_str("[Server]\nPort = 1\n[Server.Host]").unwrap();
+        assert_eq!(expected, toml);
+
+        let query = Query::parse("server.host").unwrap();
+        let result = resolve_with(&mut toml, &query, ResolveOptions::default());
+        assert!(is_match!(result, Ok(None)));
+    }
+
+    #[test]
+    fn test_resolve_with_case_insensitive_collision() {
+        let mut toml: Value = toml_from_str("[a]\nKey = 1\nkey = 2").unwrap();
+        let options = ResolveOptions {
+            case_insensitive: true,
+            ..ResolveOptions::default()
+        };
+
+        let query = Query::parse("A.KEY").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(result, Err(Error::AmbiguousKey(_, _))));
+    }
+
+    #[test]
+    fn test_resolve_with_max_depth() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let options = ResolveOptions {
+            max_depth: 1,
+            ..ResolveOptions::default()
+        };
+
+        let query = Query::parse("a.b").unwrap();
+        let result = resolve_with(&mut toml, &query, options);
+        assert!(is_match!(result, Err(Error::DepthLimitExceeded(1))));
+    }
+
+}
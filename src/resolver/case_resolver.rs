@@ -0,0 +1,117 @@
+/// The resolver for matching table keys regardless of their case
+///
+/// The single value resolvers look keys up as they are, so the identifiers of a query are replaced
+/// by the keys of the document they match when ignoring case, before the query is handed to them.
+use std::borrow::Cow;
+
+use crate::document::DocumentLike;
+use crate::error::{Error, Result};
+use crate::tokenizer::Token;
+
+/// Replaces the identifiers in `tokens` by the keys of `toml` which match them ignoring case
+///
+/// An identifier which matches no key is left as it is, as is everything after it. An identifier
+/// which matches more than one key is an error.
+///
+/// # Returns
+///
+/// `Err(Error::AmbiguousKey(_, _))` with all matching keys, if the keys of a table collide when
+/// ignoring case.
+///
+pub fn resolve_case<'t, 'q, D: DocumentLike>(
+    toml: &D,
+    tokens: &'t [Token<'q>],
+) -> Result<Cow<'t, [Token<'q>]>> {
+    let mut resolved = Cow::Borrowed(tokens);
+    let mut current = Some(toml);
+
+    for (i, token) in tokens.iter().enumerate() {
+        let doc = match current {
+            Some(doc) => doc,
+            None => break,
+        };
+
+        current = match *token {
+            Token::Identifier { ref ident } => {
+                let folded = ident.to_lowercase();
+                let keys = doc
+                    .keys()
+                    .into_iter()
+                    .filter(|key| key.to_lowercase() == folded)
+                    .collect::<Vec<_>>();
+
+                match keys.len() {
+                    0 => None,
+                    1 => {
+                        if keys[0] != ident {
+                            resolved.to_mut()[i] = Token::Identifier {
+                                ident: Cow::Owned(keys[0].to_owned()),
+                            };
+                        }
+                        doc.get_key(keys[0])
+                    }
+                    _ => {
+                        let keys = keys.into_iter().map(String::from).collect();
+                        return Err(Error::AmbiguousKey(ident.to_string(), keys));
+                    }
+                }
+            }
+
+            Token::Index { idx } => doc.get_index(idx),
+
+            _ => None,
+        };
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::resolve_case;
+    use crate::error::*;
+    use crate::tokenizer::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    macro_rules! do_resolve {
+        ( $toml:ident => $query:expr ) => {
+            resolve_case::<Value>(&$toml, &tokenize_with_seperator($query, ".").unwrap())
+                .map(|tokens| tokens.into_owned())
+        };
+    }
+
+    #[test]
+    fn test_resolve_case() {
+        let toml = toml_from_str("[Server]\nHosts = [ { Name = 'a' } ]").unwrap();
+        let result = do_resolve!(toml => "server.HOSTS.[0].name").unwrap();
+
+        let expected = tokenize_with_seperator("Server.Hosts.[0].Name", ".").unwrap();
+        assert_eq!(&expected[..], &result[..]);
+    }
+
+    #[test]
+    fn test_resolve_case_missing_key() {
+        let toml = toml_from_str("[Server]\nport = 1").unwrap();
+        let result = do_resolve!(toml => "SERVER.Host.Name").unwrap();
+
+        let expected = tokenize_with_seperator("Server.Host.Name", ".").unwrap();
+        assert_eq!(&expected[..], &result[..]);
+    }
+
+    #[test]
+    fn test_resolve_case_collision() {
+        let toml = toml_from_str("[a]\nKey = 1\nkey = 2\nKEY = 3").unwrap();
+        let result = do_resolve!(toml => "a.key");
+
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            Error::AmbiguousKey(ident, keys) => {
+                assert_eq!("key", ident);
+                assert_eq!(vec!["KEY", "Key", "key"], keys);
+            }
+            error => panic!("Unexpected error: {:?}", error),
+        }
+    }
+
+}
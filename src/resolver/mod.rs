@@ -1,3 +1,112 @@
+pub mod case_resolver;
 pub mod mut_creating_resolver;
 pub mod mut_resolver;
+pub mod non_mut_multi_resolver;
 pub mod non_mut_resolver;
+pub mod parent_resolver;
+
+use crate::document::DocumentLike;
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::tokenizer::Token;
+
+/// Fails with `Error::DepthLimitExceeded` if `tokens` descend more than `max_depth` levels
+pub fn check_depth(tokens: &[Token], max_depth: usize) -> Result<()> {
+    if tokens.len() > max_depth {
+        return Err(Error::DepthLimitExceeded(max_depth));
+    }
+    Ok(())
+}
+
+/// Wraps an error about a segment of `query` into `Error::InQuery`, with the segment which
+/// cannot be resolved in `doc`
+///
+/// Other errors are returned as they are.
+pub fn in_query<D: DocumentLike>(doc: &D, query: &Query, error: Error) -> Error {
+    if !is_segment_error(&error) {
+        return error;
+    }
+
+    let segment = failing_segment(doc, query.tokens());
+    wrap(query, segment, error)
+}
+
+/// Wraps an error about the segment `resolved` of the tokens `resolve_parents()` made of `query`
+/// into `Error::InQuery`, like `in_query()` does
+///
+/// This is for the mutable resolvers, which report the failing segment themselves, as the
+/// document cannot be inspected while it is borrowed mutably.
+pub fn in_query_at(query: &Query, resolved: usize, error: Error) -> Error {
+    if !is_segment_error(&error) {
+        return error;
+    }
+
+    // The segments of `query` which are left after removing the `^` tokens and the ones they
+    // go up from
+    let mut kept = Vec::new();
+    for (i, token) in query.tokens().iter().enumerate() {
+        match *token {
+            Token::Parent => {
+                kept.pop();
+            }
+            _ => kept.push(i),
+        }
+    }
+
+    let last = query.tokens().len().saturating_sub(1);
+    wrap(query, kept.get(resolved).cloned().unwrap_or(last), error)
+}
+
+fn is_segment_error(error: &Error) -> bool {
+    is_match!(
+        *error,
+        Error::IdentifierNotFoundInDocument(_, _)
+            | Error::NoIndexInTable(_)
+            | Error::NoIdentifierInArray(_)
+            | Error::QueryingValueAsTable(_)
+            | Error::QueryingValueAsArray(_)
+            | Error::ArrayIndexOutOfBounds(_, _)
+    )
+}
+
+fn wrap(query: &Query, segment: usize, error: Error) -> Error {
+    let segments = query.tokens();
+    let resolved = Query::from_tokens(
+        segments[..segment].iter().cloned().collect(),
+        query.seperator(),
+    )
+    .to_string();
+
+    Error::InQuery {
+        query: query.to_string(),
+        resolved,
+        seperator: String::from(query.seperator()),
+        segment,
+        error: Box::new(error),
+    }
+}
+
+/// The index of the first segment which cannot be resolved, the last one if all of them can
+fn failing_segment<D: DocumentLike>(doc: &D, segments: &[Token]) -> usize {
+    let mut path = vec![doc];
+
+    for (i, segment) in segments.iter().enumerate() {
+        let current = path[path.len() - 1];
+        let child = match *segment {
+            Token::Identifier { ref ident, .. } => current.get_key(ident),
+            Token::Index { idx, .. } => current.get_index(idx),
+            Token::Parent { .. } if path.len() > 1 => {
+                path.pop();
+                continue;
+            }
+            _ => None,
+        };
+
+        match child {
+            Some(child) => path.push(child),
+            None => return i,
+        }
+    }
+
+    segments.len().saturating_sub(1)
+}
@@ -1,79 +1,116 @@
+/// The query resolver that operates on the AST and the TOML object
+use crate::document::DocumentLike;
 use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::resolver::non_mut_resolver::{not_a_table, not_an_array};
+use crate::resolver::{check_depth, in_query_at};
 use crate::tokenizer::Token;
-/// The query resolver that operates on the AST and the TOML object
-use toml::{map::Map, Value};
 
-pub fn resolve<'doc>(toml: &'doc mut Value, tokens: &Token) -> Result<&'doc mut Value> {
+/// Resolves the path in the passed document, one token after the other, creating the tables and
+/// arrays which are missing
+///
+/// More than `max_depth` tokens are an error.
+pub fn resolve<'doc, D: DocumentLike>(
+    toml: &'doc mut D,
+    tokens: &[Token],
+    max_depth: usize,
+) -> Result<&'doc mut D> {
+    check_depth(tokens, max_depth)?;
+    resolve_tokens(toml, tokens).map_err(|(_, e)| e)
+}
+
+/// Like `resolve()`, for the `tokens` which `resolve_parents()` made of `query`
+///
+/// Errors about a segment are wrapped into `Error::InQuery`, see `in_query_at()`.
+pub fn resolve_in_query<'doc, D: DocumentLike>(
+    toml: &'doc mut D,
+    query: &Query,
+    tokens: &[Token],
+    max_depth: usize,
+) -> Result<&'doc mut D> {
+    check_depth(tokens, max_depth)?;
+    resolve_tokens(toml, tokens).map_err(|(i, e)| in_query_at(query, i, e))
+}
+
+/// Resolves `tokens`, failing with the index of the token which cannot be resolved
+fn resolve_tokens<'doc, D: DocumentLike>(
+    toml: &'doc mut D,
+    tokens: &[Token],
+) -> ::std::result::Result<&'doc mut D, (usize, Error)> {
     // Cases:
     //
     //  1. Identifier, toml: table, ident present       -> traverse
-    //  2. Identifier, toml: table, no indent present   -> create Table
+    //  2. Identifier, toml: table, no indent present   -> create Table (Array if next is index
+    //                                                     or append)
     //      2.1 If next token                           -> traverse
     //      2.2 no next token                           -> return created Table
-    //  3. Identifier, toml: array                      -> error
-    //  4. Index, toml: table                           -> error
+    //  3. Identifier, toml: array or other value       -> error
+    //  4. Index, toml: table or other value            -> error
     //  5. Index, toml: array, idx present              -> traverse
     //  6. Index, toml: array, idx not present
-    //      6.1 -> next token is ident                  -> push Table
-    //      6.2 -> next token is index                  -> push Array
+    //      6.1 -> idx is the length of the array       -> push like 7.
+    //      6.2 -> otherwise                            -> error
+    //  7. Append, toml: array
+    //      7.1 -> next token is index or append        -> push Array
+    //      7.2 -> otherwise                            -> push Table
     //      then traverse
-
-    match *tokens {
-        Token::Identifier { ref ident, .. } => match toml {
-            &mut Value::Table(ref mut t) => {
-                if t.contains_key(ident) {
-                    match tokens.next() {
-                        Some(next) => resolve(t.get_mut(ident).unwrap(), next),
-                        None => t.get_mut(ident).ok_or_else(|| unreachable!()),
-                    }
-                } else {
-                    match tokens.next() {
-                        Some(next) => {
-                            let subdoc = t.entry(ident.clone()).or_insert(Value::Table(Map::new()));
-                            resolve(subdoc, next)
-                        }
-                        None => Ok(t.entry(ident.clone()).or_insert(Value::Table(Map::new()))),
-                    }
+    //  8. Append, toml: no array                       -> error
+
+    let mut toml = toml;
+    for (i, token) in tokens.iter().enumerate() {
+        let rest = &tokens[i + 1..];
+        toml = match *token {
+            Token::Identifier { ref ident, .. } => {
+                if !toml.is_table() {
+                    return Err((i, not_a_table(toml, ident)));
                 }
-            }
-            &mut Value::Array(_) => Err(Error::NoIdentifierInArray(ident.clone())),
-            _ => unimplemented!(),
-        },
-        Token::Index { idx, .. } => {
-            match toml {
-                &mut Value::Table(_) => Err(Error::NoIndexInTable(idx)),
-                &mut Value::Array(ref mut ary) => {
-                    if ary.len() > idx {
-                        match tokens.next() {
-                            Some(next) => resolve(ary.get_mut(idx).unwrap(), next),
-                            None => ary.get_mut(idx).ok_or_else(|| unreachable!()),
-                        }
-                    } else {
-                        if let Some(next) = tokens.next() {
-                            match **next {
-                                Token::Identifier { .. } => {
-                                    ary.push(Value::Table(Map::new()));
-                                }
-                                Token::Index { .. } => {
-                                    ary.push(Value::Array(vec![]));
-                                }
-                            }
-                            //resolve(toml, next)
-                            panic!("Cannot do this")
-                        } else {
-                            unimplemented!()
-                        }
-                    }
+
+                if toml.get_key(ident).is_none() {
+                    toml.insert_key(ident, new_container(rest));
                 }
-                _ => unimplemented!(),
+                toml.get_key_mut(ident).unwrap() // safe because it was inserted if missing
             }
-        }
+            Token::Index { idx, .. } => match toml.array_len() {
+                Some(len) if len > idx => toml.get_index_mut(idx).unwrap(), // safe because of the length
+                Some(len) if len == idx => push_container(toml, len, rest),
+                Some(len) => return Err((i, Error::ArrayIndexOutOfBounds(idx, len))),
+                None => return Err((i, not_an_array(toml, idx))),
+            },
+            Token::Append { .. } => match toml.array_len() {
+                Some(len) => push_container(toml, len, rest),
+                None => return Err((i, Error::AppendToNonArray(toml.type_name()))),
+            },
+            _ => return Err((i, Error::MultiValueQuery)),
+        };
+    }
+
+    Ok(toml)
+}
+
+/// Pushes a new element to the array `ary` of length `len` for the remaining `tokens` and
+/// returns it
+fn push_container<'doc, D: DocumentLike>(
+    ary: &'doc mut D,
+    len: usize,
+    tokens: &[Token],
+) -> &'doc mut D {
+    ary.insert_index(len, new_container(tokens));
+    ary.get_index_mut(len).unwrap() // safe because we just pushed
+}
+
+/// Creates the empty structure the remaining `tokens` can be resolved on
+fn new_container<D: DocumentLike>(tokens: &[Token]) -> D {
+    match tokens.first() {
+        Some(Token::Index { .. }) | Some(Token::Append) => D::new_array(),
+        _ => D::new_table(),
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::resolve;
+    use crate::error::*;
+    use crate::query::DEFAULT_MAX_DEPTH;
     use crate::tokenizer::*;
     use toml::from_str as toml_from_str;
     use toml::Value;
@@ -82,7 +119,8 @@ mod test {
         ( $toml:ident => $query:expr ) => {
             resolve(
                 &mut $toml,
-                &tokenize_with_seperator(&String::from($query), '.').unwrap(),
+                &tokenize_with_seperator($query, ".").unwrap(),
+                DEFAULT_MAX_DEPTH,
             )
         };
     }
@@ -399,8 +437,8 @@ mod test {
         assert!(result.is_ok());
         let result = result.unwrap();
 
-        let tokens = tokenize_with_seperator(&String::from("color"), '.').unwrap();
-        let result = resolve(result, &tokens);
+        let tokens = tokenize_with_seperator("color", ".").unwrap();
+        let result = resolve(result, &tokens, DEFAULT_MAX_DEPTH);
 
         assert!(result.is_ok());
         let result = result.unwrap();
@@ -451,17 +489,17 @@ mod test {
         let mut toml = toml_from_str("").unwrap();
         let result = do_resolve!(toml => "example.[0]");
 
-        // TODO: Array creating is not yet implemented properly
-        assert!(result.is_err());
+        assert!(result.is_ok());
+        let result = result.unwrap();
 
-        //assert!(result.is_ok());
-        //let result = result.unwrap();
+        assert!(is_match!(result, &mut Value::Table(_)));
+        match result {
+            &mut Value::Table(ref t) => assert!(t.is_empty()),
+            _ => panic!("What just happened?"),
+        }
 
-        //assert!(is_match!(result, &mut Value::Array(_)));
-        //match result {
-        //    &mut Value::Array(ref a) => assert!(a.is_empty()),
-        //    _                        => panic!("What just happened?"),
-        //}
+        let expected: Value = toml_from_str("example = [ {} ]").unwrap();
+        assert_eq!(expected, toml);
     }
 
     #[test]
@@ -495,18 +533,59 @@ mod test {
     #[test]
     fn test_resolve_query_value_as_array() {
         let mut toml = toml_from_str("").unwrap();
-        let result = do_resolve!(toml => "example.foo.[0]");
+        let result = do_resolve!(toml => "example.foo.[0].[0]");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        match result {
+            &mut Value::Table(ref t) => assert!(t.is_empty()),
+            _ => panic!("What just happened?"),
+        }
+
+        let expected: Value = toml_from_str("example = { foo = [ [ {} ] ] }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_resolve_query_index_out_of_bounds() {
+        let mut toml: Value = toml_from_str("example = [ 1 ]").unwrap();
+        let result = do_resolve!(toml => "example.[2]");
 
-        // TODO: Array creating is not yet implemented properly
         assert!(result.is_err());
+        let result = result.unwrap_err();
+
+        assert!(is_match!(result, Error::ArrayIndexOutOfBounds(2, 1)));
+    }
+
+    #[test]
+    fn test_resolve_query_identifier_in_value() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+        let result = do_resolve!(toml => "a.b.c");
+
+        assert!(is_match!(result, Err(Error::QueryingValueAsTable(ref i)) if i == "b"));
+    }
+
+    #[test]
+    fn test_resolve_query_index_in_value() {
+        let mut toml: Value = toml_from_str("a = [ 1 ]").unwrap();
+        let result = do_resolve!(toml => "a.[0].[0].c");
+
+        assert!(is_match!(result, Err(Error::QueryingValueAsArray(0))));
+    }
 
-        //assert!(result.is_ok());
-        //let result = result.unwrap();
+    #[test]
+    fn test_resolve_query_append() {
+        let mut toml = toml_from_str("example = [ [ 1 ] ]").unwrap();
+        let result = do_resolve!(toml => "example.[+].[+]");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert!(is_match!(result, &mut Value::Table(_)));
 
-        //match result {
-        //    &mut Value::Array(ref a) => assert!(a.is_empty()),
-        //    _                        => panic!("What just happened?"),
-        //}
+        let expected: Value = toml_from_str("example = [ [ 1 ], [ {} ] ]").unwrap();
+        assert_eq!(expected, toml);
     }
 
 }
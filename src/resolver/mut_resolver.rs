@@ -1,69 +1,109 @@
 /// The query resolver that operates on the AST and the TOML object
-use std::ops::IndexMut;
-
+use crate::document::DocumentLike;
 use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::resolver::non_mut_resolver::{key_not_found, not_a_table, not_an_array};
+use crate::resolver::{check_depth, in_query_at};
 use crate::tokenizer::Token;
-use toml::Value;
 
-/// Resolves the path in the passed document recursively
+/// Resolves the path in the passed document, one token after the other
 ///
 /// # Guarantees
 ///
 /// If error_if_not_found is set to true, this function does not return Ok(None) in any case.
 ///
-pub fn resolve<'doc>(
-    toml: &'doc mut Value,
-    tokens: &Token,
+/// No tokens resolve to the document itself. More than `max_depth` tokens are an error.
+///
+pub fn resolve<'doc, D: DocumentLike>(
+    toml: &'doc mut D,
+    tokens: &[Token],
+    error_if_not_found: bool,
+    max_depth: usize,
+) -> Result<Option<&'doc mut D>> {
+    check_depth(tokens, max_depth)?;
+    resolve_tokens(toml, tokens, error_if_not_found).map_err(|(_, e)| e)
+}
+
+/// Like `resolve()`, for the `tokens` which `resolve_parents()` made of `query`
+///
+/// Errors about a segment are wrapped into `Error::InQuery`, see `in_query_at()`.
+pub fn resolve_in_query<'doc, D: DocumentLike>(
+    toml: &'doc mut D,
+    query: &Query,
+    tokens: &[Token],
     error_if_not_found: bool,
-) -> Result<Option<&'doc mut Value>> {
-    match toml {
-        &mut Value::Table(ref mut t) => match tokens {
-            &Token::Identifier { ref ident, .. } => match t.get_mut(ident) {
-                None => {
-                    if error_if_not_found {
-                        return Err(Error::IdentifierNotFoundInDocument(ident.to_owned()));
-                    } else {
-                        Ok(None)
+    max_depth: usize,
+) -> Result<Option<&'doc mut D>> {
+    check_depth(tokens, max_depth)?;
+    resolve_tokens(toml, tokens, error_if_not_found).map_err(|(i, e)| in_query_at(query, i, e))
+}
+
+/// Resolves `tokens`, failing with the index of the token which cannot be resolved
+fn resolve_tokens<'doc, D: DocumentLike>(
+    toml: &'doc mut D,
+    tokens: &[Token],
+    error_if_not_found: bool,
+) -> ::std::result::Result<Option<&'doc mut D>, (usize, Error)> {
+    let mut toml = toml;
+    for (i, token) in tokens.iter().enumerate() {
+        let current = toml;
+        toml = match *token {
+            Token::Append { .. } => return Err((i, Error::AppendNotSupported)),
+
+            Token::Identifier { ref ident, .. } => {
+                if !current.is_table() {
+                    return Err((i, not_a_table(current, ident)));
+                }
+
+                // The mutable borrow of `get_key_mut()` cannot be used for the error
+                if error_if_not_found && current.get_key(ident).is_none() {
+                    return Err((i, key_not_found(current, ident)));
+                }
+
+                match current.get_key_mut(ident) {
+                    Some(sub_document) => sub_document,
+                    None => return Ok(None),
+                }
+            }
+
+            Token::Index { idx, .. } => {
+                let len = match current.array_len() {
+                    Some(len) => len,
+                    None => return Err((i, not_an_array(current, idx))),
+                };
+
+                match current.get_index_mut(idx) {
+                    Some(sub_document) => sub_document,
+                    None if error_if_not_found => {
+                        return Err((i, Error::ArrayIndexOutOfBounds(idx, len)))
                     }
+                    None => return Ok(None),
                 }
-                Some(sub_document) => match tokens.next() {
-                    Some(next) => resolve(sub_document, next, error_if_not_found),
-                    None => Ok(Some(sub_document)),
-                },
-            },
-
-            &Token::Index { idx, .. } => Err(Error::NoIndexInTable(idx)),
-        },
-
-        &mut Value::Array(ref mut ary) => match tokens {
-            &Token::Index { idx, .. } => match tokens.next() {
-                Some(next) => resolve(ary.get_mut(idx).unwrap(), next, error_if_not_found),
-                None => Ok(Some(ary.index_mut(idx))),
-            },
-            &Token::Identifier { ref ident, .. } => Err(Error::NoIdentifierInArray(ident.clone())),
-        },
-
-        _ => match tokens {
-            &Token::Identifier { ref ident, .. } => Err(Error::QueryingValueAsTable(ident.clone())),
-            &Token::Index { idx, .. } => Err(Error::QueryingValueAsArray(idx)),
-        },
+            }
+
+            _ => return Err((i, Error::MultiValueQuery)),
+        };
     }
+
+    Ok(Some(toml))
 }
 
 #[cfg(test)]
 mod test {
     use super::resolve;
     use crate::error::*;
+    use crate::query::DEFAULT_MAX_DEPTH;
     use crate::tokenizer::*;
     use toml::from_str as toml_from_str;
     use toml::Value;
 
     macro_rules! do_resolve {
         ( $toml:ident => $query:expr ) => {
-            resolve(
+            resolve::<Value>(
                 &mut $toml,
-                &tokenize_with_seperator(&String::from($query), '.').unwrap(),
+                &tokenize_with_seperator($query, ".").unwrap(),
                 true,
+                DEFAULT_MAX_DEPTH,
             )
         };
     }
@@ -422,8 +462,8 @@ mod test {
         assert!(result.is_some());
         let result = result.unwrap();
 
-        let tokens = tokenize_with_seperator(&String::from("color"), '.').unwrap();
-        let result = resolve(result, &tokens, true);
+        let tokens = tokenize_with_seperator("color", ".").unwrap();
+        let result = resolve(result, &tokens, true, DEFAULT_MAX_DEPTH);
 
         assert!(result.is_ok());
         let result = result.unwrap();
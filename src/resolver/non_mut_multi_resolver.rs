@@ -0,0 +1,747 @@
+/// The query resolver for queries which may match more than one value in the TOML object
+use std::collections::HashSet;
+
+use crate::document::DocumentLike;
+use crate::error::{Error, Result};
+use crate::resolver::check_depth;
+use crate::resolver::non_mut_resolver::{not_a_table, not_an_array};
+use crate::tokenizer::{quote_identifier, Token};
+use crate::util::child_path;
+
+/// Resolves the path in the passed document recursively, collecting all matching values
+///
+/// Each match is pushed to `results` together with its full path (segments joined by `sep`).
+///
+/// # Semantics
+///
+/// Missing identifiers or indices do not yield an error, they simply do not match.
+///
+/// Type mismatches (for example querying an identifier on an array) yield an error, as long as no
+/// multi-value token (like `**`) was processed yet. After such a token, mismatching subtrees are
+/// skipped silently, as the query is expected to not match everywhere.
+///
+/// The `^` token goes up to the parent of the current value. As different matches may share a
+/// parent, a value is only reported once, at its first match.
+///
+/// More than `max_depth` tokens, or matches nested deeper than `max_depth` levels, are an error.
+///
+pub fn resolve_all<'doc, D: DocumentLike>(
+    toml: &'doc D,
+    tokens: &[Token],
+    sep: &str,
+    path: &str,
+    max_depth: usize,
+    results: &mut Vec<(String, &'doc D)>,
+) -> Result<()> {
+    resolve_all_from(toml, tokens, sep, path, true, max_depth, results)
+}
+
+/// Like `resolve_all()`, for continuing a resolution which started above `toml`
+///
+/// Type mismatches only yield an error if `strict` is set, `^` cannot go up from `toml`.
+pub fn resolve_all_from<'doc, D: DocumentLike>(
+    toml: &'doc D,
+    tokens: &[Token],
+    sep: &str,
+    path: &str,
+    strict: bool,
+    max_depth: usize,
+    results: &mut Vec<(String, &'doc D)>,
+) -> Result<()> {
+    check_depth(tokens, max_depth)?;
+
+    let mut matches = Vec::new();
+    let mut state = State {
+        sep,
+        max_depth,
+        ancestors: Vec::new(),
+        results: &mut matches,
+    };
+    do_resolve_all(toml, tokens, path, strict, &mut state)?;
+
+    let mut seen = HashSet::new();
+    results.extend(
+        matches
+            .into_iter()
+            .filter(|(path, _)| seen.insert(path.clone())),
+    );
+    Ok(())
+}
+
+/// The values (and their paths) from the document root down to the parent of the current value
+type Ancestors<'doc, D> = Vec<(&'doc D, String)>;
+
+/// What all branches of the resolution share
+struct State<'doc, 'r, D> {
+    sep: &'r str,
+    max_depth: usize,
+    ancestors: Ancestors<'doc, D>,
+    results: &'r mut Vec<(String, &'doc D)>,
+}
+
+fn do_resolve_all<'doc, D: DocumentLike>(
+    toml: &'doc D,
+    tokens: &[Token],
+    path: &str,
+    strict: bool,
+    state: &mut State<'doc, '_, D>,
+) -> Result<()> {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => {
+            state.results.push((path.to_owned(), toml));
+            return Ok(());
+        }
+    };
+
+    match *token {
+        Token::Parent => {
+            let (parent, parent_path) = match state.ancestors.pop() {
+                Some(parent) => parent,
+                None if strict => return Err(Error::ParentOfRoot),
+                None => return Ok(()),
+            };
+
+            let result = do_resolve_all(parent, rest, &parent_path, strict, state);
+
+            state.ancestors.push((parent, parent_path));
+            result
+        }
+
+        Token::RecursiveDescent => {
+            // `**` matching zero levels
+            do_resolve_all(toml, rest, path, false, state)?;
+
+            resolve_children(toml, tokens, path, strict, state)
+        }
+
+        Token::Append { .. } => Err(Error::AppendNotSupported),
+
+        _ => resolve_children(toml, tokens, path, strict, state),
+    }
+}
+
+/// Resolve the first of the `tokens` on the children of `toml`, with `toml` being their parent
+fn resolve_children<'doc, D: DocumentLike>(
+    toml: &'doc D,
+    tokens: &[Token],
+    path: &str,
+    strict: bool,
+    state: &mut State<'doc, '_, D>,
+) -> Result<()> {
+    // Scalars have no children which could be too deep
+    let has_children = toml.is_table() || toml.array_len().is_some();
+    if has_children && state.ancestors.len() >= state.max_depth {
+        return Err(Error::DepthLimitExceeded(state.max_depth));
+    }
+
+    let sep = state.sep;
+    state.ancestors.push((toml, path.to_owned()));
+    let result = match tokens[0] {
+        Token::Identifier { ref ident, .. } if toml.is_table() => match toml.get_key(ident) {
+            None => Ok(()),
+            Some(sub_document) => {
+                let path = child_path(path, &quote_identifier(ident, sep), sep);
+                descend(sub_document, tokens, path, strict, state)
+            }
+        },
+        Token::Identifier { ref ident, .. } if strict => Err(not_a_table(toml, ident)),
+
+        Token::Index { idx, .. } if toml.array_len().is_some() => match toml.get_index(idx) {
+            None => Ok(()),
+            Some(sub_document) => {
+                let path = child_path(path, &format!("[{}]", idx), sep);
+                descend(sub_document, tokens, path, strict, state)
+            }
+        },
+        Token::Index { idx, .. } if strict => Err(not_an_array(toml, idx)),
+
+        // `**` matching one or more levels
+        Token::RecursiveDescent { .. } => {
+            for (segment, sub_document) in children(toml, sep) {
+                let path = child_path(path, &segment, sep);
+                do_resolve_all(sub_document, tokens, &path, false, state)?;
+            }
+
+            Ok(())
+        }
+
+        // `*` matching exactly one level, values which are no tables or arrays have no children
+        Token::Wildcard { .. } => {
+            for (segment, sub_document) in children(toml, sep) {
+                let path = child_path(path, &segment, sep);
+                descend(sub_document, tokens, path, false, state)?;
+            }
+
+            Ok(())
+        }
+
+        Token::Slice { start, end, .. } => match toml.array_len() {
+            Some(len) => {
+                let start = start.unwrap_or(0);
+                let end = end.unwrap_or(len).min(len);
+
+                for idx in start..end {
+                    if let Some(sub_document) = toml.get_index(idx) {
+                        let path = child_path(path, &format!("[{}]", idx), sep);
+                        descend(sub_document, tokens, path, false, state)?;
+                    }
+                }
+
+                Ok(())
+            }
+            None if strict => Err(not_an_array(toml, start.unwrap_or(0))),
+            None => Ok(()),
+        },
+
+        Token::IndexUnion { ref idxs, .. } => match toml.array_len() {
+            Some(_) => {
+                for &idx in idxs.iter() {
+                    if let Some(sub_document) = toml.get_index(idx) {
+                        let path = child_path(path, &format!("[{}]", idx), sep);
+                        descend(sub_document, tokens, path, false, state)?;
+                    }
+                }
+
+                Ok(())
+            }
+            None if strict => Err(not_an_array(toml, idxs[0])),
+            None => Ok(()),
+        },
+
+        Token::KeyUnion { ref idents, .. } => {
+            if toml.is_table() {
+                for ident in idents.iter() {
+                    if let Some(sub_document) = toml.get_key(ident) {
+                        let path = child_path(path, &quote_identifier(ident, sep), sep);
+                        descend(sub_document, tokens, path, false, state)?;
+                    }
+                }
+
+                Ok(())
+            } else if strict {
+                Err(not_a_table(toml, &idents[0]))
+            } else {
+                Ok(())
+            }
+        }
+
+        Token::Filter { ref predicate, .. } => match toml.array_len() {
+            Some(len) => {
+                for idx in 0..len {
+                    match toml.get_index(idx) {
+                        Some(sub_document) if predicate.matches(sub_document) => {
+                            let path = child_path(path, &format!("[{}]", idx), sep);
+                            descend(sub_document, tokens, path, false, state)?;
+                        }
+                        _ => {}
+                    }
+                }
+
+                Ok(())
+            }
+            None if strict => Err(Error::FilterOnNonArray(toml.type_name())),
+            None => Ok(()),
+        },
+
+        #[cfg(feature = "regex")]
+        Token::KeyRegex { ref regex, .. } => {
+            if toml.is_table() {
+                for key in toml.keys().into_iter().filter(|k| regex.is_match(k)) {
+                    if let Some(sub_document) = toml.get_key(key) {
+                        let path = child_path(path, &quote_identifier(key, sep), sep);
+                        descend(sub_document, tokens, path, false, state)?;
+                    }
+                }
+
+                Ok(())
+            } else if strict {
+                Err(not_a_table(toml, &format!("/{}/", regex.as_str())))
+            } else {
+                Ok(())
+            }
+        }
+
+        Token::Identifier { .. } | Token::Index { .. } => Ok(()),
+
+        Token::Parent { .. } | Token::Append { .. } => unreachable!("handled in do_resolve_all()"),
+    };
+
+    state.ancestors.pop();
+    result
+}
+
+/// The children of a table or an array, with the query segments which refer to them
+fn children<'doc, D: DocumentLike>(toml: &'doc D, sep: &str) -> Vec<(String, &'doc D)> {
+    if toml.is_table() {
+        toml.keys()
+            .into_iter()
+            .filter_map(|key| {
+                toml.get_key(key)
+                    .map(|sub_document| (quote_identifier(key, sep), sub_document))
+            })
+            .collect()
+    } else {
+        (0..toml.array_len().unwrap_or(0))
+            .filter_map(|idx| {
+                toml.get_index(idx)
+                    .map(|sub_document| (format!("[{}]", idx), sub_document))
+            })
+            .collect()
+    }
+}
+
+/// Continue with the tokens after the first one on `sub_document`, or record it as a match if
+/// there are none
+fn descend<'doc, D: DocumentLike>(
+    sub_document: &'doc D,
+    tokens: &[Token],
+    path: String,
+    strict: bool,
+    state: &mut State<'doc, '_, D>,
+) -> Result<()> {
+    do_resolve_all(sub_document, &tokens[1..], &path, strict, state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::resolve_all;
+    use crate::error::*;
+    use crate::query::DEFAULT_MAX_DEPTH;
+    use crate::tokenizer::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    macro_rules! do_resolve_all {
+        ( $toml:ident => $query:expr ) => {{
+            let mut results = Vec::new();
+            let tokens = tokenize_with_seperator($query, ".").unwrap();
+            resolve_all(&$toml, &tokens, ".", "", DEFAULT_MAX_DEPTH, &mut results).map(|_| results)
+        }};
+    }
+
+    fn paths(results: &[(String, &Value)]) -> Vec<String> {
+        results.iter().map(|(p, _)| p.clone()).collect()
+    }
+
+    #[test]
+    fn test_resolve_all_plain_query() {
+        let toml: Value = toml_from_str(
+            r#"
+        [example]
+        foo = 1
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "example.foo");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["example.foo"], paths(&result));
+        assert!(is_match!(result[0].1, &Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_resolve_all_missing_is_empty() {
+        let toml: Value = toml_from_str("").unwrap();
+        let result = do_resolve_all!(toml => "example.foo");
+
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_all_mismatch_before_recursive_descent_is_error() {
+        let toml: Value = toml_from_str("example = 1").unwrap();
+        let result = do_resolve_all!(toml => "example.foo.**");
+
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+
+        assert!(is_match!(result, Error::QueryingValueAsTable { .. }));
+    }
+
+    #[test]
+    fn test_resolve_all_recursive_descent() {
+        let toml: Value = toml_from_str(
+            r#"
+        password = "a"
+
+        [db]
+        password = "b"
+        user = "c"
+
+        [db.replica]
+        password = "d"
+
+        [[bins]]
+        password = "e"
+
+        [[bins]]
+        name = "f"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "**.password");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(
+            vec![
+                "password",
+                "bins.[0].password",
+                "db.password",
+                "db.replica.password",
+            ],
+            paths(&result)
+        );
+    }
+
+    #[test]
+    fn test_resolve_all_recursive_descent_in_the_middle() {
+        let toml: Value = toml_from_str(
+            r#"
+        [a.b.c]
+        d = 1
+
+        [x.b.c]
+        d = 2
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "a.**.d");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["a.b.c.d"], paths(&result));
+        assert!(is_match!(result[0].1, &Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_resolve_all_trailing_recursive_descent() {
+        let toml: Value = toml_from_str(
+            r#"
+        [a]
+        b = [ 1 ]
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "a.**");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["a", "a.b", "a.b.[0]"], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_quotes_paths() {
+        let toml: Value = toml_from_str(
+            r#"
+        [a."b.c"]
+        d = 1
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "**.d");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec![r#"a."b.c".d"#], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_wildcard() {
+        let toml: Value = toml_from_str(
+            r#"
+        secret = "a"
+
+        [db]
+        secret = "b"
+        user = "c"
+
+        [db.replica]
+        secret = "d"
+
+        [[bins]]
+        secret = "e"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "*.secret");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["db.secret"], paths(&result));
+
+        let result = do_resolve_all!(toml => "bins.*.secret").unwrap();
+        assert_eq!(vec!["bins.[0].secret"], paths(&result));
+
+        let result = do_resolve_all!(toml => "db.*").unwrap();
+        assert_eq!(vec!["db.replica", "db.secret", "db.user"], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_slice() {
+        let toml: Value = toml_from_str("a = [ 0, 1, 2, 3, 4, 5 ]").unwrap();
+        let result = do_resolve_all!(toml => "a.[1:4]");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["a.[1]", "a.[2]", "a.[3]"], paths(&result));
+        assert!(is_match!(result[0].1, &Value::Integer(1)));
+        assert!(is_match!(result[2].1, &Value::Integer(3)));
+    }
+
+    #[test]
+    fn test_resolve_all_open_slices() {
+        let toml: Value = toml_from_str("a = [ 0, 1, 2, 3 ]").unwrap();
+
+        let result = do_resolve_all!(toml => "a.[:2]").unwrap();
+        assert_eq!(vec!["a.[0]", "a.[1]"], paths(&result));
+
+        let result = do_resolve_all!(toml => "a.[2:]").unwrap();
+        assert_eq!(vec!["a.[2]", "a.[3]"], paths(&result));
+
+        let result = do_resolve_all!(toml => "a.[2:100]").unwrap();
+        assert_eq!(vec!["a.[2]", "a.[3]"], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_slice_then_identifier() {
+        let toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "a"
+
+        [[bins]]
+        path = "b"
+
+        [[bins]]
+        name = "c"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "bins.[:].name");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["bins.[0].name", "bins.[2].name"], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_index_union() {
+        let toml: Value = toml_from_str("a = [ 0, 1, 2, 3, 4, 5 ]").unwrap();
+        let result = do_resolve_all!(toml => "a.[5,0,2,9]");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["a.[0]", "a.[2]", "a.[5]"], paths(&result));
+        assert!(is_match!(result[2].1, &Value::Integer(5)));
+    }
+
+    #[test]
+    fn test_resolve_all_key_union() {
+        let toml: Value = toml_from_str(
+            r#"
+        [db]
+        host = "localhost"
+        port = 5432
+        user = "admin"
+        password = "secret"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "db.{port,host,missing}");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["db.port", "db.host"], paths(&result));
+        assert!(is_match!(result[0].1, &Value::Integer(5432)));
+    }
+
+    #[test]
+    fn test_resolve_all_key_union_on_array_is_error() {
+        let toml: Value = toml_from_str("db = [ 1 ]").unwrap();
+        let result = do_resolve_all!(toml => "db.{host,port}");
+
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+
+        assert!(is_match!(result, Error::NoIdentifierInArray { .. }));
+    }
+
+    #[test]
+    fn test_resolve_all_filter() {
+        let toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "server"
+        path = "src/server.rs"
+
+        [[bins]]
+        name = "client"
+        path = "src/client.rs"
+
+        [[bins]]
+        name = "server"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => r#"bins.[?(@.name == "server")].path"#);
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["bins.[0].path"], paths(&result));
+        assert_eq!(Some("src/server.rs"), result[0].1.as_str());
+    }
+
+    #[test]
+    fn test_resolve_all_filter_on_table_is_error() {
+        let toml: Value = toml_from_str(
+            r#"
+        [bins]
+        name = "server"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "bins.[?(@.name)]");
+
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+
+        assert!(is_match!(result, Error::FilterOnNonArray("Table")));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_resolve_all_key_regex() {
+        let toml: Value = toml_from_str(
+            r#"
+        [logging]
+        log_level = "debug"
+        log_file = "/tmp/log"
+        level = "info"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "logging./^log_.*/");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(
+            vec!["logging.log_file", "logging.log_level"],
+            paths(&result)
+        );
+    }
+
+    #[test]
+    fn test_resolve_all_slice_on_table_is_error() {
+        let toml: Value = toml_from_str(
+            r#"
+        [a]
+        b = 1
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "a.[0:1]");
+
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+
+        assert!(is_match!(result, Error::NoIndexInTable { .. }));
+    }
+
+    #[test]
+    fn test_resolve_all_parent_after_filter() {
+        let toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "a"
+        path = "a.rs"
+
+        [[bins]]
+        name = "b"
+        path = "b.rs"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => r#"bins.[?(@.name == "b")].name.^.path"#);
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["bins.[1].path"], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_parent_reports_each_value_once() {
+        let toml: Value = toml_from_str(
+            r#"
+        [a]
+        password = "x"
+
+        [a.b]
+        password = "y"
+        user = "z"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_all!(toml => "**.password.^");
+
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        assert_eq!(vec!["a", "a.b"], paths(&result));
+    }
+
+    #[test]
+    fn test_resolve_all_parent_of_root() {
+        let toml: Value = toml_from_str("a = 1").unwrap();
+
+        let result = do_resolve_all!(toml => "a.^");
+        assert!(result.is_ok());
+        assert_eq!(vec![""], paths(&result.unwrap()));
+
+        let result = do_resolve_all!(toml => "^");
+        assert!(result.is_err());
+        assert!(is_match!(result.unwrap_err(), Error::ParentOfRoot));
+
+        // after a multi-value token, going up beyond the root does not match
+        let result = do_resolve_all!(toml => "**.^");
+        assert!(result.is_ok());
+        assert_eq!(vec![""], paths(&result.unwrap()));
+    }
+
+    #[test]
+    fn test_resolve_all_depth_limit() {
+        let toml: Value = toml_from_str("a = { b = { c = { d = 1 } } }").unwrap();
+        let tokens = tokenize_with_seperator("**.d", ".").unwrap();
+
+        let mut results = Vec::new();
+        let result = resolve_all(&toml, &tokens, ".", "", 3, &mut results);
+        assert!(is_match!(result, Err(Error::DepthLimitExceeded(3))));
+
+        let mut results = Vec::new();
+        let result = resolve_all(&toml, &tokens, ".", "", 4, &mut results);
+        assert!(result.is_ok());
+        assert_eq!(vec!["a.b.c.d"], paths(&results));
+
+        let tokens = tokenize_with_seperator("a.b.c.d", ".").unwrap();
+        let mut results = Vec::new();
+        let result = resolve_all(&toml, &tokens, ".", "", 3, &mut results);
+        assert!(is_match!(result, Err(Error::DepthLimitExceeded(3))));
+    }
+
+}
@@ -1,53 +1,82 @@
 /// The query resolver that operates on the AST and the TOML object
-use std::ops::Index;
-
+use crate::document::DocumentLike;
 use crate::error::{Error, Result};
+use crate::resolver::check_depth;
 use crate::tokenizer::Token;
-use toml::Value;
+use crate::util::suggest;
 
-/// Resolves the path in the passed document recursively
+/// Resolves the path in the passed document, one token after the other
 ///
 /// # Guarantees
 ///
 /// If error_if_not_found is set to true, this function does not return Ok(None) in any case.
 ///
-pub fn resolve<'doc>(
-    toml: &'doc Value,
-    tokens: &Token,
+/// No tokens resolve to the document itself. More than `max_depth` tokens are an error.
+///
+pub fn resolve<'doc, D: DocumentLike>(
+    toml: &'doc D,
+    tokens: &[Token],
     error_if_not_found: bool,
-) -> Result<Option<&'doc Value>> {
-    match toml {
-        &Value::Table(ref t) => match tokens {
-            &Token::Identifier { ref ident, .. } => match t.get(ident) {
-                None => {
-                    if error_if_not_found {
-                        return Err(Error::IdentifierNotFoundInDocument(ident.to_owned()));
-                    } else {
-                        Ok(None)
+    max_depth: usize,
+) -> Result<Option<&'doc D>> {
+    check_depth(tokens, max_depth)?;
+
+    let mut toml = toml;
+    for token in tokens {
+        toml = match *token {
+            Token::Append { .. } => return Err(Error::AppendNotSupported),
+
+            Token::Identifier { ref ident, .. } => {
+                if !toml.is_table() {
+                    return Err(not_a_table(toml, ident));
+                }
+
+                match toml.get_key(ident) {
+                    Some(sub_document) => sub_document,
+                    None if error_if_not_found => return Err(key_not_found(toml, ident)),
+                    None => return Ok(None),
+                }
+            }
+
+            Token::Index { idx, .. } => {
+                let len = toml.array_len().ok_or_else(|| not_an_array(toml, idx))?;
+
+                match toml.get_index(idx) {
+                    Some(sub_document) => sub_document,
+                    None if error_if_not_found => {
+                        return Err(Error::ArrayIndexOutOfBounds(idx, len))
                     }
+                    None => return Ok(None),
                 }
-                Some(sub_document) => match tokens.next() {
-                    Some(next) => resolve(sub_document, next, error_if_not_found),
-                    None => Ok(Some(sub_document)),
-                },
-            },
-
-            &Token::Index { idx, .. } => Err(Error::NoIndexInTable(idx)),
-        },
-
-        &Value::Array(ref ary) => match tokens {
-            &Token::Index { idx, .. } => match tokens.next() {
-                Some(next) => resolve(ary.get(idx).unwrap(), next, error_if_not_found),
-                None => Ok(Some(ary.index(idx))),
-            },
-            &Token::Identifier { ref ident, .. } => Err(Error::NoIdentifierInArray(ident.clone())),
-        },
-
-        _ => match tokens {
-            &Token::Identifier { ref ident, .. } => Err(Error::QueryingValueAsTable(ident.clone())),
-
-            &Token::Index { idx, .. } => Err(Error::QueryingValueAsArray(idx)),
-        },
+            }
+
+            _ => return Err(Error::MultiValueQuery),
+        };
+    }
+
+    Ok(Some(toml))
+}
+
+/// The error for looking up `ident` in a value which is not a table
+pub fn not_a_table<D: DocumentLike>(toml: &D, ident: &str) -> Error {
+    if toml.array_len().is_some() {
+        Error::NoIdentifierInArray(ident.to_owned())
+    } else {
+        Error::QueryingValueAsTable(ident.to_owned())
+    }
+}
+
+/// The error for looking up `ident` in a table which does not contain it
+pub fn key_not_found<D: DocumentLike>(toml: &D, ident: &str) -> Error {
+    Error::IdentifierNotFoundInDocument(ident.to_owned(), suggest(ident, toml.keys()))
+}
+
+/// The error for looking up `idx` in a value which is not an array
+pub fn not_an_array<D: DocumentLike>(toml: &D, idx: usize) -> Error {
+    if toml.is_table() {
+        Error::NoIndexInTable(idx)
+    } else {
+        Error::QueryingValueAsArray(idx)
     }
 }
 
@@ -55,16 +84,18 @@ pub fn resolve<'doc>(
 mod test {
     use super::resolve;
     use crate::error::*;
+    use crate::query::DEFAULT_MAX_DEPTH;
     use crate::tokenizer::*;
     use toml::from_str as toml_from_str;
     use toml::Value;
 
     macro_rules! do_resolve {
         ( $toml:ident => $query:expr ) => {
-            resolve(
+            resolve::<Value>(
                 &$toml,
-                &tokenize_with_seperator(&String::from($query), '.').unwrap(),
+                &tokenize_with_seperator($query, ".").unwrap(),
                 true,
+                DEFAULT_MAX_DEPTH,
             )
         };
     }
@@ -80,6 +111,31 @@ mod test {
         assert!(is_match!(result, Error::IdentifierNotFoundInDocument { .. }));
     }
 
+    #[test]
+    fn test_resolve_missing_key_suggestion() {
+        let toml = toml_from_str("[server]\nhost = 'a'\nport = 80\nports = [ 80 ]").unwrap();
+
+        let result = do_resolve!(toml => "server.prot").unwrap_err();
+        assert!(is_match!(
+            result,
+            Error::IdentifierNotFoundInDocument(_, Suggestion(Some(ref key))) if key == "port"
+        ));
+        assert_eq!(
+            "The identfier 'prot' is not present in the document, did you mean 'port'?",
+            result.to_string()
+        );
+
+        let result = do_resolve!(toml => "server.address").unwrap_err();
+        assert!(is_match!(
+            result,
+            Error::IdentifierNotFoundInDocument(_, Suggestion(None))
+        ));
+        assert_eq!(
+            "The identfier 'address' is not present in the document",
+            result.to_string()
+        );
+    }
+
     #[test]
     fn test_resolve_present_bool() {
         let toml = toml_from_str("example = true").unwrap();
@@ -429,8 +485,8 @@ mod test {
         assert!(result.is_some());
         let result = result.unwrap();
 
-        let tokens = tokenize_with_seperator(&String::from("color"), '.').unwrap();
-        let result = resolve(result, &tokens, true);
+        let tokens = tokenize_with_seperator("color", ".").unwrap();
+        let result = resolve(result, &tokens, true, DEFAULT_MAX_DEPTH);
 
         assert!(result.is_ok());
         let result = result.unwrap();
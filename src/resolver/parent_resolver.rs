@@ -0,0 +1,207 @@
+/// The resolver for `^` (parent) tokens in queries which match a single value
+///
+/// The single value resolvers cannot walk back up the document (the mutable ones would need to
+/// hold mutable references to all ancestors at once), so queries containing `^` are rewritten to
+/// an equivalent query without `^` before they are handed to them.
+use std::borrow::Cow;
+
+use crate::document::DocumentLike;
+use crate::error::{Error, Result, Suggestion};
+use crate::resolver::non_mut_resolver::{key_not_found, not_a_table, not_an_array};
+use crate::tokenizer::Token;
+
+/// Rewrites `tokens` so that they do not contain `^` tokens anymore
+///
+/// `a.b.^.c` becomes `a.c`. The value a `^` goes up from must exist in the document, everything
+/// after the last `^` is left for the actual resolver to handle.
+///
+/// # Returns
+///
+/// `Ok(None)` if the query goes back up to the document root itself.
+///
+pub fn resolve_parents<'t, 'q, D: DocumentLike>(
+    toml: &D,
+    tokens: &'t [Token<'q>],
+) -> Result<Option<Cow<'t, [Token<'q>]>>> {
+    if !tokens.iter().any(|t| is_match!(*t, Token::Parent)) {
+        return Ok(Some(Cow::Borrowed(tokens)));
+    }
+
+    // The segments leading to the current value, each with the value it refers to, if present
+    let mut segments: Vec<(Token, Option<&D>)> = Vec::new();
+    for t in tokens {
+        match *t {
+            Token::Identifier { .. } | Token::Index { .. } => {
+                let value = match current(toml, &segments) {
+                    Some(value) => child(value, t)?,
+                    None => None,
+                };
+                segments.push((t.clone(), value));
+            }
+
+            // The appended element does not exist yet
+            Token::Append => segments.push((Token::Append, None)),
+
+            Token::Parent => match segments.pop() {
+                None => return Err(Error::ParentOfRoot),
+                Some((_, Some(_))) => {}
+                Some((segment, None)) => {
+                    // Report the first segment which is not in the document
+                    segments.push((segment, None));
+                    let missing = segments.iter().position(|s| s.1.is_none()).unwrap(); // safe
+                    return Err(not_found(
+                        current(toml, &segments[..missing]),
+                        &segments[missing].0,
+                    ));
+                }
+            },
+
+            _ => return Err(Error::MultiValueQuery),
+        }
+    }
+
+    if segments.is_empty() {
+        return Ok(None);
+    }
+
+    let tokens = segments.into_iter().map(|(segment, _)| segment).collect();
+    Ok(Some(Cow::Owned(tokens)))
+}
+
+/// The value the segments refer to, or None if it is not in the document
+fn current<'doc, D: DocumentLike>(
+    toml: &'doc D,
+    segments: &[(Token, Option<&'doc D>)],
+) -> Option<&'doc D> {
+    match segments.last() {
+        None => Some(toml),
+        Some((_, value)) => *value,
+    }
+}
+
+fn child<'doc, D: DocumentLike>(toml: &'doc D, token: &Token) -> Result<Option<&'doc D>> {
+    match *token {
+        Token::Identifier { ref ident, .. } if toml.is_table() => Ok(toml.get_key(ident)),
+        Token::Identifier { ref ident, .. } => Err(not_a_table(toml, ident)),
+        Token::Index { idx, .. } => match toml.array_len() {
+            Some(_) => Ok(toml.get_index(idx)),
+            None => Err(not_an_array(toml, idx)),
+        },
+        _ => Err(Error::MultiValueQuery),
+    }
+}
+
+fn not_found<D: DocumentLike>(parent: Option<&D>, token: &Token) -> Error {
+    match *token {
+        Token::Index { idx, .. } => {
+            let len = parent.and_then(D::array_len).unwrap_or(0);
+            Error::ArrayIndexOutOfBounds(idx, len)
+        }
+        Token::Identifier { ref ident, .. } => match parent {
+            Some(parent) => key_not_found(parent, ident),
+            None => Error::IdentifierNotFoundInDocument(ident.to_string(), Suggestion(None)),
+        },
+        Token::Append { .. } => Error::AppendNotSupported,
+        _ => Error::MultiValueQuery,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::resolve_parents;
+    use crate::error::*;
+    use crate::tokenizer::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    macro_rules! do_resolve_parents {
+        ( $toml:ident => $query:expr ) => {
+            resolve_parents(&$toml, &tokenize_with_seperator($query, ".").unwrap())
+                .map(|tokens| tokens.map(|t| t.into_owned()))
+        };
+    }
+
+    fn tokens(query: &str) -> Option<Vec<Token<'_>>> {
+        Some(tokenize_with_seperator(query, ".").unwrap().into_vec())
+    }
+
+    #[test]
+    fn test_resolve_parents_without_parent_is_unchanged() {
+        let toml: Value = toml_from_str("").unwrap();
+        let result = do_resolve_parents!(toml => "a.b.[1]");
+
+        assert!(result.is_ok());
+        assert_eq!(tokens("a.b.[1]"), result.unwrap());
+    }
+
+    #[test]
+    fn test_resolve_parents_sibling() {
+        let toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "a"
+        path = "b"
+        "#,
+        )
+        .unwrap();
+        let result = do_resolve_parents!(toml => "bins.[0].name.^.path");
+
+        assert!(result.is_ok());
+        assert_eq!(tokens("bins.[0].path"), result.unwrap());
+    }
+
+    #[test]
+    fn test_resolve_parents_keeps_missing_tail() {
+        let toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+        let result = do_resolve_parents!(toml => "a.b.^.^.x.y");
+
+        assert!(result.is_ok());
+        assert_eq!(tokens("x.y"), result.unwrap());
+    }
+
+    #[test]
+    fn test_resolve_parents_to_root() {
+        let toml: Value = toml_from_str("a = 1").unwrap();
+        let result = do_resolve_parents!(toml => "a.^");
+
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resolve_parents_beyond_root_is_error() {
+        let toml: Value = toml_from_str("a = 1").unwrap();
+        let result = do_resolve_parents!(toml => "a.^.^");
+
+        assert!(result.is_err());
+        assert!(is_match!(result.unwrap_err(), Error::ParentOfRoot));
+    }
+
+    #[test]
+    fn test_resolve_parents_from_missing_value_is_error() {
+        let toml: Value = toml_from_str("[a]\nb = [ 1 ]").unwrap();
+
+        let result = do_resolve_parents!(toml => "a.x.y.^.^.b");
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+        assert!(is_match!(result, Error::IdentifierNotFoundInDocument { .. }));
+
+        let result = do_resolve_parents!(toml => "a.b.[3].^");
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+        assert!(is_match!(result, Error::ArrayIndexOutOfBounds(3, 1)));
+    }
+
+    #[test]
+    fn test_resolve_parents_type_mismatch_is_error() {
+        let toml: Value = toml_from_str("a = 1").unwrap();
+        let result = do_resolve_parents!(toml => "a.b.^");
+
+        assert!(result.is_err());
+        assert!(is_match!(
+            result.unwrap_err(),
+            Error::QueryingValueAsTable { .. }
+        ));
+    }
+
+}
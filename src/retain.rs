@@ -0,0 +1,123 @@
+/// The Toml Retain extensions
+use std::mem;
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::util::name_of_val;
+
+/// The key of a table entry or the index of an array element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOrIndex<'a> {
+    /// The key of a table entry
+    Key(&'a str),
+
+    /// The index of an array element
+    Index(usize),
+}
+
+pub trait TomlValueRetainExt {
+    /// Extension function for removing the entries of the table (or the elements of the array)
+    /// at a path in the current toml::Value document, for which `f` returns false
+    ///
+    /// # Semantics
+    ///
+    /// `f` is called once for every entry, in document order, with its key (or the index it had
+    /// before any element was removed) and its value. The number of removed entries is returned.
+    ///
+    /// * If there is no value at the queried path: `Error::NotAvailable`
+    /// * If the value is neither a table nor an array: `Error::TypeError`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::retain::{KeyOrIndex, TomlValueRetainExt};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[features]\ndefault = []\nlog = []\n_x = []").unwrap();
+    ///
+    /// let removed = toml
+    ///     .retain("features", |key, _| match key {
+    ///         KeyOrIndex::Key(key) => !key.starts_with('_'),
+    ///         KeyOrIndex::Index(_) => true,
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(removed, 1);
+    /// ```
+    fn retain<F>(&mut self, query: &str, f: F) -> Result<usize>
+    where
+        F: FnMut(KeyOrIndex, &Value) -> bool,
+    {
+        self.retain_query(&Query::parse(query)?, f)
+    }
+
+    /// Extension function for removing the entries of the table (or the elements of the array)
+    /// at a path in the current toml::Value document using a pre-compiled query, for which `f`
+    /// returns false
+    ///
+    /// See documentation of `TomlValueRetainExt::retain`
+    fn retain_query<F>(&mut self, query: &Query, f: F) -> Result<usize>
+    where
+        F: FnMut(KeyOrIndex, &Value) -> bool;
+}
+
+impl TomlValueRetainExt for Value {
+    fn retain_query<F>(&mut self, query: &Query, mut f: F) -> Result<usize>
+    where
+        F: FnMut(KeyOrIndex, &Value) -> bool,
+    {
+        match self.read_mut_query(query)? {
+            Some(Value::Table(table)) => {
+                let len = table.len();
+                *table = mem::replace(table, Default::default())
+                    .into_iter()
+                    .filter(|(key, value)| f(KeyOrIndex::Key(key), value))
+                    .collect();
+                Ok(len - table.len())
+            }
+            Some(Value::Array(array)) => {
+                let len = array.len();
+                *array = mem::replace(array, Default::default())
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(idx, value)| f(KeyOrIndex::Index(*idx), value))
+                    .map(|(_, value)| value)
+                    .collect();
+                Ok(len - array.len())
+            }
+            Some(other) => Err(Error::TypeError("Table or Array", name_of_val(other))),
+            None => Err(Error::NotAvailable(query.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_retain() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1\nc = 2\nd = [ 1, 2, 3, 4 ]").unwrap();
+
+        let res = toml.retain("a", |key, _| key != KeyOrIndex::Key("b"));
+        assert!(is_match!(res, Ok(1)));
+        let res = toml.retain("a.d", |idx, value| {
+            idx != KeyOrIndex::Index(0) && value.as_integer() != Some(3)
+        });
+        assert!(is_match!(res, Ok(2)));
+
+        let res = toml.retain("a.c", |_, _| true);
+        assert!(is_match!(res, Err(Error::TypeError(_, "Integer"))));
+        let res = toml.retain("x", |_, _| true);
+        assert!(is_match!(res, Err(Error::NotAvailable(_))));
+
+        let expected: Value = toml_from_str("[a]\nc = 2\nd = [ 2, 4 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+}
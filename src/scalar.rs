@@ -0,0 +1,90 @@
+/// The Toml Scalar extensions
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::read::TomlValueReadExt;
+use crate::util::name_of_val;
+
+/// Extension functions for modifying the boolean or string at a path in a toml::Value document
+///
+/// All functions fail with `Error::NotAvailable` if there is no value at the queried path and
+/// with `Error::TypeError` if the value has another type.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::scalar::TomlValueScalarExt;
+///
+/// let mut toml : toml::Value = toml::from_str("[server]\ntls = false\nname = 'web'").unwrap();
+///
+/// assert_eq!(true, toml.toggle("server.tls").unwrap());
+/// toml.append_str("server.name", "-1").unwrap();
+///
+/// assert_eq!(toml, toml::from_str("[server]\ntls = true\nname = 'web-1'").unwrap());
+/// ```
+pub trait TomlValueScalarExt {
+    /// Negate the boolean at `query`, returning its new value
+    fn toggle(&mut self, query: &str) -> Result<bool>;
+
+    /// Append `suffix` to the string at `query`
+    fn append_str(&mut self, query: &str, suffix: &str) -> Result<()>;
+}
+
+impl TomlValueScalarExt for Value {
+    fn toggle(&mut self, query: &str) -> Result<bool> {
+        match self.read_mut(query)? {
+            Some(Value::Boolean(ref mut b)) => {
+                *b = !*b;
+                Ok(*b)
+            }
+            Some(other) => Err(Error::TypeError("Boolean", name_of_val(other))),
+            None => Err(Error::NotAvailable(String::from(query))),
+        }
+    }
+
+    fn append_str(&mut self, query: &str, suffix: &str) -> Result<()> {
+        match self.read_mut(query)? {
+            Some(Value::String(ref mut s)) => {
+                s.push_str(suffix);
+                Ok(())
+            }
+            Some(other) => Err(Error::TypeError("String", name_of_val(other))),
+            None => Err(Error::NotAvailable(String::from(query))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_toggle() {
+        let mut toml: Value = toml_from_str("a = [ true ]\nb = 1").unwrap();
+
+        assert!(is_match!(toml.toggle("a.[0]"), Ok(false)));
+        assert!(is_match!(toml.toggle("a.[0]"), Ok(true)));
+        assert!(is_match!(
+            toml.toggle("b"),
+            Err(Error::TypeError("Boolean", "Integer"))
+        ));
+        assert!(is_match!(toml.toggle("c"), Err(Error::NotAvailable(_))));
+    }
+
+    #[test]
+    fn test_append_str() {
+        let mut toml: Value = toml_from_str("[a]\nb = 'x'\nc = true").unwrap();
+
+        assert!(toml.append_str("a.b", "yz").is_ok());
+        assert!(is_match!(
+            toml.append_str("a.c", "yz"),
+            Err(Error::TypeError("String", "Boolean"))
+        ));
+
+        let expected: Value = toml_from_str("[a]\nb = 'xyz'\nc = true").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -0,0 +1,261 @@
+/// Schemas for documents
+use std::fmt;
+
+use toml::Value;
+
+use crate::error::Result;
+use crate::inspect::ValueType;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::util::child_path;
+
+/// The values a document is expected to contain
+///
+/// The paths are queries, so they may match more than one value (like `bin.*.name`), see
+/// `TomlValueReadExt::read_all`. Each value they match is checked.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::inspect::ValueType;
+/// use toml_query::schema::{Schema, Violation};
+///
+/// let schema = Schema::new()
+///     .required("server.host", ValueType::String)
+///     .optional("server.port", ValueType::Integer)
+///     .required_array("server.aliases", ValueType::String);
+///
+/// let toml : toml::Value = toml::from_str(r#"
+///     [server]
+///     port = "80"
+///     aliases = [ "a", 1 ]
+/// "#).unwrap();
+///
+/// let violations = schema.validate(&toml).unwrap();
+/// assert_eq!(violations, vec![
+///     Violation::Missing { path: String::from("server.host") },
+///     Violation::WrongType {
+///         path: String::from("server.port"),
+///         expected: ValueType::Integer,
+///         found: ValueType::String,
+///     },
+///     Violation::WrongType {
+///         path: String::from("server.aliases.[1]"),
+///         expected: ValueType::String,
+///         found: ValueType::Integer,
+///     },
+/// ]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    path: String,
+    value_type: ValueType,
+    required: bool,
+    elements: Option<ValueType>,
+}
+
+/// A way a document does not match a `Schema`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A required value is missing
+    Missing { path: String },
+
+    /// A value has another type than expected
+    WrongType {
+        path: String,
+        expected: ValueType,
+        found: ValueType,
+    },
+
+    /// The path cannot be resolved in the document, for example because a value on the way is
+    /// not a table
+    Unresolvable { path: String, reason: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Missing { ref path } => write!(f, "'{}' is missing", path),
+            Violation::WrongType {
+                ref path,
+                expected,
+                found,
+            } => write!(f, "'{}' should be {}, but is {}", path, expected, found),
+            Violation::Unresolvable {
+                ref path,
+                ref reason,
+            } => write!(f, "'{}' cannot be resolved: {}", path, reason),
+        }
+    }
+}
+
+impl Schema {
+    /// An empty schema, which every document matches
+    pub fn new() -> Schema {
+        Schema::default()
+    }
+
+    /// Declare a value which must be present and of type `value_type`
+    pub fn required(self, path: &str, value_type: ValueType) -> Schema {
+        self.field(path, value_type, true, None)
+    }
+
+    /// Declare a value which must be of type `value_type`, if it is present
+    pub fn optional(self, path: &str, value_type: ValueType) -> Schema {
+        self.field(path, value_type, false, None)
+    }
+
+    /// Declare an array which must be present and whose elements must be of type `element_type`
+    pub fn required_array(self, path: &str, element_type: ValueType) -> Schema {
+        self.field(path, ValueType::Array, true, Some(element_type))
+    }
+
+    /// Declare an array whose elements must be of type `element_type`, if it is present
+    pub fn optional_array(self, path: &str, element_type: ValueType) -> Schema {
+        self.field(path, ValueType::Array, false, Some(element_type))
+    }
+
+    fn field(
+        mut self,
+        path: &str,
+        value_type: ValueType,
+        required: bool,
+        elements: Option<ValueType>,
+    ) -> Schema {
+        self.fields.push(Field {
+            path: path.to_owned(),
+            value_type,
+            required,
+            elements,
+        });
+        self
+    }
+
+    /// Check a document against the schema
+    ///
+    /// # Return value
+    ///
+    /// All violations, in the order the values were declared, or an error if one of the declared
+    /// paths is not a valid query.
+    pub fn validate(&self, doc: &Value) -> Result<Vec<Violation>> {
+        let mut violations = Vec::new();
+
+        for field in &self.fields {
+            Query::parse(&field.path)?;
+
+            let values = match doc.read_all(&field.path) {
+                Ok(values) => values,
+                Err(e) => {
+                    violations.push(Violation::Unresolvable {
+                        path: field.path.clone(),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            if values.is_empty() && field.required {
+                violations.push(Violation::Missing {
+                    path: field.path.clone(),
+                });
+            }
+
+            for (path, value) in values {
+                check_type(&path, value, field.value_type, &mut violations);
+
+                if let (Some(expected), Value::Array(elements)) = (field.elements, value) {
+                    for (idx, element) in elements.iter().enumerate() {
+                        let path = child_path(&path, &format!("[{}]", idx), ".");
+                        check_type(&path, element, expected, &mut violations);
+                    }
+                }
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+fn check_type(path: &str, value: &Value, expected: ValueType, violations: &mut Vec<Violation>) {
+    let found = ValueType::of(value);
+    if found != expected {
+        violations.push(Violation::WrongType {
+            path: path.to_owned(),
+            expected,
+            found,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_validate_valid() {
+        let schema = Schema::new()
+            .required("a", ValueType::Integer)
+            .optional("b", ValueType::String)
+            .optional_array("c", ValueType::Table)
+            .required("c.*.d", ValueType::Boolean);
+
+        let toml: Value = toml_from_str("a = 1\n[[c]]\nd = true\n[[c]]\nd = false").unwrap();
+        assert!(schema.validate(&toml).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_violations() {
+        let schema = Schema::new()
+            .required("a.b", ValueType::Integer)
+            .required("c", ValueType::Table)
+            .required("d.*.e", ValueType::String)
+            .required("f", ValueType::Datetime);
+
+        let toml: Value = toml_from_str(
+            r#"
+        a = 1
+        c = []
+
+        [[d]]
+        e = "x"
+
+        [[d]]
+        e = 2
+        "#,
+        )
+        .unwrap();
+
+        let violations = schema.validate(&toml).unwrap();
+        assert_eq!(4, violations.len());
+        assert!(
+            is_match!(violations[0], Violation::Unresolvable { ref path, .. } if path == "a.b")
+        );
+        assert_eq!(
+            "'c' should be Table, but is Array",
+            violations[1].to_string()
+        );
+        assert_eq!(
+            "'d.[1].e' should be String, but is Integer",
+            violations[2].to_string()
+        );
+        assert_eq!("'f' is missing", violations[3].to_string());
+    }
+
+    #[test]
+    fn test_validate_invalid_path() {
+        let schema = Schema::new().required("a..b", ValueType::Integer);
+        let toml: Value = toml_from_str("").unwrap();
+
+        let err = schema.validate(&toml).unwrap_err();
+        assert!(is_match!(*err.kind(), Error::EmptyIdentifier));
+    }
+
+}
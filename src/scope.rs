@@ -0,0 +1,174 @@
+/// Views of a part of a document
+use std::ops::{Deref, DerefMut};
+
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+
+pub trait TomlValueScopeExt {
+    /// Extension function for getting a view of the value at `prefix` in the current toml::Value
+    /// document
+    ///
+    /// # Semantics
+    ///
+    /// The view dereferences to the value at `prefix`, so all query traits can be used on it and
+    /// their queries are relative to `prefix`. The rest of the document cannot be reached through
+    /// the view, `^` queries do not go up beyond `prefix`.
+    ///
+    /// * If there is no value at `prefix`: `Error::NotAvailable`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::read::TomlValueReadExt;
+    /// use toml_query::scope::TomlValueScopeExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("[server.http]\nport = 80").unwrap();
+    ///
+    /// let http = toml.scoped("server.http").unwrap();
+    /// assert_eq!(http.read("port").unwrap(), Some(&toml::Value::Integer(80)));
+    /// ```
+    fn scoped(&self, prefix: &str) -> Result<SubDoc<&Value>>;
+
+    /// Extension function for getting a view of the value at `prefix` in the current toml::Value
+    /// document, through which the value can be changed
+    ///
+    /// See documentation of `TomlValueScopeExt::scoped`
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::scope::TomlValueScopeExt;
+    /// use toml_query::set::TomlValueSetExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[server.http]\nport = 80").unwrap();
+    ///
+    /// toml.scoped_mut("server.http").unwrap().set("port", 8080.into()).unwrap();
+    /// assert_eq!(toml["server"]["http"]["port"].as_integer(), Some(8080));
+    /// ```
+    fn scoped_mut(&mut self, prefix: &str) -> Result<SubDoc<&mut Value>>;
+}
+
+impl TomlValueScopeExt for Value {
+    fn scoped(&self, prefix: &str) -> Result<SubDoc<&Value>> {
+        let query = Query::parse(prefix)?;
+        let doc = self
+            .read_query(&query)?
+            .ok_or_else(|| Error::NotAvailable(prefix.to_owned()))?;
+
+        Ok(SubDoc {
+            prefix: query.to_string(),
+            doc,
+        })
+    }
+
+    fn scoped_mut(&mut self, prefix: &str) -> Result<SubDoc<&mut Value>> {
+        let query = Query::parse(prefix)?;
+        let doc = self
+            .read_mut_query(&query)?
+            .ok_or_else(|| Error::NotAvailable(prefix.to_owned()))?;
+
+        Ok(SubDoc {
+            prefix: query.to_string(),
+            doc,
+        })
+    }
+}
+
+/// A view of the value at a path in a document, see `TomlValueScopeExt::scoped`
+#[derive(Debug)]
+pub struct SubDoc<D> {
+    prefix: String,
+    doc: D,
+}
+
+impl<D> SubDoc<D> {
+    /// The path of the value in the document, in its canonical form
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The path in the document of `query`, which is relative to the value
+    pub fn absolute(&self, query: &str) -> String {
+        format!("{}.{}", self.prefix, query)
+    }
+}
+
+impl<'doc> SubDoc<&'doc Value> {
+    /// The value, with the lifetime of the document
+    pub fn into_inner(self) -> &'doc Value {
+        self.doc
+    }
+}
+
+impl<'doc> SubDoc<&'doc mut Value> {
+    /// The value, with the lifetime of the document
+    pub fn into_inner(self) -> &'doc mut Value {
+        self.doc
+    }
+}
+
+impl Deref for SubDoc<&Value> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        self.doc
+    }
+}
+
+impl Deref for SubDoc<&mut Value> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        self.doc
+    }
+}
+
+impl DerefMut for SubDoc<&mut Value> {
+    fn deref_mut(&mut self) -> &mut Value {
+        self.doc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::delete::TomlValueDeleteExt;
+    use crate::insert::TomlValueInsertExt;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_scoped() {
+        let toml: Value = toml_from_str("x = 1\n[a.b]\nc = [ 1, 2 ]").unwrap();
+
+        let sub = toml.scoped("a.b").unwrap();
+        assert_eq!("a.b", sub.prefix());
+        assert_eq!("a.b.c.[1]", sub.absolute("c.[1]"));
+        assert!(is_match!(sub.read("c.[1]"), Ok(Some(&Value::Integer(2)))));
+        assert!(is_match!(sub.read("x"), Ok(None)));
+        assert!(is_match!(sub.read("^.x"), Err(Error::ParentOfRoot)));
+
+        assert!(is_match!(toml.scoped("a.x"), Err(Error::NotAvailable(_))));
+    }
+
+    #[test]
+    fn test_scoped_mut() {
+        let mut toml: Value = toml_from_str("[a.b]\nc = 1\nd = 2").unwrap();
+
+        {
+            let mut sub = toml.scoped_mut("a.b").unwrap();
+            assert!(sub.insert("e.f", Value::Integer(3)).is_ok());
+            assert!(is_match!(sub.delete("c"), Ok(Some(Value::Integer(1)))));
+        }
+
+        let expected: Value = toml_from_str("[a.b]\nd = 2\n[a.b.e]\nf = 3").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -1,12 +1,15 @@
 /// The Toml Set extensions
+use std::mem;
 
 #[cfg(feature = "typed")]
 use serde::Serialize;
 use toml::Value;
 
 use crate::error::{Error, Result};
-use crate::tokenizer::tokenize_with_seperator;
+use crate::query::Query;
+use crate::resolver::in_query;
 use crate::tokenizer::Token;
+use crate::util::name_of_val;
 
 pub trait TomlValueSetExt {
     /// Extension function for setting a value in the current toml::Value document
@@ -17,6 +20,9 @@ pub trait TomlValueSetExt {
     /// The function _never_ creates intermediate data structures (Tables or Arrays) in the
     /// document.
     ///
+    /// Setting the index right after the last element of an array (or using the `[+]` token, as
+    /// in `"a.b.[+]"`) appends the value to the array.
+    ///
     /// # Return value
     ///
     /// * If the set operation worked correctly, `Ok(None)` is returned.
@@ -24,19 +30,69 @@ pub trait TomlValueSetExt {
     /// * On failure, `Err(e)` is returned:
     ///     * If the query is `"a.b.c"` but there is no table `"b"`: error
     ///     * If the query is `"a.b.[0]"` but "`b"` is not an array: error
-    ///     * If the query is `"a.b.[3]"` but the array at "`b"` has no index `3`: error
+    ///     * If the query is `"a.b.[4]"` but the array at "`b"` has less than 4 elements: error
     ///     * etc.
     ///
-    fn set_with_seperator(&mut self, query: &str, sep: char, value: Value)
+    fn set_with_seperator(&mut self, query: &str, sep: &str, value: Value)
         -> Result<Option<Value>>;
 
     /// Extension function for setting a value from the current toml::Value document
     ///
     /// See documentation of `TomlValueSetExt::set_with_seperator`
     fn set(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
-        self.set_with_seperator(query, '.', value)
+        self.set_with_seperator(query, ".", value)
     }
 
+    /// Extension function for setting a value in the current toml::Value document using a
+    /// pre-compiled query
+    ///
+    /// See documentation of `TomlValueSetExt::set_with_seperator`
+    fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>>;
+
+    /// Extension function for setting a value in the current toml::Value document, which never
+    /// grows arrays
+    ///
+    /// Other than `TomlValueSetExt::set`, setting the index right after the last element of an
+    /// array or using the `[+]` token returns `Error::ArrayIndexOutOfBounds`, so only existing
+    /// values are replaced.
+    fn set_strict(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
+        self.set_query_strict(&Query::parse(query)?, value)
+    }
+
+    /// Extension function for setting a value in the current toml::Value document using a
+    /// pre-compiled query, which never grows arrays
+    ///
+    /// See documentation of `TomlValueSetExt::set_strict`
+    fn set_query_strict(&mut self, query: &Query, value: Value) -> Result<Option<Value>>;
+
+    /// Extension function for setting a value in the current toml::Value document, which only
+    /// replaces values of the same type
+    ///
+    /// Other than `TomlValueSetExt::set`, replacing a value of another TOML type (like a string
+    /// with an integer, or an integer with a float) returns `Error::TypeMismatch` and leaves the
+    /// document as it is. New values can be set like with `TomlValueSetExt::set`.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::set::TomlValueSetExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[server]\nport = 80").unwrap();
+    ///
+    /// assert!(toml.set_checked("server.port", toml::Value::Integer(8080)).is_ok());
+    /// assert!(toml.set_checked("server.port", toml::Value::from("8080")).is_err());
+    /// ```
+    fn set_checked(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
+        self.set_query_checked(&Query::parse(query)?, value)
+    }
+
+    /// Extension function for setting a value in the current toml::Value document using a
+    /// pre-compiled query, which only replaces values of the same type
+    ///
+    /// See documentation of `TomlValueSetExt::set_checked`
+    fn set_query_checked(&mut self, query: &Query, value: Value) -> Result<Option<Value>>;
+
     /// A convenience method for setting any arbitrary serializable value.
     #[cfg(feature = "typed")]
     fn set_serialized<S: Serialize>(&mut self, query: &str, value: S) -> Result<Option<Value>> {
@@ -49,39 +105,91 @@ impl TomlValueSetExt for Value {
     fn set_with_seperator(
         &mut self,
         query: &str,
-        sep: char,
+        sep: &str,
         value: Value,
     ) -> Result<Option<Value>> {
-        use crate::resolver::mut_resolver::resolve;
+        self.set_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
 
-        let mut tokens = tokenize_with_seperator(query, sep)?;
-        let last = tokens.pop_last();
+    fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        set_impl(self, query, value, false, false).map_err(|e| in_query(self, query, e))
+    }
 
-        let val = resolve(self, &tokens, true)?.unwrap(); // safe because of resolve() guarantees
-        let last = last.unwrap_or_else(|| Box::new(tokens));
+    fn set_query_strict(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        set_impl(self, query, value, true, false).map_err(|e| in_query(self, query, e))
+    }
 
-        match *last {
-            Token::Identifier { ident, .. } => match val {
-                &mut Value::Table(ref mut t) => Ok(t.insert(ident, value)),
-                &mut Value::Array(_) => Err(Error::NoIdentifierInArray(ident)),
-                _ => Err(Error::QueryingValueAsTable(ident)),
-            },
+    fn set_query_checked(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        set_impl(self, query, value, false, true).map_err(|e| in_query(self, query, e))
+    }
+}
 
-            Token::Index { idx, .. } => match val {
-                &mut Value::Array(ref mut a) => {
-                    if a.len() > idx {
-                        let result = a.swap_remove(idx);
-                        a.insert(idx, value);
-                        Ok(Some(result))
-                    } else {
-                        a.push(value);
-                        Ok(None)
-                    }
-                }
-                &mut Value::Table(_) => Err(Error::NoIndexInTable(idx)),
-                _ => Err(Error::QueryingValueAsArray(idx)),
-            },
+/// Set the value, `strict` forbids growing arrays and `checked` replacing values of another type
+fn set_impl(
+    doc: &mut Value,
+    query: &Query,
+    value: Value,
+    strict: bool,
+    checked: bool,
+) -> Result<Option<Value>> {
+    use crate::resolver::mut_resolver::resolve;
+    use crate::resolver::parent_resolver::resolve_parents;
+
+    let mut tokens = resolve_parents(doc, query.tokens())?
+        .ok_or(Error::EmptyQueryError)?
+        .into_owned();
+    let last = tokens.pop().unwrap(); // safe because resolve_parents() yields no empty list
+    let val = resolve(doc, &tokens, true, query.max_depth())?.unwrap(); // safe because of resolve() guarantees
+
+    let check_type = |old: &Value| {
+        if checked && name_of_val(old) != name_of_val(&value) {
+            Err(Error::TypeMismatch {
+                expected: name_of_val(old),
+                found: name_of_val(&value),
+            })
+        } else {
+            Ok(())
         }
+    };
+
+    match last {
+        Token::Identifier { ident, .. } => match *val {
+            Value::Table(ref mut t) => {
+                if let Some(old) = t.get(&*ident) {
+                    check_type(old)?;
+                }
+                Ok(t.insert(ident.into_owned(), value))
+            }
+            Value::Array(_) => Err(Error::NoIdentifierInArray(ident.into_owned())),
+            _ => Err(Error::QueryingValueAsTable(ident.into_owned())),
+        },
+
+        Token::Index { idx, .. } => match *val {
+            Value::Array(ref mut a) => {
+                if a.len() > idx {
+                    check_type(&a[idx])?;
+                    Ok(Some(mem::replace(&mut a[idx], value)))
+                } else if a.len() == idx && !strict {
+                    a.push(value);
+                    Ok(None)
+                } else {
+                    Err(Error::ArrayIndexOutOfBounds(idx, a.len()))
+                }
+            }
+            Value::Table(_) => Err(Error::NoIndexInTable(idx)),
+            _ => Err(Error::QueryingValueAsArray(idx)),
+        },
+
+        Token::Append { .. } => match *val {
+            Value::Array(ref a) if strict => Err(Error::ArrayIndexOutOfBounds(a.len(), a.len())),
+            Value::Array(ref mut a) => {
+                a.push(value);
+                Ok(None)
+            }
+            _ => Err(Error::AppendToNonArray(name_of_val(val))),
+        },
+
+        _ => Err(Error::MultiValueQuery),
     }
 }
 
@@ -101,7 +209,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("table.a"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("table.a"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -146,7 +254,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("table.a"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("table.a"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
         let res = res.unwrap();
@@ -191,7 +299,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("array.[0]"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("array.[0]"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -233,7 +341,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("array.[0]"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("array.[0]"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -272,7 +380,7 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("a.b.c.d"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("a.b.c.d"), ".", Value::Integer(1));
 
         assert!(res.is_ok());
 
@@ -336,24 +444,27 @@ mod test {
     fn test_set_with_seperator_into_nonexistent_table() {
         let mut toml: Value = toml_from_str("").unwrap();
 
-        let res = toml.set_with_seperator(&String::from("table.a"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("table.a"), ".", Value::Integer(1));
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::IdentifierNotFoundInDocument(_)));
+        assert!(is_match!(
+            *res.kind(),
+            Error::IdentifierNotFoundInDocument(_, _)
+        ));
     }
 
     #[test]
     fn test_set_with_seperator_into_nonexistent_array() {
         let mut toml: Value = toml_from_str("").unwrap();
 
-        let res = toml.set_with_seperator(&String::from("[0]"), '.', Value::Integer(1));
+        let res = toml.set_with_seperator(&String::from("[0]"), ".", Value::Integer(1));
 
         assert!(res.is_err());
 
         let res = res.unwrap_err();
-        assert!(is_match!(res, Error::NoIndexInTable(0)));
+        assert!(is_match!(*res.kind(), Error::NoIndexInTable(0)));
     }
 
     #[test]
@@ -365,12 +476,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("array.foo"), '.', Value::Integer(2));
+        let res = toml.set_with_seperator(&String::from("array.foo"), ".", Value::Integer(2));
 
         assert!(res.is_err());
         let res = res.unwrap_err();
 
-        assert!(is_match!(res, Error::NoIdentifierInArray(_)));
+        assert!(is_match!(*res.kind(), Error::NoIdentifierInArray(_)));
     }
 
     #[test]
@@ -382,12 +493,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("foo.[0]"), '.', Value::Integer(2));
+        let res = toml.set_with_seperator(&String::from("foo.[0]"), ".", Value::Integer(2));
 
         assert!(res.is_err());
         let res = res.unwrap_err();
 
-        assert!(is_match!(res, Error::NoIndexInTable(_)));
+        assert!(is_match!(*res.kind(), Error::NoIndexInTable(_)));
     }
 
     #[test]
@@ -399,12 +510,12 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("val.foo"), '.', Value::Integer(2));
+        let res = toml.set_with_seperator(&String::from("val.foo"), ".", Value::Integer(2));
 
         assert!(res.is_err());
         let res = res.unwrap_err();
 
-        assert!(is_match!(res, Error::QueryingValueAsTable(_)));
+        assert!(is_match!(*res.kind(), Error::QueryingValueAsTable(_)));
     }
 
     #[test]
@@ -416,12 +527,216 @@ mod test {
         )
         .unwrap();
 
-        let res = toml.set_with_seperator(&String::from("foo.[0]"), '.', Value::Integer(2));
+        let res = toml.set_with_seperator(&String::from("foo.[0]"), ".", Value::Integer(2));
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+
+        assert!(is_match!(*res.kind(), Error::QueryingValueAsArray(_)));
+    }
+
+    #[test]
+    fn test_set_query() {
+        let mut toml: Value = toml_from_str("array = [ 0 ]").unwrap();
+        let query = Query::parse("array.[0]").unwrap();
+
+        for i in 1..4 {
+            let res = toml.set_query(&query, Value::Integer(i));
+            assert!(res.is_ok());
+            assert_eq!(Some(Value::Integer(i - 1)), res.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_set_with_seperator_append() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        array = [ 1 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.set_with_seperator(&String::from("array.[+]"), ".", Value::Integer(2));
+
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str("array = [ 1, 2 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_with_seperator_index_out_of_bounds() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        array = [ 1 ]
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.set_with_seperator(&String::from("array.[3]"), ".", Value::Integer(2));
+
+        assert!(res.is_err());
+        let res = res.unwrap_err();
+
+        assert!(is_match!(*res.kind(), Error::ArrayIndexOutOfBounds(3, 1)));
+    }
+
+    #[test]
+    fn test_set_with_seperator_keeps_order() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2, 3, 4 ]").unwrap();
+
+        let res = toml.set_with_seperator(&String::from("array.[1]"), ".", Value::Integer(5));
+
+        assert!(res.is_ok());
+        assert!(is_match!(res.unwrap(), Some(Value::Integer(2))));
+
+        let expected: Value = toml_from_str("array = [ 1, 5, 3, 4 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_with_seperator_keeps_capacity() {
+        use toml::map::Map;
+
+        let mut array = Vec::with_capacity(4);
+        array.push(Value::Integer(1));
+
+        let mut toml = Value::Table(Map::new());
+        if let Value::Table(ref mut t) = toml {
+            t.insert(String::from("array"), Value::Array(array));
+        }
+
+        let res = toml.set_with_seperator(&String::from("array.[0]"), ".", Value::Integer(2));
+        assert!(res.is_ok());
+
+        let array = toml.get("array").and_then(Value::as_array).unwrap();
+        assert_eq!(&vec![Value::Integer(2)], array);
+        assert_eq!(4, array.capacity());
+    }
+
+    #[test]
+    fn test_set_top_level_key() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+
+        let res = toml.set("a", Value::Integer(2));
+        assert!(res.is_ok());
+        assert_eq!(Some(Value::Integer(1)), res.unwrap());
+
+        let res = toml.set("b", Value::Integer(3));
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+
+        let expected: Value = toml_from_str(
+            "a = 2
+b = 3",
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_checked() {
+        let mut toml: Value = toml_from_str("a = 1\nb = [ 'x' ]").unwrap();
+
+        let res = toml.set_checked("a", Value::Integer(2));
+        assert!(is_match!(res, Ok(Some(Value::Integer(1)))));
+        let res = toml.set_checked("c", Value::Boolean(true));
+        assert!(is_match!(res, Ok(None)));
+
+        let err = toml.set_checked("a", Value::Float(2.5)).unwrap_err();
+        assert!(is_match!(
+            *err.kind(),
+            Error::TypeMismatch {
+                expected: "Integer",
+                found: "Float"
+            }
+        ));
+        let err = toml.set_checked("b.[0]", Value::Integer(1)).unwrap_err();
+        assert!(is_match!(*err.kind(), Error::TypeMismatch { .. }));
+
+        assert_eq!(toml, toml_from_str("a = 2\nb = [ 'x' ]\nc = true").unwrap());
+    }
+
+    #[test]
+    fn test_set_strict() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2 ]").unwrap();
+
+        let res = toml.set_strict("array.[1]", Value::Integer(3));
+        assert!(res.is_ok());
+        assert!(is_match!(res.unwrap(), Some(Value::Integer(2))));
+
+        let expected: Value = toml_from_str("array = [ 1, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_strict_does_not_grow_arrays() {
+        let mut toml: Value = toml_from_str("array = [ 1 ]").unwrap();
+
+        let res = toml.set_strict("array.[1]", Value::Integer(2));
+        assert!(res.is_err());
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::ArrayIndexOutOfBounds(1, 1)
+        ));
+
+        let res = toml.set_strict("array.[+]", Value::Integer(2));
+        assert!(res.is_err());
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::ArrayIndexOutOfBounds(1, 1)
+        ));
+
+        let expected: Value = toml_from_str("array = [ 1 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_with_seperator_append_in_the_middle_is_error() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[array]]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.set_with_seperator(&String::from("array.[+].a"), ".", Value::Integer(2));
 
         assert!(res.is_err());
         let res = res.unwrap_err();
 
-        assert!(is_match!(res, Error::QueryingValueAsArray(_)));
+        assert!(is_match!(res, Error::AppendNotSupported));
+    }
+
+    #[test]
+    fn test_set_sibling_via_parent() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [[bins]]
+        name = "a"
+        path = "a.rs"
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.set_with_seperator(
+            &String::from("bins.[0].name.^.path"),
+            ".",
+            Value::String(String::from("b.rs")),
+        );
+
+        assert!(res.is_ok());
+        let res = res.unwrap();
+
+        assert!(is_match!(res, Some(Value::String(_))));
+        assert_eq!(
+            Some(&Value::String(String::from("b.rs"))),
+            toml.get("bins")
+                .and_then(|b| b.get(0))
+                .and_then(|b| b.get("path"))
+        );
     }
 
     #[cfg(feature = "typed")]
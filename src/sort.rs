@@ -0,0 +1,112 @@
+/// The Toml Sort extensions
+use toml::Value;
+
+use crate::array::sort_by_key;
+use crate::error::Result;
+
+pub trait TomlValueSortExt {
+    /// Extension function for ordering the tables in the current toml::Value document by their
+    /// keys, recursively
+    ///
+    /// # Semantics
+    ///
+    /// The order of the keys only matters if the order of insertion is preserved (the
+    /// `preserve_order` feature of the toml crate), otherwise tables are always ordered by key.
+    ///
+    /// If `array_key` is given, the arrays of tables in the document are sorted by the values at
+    /// `array_key` in their tables as well, like `TomlValueArrayExt::sort_array_by_key` does.
+    /// Arrays which contain other values than tables are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::sort::TomlValueSortExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str(r#"
+    /// [[bin]]
+    /// path = "b.rs"
+    /// name = "b"
+    /// [[bin]]
+    /// path = "a.rs"
+    /// name = "a"
+    /// "#).unwrap();
+    ///
+    /// toml.sort_tables_recursive(Some("name")).unwrap();
+    ///
+    /// let keys = toml["bin"][0].as_table().unwrap().keys().collect::<Vec<_>>();
+    /// assert_eq!(keys, vec!["name", "path"]);
+    /// assert_eq!(toml["bin"][0]["name"].as_str(), Some("a"));
+    /// ```
+    fn sort_tables_recursive(&mut self, array_key: Option<&str>) -> Result<()>;
+}
+
+impl TomlValueSortExt for Value {
+    fn sort_tables_recursive(&mut self, array_key: Option<&str>) -> Result<()> {
+        match *self {
+            Value::Table(ref mut table) => {
+                let mut entries = ::std::mem::replace(table, Default::default())
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (key, mut value) in entries {
+                    value.sort_tables_recursive(array_key)?;
+                    table.insert(key, value);
+                }
+            }
+            Value::Array(ref mut array) => {
+                for elem in array.iter_mut() {
+                    elem.sort_tables_recursive(array_key)?;
+                }
+                if let Some(key) = array_key {
+                    if !array.is_empty() && array.iter().all(Value::is_table) {
+                        sort_by_key(array, key)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_sort_tables_recursive() {
+        let mut toml: Value = toml_from_str(
+            r#"
+            [b]
+            y = 1
+            x = [ { k = 2 }, { k = 1 }, { j = 0 } ]
+            [a]
+            z = [ 2, 1 ]
+            "#,
+        )
+        .unwrap();
+
+        toml.sort_tables_recursive(Some("k")).unwrap();
+
+        let expected: Value = toml_from_str(
+            r#"
+            [a]
+            z = [ 2, 1 ]
+            [b]
+            x = [ { k = 1 }, { k = 2 }, { j = 0 } ]
+            y = 1
+            "#,
+        )
+        .unwrap();
+        assert_eq!(expected, toml);
+        assert_eq!(
+            vec!["a", "b"],
+            toml.as_table().unwrap().keys().collect::<Vec<_>>()
+        );
+    }
+
+}
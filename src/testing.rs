@@ -0,0 +1,186 @@
+/// Assertions on documents for tests
+///
+/// The macros panic with a message which shows the query, the path of the value it resolved to
+/// and both values.
+use std::fmt::Write;
+
+use toml::Value;
+
+use crate::read::TomlValueReadExt;
+use crate::tokenizer::quote_identifier;
+use crate::util::child_path;
+
+/// Assert that the value at a query in a document equals a value, see `testing::path_eq()`
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate toml_query;
+/// extern crate toml;
+///
+/// # fn main() {
+/// let toml : toml::Value = toml::from_str("[[bin]]\nname = 'a'").unwrap();
+/// assert_toml_path_eq!(toml, "bin.[0].name", "a");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_toml_path_eq {
+    ($doc:expr, $query:expr, $expected:expr) => {
+        if let Some(msg) = $crate::testing::path_eq(&$doc, $query, $expected) {
+            panic!("{}", msg);
+        }
+    };
+}
+
+/// Assert that a document contains the values of a pattern document, see
+/// `testing::matches()`
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate toml_query;
+/// extern crate toml;
+///
+/// # fn main() {
+/// let toml : toml::Value = toml::from_str("[server]\nport = 80\nhost = 'a'").unwrap();
+/// let pattern : toml::Value = toml::from_str("[server]\nport = 80").unwrap();
+/// assert_toml_matches!(toml, pattern);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_toml_matches {
+    ($doc:expr, $pattern:expr) => {
+        if let Some(msg) = $crate::testing::matches(&$doc, &$pattern) {
+            panic!("{}", msg);
+        }
+    };
+}
+
+/// Check that `query` refers to exactly one value in `doc`, which equals `expected`
+///
+/// Returns the failure message, if not.
+pub fn path_eq<V: Into<Value>>(doc: &Value, query: &str, expected: V) -> Option<String> {
+    let expected = expected.into();
+    let mut values = match doc.read_all(query) {
+        Ok(values) => values,
+        Err(e) => return Some(format!("assertion failed: query `{}` failed: {}", query, e)),
+    };
+
+    match values.len() {
+        0 => Some(failure(query, "no value", None, &expected)),
+        1 => {
+            let (path, value) = values.pop().unwrap(); // safe, there is one value
+            if *value == expected {
+                None
+            } else {
+                Some(failure(query, &path, Some(value), &expected))
+            }
+        }
+        n => Some(format!(
+            "assertion failed: query `{}` refers to {} values, not one",
+            query, n
+        )),
+    }
+}
+
+/// Check that `doc` contains the values of `pattern`
+///
+/// Tables match if the table in `doc` has at least the keys of the table in `pattern` and their
+/// values match. Arrays match if they have the same length and their elements match, other
+/// values if they are equal.
+///
+/// Returns the failure message for the first value which does not match, if any.
+pub fn matches(doc: &Value, pattern: &Value) -> Option<String> {
+    mismatch(doc, pattern, "").map(|(path, value, expected)| {
+        let query = if path.is_empty() { "<document>" } else { &path };
+        failure(query, query, value, expected)
+    })
+}
+
+/// The path, the value and the expected value of the first mismatch below `path`
+fn mismatch<'v>(
+    value: &'v Value,
+    pattern: &'v Value,
+    path: &str,
+) -> Option<(String, Option<&'v Value>, &'v Value)> {
+    match (value, pattern) {
+        (Value::Table(table), Value::Table(pattern)) => {
+            pattern.iter().find_map(|(key, pattern)| {
+                let path = child_path(path, &quote_identifier(key, "."), ".");
+                match table.get(key) {
+                    Some(value) => mismatch(value, pattern, &path),
+                    None => Some((path, None, pattern)),
+                }
+            })
+        }
+        (Value::Array(array), Value::Array(patterns)) if array.len() == patterns.len() => array
+            .iter()
+            .zip(patterns)
+            .enumerate()
+            .find_map(|(idx, (value, pattern))| {
+                mismatch(
+                    value,
+                    pattern,
+                    &child_path(path, &format!("[{}]", idx), "."),
+                )
+            }),
+        (value, pattern) if value == pattern => None,
+        (value, pattern) => Some((path.to_owned(), Some(value), pattern)),
+    }
+}
+
+fn failure(query: &str, path: &str, value: Option<&Value>, expected: &Value) -> String {
+    let mut msg = String::from("assertion failed: `(doc[query] == expected)`\n");
+    let _ = writeln!(msg, "    query: `{}`", query);
+    let _ = writeln!(msg, "     path: `{}`", path);
+    match value {
+        Some(value) => {
+            let _ = writeln!(msg, "    value: `{}`", value);
+        }
+        None => msg.push_str("    value: none\n"),
+    }
+    let _ = write!(msg, " expected: `{}`", expected);
+    msg
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_path_eq() {
+        let toml: Value = toml_from_str("[a]\nb = [ 1, 2 ]").unwrap();
+
+        assert_toml_path_eq!(toml, "a.b.[1]", 2);
+        assert_toml_path_eq!(toml, "a.b", vec![1, 2]);
+
+        let msg = path_eq(&toml, "a.b.[1].^.[0]", 3).unwrap();
+        assert!(msg.contains("path: `a.b.[0]`"));
+        assert!(msg.contains("value: `1`"));
+        assert!(msg.contains("expected: `3`"));
+        assert!(path_eq(&toml, "a.c", 1).unwrap().contains("value: none"));
+        assert!(path_eq(&toml, "a.b.*", 1).unwrap().contains("2 values"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let toml: Value = toml_from_str("x = 1\n[a]\nb = [ { c = 1, d = 2 } ]").unwrap();
+
+        let pattern: Value = toml_from_str("[a]\nb = [ { c = 1 } ]").unwrap();
+        assert_toml_matches!(toml, pattern);
+
+        let pattern: Value = toml_from_str("[a]\nb = [ { c = 2 } ]").unwrap();
+        let msg = matches(&toml, &pattern).unwrap();
+        assert!(msg.contains("path: `a.b.[0].c`"));
+
+        let pattern: Value = toml_from_str("[a]\nb = []").unwrap();
+        assert!(matches(&toml, &pattern).unwrap().contains("path: `a.b`"));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected: `\"y\"`")]
+    fn test_assert_toml_path_eq_panics() {
+        let toml: Value = toml_from_str("a = 'x'").unwrap();
+        assert_toml_path_eq!(toml, "a", "y");
+    }
+
+}
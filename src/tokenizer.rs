@@ -1,109 +1,129 @@
 /// The tokenizer for the query interpreter
-use crate::error::{Error, Result};
+use std::borrow::Cow;
+
+use smallvec::SmallVec;
+use toml_query_tokenizer::{ErrorKind, Segment};
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum Token {
+use crate::error::{Error, Result};
+use crate::predicate::Predicate;
+use crate::query::DEFAULT_ESCAPE;
+
+/// A segment of a query
+///
+/// Identifiers borrow from the query they were parsed from where possible.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
     Identifier {
-        ident: String,
-        next: Option<Box<Token>>,
+        ident: Cow<'a, str>,
     },
 
     Index {
         idx: usize,
-        next: Option<Box<Token>>,
+    },
+
+    /// The `**` token, which matches any nesting depth (including none)
+    RecursiveDescent,
+
+    /// The `*` token, which matches all values of a table or array, one level deep
+    Wildcard,
+
+    /// The `^` token, which goes up one level to the parent of the current value
+    Parent,
+
+    /// The `[+]` token, which refers to the position after the last element of an array
+    Append,
+
+    /// The `[start:end]` token, which matches the array elements from `start` (inclusive) to
+    /// `end` (exclusive). Omitted bounds default to the start and end of the array.
+    Slice {
+        start: Option<usize>,
+        end: Option<usize>,
+    },
+
+    /// The `[0,2,5]` token, which matches the array elements at the listed indices. The indices
+    /// are sorted and deduplicated.
+    IndexUnion {
+        idxs: Vec<usize>,
+    },
+
+    /// The `{a,b,c}` token, which matches the listed keys of a table
+    KeyUnion {
+        idents: Vec<String>,
+    },
+
+    /// The `[?(@.name == "value")]` token, which matches the array elements for which the
+    /// predicate holds
+    Filter {
+        predicate: Predicate,
+    },
+
+    /// The `/regex/` token, which matches the keys of a table matching the regex
+    #[cfg(feature = "regex")]
+    KeyRegex {
+        regex: KeyRegex,
     },
 }
 
-impl Token {
-    pub fn next(&self) -> Option<&Box<Token>> {
-        trace!("Matching token (self): {:?}", self);
-        match self {
-            &Token::Identifier { ref next, .. } => next.as_ref(),
-            &Token::Index { ref next, .. } => next.as_ref(),
-        }
+/// The segments of a query, in order
+///
+/// Almost all queries have no more than four segments, these are stored without allocating.
+pub type Tokens<'a> = SmallVec<[Token<'a>; 4]>;
+
+/// A regex for matching table keys
+///
+/// Wrapped so that tokens can be compared, two regexes are equal if they were built from the same
+/// pattern.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct KeyRegex(::regex::Regex);
+
+#[cfg(feature = "regex")]
+impl KeyRegex {
+    pub fn new(pattern: &str) -> Result<KeyRegex> {
+        ::regex::Regex::new(pattern)
+            .map(KeyRegex)
+            .map_err(Error::Regex)
     }
 
-    /// Convenience function for `token.next().is_some()`
-    pub fn has_next(&self) -> bool {
-        trace!("self.has_next(): {:?}", self.next().is_some());
-        self.next().is_some()
+    pub fn is_match(&self, key: &str) -> bool {
+        self.0.is_match(key)
     }
 
-    pub fn set_next(&mut self, token: Token) {
-        trace!("self.set_next({:?})", token);
-        match self {
-            &mut Token::Identifier { ref mut next, .. } => *next = Some(Box::new(token)),
-            &mut Token::Index { ref mut next, .. } => *next = Some(Box::new(token)),
-        }
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
     }
+}
 
-    /// Pop the last token from the chain of tokens
-    ///
-    /// Returns None if the current Token has no next token
-    pub fn pop_last(&mut self) -> Option<Box<Token>> {
-        trace!("self.pop_last()");
-        if !self.has_next() {
-            trace!("self.pop_last(): No next");
-            None
-        } else {
-            trace!("self.pop_last(): Having next");
-            match self {
-                &mut Token::Identifier { ref mut next, .. } => {
-                    trace!("self.pop_last(): self is Identifier");
-                    if next.is_some() {
-                        trace!("self.pop_last(): next is Some(_)");
-                        let mut n = next.take().unwrap();
-                        if n.has_next() {
-                            trace!("self.pop_last(): next also has a next");
-
-                            trace!("self.pop_last(): Recursing now");
-                            let result = n.pop_last();
-
-                            *next = Some(n);
-
-                            trace!("self.pop_last(): Returning Result");
-                            return result;
-                        } else {
-                            trace!("self.pop_last(): next itself has no next, returning Some");
-                            Some(n)
-                        }
-                    } else {
-                        trace!("self.pop_last(): next is none, returning None");
-                        None
-                    }
-                }
+#[cfg(feature = "regex")]
+impl PartialEq for KeyRegex {
+    fn eq(&self, other: &KeyRegex) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
 
-                &mut Token::Index { ref mut next, .. } => {
-                    trace!("self.pop_last(): self is Index");
-                    if next.is_some() {
-                        trace!("self.pop_last(): next is Some(_)");
-
-                        let mut n = next.take().unwrap();
-                        if n.has_next() {
-                            trace!("self.pop_last(): next also has a next");
-
-                            trace!("self.pop_last(): Recursing now");
-                            let result = n.pop_last();
-
-                            *next = Some(n);
-
-                            trace!("self.pop_last(): Returning Result");
-                            return result;
-                        } else {
-                            trace!("self.pop_last(): next itself has no next, returning Some");
-                            Some(n)
-                        }
-                    } else {
-                        trace!("self.pop_last(): next is none, returning None");
-                        None
-                    }
-                }
-            }
+impl<'a> Token<'a> {
+    /// Copy the borrowed identifier, so the token does not borrow from the query anymore
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::Identifier { ident } => Token::Identifier {
+                ident: Cow::Owned(ident.into_owned()),
+            },
+            Token::Index { idx } => Token::Index { idx },
+            Token::RecursiveDescent => Token::RecursiveDescent,
+            Token::Wildcard => Token::Wildcard,
+            Token::Parent => Token::Parent,
+            Token::Append => Token::Append,
+            Token::Slice { start, end } => Token::Slice { start, end },
+            Token::IndexUnion { idxs } => Token::IndexUnion { idxs },
+            Token::KeyUnion { idents } => Token::KeyUnion { idents },
+            Token::Filter { predicate } => Token::Filter { predicate },
+            #[cfg(feature = "regex")]
+            Token::KeyRegex { regex } => Token::KeyRegex { regex },
         }
     }
 
     #[cfg(test)]
-    pub fn identifier(&self) -> &String {
+    pub fn identifier(&self) -> &str {
         trace!("self.identifier()");
         match self {
             &Token::Identifier { ref ident, .. } => &ident,
@@ -121,132 +141,269 @@ impl Token {
     }
 }
 
-pub fn tokenize_with_seperator(query: &str, seperator: char) -> Result<Token> {
-    use std::str::Split;
+/// Tokenize a query
+///
+/// The syntax is checked by `toml_query_tokenizer::tokenize()`, which the `toml_path!()` macro
+/// uses too. The first error in the query is returned as `Error::QuerySyntax`, which points at
+/// the part of the query it is about.
+pub fn tokenize_with_seperator<'a>(query: &'a str, seperator: &str) -> Result<Tokens<'a>> {
+    tokenize(query, seperator, Some(DEFAULT_ESCAPE))
+}
+
+/// Tokenize a query, with `escape` as escape character
+///
+/// In segments which extend to the next seperator, the character following the escape character
+/// is taken literally, so `a\.b` is the identifier `a.b`. Such a segment is always an identifier,
+/// `\*` is the key `*`. Quoted identifiers, key unions, filters and regexes are not affected.
+pub fn tokenize<'a>(query: &'a str, seperator: &str, escape: Option<char>) -> Result<Tokens<'a>> {
     trace!(
-        "tokenize_with_seperator(query: {:?}, seperator: {:?})",
+        "tokenize(query: {:?}, seperator: {:?}, escape: {:?})",
         query,
-        seperator
+        seperator,
+        escape
     );
 
-    /// Creates a Token object from a string
-    ///
-    /// # Panics
-    ///
-    /// * If the internal regex does not compile (should never happen)
-    /// * If the token is non-valid (that is, a array index with a non-i64)
-    /// * If the regex does not find anything
-    /// * If the integer in the brackets (`[]`) cannot be parsed to a valid i64
-    ///
-    /// # Incorrect behaviour
-    ///
-    /// * If the regex finds multiple captures
-    ///
-    /// # Returns
-    ///
-    /// The `Token` object with the correct identifier/index for this token and no next token.
-    ///
-    fn mk_token_object(s: &str) -> Result<Token> {
-        use regex::Regex;
-        use std::str::FromStr;
-
-        trace!("mk_token_object(s: {:?})", s);
-
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^\[\d+\]$").unwrap();
-        }
-
-        if !has_array_brackets(s) {
-            trace!("returning Ok(Identifier(ident: {:?}, next: None))", s);
-            return Ok(Token::Identifier {
-                ident: String::from(s),
-                next: None,
-            });
-        }
-
-        match RE.captures(s) {
-            None => return Err(Error::ArrayAccessWithoutIndex),
-            Some(captures) => {
-                trace!("Captured: {:?}", captures);
-                match captures.get(0) {
-                    None => Ok(Token::Identifier {
-                        ident: String::from(s),
-                        next: None,
-                    }),
-                    Some(mtch) => {
-                        trace!("First capture: {:?}", mtch);
-
-                        let mtch = without_array_brackets(mtch.as_str());
-                        trace!(".. without array brackets: {:?}", mtch);
-
-                        let i: usize = FromStr::from_str(&mtch).unwrap(); // save because regex
-
-                        trace!("returning Ok(Index(idx: {}, next: None)", i);
-                        Ok(Token::Index { idx: i, next: None })
-                    }
+    let syntax_error = |error, offset, len| Error::QuerySyntax {
+        query: String::from(query),
+        offset,
+        len,
+        error: Box::new(error),
+    };
+
+    let regexes = cfg!(feature = "regex");
+    let segments =
+        toml_query_tokenizer::tokenize(query, seperator, escape, regexes).map_err(|e| {
+            match e.kind {
+                ErrorKind::EmptyQuery => Error::EmptyQueryError,
+                ErrorKind::EmptySeperator => Error::EmptySeperator,
+                ErrorKind::EmptyIdentifier => syntax_error(Error::EmptyIdentifier, e.offset, e.len),
+                ErrorKind::ArrayAccessWithoutIndex => {
+                    syntax_error(Error::ArrayAccessWithoutIndex, e.offset, e.len)
+                }
+                ErrorKind::ArrayAccessWithInvalidIndex => {
+                    syntax_error(Error::ArrayAccessWithInvalidIndex, e.offset, e.len)
+                }
+                ErrorKind::Malformed => {
+                    let error = Error::QueryParsingError(String::from(query));
+                    syntax_error(error, e.offset, e.len)
                 }
             }
+        })?;
+
+    let tokens = segments
+        .into_iter()
+        .map(|segment| {
+            Ok(match segment {
+                Segment::Identifier(ident) => Token::Identifier { ident },
+                Segment::Index(idx) => Token::Index { idx },
+                Segment::RecursiveDescent => Token::RecursiveDescent,
+                Segment::Wildcard => Token::Wildcard,
+                Segment::Parent => Token::Parent,
+                Segment::Append => Token::Append,
+                Segment::Slice(start, end) => Token::Slice { start, end },
+                Segment::IndexUnion(idxs) => Token::IndexUnion { idxs },
+                Segment::KeyUnion(idents) => Token::KeyUnion { idents },
+                Segment::Filter { predicate, .. } => Token::Filter {
+                    predicate: predicate.into(),
+                },
+                #[cfg(feature = "regex")]
+                Segment::Regex(pattern) => Token::KeyRegex {
+                    // The error points at the whole segment, including the slashes
+                    regex: KeyRegex::new(pattern).map_err(|error| {
+                        let offset = pattern.as_ptr() as usize - query.as_ptr() as usize;
+                        syntax_error(error, offset - 1, pattern.len() + 2)
+                    })?,
+                },
+                #[cfg(not(feature = "regex"))]
+                Segment::Regex(_) => unreachable!("regexes are only tokenized with `regex`"),
+            })
+        })
+        .collect::<Result<Tokens<'a>>>()?;
+
+    trace!("tokenize(...): returning Ok({:?})", tokens);
+    Ok(tokens)
+}
+
+/// Tokenize a JSON Pointer (RFC 6901), like `/a/b/0`
+///
+/// `~1` and `~0` are unescaped to `/` and `~`. Segments which are array indices in the notation of
+/// RFC 6901 (`0` or `12`, but not `012`) become index tokens, `-` becomes the `[+]` token and all
+/// other segments become identifiers. Whether an index refers to an array element or to a table
+/// key depends on the document, so the `json_pointer` module turns them into identifiers where
+/// they are applied to tables.
+///
+/// The empty pointer refers to the whole document, for which there is no token, so it yields
+/// `Error::EmptyQueryError`.
+pub fn tokenize_json_pointer(pointer: &str) -> Result<Tokens<'static>> {
+    fn unescape(segment: &str) -> Option<String> {
+        let mut result = String::with_capacity(segment.len());
+        let mut chars = segment.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '~' => match chars.next() {
+                    Some('0') => result.push('~'),
+                    Some('1') => result.push('/'),
+                    _ => return None,
+                },
+                c => result.push(c),
+            }
         }
+
+        Some(result)
     }
 
-    /// Check whether a str begins with '[' and ends with ']'
-    fn has_array_brackets(s: &str) -> bool {
-        trace!("has_array_brackets({:?})", s);
-        s.as_bytes()[0] == b'[' && s.as_bytes()[s.len() - 1] == b']'
+    fn is_index(segment: &str) -> bool {
+        segment == "0" || (!segment.starts_with('0') && segment.chars().all(|c| c.is_ascii_digit()))
     }
 
-    /// Remove '[' and ']' from a str
-    fn without_array_brackets(s: &str) -> String {
-        trace!("without_array_brackets({:?})", s);
-        s.replace("[", "").replace("]", "")
+    trace!("tokenize_json_pointer(pointer: {:?})", pointer);
+
+    if pointer.is_empty() {
+        return Err(Error::EmptyQueryError);
     }
 
-    fn build_token_tree(split: &mut Split<char>, last: &mut Token) -> Result<()> {
-        trace!("build_token_tree(split: {:?}, last: {:?})", split, last);
-        match split.next() {
-            None => { /* No more tokens */ }
-            Some(token) => {
-                trace!("build_token_tree(...): next from split: {:?}", token);
+    if !pointer.starts_with('/') {
+        return Err(Error::InvalidJsonPointer(String::from(pointer)));
+    }
 
-                if token.len() == 0 {
-                    trace!("build_token_tree(...): Empty identifier... returning Error");
-                    return Err(Error::EmptyIdentifier);
-                }
+    pointer[1..]
+        .split('/')
+        .map(|segment| {
+            let segment = unescape(segment)
+                .ok_or_else(|| Error::InvalidJsonPointer(String::from(pointer)))?;
 
-                let mut token = mk_token_object(token)?;
-                build_token_tree(split, &mut token)?;
-                last.set_next(token);
+            if segment == "-" {
+                return Ok(Token::Append);
             }
-        }
 
-        trace!("build_token_tree(...): returning Ok(())");
-        Ok(())
+            if !segment.is_empty() && is_index(&segment) {
+                if let Ok(idx) = segment.parse() {
+                    return Ok(Token::Index { idx });
+                }
+            }
+
+            Ok(Token::Identifier {
+                ident: Cow::Owned(segment),
+            })
+        })
+        .collect()
+}
+
+/// Render an identifier so that `tokenize_with_seperator()` parses it back to the same identifier
+///
+/// Identifiers which contain the seperator or the escape character, end with the start of the
+/// seperator (like `a:` with the seperator `::`), or would otherwise be parsed as something else
+/// (like `[0]`, `bin[0]`, `**` or `^`) are quoted.
+pub fn quote_identifier(ident: &str, seperator: &str) -> String {
+    quote_identifier_with_escape(ident, seperator, Some(DEFAULT_ESCAPE))
+}
+
+/// Render an identifier so that `tokenize()` with `escape` parses it back to the same identifier
+///
+/// Identifiers are quoted as with `quote_identifier()`. Identifiers which contain both `"` and
+/// `'` cannot be quoted, they are escaped with `escape` instead. Without an escape character,
+/// such identifiers cannot be rendered so that they are parsed back.
+pub fn quote_identifier_with_escape(ident: &str, seperator: &str, escape: Option<char>) -> String {
+    let needs_quotes = ident.is_empty()
+        || ident.contains(seperator)
+        || ends_with_seperator_start(ident, seperator)
+        || ident.contains(DEFAULT_ESCAPE)
+        || ident.chars().any(|c| Some(c) == escape)
+        || ident.starts_with('"')
+        || ident.starts_with('\'')
+        || ident == "**"
+        || ident == "*"
+        || ident == "^"
+        || ident.starts_with('{')
+        || ident.starts_with('/')
+        || ident.starts_with('[')
+        || ident.ends_with(']');
+
+    match escape {
+        _ if !needs_quotes => String::from(ident),
+        Some(escape) if ident.contains('"') && ident.contains('\'') => {
+            escape_identifier(ident, seperator, escape)
+        }
+        _ => quote(ident),
     }
+}
 
-    if query.is_empty() {
-        trace!("Query is empty. Returning error");
-        return Err(Error::EmptyQueryError);
+/// Whether `ident` ends with a part of `seperator`, so that the seperator following it would be
+/// found too early
+fn ends_with_seperator_start(ident: &str, seperator: &str) -> bool {
+    seperator
+        .char_indices()
+        .skip(1)
+        .any(|(end, _)| ident.ends_with(&seperator[..end]))
+}
+
+/// Escape the characters of `ident` which are not alphanumeric, and the ones of the seperator
+fn escape_identifier(ident: &str, seperator: &str, escape: char) -> String {
+    let mut escaped = String::with_capacity(ident.len() * 2);
+    for c in ident.chars() {
+        if !(c.is_alphanumeric() || c == '_') || c == escape || seperator.contains(c) {
+            escaped.push(escape);
+        }
+        escaped.push(c);
     }
+    escaped
+}
 
-    let mut tokens = query.split(seperator);
-    trace!("Tokens splitted: {:?}", tokens);
+/// Quote a string with `"`, or with `'` if it contains a `"`
+///
+/// Strings which contain both cannot be quoted, see `quote_identifier_with_escape()`.
+pub fn quote(s: &str) -> String {
+    if s.contains('"') {
+        format!("'{}'", s)
+    } else {
+        format!("\"{}\"", s)
+    }
+}
 
-    match tokens.next() {
-        None => Err(Error::EmptyQueryError),
-        Some(token) => {
-            trace!("next Token: {:?}", token);
+/// Render a single token so that `tokenize_with_seperator()` parses it back to the same token
+pub fn render_token(token: &Token, seperator: &str) -> String {
+    fn join<T: ToString>(items: &[T]) -> String {
+        items
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
 
-            if token.len() == 0 {
-                trace!("Empty token. Returning Error");
-                return Err(Error::EmptyIdentifier);
-            }
+    fn quote_union_key(key: &str) -> String {
+        let needs_quotes =
+            key.is_empty() || key.trim() != key || key.contains(&[',', '}', '"', '\''][..]);
 
-            let mut tok = mk_token_object(token)?;
-            let _ = build_token_tree(&mut tokens, &mut tok)?;
+        if needs_quotes {
+            quote(key)
+        } else {
+            String::from(key)
+        }
+    }
 
-            trace!("Returning Ok({:?})", tok);
-            Ok(tok)
+    match *token {
+        Token::Identifier { ref ident, .. } => quote_identifier(ident, seperator),
+        Token::Index { idx, .. } => format!("[{}]", idx),
+        Token::RecursiveDescent { .. } => String::from("**"),
+        Token::Wildcard { .. } => String::from("*"),
+        Token::Parent { .. } => String::from("^"),
+        Token::Append { .. } => String::from("[+]"),
+        Token::Slice { start, end, .. } => {
+            let bound = |b: Option<usize>| b.map(|b| b.to_string()).unwrap_or_default();
+            format!("[{}:{}]", bound(start), bound(end))
         }
+        Token::IndexUnion { ref idxs, .. } => format!("[{}]", join(idxs)),
+        Token::KeyUnion { ref idents, .. } => {
+            let idents = idents
+                .iter()
+                .map(|i| quote_union_key(i))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", join(&idents))
+        }
+        Token::Filter { ref predicate, .. } => format!("[?({})]", predicate),
+        #[cfg(feature = "regex")]
+        Token::KeyRegex { ref regex, .. } => format!("/{}/", regex.as_str()),
     }
 }
 
@@ -255,299 +412,712 @@ mod test {
     use super::*;
     use crate::error::Error;
 
-    use std::ops::Deref;
+    fn ident(ident: &str) -> Token<'_> {
+        Token::Identifier {
+            ident: Cow::Borrowed(ident),
+        }
+    }
 
     #[test]
     fn test_tokenize_empty_query_to_error() {
-        let tokens = tokenize_with_seperator(&String::from(""), '.');
+        let tokens = tokenize_with_seperator("", ".");
         assert!(tokens.is_err());
         let tokens = tokens.unwrap_err();
 
-        assert!(is_match!(tokens, Error::EmptyQueryError { .. }));
+        assert!(is_match!(tokens, Error::EmptyQueryError));
     }
 
     #[test]
     fn test_tokenize_seperator_only() {
-        let tokens = tokenize_with_seperator(&String::from("."), '.');
+        let tokens = tokenize_with_seperator(".", ".");
         assert!(tokens.is_err());
         let tokens = tokens.unwrap_err();
 
-        assert!(is_match!(tokens, Error::EmptyIdentifier { .. }));
+        assert!(is_match!(*tokens.kind(), Error::EmptyIdentifier));
+    }
+
+    #[test]
+    fn test_tokenize_multi_char_seperator() {
+        let tokens = tokenize_with_seperator(r#"a::"b::c"::[0]::d:e"#, "::").unwrap();
+        let expected = [
+            ident("a"),
+            ident("b::c"),
+            Token::Index { idx: 0 },
+            ident("d:e"),
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_multi_char_seperator_trailing() {
+        let tokens = tokenize_with_seperator("a->", "->");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::EmptyIdentifier));
+        assert_eq!(Some((3, 0)), tokens.span());
+    }
+
+    #[test]
+    fn test_tokenize_empty_seperator() {
+        let tokens = tokenize_with_seperator("a", "");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(tokens, Error::EmptySeperator));
+    }
+
+    #[test]
+    fn test_tokenize_escaped_identifiers() {
+        let tokens = tokenize_with_seperator(r#"a\.b.c.\*.\[0].\"d\\"#, ".").unwrap();
+        let expected = [
+            ident("a.b"),
+            ident("c"),
+            ident("*"),
+            ident("[0]"),
+            ident(r#""d\"#),
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_escape_in_quotes_is_literal() {
+        let tokens = tokenize_with_seperator(r#""a\.b".c"#, ".").unwrap();
+        assert_eq!(&[ident(r"a\.b"), ident("c")][..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_custom_escape() {
+        let tokens = tokenize(r"a%.b.c\d", ".", Some('%')).unwrap();
+        assert_eq!(&[ident("a.b"), ident(r"c\d")][..], &tokens[..]);
+
+        let tokens = tokenize(r"a\.b", ".", None).unwrap();
+        assert_eq!(&[ident(r"a\"), ident("b")][..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_escape_at_end() {
+        let tokens = tokenize_with_seperator(r"a.b\", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::QueryParsingError(_)));
+        assert_eq!(Some((3, 1)), tokens.span());
     }
 
     #[test]
     fn test_tokenize_array_brackets_only() {
-        let tokens = tokenize_with_seperator(&String::from("[]"), '.');
+        let tokens = tokenize_with_seperator("[]", ".");
         assert!(tokens.is_err());
         let tokens = tokens.unwrap_err();
 
-        assert!(is_match!(tokens, Error::ArrayAccessWithoutIndex { .. }));
+        assert!(is_match!(*tokens.kind(), Error::ArrayAccessWithoutIndex));
     }
 
     #[test]
     fn test_tokenize_identifiers_with_array_brackets_only() {
-        let tokens = tokenize_with_seperator(&String::from("a.b.c.[]"), '.');
+        let tokens = tokenize_with_seperator("a.b.c.[]", ".");
         assert!(tokens.is_err());
         let tokens = tokens.unwrap_err();
 
-        assert!(is_match!(tokens, Error::ArrayAccessWithoutIndex { .. }));
+        assert!(is_match!(*tokens.kind(), Error::ArrayAccessWithoutIndex));
     }
 
     #[test]
     fn test_tokenize_identifiers_in_array_brackets() {
-        let tokens = tokenize_with_seperator(&String::from("[a]"), '.');
+        let tokens = tokenize_with_seperator("[a]", ".");
         assert!(tokens.is_err());
         let tokens = tokens.unwrap_err();
 
-        assert!(is_match!(tokens, Error::ArrayAccessWithoutIndex { .. }));
+        assert!(is_match!(*tokens.kind(), Error::ArrayAccessWithoutIndex));
+    }
+
+    #[test]
+    fn test_tokenize_identifier_with_index() {
+        let tokens = tokenize_with_seperator("bin[2].name", ".").unwrap();
+        let expected = [ident("bin"), Token::Index { idx: 2 }, ident("name")];
+        assert_eq!(&expected[..], &tokens[..]);
+
+        let tokens = tokenize_with_seperator("a[0][1:].*[+]", ".").unwrap();
+        let expected = [
+            ident("a"),
+            Token::Index { idx: 0 },
+            Token::Slice {
+                start: Some(1),
+                end: None,
+            },
+            Token::Wildcard,
+            Token::Append,
+        ];
+        assert_eq!(&expected[..], &tokens[..]);
+
+        let tokens = tokenize_with_seperator(r#"bin\[2]."a[0]""#, ".").unwrap();
+        assert_eq!(&[ident("bin[2]"), ident("a[0]")][..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_identifier_with_invalid_index() {
+        let tokens = tokenize_with_seperator("a.b[x]", ".").unwrap_err();
+        assert!(is_match!(*tokens.kind(), Error::ArrayAccessWithoutIndex));
+        assert_eq!(Some((4, 1)), tokens.span());
+
+        let tokens = tokenize_with_seperator("a.b[0]c]", ".").unwrap_err();
+        assert!(is_match!(*tokens.kind(), Error::QueryParsingError(_)));
+        assert_eq!(Some((6, 2)), tokens.span());
     }
 
     #[test]
     fn test_tokenize_single_token_query() {
-        let tokens = tokenize_with_seperator(&String::from("example"), '.');
+        let tokens = tokenize_with_seperator("example", ".");
         assert!(tokens.is_ok());
         let tokens = tokens.unwrap();
 
-        assert!(match tokens {
-            Token::Identifier {
-                ref ident,
-                next: None,
-            } => {
-                assert_eq!("example", ident);
-                true
-            }
-            _ => false,
-        });
+        assert_eq!(1, tokens.len());
+        assert_eq!("example", tokens[0].identifier());
     }
 
     #[test]
     fn test_tokenize_double_token_query() {
-        let tokens = tokenize_with_seperator(&String::from("a.b"), '.');
+        let tokens = tokenize_with_seperator("a.b", ".");
         assert!(tokens.is_ok());
         let tokens = tokens.unwrap();
 
-        assert!(match tokens {
+        assert_eq!(2, tokens.len());
+        assert_eq!("a", tokens[0].identifier());
+        assert_eq!("b", tokens[1].identifier());
+    }
+
+    #[test]
+    fn test_tokenize_borrows_identifiers() {
+        let query = String::from("a.b");
+        let tokens = tokenize_with_seperator(&query, ".").unwrap();
+
+        assert!(match tokens[0] {
             Token::Identifier {
-                next: Some(ref next),
-                ..
-            } => {
-                assert_eq!("b", next.deref().identifier());
-                match next.deref() {
-                    &Token::Identifier { next: None, .. } => true,
-                    _ => false,
-                }
-            }
+                ident: Cow::Borrowed(ident),
+            } => ident.as_ptr() == query.as_ptr(),
             _ => false,
         });
-        assert_eq!("a", tokens.identifier());
+
+        let owned: Token<'static> = tokens[0].clone().into_owned();
+        drop(tokens);
+        drop(query);
+        assert!(is_match!(
+            owned,
+            Token::Identifier {
+                ident: Cow::Owned(_),
+            }
+        ));
+        assert_eq!("a", owned.identifier());
+    }
+
+    #[test]
+    fn test_tokenize_short_query_does_not_allocate() {
+        let tokens = tokenize_with_seperator("a.[0].b.c", ".").unwrap();
+        assert!(!tokens.spilled());
+
+        let tokens = tokenize_with_seperator("a.[0].b.c.d", ".").unwrap();
+        assert!(tokens.spilled());
+        assert_eq!("d", tokens[4].identifier());
     }
 
     #[test]
     fn test_tokenize_ident_then_array_query() {
-        let tokens = tokenize_with_seperator(&String::from("a.[0]"), '.');
+        let tokens = tokenize_with_seperator("a.[0]", ".");
         assert!(tokens.is_ok());
         let tokens = tokens.unwrap();
 
-        assert_eq!("a", tokens.identifier());
-        assert!(match tokens {
-            Token::Identifier {
-                next: Some(ref next),
-                ..
-            } => match next.deref() {
-                &Token::Index { idx: 0, next: None } => true,
-                _ => false,
-            },
-            _ => false,
-        });
+        assert_eq!(2, tokens.len());
+        assert_eq!("a", tokens[0].identifier());
+        assert_eq!(0, tokens[1].idx());
     }
 
     #[test]
     fn test_tokenize_many_idents_then_array_query() {
-        let tokens = tokenize_with_seperator(&String::from("a.b.c.[1000]"), '.');
+        let tokens = tokenize_with_seperator("a.b.c.[1000]", ".");
         assert!(tokens.is_ok());
         let tokens = tokens.unwrap();
 
-        assert_eq!("a", tokens.identifier());
-
-        let expected = Token::Identifier {
-            ident: String::from("a"),
-            next: Some(Box::new(Token::Identifier {
-                ident: String::from("b"),
-                next: Some(Box::new(Token::Identifier {
-                    ident: String::from("c"),
-                    next: Some(Box::new(Token::Index {
-                        idx: 1000,
-                        next: None,
-                    })),
-                })),
-            })),
-        };
+        let expected = [
+            ident("a"),
+            ident("b"),
+            ident("c"),
+            Token::Index { idx: 1000 },
+        ];
 
-        assert_eq!(expected, tokens);
+        assert_eq!(&expected[..], &tokens[..]);
     }
 
     #[test]
     fn test_tokenize_empty_token_after_good_token() {
-        let tokens = tokenize_with_seperator(&String::from("a..b"), '.');
+        let tokens = tokenize_with_seperator("a..b", ".");
         assert!(tokens.is_err());
         let tokens = tokens.unwrap_err();
 
-        assert!(is_match!(tokens, Error::EmptyIdentifier { .. }));
+        assert!(is_match!(*tokens.kind(), Error::EmptyIdentifier));
     }
 
     quickcheck! {
         fn test_array_index(i: usize) -> bool {
-            match tokenize_with_seperator(&format!("[{}]", i), '.') {
-                Ok(Token::Index { next: None, ..  }) => true,
-                _                                    => false,
+            match tokenize_with_seperator(&format!("[{}]", i), ".") {
+                Ok(ref tokens) => is_match!(tokens[..], [Token::Index { .. }]),
+                _              => false,
             }
         }
     }
 
     #[test]
-    fn test_pop_last_token_from_single_identifier_token_is_none() {
-        let mut token = Token::Identifier {
-            ident: String::from("something"),
-            next: None,
-        };
+    fn test_tokenize_recursive_descent() {
+        let tokens = tokenize_with_seperator("**.password", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
 
-        let last = token.pop_last();
-        assert!(last.is_none());
+        let expected = [Token::RecursiveDescent, ident("password")];
+
+        assert_eq!(&expected[..], &tokens[..]);
     }
 
     #[test]
-    fn test_pop_last_token_from_single_index_token_is_none() {
-        let mut token = Token::Index { idx: 0, next: None };
+    fn test_tokenize_wildcard() {
+        let tokens = tokenize_with_seperator("*.secret", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
 
-        let last = token.pop_last();
-        assert!(last.is_none());
+        let expected = [Token::Wildcard, ident("secret")];
+
+        assert_eq!(&expected[..], &tokens[..]);
     }
 
     #[test]
-    fn test_pop_last_token_from_single_identifier_token_is_one() {
-        let mut token = Token::Identifier {
-            ident: String::from("some"),
-            next: Some(Box::new(Token::Identifier {
-                ident: String::from("thing"),
-                next: None,
-            })),
-        };
+    fn test_tokenize_parent() {
+        let tokens = tokenize_with_seperator("a.^.b", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
 
-        let last = token.pop_last();
+        let expected = [ident("a"), Token::Parent, ident("b")];
 
-        assert!(last.is_some());
-        let last = last.unwrap();
+        assert_eq!(&expected[..], &tokens[..]);
+    }
 
-        assert!(is_match!(*last, Token::Identifier { .. }));
-        match *last {
-            Token::Identifier { ident, .. } => {
-                assert_eq!("thing", ident);
+    #[test]
+    fn test_tokenize_append() {
+        let tokens = tokenize_with_seperator("jobs.[+]", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
+
+        let expected = [ident("jobs"), Token::Append];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_slice() {
+        let tokens = tokenize_with_seperator("a.[1:4]", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
+
+        let expected = [
+            ident("a"),
+            Token::Slice {
+                start: Some(1),
+                end: Some(4),
+            },
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_open_slices() {
+        let tokens = tokenize_with_seperator("[:3]", ".");
+        assert!(tokens.is_ok());
+        assert!(is_match!(
+            tokens.unwrap()[0],
+            Token::Slice {
+                start: None,
+                end: Some(3),
+                ..
             }
-            _ => panic!("What just happened?"),
-        }
+        ));
+
+        let tokens = tokenize_with_seperator("[2:]", ".");
+        assert!(tokens.is_ok());
+        assert!(is_match!(
+            tokens.unwrap()[0],
+            Token::Slice {
+                start: Some(2),
+                end: None,
+                ..
+            }
+        ));
+
+        let tokens = tokenize_with_seperator("[:]", ".");
+        assert!(tokens.is_ok());
+        assert!(is_match!(
+            tokens.unwrap()[0],
+            Token::Slice {
+                start: None,
+                end: None,
+                ..
+            }
+        ));
     }
 
     #[test]
-    fn test_pop_last_token_from_single_index_token_is_one() {
-        let mut token = Token::Index {
-            idx: 0,
-            next: Some(Box::new(Token::Index { idx: 1, next: None })),
-        };
+    fn test_tokenize_reversed_slice_is_error() {
+        let tokens = tokenize_with_seperator("a.[4:1]", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(
+            *tokens.kind(),
+            Error::ArrayAccessWithInvalidIndex
+        ));
+    }
 
-        let last = token.pop_last();
+    #[test]
+    fn test_tokenize_index_union() {
+        let tokens = tokenize_with_seperator("a.[5, 0,2,0]", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
 
-        assert!(last.is_some());
-        let last = last.unwrap();
+        let expected = [
+            ident("a"),
+            Token::IndexUnion {
+                idxs: vec![0, 2, 5],
+            },
+        ];
 
-        assert!(is_match!(*last, Token::Index { idx: 1, .. }));
+        assert_eq!(&expected[..], &tokens[..]);
     }
 
     #[test]
-    fn test_pop_last_token_from_identifier_chain() {
-        let tokens = tokenize_with_seperator(&String::from("a.b.c.d.e.f"), '.');
+    fn test_tokenize_broken_index_union() {
+        let tokens = tokenize_with_seperator("a.[0,]", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::ArrayAccessWithoutIndex));
+    }
+
+    #[test]
+    fn test_tokenize_key_union() {
+        let tokens = tokenize_with_seperator("db.{host, port,user,host}", ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
+
+        let expected = [
+            ident("db"),
+            Token::KeyUnion {
+                idents: vec![
+                    String::from("host"),
+                    String::from("port"),
+                    String::from("user"),
+                ],
+            },
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_key_union_with_quoted_keys() {
+        let tokens = tokenize_with_seperator(r#"{"a.b",'c,d'}.e"#, ".");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
+
+        let expected = [
+            Token::KeyUnion {
+                idents: vec![String::from("a.b"), String::from("c,d")],
+            },
+            ident("e"),
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_key_union_with_empty_key() {
+        let tokens = tokenize_with_seperator("db.{host,}", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::EmptyIdentifier));
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_key_union() {
+        let tokens = tokenize_with_seperator("db.{host.port", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::QueryParsingError(_)));
+    }
+
+    #[test]
+    fn test_tokenize_filter() {
+        use crate::predicate::Comparison;
+        use toml::Value;
+
+        let query = r#"bins.[?(@.name == "a.b]")].path"#;
+        let tokens = tokenize_with_seperator(query, ".");
         assert!(tokens.is_ok());
-        let mut tokens = tokens.unwrap();
+        let tokens = tokens.unwrap();
+
+        let expected = [
+            ident("bins"),
+            Token::Filter {
+                predicate: Predicate::Compare(
+                    vec![String::from("name")],
+                    Comparison::Eq,
+                    Value::String(String::from("a.b]")),
+                ),
+            },
+            ident("path"),
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!("f", last.unwrap().identifier());
+    #[test]
+    fn test_tokenize_broken_filter() {
+        let tokens = tokenize_with_seperator("bins.[?(@.name ==)]", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::QueryParsingError(_)));
+    }
+
+    #[test]
+    fn test_tokenize_huge_index() {
+        let tokens = tokenize_with_seperator("a.[99999999999999999999999]", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(
+            *tokens.kind(),
+            Error::ArrayAccessWithInvalidIndex
+        ));
     }
 
+    #[cfg(feature = "regex")]
     #[test]
-    fn test_pop_last_token_from_mixed_chain() {
-        let tokens = tokenize_with_seperator(&String::from("a.[100].c.[3].e.f"), '.');
+    fn test_tokenize_key_regex() {
+        let tokens = tokenize_with_seperator(r"a./^log\.[a-z]+\/x$/.b", ".");
         assert!(tokens.is_ok());
-        let mut tokens = tokens.unwrap();
+        let tokens = tokens.unwrap();
+
+        let expected = [
+            ident("a"),
+            Token::KeyRegex {
+                regex: KeyRegex::new(r"^log\.[a-z]+\/x$").unwrap(),
+            },
+            ident("b"),
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_tokenize_invalid_key_regex() {
+        let tokens = tokenize_with_seperator("a./(/", ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!("f", last.unwrap().identifier());
+        assert!(is_match!(*tokens.kind(), Error::Regex(_)));
     }
 
+    #[cfg(not(feature = "regex"))]
     #[test]
-    fn test_pop_last_token_from_identifier_chain_is_array() {
-        let tokens = tokenize_with_seperator(&String::from("a.b.c.d.e.f.[1000]"), '.');
+    fn test_tokenize_slashes_without_regex_feature() {
+        let tokens = tokenize_with_seperator("a./b/", ".");
         assert!(tokens.is_ok());
-        let mut tokens = tokens.unwrap();
+        let tokens = tokens.unwrap();
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!(1000, last.unwrap().idx());
+        assert_eq!("/b/", tokens[1].identifier());
     }
 
     #[test]
-    fn test_pop_last_token_from_mixed_chain_is_array() {
-        let tokens = tokenize_with_seperator(&String::from("a.[100].c.[3].e.f.[1000]"), '.');
+    fn test_tokenize_quoted_identifier_with_seperator() {
+        let tokens = tokenize_with_seperator(r#"a."b.c".d"#, ".");
         assert!(tokens.is_ok());
-        let mut tokens = tokens.unwrap();
+        let tokens = tokens.unwrap();
+
+        let expected = [ident("a"), ident("b.c"), ident("d")];
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!(1000, last.unwrap().idx());
+        assert_eq!(&expected[..], &tokens[..]);
     }
 
     #[test]
-    fn test_pop_last_token_from_one_token() {
-        let tokens = tokenize_with_seperator(&String::from("a"), '.');
+    fn test_tokenize_single_quoted_identifier() {
+        let tokens = tokenize_with_seperator(r#"'a."b"'.[0]"#, ".");
         assert!(tokens.is_ok());
-        let mut tokens = tokens.unwrap();
+        let tokens = tokens.unwrap();
 
-        let last = tokens.pop_last();
-        assert!(last.is_none());
+        assert_eq!(r#"a."b""#, tokens[0].identifier());
+        assert_eq!(0, tokens[1].idx());
     }
 
     #[test]
-    fn test_pop_last_chain() {
-        let tokens = tokenize_with_seperator(&String::from("a.[100].c.[3].e.f.[1000]"), '.');
+    fn test_tokenize_quoted_special_identifiers() {
+        let tokens = tokenize_with_seperator("\"[0]\".'**'.\"\"", ".");
         assert!(tokens.is_ok());
-        let mut tokens = tokens.unwrap();
+        let tokens = tokens.unwrap();
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!(1000, last.unwrap().idx());
+        let expected = [ident("[0]"), ident("**"), ident("")];
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!("f", last.unwrap().identifier());
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_quote() {
+        let tokens = tokenize_with_seperator(r#"a."b.c"#, ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!("e", last.unwrap().identifier());
+        assert!(is_match!(*tokens.kind(), Error::QueryParsingError(_)));
+    }
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!(3, last.unwrap().idx());
+    #[test]
+    fn test_tokenize_error_span() {
+        let span = |query: &str| tokenize_with_seperator(query, ".").unwrap_err().span();
+
+        assert_eq!(Some((2, 4)), span(r#"a."b.c"#));
+        assert_eq!(Some((5, 3)), span(r#"a."b"c.d"#));
+        assert_eq!(Some((2, 0)), span("a..b"));
+        assert_eq!(Some((3, 1)), span("a.[x]"));
+        assert_eq!(Some((5, 1)), span("a.[1,y]"));
+        assert_eq!(Some((2, 9)), span("a.[?(@ ==)]"));
+        assert_eq!(Some((5, 0)), span("äb.[]"));
+
+        let err = tokenize_with_seperator("a.[x]", ".").unwrap_err();
+        assert_eq!(
+            "Invalid query 'a.[x]' at offset 3: \
+             The passed query tries to access an array but does not specify the index",
+            err.to_string()
+        );
+    }
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!("c", last.unwrap().identifier());
+    #[test]
+    fn test_tokenize_reports_first_error() {
+        let span = |query: &str| tokenize_with_seperator(query, ".").unwrap_err().span();
 
-        let last = tokens.pop_last();
-        assert!(last.is_some());
-        assert_eq!(100, last.unwrap().idx());
+        assert_eq!(Some((1, 1)), span(r#"[x]."b"#));
+        assert_eq!(Some((2, 0)), span(r#"a..{b"#));
+        assert_eq!(Some((2, 6)), span(r#"a.{b.[x]"#));
+    }
 
-        let last = tokens.pop_last();
-        assert!(last.is_none());
+    #[test]
+    fn test_tokenize_garbage_after_quote() {
+        let tokens = tokenize_with_seperator(r#"a."b"c.d"#, ".");
+        assert!(tokens.is_err());
+        let tokens = tokens.unwrap_err();
+
+        assert!(is_match!(*tokens.kind(), Error::QueryParsingError(_)));
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!("a", quote_identifier("a", "."));
+        assert_eq!(r#""a.b""#, quote_identifier("a.b", "."));
+        assert_eq!("a.b", quote_identifier("a.b", "/"));
+        assert_eq!(r#""[0]""#, quote_identifier("[0]", "."));
+        assert_eq!(r#""a[0]""#, quote_identifier("a[0]", "."));
+        assert_eq!(r#"'"a".b'"#, quote_identifier(r#""a".b"#, "."));
+        assert_eq!(r#""{a}""#, quote_identifier("{a}", "."));
+        assert_eq!(r#""^""#, quote_identifier("^", "."));
+        assert_eq!(r#""*""#, quote_identifier("*", "."));
+        assert_eq!("*a", quote_identifier("*a", "."));
+        assert_eq!(r#""a\b""#, quote_identifier(r"a\b", "."));
+        assert_eq!(r#""a:""#, quote_identifier("a:", "::"));
+        assert_eq!(":a", quote_identifier(":a", "::"));
+        assert_eq!(r#""a-=""#, quote_identifier("a-=", "-=>"));
+        assert_eq!(r#"\"a\.\'b"#, quote_identifier(r#""a.'b"#, "."));
+        assert_eq!(
+            r#"%"a%'b"#,
+            quote_identifier_with_escape(r#""a'b"#, ".", Some('%'))
+        );
+    }
+
+    quickcheck! {
+        fn test_quote_identifier_roundtrip(ident: String) -> bool {
+            match tokenize_with_seperator(&quote_identifier(&ident, "."), ".") {
+                Ok(ref tokens) => match tokens[..] {
+                    [Token::Identifier { ident: ref parsed }] => *parsed == ident,
+                    _ => false,
+                },
+                _ => false,
+            }
+        }
+    }
+
+    quickcheck! {
+        fn test_quote_identifier_roundtrip_long_seperator(idents: Vec<String>) -> bool {
+            if idents.is_empty() {
+                return true;
+            }
+
+            let query = idents
+                .iter()
+                .map(|ident| quote_identifier(ident, "::"))
+                .collect::<Vec<_>>()
+                .join("::");
+
+            let roundtrip = match tokenize_with_seperator(&query, "::") {
+                Ok(ref tokens) => {
+                    tokens.len() == idents.len()
+                        && tokens.iter().zip(&idents).all(|(token, ident)| match *token {
+                            Token::Identifier { ident: ref parsed } => parsed == ident,
+                            _ => false,
+                        })
+                }
+                _ => false,
+            };
+            roundtrip
+        }
+    }
+
+    #[test]
+    fn test_quote_identifier_ending_with_seperator_start() {
+        for ident in &["a:", "a::", ":", "b:x:"] {
+            let query = format!("{}::c", quote_identifier(ident, "::"));
+            let tokens = tokenize_with_seperator(&query, "::").unwrap();
+            assert_eq!(&[self::ident(ident), self::ident("c")][..], &tokens[..]);
+        }
+    }
+
+    #[test]
+    fn test_tokenize_json_pointer() {
+        let tokens = tokenize_json_pointer("/a/0/-/01/~1b~0/");
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
+
+        let expected = [
+            ident("a"),
+            Token::Index { idx: 0 },
+            Token::Append,
+            ident("01"),
+            ident("/b~"),
+            ident(""),
+        ];
+
+        assert_eq!(&expected[..], &tokens[..]);
+    }
+
+    #[test]
+    fn test_tokenize_invalid_json_pointer() {
+        for pointer in &["a/b", "/a~", "/a~2"] {
+            let tokens = tokenize_json_pointer(pointer);
+            assert!(tokens.is_err());
+            assert!(is_match!(tokens.unwrap_err(), Error::InvalidJsonPointer(_)));
+        }
+
+        let tokens = tokenize_json_pointer("");
+        assert!(tokens.is_err());
+        assert!(is_match!(tokens.unwrap_err(), Error::EmptyQueryError));
     }
 
 }
@@ -0,0 +1,640 @@
+/// The Toml Transaction type
+use std::borrow::Cow;
+use std::mem;
+use std::ops::Deref;
+
+use toml::Value;
+
+use crate::array::{SortOrder, TomlValueArrayExt};
+use crate::copy::TomlValueCopyExt;
+use crate::delete::{DeleteMode, TomlValueDeleteExt};
+use crate::error::{Error, Result};
+use crate::insert::{InsertPolicy, TomlValueInsertExt};
+use crate::mv::{put, MoveOptions, TomlValueMoveExt};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::rename::TomlValueRenameExt;
+use crate::resolver::parent_resolver::resolve_parents;
+use crate::set::TomlValueSetExt;
+use crate::tokenizer::Token;
+use crate::upsert::{Intermediates, TomlValueUpsertExt, Upserted};
+
+/// A set of changes to a toml::Value document which can be undone
+///
+/// The transaction implements the extension traits which modify a document (`TomlValueSetExt`,
+/// `TomlValueInsertExt`, `TomlValueDeleteExt`, ...). For every change made through it, the
+/// previous state of the changed part of the document is recorded, so the changes can be
+/// undone without cloning the whole document. Reading works through `Deref`.
+///
+/// A failing operation leaves the document unchanged. The changes are kept with `commit()`
+/// and undone with `rollback()`. Dropping the transaction without committing it undoes the
+/// changes as well, so returning early with `?` is safe.
+///
+/// The previous state is recorded per table key. For changes to arrays and for queries
+/// matching more than one value, the whole array or the value containing all matches is
+/// recorded.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::delete::TomlValueDeleteExt;
+/// use toml_query::insert::TomlValueInsertExt;
+/// use toml_query::transaction::Transaction;
+///
+/// let mut toml : toml::Value = toml::from_str("[server]\nport = 80").unwrap();
+///
+/// {
+///     let mut tx = Transaction::new(&mut toml);
+///     tx.insert("server.host", toml::Value::String("localhost".into())).unwrap();
+///     tx.delete("server.port").unwrap();
+///     tx.rollback();
+/// }
+/// assert_eq!(toml, toml::from_str("[server]\nport = 80").unwrap());
+///
+/// {
+///     let mut tx = Transaction::new(&mut toml);
+///     tx.delete("server.port").unwrap();
+///     tx.commit();
+/// }
+/// assert_eq!(toml, toml::from_str("[server]").unwrap());
+/// ```
+#[derive(Debug)]
+pub struct Transaction<'doc> {
+    doc: &'doc mut Value,
+    journal: Vec<Undo>,
+}
+
+/// The previous state of a part of the document
+#[derive(Debug)]
+enum Undo {
+    /// The key `key` of the table at `path` had the value `old` (or did not exist)
+    Key {
+        path: Vec<Token<'static>>,
+        key: String,
+        old: Option<Value>,
+    },
+
+    /// The value at `path` was `old`
+    Value {
+        path: Vec<Token<'static>>,
+        old: Value,
+    },
+}
+
+impl<'doc> Transaction<'doc> {
+    /// Start a transaction on `doc`
+    pub fn new(doc: &'doc mut Value) -> Transaction<'doc> {
+        Transaction {
+            doc,
+            journal: Vec::new(),
+        }
+    }
+
+    /// Keep all changes made through the transaction
+    pub fn commit(mut self) {
+        self.journal.clear();
+    }
+
+    /// Undo all changes made through the transaction
+    pub fn rollback(mut self) {
+        self.undo_to(0);
+    }
+
+    /// Replace the whole document with `value`
+    pub(crate) fn replace_document(&mut self, value: Value) {
+        let old = mem::replace(&mut *self.doc, value);
+        self.journal.push(Undo::Value {
+            path: Vec::new(),
+            old,
+        });
+    }
+
+    /// Run `f`, which records the previous state of the parts of the document it changes in the
+    /// journal. If `f` fails, its changes are undone.
+    fn record<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Value, &mut Vec<Undo>) -> Result<T>,
+    {
+        let mark = self.journal.len();
+        let result = f(self.doc, &mut self.journal);
+        if result.is_err() {
+            self.undo_to(mark);
+        }
+
+        result
+    }
+
+    /// Undo the journal entries after `mark`, the newest first
+    fn undo_to(&mut self, mark: usize) {
+        while self.journal.len() > mark {
+            let entry = self.journal.pop().unwrap(); // safe because of the length check
+            undo(self.doc, entry);
+        }
+    }
+}
+
+/// Run `f` on a transaction on `doc` and keep its changes only if it succeeds
+///
+/// This is how operations which consist of several steps leave the document unchanged when one
+/// of the steps fails, without cloning the whole document.
+pub(crate) fn atomically<T, F>(doc: &mut Value, f: F) -> Result<T>
+where
+    F: FnOnce(&mut Transaction) -> Result<T>,
+{
+    let mut tx = Transaction::new(doc);
+    let result = f(&mut tx)?;
+    tx.commit();
+    Ok(result)
+}
+
+impl<'doc> Deref for Transaction<'doc> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        self.doc
+    }
+}
+
+impl<'doc> Drop for Transaction<'doc> {
+    fn drop(&mut self) {
+        self.undo_to(0);
+    }
+}
+
+/// Record the part of `doc` which is changed by an operation on `query`
+///
+/// This is the key of the innermost table on the path of the query, or the innermost array or
+/// value which contains everything the query might change.
+fn checkpoint(doc: &Value, query: &Query) -> Undo {
+    match resolve_parents(doc, query.tokens()) {
+        Ok(Some(tokens)) => {
+            checkpoint_segments(doc, tokens.iter().cloned().map(Token::into_owned).collect())
+        }
+        // The operation will fail or change the whole document
+        _ => Undo::Value {
+            path: Vec::new(),
+            old: doc.clone(),
+        },
+    }
+}
+
+fn checkpoint_segments(doc: &Value, segments: Vec<Token<'static>>) -> Undo {
+    let mut path = Vec::new();
+    let mut cur = doc;
+    let mut segments = segments.into_iter().peekable();
+
+    while let Some(token) = segments.next() {
+        let is_last = segments.peek().is_none();
+        let child = match token {
+            Token::Identifier { ref ident, .. } => match *cur {
+                Value::Table(ref t) => match t.get(&**ident) {
+                    Some(child) if !is_last => child,
+                    old => {
+                        return Undo::Key {
+                            path,
+                            key: ident.to_string(),
+                            old: old.cloned(),
+                        }
+                    }
+                },
+                _ => break,
+            },
+            Token::Index { idx, .. } => match *cur {
+                Value::Array(ref a) => match a.get(idx) {
+                    Some(child) if !is_last => child,
+                    _ => break,
+                },
+                _ => break,
+            },
+            _ => break,
+        };
+
+        path.push(token);
+        cur = child;
+    }
+
+    Undo::Value {
+        path,
+        old: cur.clone(),
+    }
+}
+
+fn undo(doc: &mut Value, entry: Undo) {
+    match entry {
+        Undo::Key { path, key, old } => {
+            if let Value::Table(ref mut t) = *descend(doc, &path) {
+                match old {
+                    Some(old) => t.insert(key, old),
+                    None => t.remove(&key),
+                };
+            }
+        }
+        Undo::Value { path, old } => *descend(doc, &path) = old,
+    }
+}
+
+/// Get the value at `path`, which consists of identifiers and indices only
+///
+/// Panics if there is no value at `path`.
+fn descend<'doc>(mut doc: &'doc mut Value, path: &[Token]) -> &'doc mut Value {
+    for token in path {
+        doc = match *token {
+            Token::Identifier { ref ident, .. } => doc.get_mut(&**ident),
+            Token::Index { idx, .. } => doc.get_mut(idx),
+            _ => None,
+        }
+        .unwrap();
+    }
+
+    doc
+}
+
+impl<'doc> TomlValueSetExt for Transaction<'doc> {
+    fn set_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        self.set_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.set_query(query, value)
+        })
+    }
+
+    fn set_query_strict(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.set_query_strict(query, value)
+        })
+    }
+
+    fn set_query_checked(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.set_query_checked(query, value)
+        })
+    }
+}
+
+impl<'doc> TomlValueInsertExt for Transaction<'doc> {
+    fn insert_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+        value: Value,
+    ) -> Result<Option<Value>> {
+        self.insert_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    fn insert_query_with_policy(
+        &mut self,
+        query: &Query,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.insert_query_with_policy(query, value, policy)
+        })
+    }
+}
+
+impl<'doc> TomlValueDeleteExt for Transaction<'doc> {
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Value>> {
+        self.delete_query(&Query::parse_with_seperator(query, sep)?)
+    }
+
+    fn delete_query_with_mode(&mut self, query: &Query, mode: DeleteMode) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.delete_query_with_mode(query, mode)
+        })
+    }
+
+    fn delete_all_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, Value)>> {
+        let parsed = Query::parse_with_seperator(query, sep)?;
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &parsed));
+            doc.delete_all_with_seperator(query, sep)
+        })
+    }
+
+    fn delete_recursive_query(
+        &mut self,
+        query: &Query,
+        prune_empty_parents: bool,
+    ) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            let tokens = resolve_parents(doc, query.tokens())?.ok_or(Error::EmptyQueryError)?;
+            let mut segments: Vec<Token<'static>> =
+                tokens.iter().cloned().map(Token::into_owned).collect();
+            segments.pop();
+
+            journal.push(checkpoint(doc, query));
+            let removed = doc.delete_recursive_query(query, false)?;
+            if removed.is_none() || !prune_empty_parents {
+                return Ok(removed);
+            }
+
+            // Prune the empty tables here, so each of them is recorded once it is empty
+            while let Some(token) = segments.pop() {
+                let mut ancestor = segments.clone();
+                ancestor.push(token);
+
+                match checkpoint_segments(doc, ancestor) {
+                    Undo::Key {
+                        path,
+                        key,
+                        old: Some(Value::Table(ref t)),
+                    } if t.is_empty() => {
+                        if let Value::Table(ref mut parent) = *descend(doc, &path) {
+                            parent.remove(&key);
+                        }
+                        journal.push(Undo::Key {
+                            path,
+                            key,
+                            old: Some(Value::Table(t.clone())),
+                        });
+                    }
+                    _ => break,
+                }
+            }
+
+            Ok(removed)
+        })
+    }
+}
+
+impl<'doc> TomlValueUpsertExt for Transaction<'doc> {
+    fn upsert_with_seperator(&mut self, query: &str, sep: &str, value: Value) -> Result<Upserted> {
+        self.upsert_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    fn upsert_query(&mut self, query: &Query, value: Value) -> Result<Upserted> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.upsert_query(query, value)
+        })
+    }
+
+    /// The value returned by `f` is recorded like an insertion, changes made through the
+    /// returned reference are undone with the transaction as well
+    fn get_or_insert_with_query<F>(
+        &mut self,
+        query: &Query,
+        intermediates: Intermediates,
+        f: F,
+    ) -> Result<&mut Value>
+    where
+        F: FnOnce() -> Value,
+    {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, query));
+            doc.get_or_insert_with_query(query, intermediates, f)
+                .map(|_| ())
+        })?;
+
+        Ok(self.doc.read_mut_query(query)?.unwrap()) // safe because the value exists now
+    }
+}
+
+impl<'doc> TomlValueRenameExt for Transaction<'doc> {
+    fn rename_query(
+        &mut self,
+        query: &Query,
+        new_key: &str,
+        overwrite: bool,
+    ) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            let entry = checkpoint(doc, query);
+            if let Undo::Key { ref path, .. } = entry {
+                let mut segments = path.clone();
+                segments.push(Token::Identifier {
+                    ident: Cow::Owned(String::from(new_key)),
+                });
+                journal.push(checkpoint_segments(doc, segments));
+            }
+            journal.push(entry);
+
+            doc.rename_query(query, new_key, overwrite)
+        })
+    }
+}
+
+impl<'doc> TomlValueMoveExt for Transaction<'doc> {
+    fn mv_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, from));
+            let value = doc
+                .delete_recursive_query(from, false)?
+                .ok_or_else(|| Error::NotAvailable(from.to_string()))?;
+
+            journal.push(checkpoint(doc, to));
+            put(doc, to, value, options)
+        })
+    }
+}
+
+impl<'doc> TomlValueCopyExt for Transaction<'doc> {
+    fn copy_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        let value = self
+            .doc
+            .read_query(from)?
+            .cloned()
+            .ok_or_else(|| Error::NotAvailable(from.to_string()))?;
+
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, to));
+            put(doc, to, value, options)
+        })
+    }
+}
+
+impl<'doc> TomlValueArrayExt for Transaction<'doc> {
+    fn push(&mut self, query: &str, value: Value) -> Result<()> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.push(query, value)
+        })
+    }
+
+    fn pop(&mut self, query: &str) -> Result<Option<Value>> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.pop(query)
+        })
+    }
+
+    fn insert_at(&mut self, query: &str, idx: usize, value: Value) -> Result<()> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.insert_at(query, idx, value)
+        })
+    }
+
+    fn remove_at(&mut self, query: &str, idx: usize) -> Result<Value> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.remove_at(query, idx)
+        })
+    }
+
+    fn sort_array(&mut self, query: &str, order: SortOrder) -> Result<()> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.sort_array(query, order)
+        })
+    }
+
+    fn sort_array_by_key(&mut self, query: &str, key: &str) -> Result<()> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.sort_array_by_key(query, key)
+        })
+    }
+
+    fn dedup_array(&mut self, query: &str) -> Result<usize> {
+        self.record(|doc, journal| {
+            journal.push(checkpoint(doc, &Query::parse(query)?));
+            doc.dedup_array(query)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    fn doc() -> Value {
+        toml_from_str(
+            r#"
+        [a]
+        b = 1
+        c = [ 1, 2, 3 ]
+
+        [a.d.e]
+        f = 2
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut toml = doc();
+
+        {
+            let mut tx = Transaction::new(&mut toml);
+            assert!(tx.set("a.b", Value::Integer(2)).is_ok());
+            assert!(tx.insert("x.y.[0]", Value::Integer(3)).is_ok());
+            assert!(tx.delete("a.c.[0]").is_ok());
+            assert!(tx.delete_recursive_and_prune("a.d.e.f").is_ok());
+            assert!(tx.upsert("a.c.[2]", Value::Integer(4)).is_ok());
+            assert!(tx.rename("a.b", "g").is_ok());
+            assert!(tx.mv("x", "a.x").is_ok());
+            assert!(tx.copy("a.x", "b").is_ok());
+            assert!(tx.push("a.c", Value::Integer(5)).is_ok());
+            assert!(tx.delete_all("*.c.[0,1]").is_ok());
+            assert_ne!(doc(), *tx);
+
+            tx.rollback();
+        }
+
+        assert_eq!(doc(), toml);
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut toml = doc();
+
+        {
+            let mut tx = Transaction::new(&mut toml);
+            assert!(tx.set("a.b", Value::Integer(2)).is_ok());
+            assert!(tx.delete_recursive("a.d").is_ok());
+            tx.commit();
+        }
+
+        let expected: Value = toml_from_str("a = { b = 2, c = [ 1, 2, 3 ] }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_drop_rolls_back() {
+        let mut toml = doc();
+
+        {
+            let mut tx = Transaction::new(&mut toml);
+            assert!(tx.delete_recursive("a").is_ok());
+        }
+
+        assert_eq!(doc(), toml);
+    }
+
+    #[test]
+    fn test_failing_operation_leaves_document_unchanged() {
+        let mut toml = doc();
+
+        {
+            let mut tx = Transaction::new(&mut toml);
+            assert!(tx.set("a.b", Value::Integer(2)).is_ok());
+
+            // creates the table `x` before failing on the out of bounds index
+            let res = tx.insert("x.y.[3]", Value::Integer(3));
+            assert!(res.is_err());
+            assert!(tx.get("x").is_none());
+
+            tx.commit();
+        }
+
+        let mut expected = doc();
+        assert!(expected.set("a.b", Value::Integer(2)).is_ok());
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_rollback_after_overwriting() {
+        let mut toml: Value = toml_from_str("a = 1\nb = 2\nc = [ 3 ]").unwrap();
+
+        {
+            let mut tx = Transaction::new(&mut toml);
+            assert!(tx.rename_overwriting("a", "b").is_ok());
+            assert!(tx
+                .mv_with_options(
+                    "b",
+                    "c.[0]",
+                    MoveOptions {
+                        overwrite: true,
+                        ..MoveOptions::default()
+                    }
+                )
+                .is_ok());
+            assert_eq!(Some(&Value::Integer(1)), tx.get("c").and_then(|c| c.get(0)));
+        }
+
+        let expected: Value = toml_from_str("a = 1\nb = 2\nc = [ 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -0,0 +1,117 @@
+/// The Toml Transform extensions
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::util::name_of_val;
+
+/// Extension functions for changing the value at a path in a toml::Value document with a
+/// closure
+///
+/// All functions fail with `Error::NotAvailable` if there is no value at the queried path.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::transform::TomlValueTransformExt;
+///
+/// let mut toml : toml::Value = toml::from_str("[server]\nname = 'web'\nports = [ 80, 443 ]").unwrap();
+///
+/// toml.map_in_place("server.name", |name| *name = toml::Value::from("api")).unwrap();
+/// toml.map_array("server.ports", |port| *port = toml::Value::from(port.as_integer().unwrap() + 8000)).unwrap();
+///
+/// assert_eq!(toml, toml::from_str("[server]\nname = 'api'\nports = [ 8080, 8443 ]").unwrap());
+/// ```
+pub trait TomlValueTransformExt {
+    /// Call `f` with the value at `query`
+    fn map_in_place<F>(&mut self, query: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Value),
+    {
+        self.map_in_place_query(&Query::parse(query)?, f)
+    }
+
+    /// Call `f` with the value at the pre-compiled `query`
+    fn map_in_place_query<F>(&mut self, query: &Query, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Value);
+
+    /// Call `f` with each element of the array at `query`, in order
+    ///
+    /// Fails with `Error::TypeError` if the value is no array.
+    fn map_array<F>(&mut self, query: &str, f: F) -> Result<()>
+    where
+        F: FnMut(&mut Value),
+    {
+        self.map_array_query(&Query::parse(query)?, f)
+    }
+
+    /// Call `f` with each element of the array at the pre-compiled `query`, in order
+    fn map_array_query<F>(&mut self, query: &Query, f: F) -> Result<()>
+    where
+        F: FnMut(&mut Value);
+}
+
+impl TomlValueTransformExt for Value {
+    fn map_in_place_query<F>(&mut self, query: &Query, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Value),
+    {
+        let value = self
+            .read_mut_query(query)?
+            .ok_or_else(|| Error::NotAvailable(query.to_string()))?;
+        f(value);
+        Ok(())
+    }
+
+    fn map_array_query<F>(&mut self, query: &Query, f: F) -> Result<()>
+    where
+        F: FnMut(&mut Value),
+    {
+        match self.read_mut_query(query)? {
+            Some(Value::Array(array)) => {
+                array.iter_mut().for_each(f);
+                Ok(())
+            }
+            Some(other) => Err(Error::TypeError("Array", name_of_val(other))),
+            None => Err(Error::NotAvailable(query.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_map_in_place() {
+        let mut toml: Value = toml_from_str("[a]\nb = [ 1, 2 ]").unwrap();
+
+        let res = toml.map_in_place("a.b", |b| b.as_array_mut().unwrap().push(Value::Integer(3)));
+        assert!(res.is_ok());
+        let res = toml.map_in_place("a.c", |_| panic!("Called for a missing value"));
+        assert!(is_match!(res, Err(Error::NotAvailable(_))));
+
+        let expected: Value = toml_from_str("[a]\nb = [ 1, 2, 3 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_map_array() {
+        let mut toml: Value = toml_from_str("a = [ 'x', 'y' ]\nb = 1").unwrap();
+
+        let res = toml.map_array("a", |s| {
+            *s = Value::String(format!("{}!", s.as_str().unwrap()))
+        });
+        assert!(res.is_ok());
+        let res = toml.map_array("b", |_| ());
+        assert!(is_match!(res, Err(Error::TypeError("Array", "Integer"))));
+
+        let expected: Value = toml_from_str("a = [ 'x!', 'y!' ]\nb = 1").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+}
@@ -0,0 +1,387 @@
+/// The Toml Upsert extensions
+use toml::Value;
+
+use crate::error::{Error, Result};
+use crate::insert::TomlValueInsertExt;
+use crate::query::Query;
+use crate::read::TomlValueReadExt;
+use crate::resolver::in_query;
+use crate::set::TomlValueSetExt;
+
+/// What an upsert operation did
+#[derive(Debug, Clone, PartialEq)]
+pub enum Upserted {
+    /// The value existed and was replaced, the old value is returned
+    Replaced(Value),
+
+    /// The value did not exist and was inserted
+    Inserted,
+}
+
+/// What `get_or_insert_with` does if the tables and arrays leading to a missing value are missing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intermediates {
+    /// Create them, like `TomlValueInsertExt::insert` does
+    Create,
+
+    /// Only insert the value itself, the missing tables and arrays are an error like for
+    /// `TomlValueSetExt::set`
+    Require,
+}
+
+impl Default for Intermediates {
+    fn default() -> Intermediates {
+        Intermediates::Create
+    }
+}
+
+pub trait TomlValueUpsertExt {
+    /// Extension function for setting or inserting a value in the current toml::Value document
+    /// using a custom seperator
+    ///
+    /// # Semantics
+    ///
+    /// If there is a value at the queried path, it is replaced like `TomlValueSetExt::set` does.
+    /// Otherwise the value is inserted like `TomlValueInsertExt::insert` does, creating
+    /// intermediate tables and arrays. Queries containing the `[+]` token are always inserted.
+    ///
+    /// # Return value
+    ///
+    /// * `Ok(Upserted::Replaced(old_value))` if an existing value was replaced
+    /// * `Ok(Upserted::Inserted)` if the value was inserted
+    /// * On failure (for example if the query goes through a value which is not a table),
+    ///   `Err(e)` is returned
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::upsert::{TomlValueUpsertExt, Upserted};
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[a]\nb = 1").unwrap();
+    ///
+    /// let res = toml.upsert("a.b", toml::Value::Integer(2)).unwrap();
+    /// assert_eq!(Upserted::Replaced(toml::Value::Integer(1)), res);
+    ///
+    /// let res = toml.upsert("a.c.d", toml::Value::Integer(3)).unwrap();
+    /// assert_eq!(Upserted::Inserted, res);
+    /// ```
+    fn upsert_with_seperator(&mut self, query: &str, sep: &str, value: Value) -> Result<Upserted>;
+
+    /// Extension function for setting or inserting a value in the current toml::Value document
+    ///
+    /// See documentation of `TomlValueUpsertExt::upsert_with_seperator`
+    fn upsert(&mut self, query: &str, value: Value) -> Result<Upserted> {
+        self.upsert_with_seperator(query, ".", value)
+    }
+
+    /// Extension function for setting or inserting a value in the current toml::Value document
+    /// using a pre-compiled query
+    ///
+    /// See documentation of `TomlValueUpsertExt::upsert_with_seperator`
+    fn upsert_query(&mut self, query: &Query, value: Value) -> Result<Upserted>;
+
+    /// Extension function for reading a value from the current toml::Value document mutably,
+    /// inserting the value returned by `f` first if there is none
+    ///
+    /// # Semantics
+    ///
+    /// If there is a value at the queried path, it is returned and `f` is not called. Otherwise
+    /// the value returned by `f` is inserted like `TomlValueInsertExt::insert` does, creating
+    /// intermediate tables and arrays. Queries containing the `[+]` token are an
+    /// `Error::AppendNotSupported`, as there is never a value to return.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::upsert::TomlValueUpsertExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[a]\nb = 1").unwrap();
+    ///
+    /// let authors = toml.get_or_insert_with("a.authors", || toml::Value::Array(vec![])).unwrap();
+    /// authors.as_array_mut().unwrap().push(toml::Value::from("me"));
+    ///
+    /// assert_eq!(toml, toml::from_str("[a]\nb = 1\nauthors = [ 'me' ]").unwrap());
+    /// ```
+    fn get_or_insert_with<F>(&mut self, query: &str, f: F) -> Result<&mut Value>
+    where
+        F: FnOnce() -> Value,
+    {
+        self.get_or_insert_with_query(&Query::parse(query)?, Intermediates::Create, f)
+    }
+
+    /// Extension function for reading a value from the current toml::Value document mutably
+    /// using a pre-compiled query, inserting the value returned by `f` first if there is none
+    ///
+    /// See documentation of `TomlValueUpsertExt::get_or_insert_with`, `intermediates` decides
+    /// whether missing tables and arrays on the path are created.
+    fn get_or_insert_with_query<F>(
+        &mut self,
+        query: &Query,
+        intermediates: Intermediates,
+        f: F,
+    ) -> Result<&mut Value>
+    where
+        F: FnOnce() -> Value;
+
+    /// Extension function for setting a value in the current toml::Value document, if there is
+    /// no value at the queried path yet
+    ///
+    /// Like for `TomlValueSetExt::set`, the tables and arrays leading to the value must exist.
+    /// Returns whether the value was set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::upsert::TomlValueUpsertExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("[server]\nport = 8080").unwrap();
+    ///
+    /// assert!(!toml.set_if_absent("server.port", toml::Value::Integer(80)).unwrap());
+    /// assert!(toml.set_if_absent("server.host", toml::Value::from("localhost")).unwrap());
+    ///
+    /// assert_eq!(toml, toml::from_str("[server]\nport = 8080\nhost = 'localhost'").unwrap());
+    /// ```
+    fn set_if_absent(&mut self, query: &str, value: Value) -> Result<bool> {
+        write_if_absent(self, query, Intermediates::Require, value)
+    }
+
+    /// Extension function for inserting a value in the current toml::Value document, if there is
+    /// no value at the queried path yet
+    ///
+    /// Like for `TomlValueInsertExt::insert`, missing tables and arrays leading to the value are
+    /// created. Returns whether the value was inserted.
+    fn insert_if_absent(&mut self, query: &str, value: Value) -> Result<bool> {
+        write_if_absent(self, query, Intermediates::Create, value)
+    }
+}
+
+/// Write `value` with `get_or_insert_with_query()`, returning whether it was written
+fn write_if_absent<D: TomlValueUpsertExt + ?Sized>(
+    doc: &mut D,
+    query: &str,
+    intermediates: Intermediates,
+    value: Value,
+) -> Result<bool> {
+    let mut written = false;
+    doc.get_or_insert_with_query(&Query::parse(query)?, intermediates, || {
+        written = true;
+        value
+    })?;
+    Ok(written)
+}
+
+impl TomlValueUpsertExt for Value {
+    fn upsert_with_seperator(&mut self, query: &str, sep: &str, value: Value) -> Result<Upserted> {
+        self.upsert_query(&Query::parse_with_seperator(query, sep)?, value)
+    }
+
+    fn upsert_query(&mut self, query: &Query, value: Value) -> Result<Upserted> {
+        let exists = match self.read_query(query) {
+            Ok(found) => found.is_some(),
+            // The appended element never exists
+            Err(Error::AppendNotSupported) => false,
+            Err(e) => return Err(e),
+        };
+
+        if exists {
+            self.set_query(query, value)
+                .map(|old| old.map(Upserted::Replaced).unwrap_or(Upserted::Inserted))
+        } else {
+            self.insert_query(query, value).map(|_| Upserted::Inserted)
+        }
+    }
+
+    fn get_or_insert_with_query<F>(
+        &mut self,
+        query: &Query,
+        intermediates: Intermediates,
+        f: F,
+    ) -> Result<&mut Value>
+    where
+        F: FnOnce() -> Value,
+    {
+        use crate::resolver::non_mut_resolver::resolve;
+        use crate::resolver::parent_resolver::resolve_parents;
+
+        if self.read_query(query)?.is_none() {
+            if intermediates == Intermediates::Require {
+                let tokens = resolve_parents(&*self, query.tokens())
+                    .map_err(|e| in_query(&*self, query, e))?
+                    .ok_or(Error::EmptyQueryError)?;
+                let parent = &tokens[..tokens.len() - 1];
+                resolve(&*self, parent, true, query.max_depth())
+                    .map_err(|e| in_query(&*self, query, e))?;
+            }
+
+            self.insert_query(query, f())?;
+        }
+
+        Ok(self.read_mut_query(query)?.unwrap()) // safe because the value exists now
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+    use toml::Value;
+
+    #[test]
+    fn test_upsert_replaces_existing_value() {
+        let mut toml: Value = toml_from_str(
+            r#"
+        [table]
+        a = 1
+        "#,
+        )
+        .unwrap();
+
+        let res = toml.upsert("table.a", Value::Integer(2));
+        assert!(res.is_ok());
+        assert_eq!(Upserted::Replaced(Value::Integer(1)), res.unwrap());
+
+        let expected: Value = toml_from_str("table = { a = 2 }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_upsert_inserts_missing_value() {
+        let mut toml: Value = toml_from_str("").unwrap();
+
+        let res = toml.upsert("table.a.[0]", Value::Integer(1));
+        assert!(res.is_ok());
+        assert_eq!(Upserted::Inserted, res.unwrap());
+
+        let expected: Value = toml_from_str("table = { a = [ 1 ] }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_set_and_insert_if_absent() {
+        let mut toml: Value = toml_from_str("[a]\nb = 1").unwrap();
+
+        assert!(is_match!(
+            toml.set_if_absent("a.b", Value::Integer(2)),
+            Ok(false)
+        ));
+        assert!(is_match!(
+            toml.set_if_absent("a.c", Value::Integer(3)),
+            Ok(true)
+        ));
+        assert!(toml.set_if_absent("x.y", Value::Integer(4)).is_err());
+
+        assert!(is_match!(
+            toml.insert_if_absent("a.b", Value::Integer(2)),
+            Ok(false)
+        ));
+        assert!(is_match!(
+            toml.insert_if_absent("x.y", Value::Integer(4)),
+            Ok(true)
+        ));
+
+        let expected: Value = toml_from_str("[a]\nb = 1\nc = 3\n[x]\ny = 4").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_upsert_array_element() {
+        let mut toml: Value = toml_from_str("array = [ 1, 2 ]").unwrap();
+
+        let res = toml.upsert("array.[0]", Value::Integer(3));
+        assert!(res.is_ok());
+        assert_eq!(Upserted::Replaced(Value::Integer(1)), res.unwrap());
+
+        let res = toml.upsert("array.[2]", Value::Integer(4));
+        assert!(res.is_ok());
+        assert_eq!(Upserted::Inserted, res.unwrap());
+
+        let res = toml.upsert("array.[+]", Value::Integer(5));
+        assert!(res.is_ok());
+        assert_eq!(Upserted::Inserted, res.unwrap());
+
+        let expected: Value = toml_from_str("array = [ 3, 2, 4, 5 ]").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_upsert_type_mismatch_is_error() {
+        let mut toml: Value = toml_from_str("table = 1").unwrap();
+
+        let res = toml.upsert("table.a", Value::Integer(2));
+        assert!(res.is_err());
+
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::QueryingValueAsTable(_)
+        ));
+    }
+
+    #[test]
+    fn test_upsert_with_seperator() {
+        let mut toml: Value = toml_from_str("").unwrap();
+
+        let res = toml.upsert_with_seperator("a/b.c", "/", Value::Integer(1));
+        assert!(res.is_ok());
+        assert_eq!(Upserted::Inserted, res.unwrap());
+
+        let expected: Value = toml_from_str(r#"a = { "b.c" = 1 }"#).unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_existing_value() {
+        let mut toml: Value = toml_from_str("[table]\na = 1").unwrap();
+
+        let res = toml.get_or_insert_with("table.a", || panic!("value exists"));
+        assert!(is_match!(res, Ok(&mut Value::Integer(1))));
+    }
+
+    #[test]
+    fn test_get_or_insert_with_missing_value() {
+        let mut toml: Value = toml_from_str("").unwrap();
+
+        {
+            let res = toml.get_or_insert_with("table.a.[0]", || Value::Integer(1));
+            assert!(res.is_ok());
+            *res.unwrap() = Value::Integer(2);
+        }
+
+        let expected: Value = toml_from_str("table = { a = [ 2 ] }").unwrap();
+        assert_eq!(expected, toml);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_require_intermediates() {
+        let mut toml: Value = toml_from_str("[table]").unwrap();
+
+        let query = Query::parse("table.a.b").unwrap();
+        let res = toml.get_or_insert_with_query(&query, Intermediates::Require, || 1.into());
+        assert!(res.is_err());
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::IdentifierNotFoundInDocument(_, _)
+        ));
+
+        let query = Query::parse("table.a").unwrap();
+        let res = toml.get_or_insert_with_query(&query, Intermediates::Require, || 1.into());
+        assert!(is_match!(res, Ok(&mut Value::Integer(1))));
+    }
+
+    #[test]
+    fn test_get_or_insert_with_append() {
+        let mut toml: Value = toml_from_str("array = []").unwrap();
+
+        let res = toml.get_or_insert_with("array.[+]", || Value::Integer(1));
+        assert!(is_match!(res, Err(Error::AppendNotSupported)));
+    }
+
+}
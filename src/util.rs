@@ -1,13 +1,67 @@
 use toml::Value;
 
+use crate::error::Suggestion;
+use crate::inspect::ValueType;
+
 pub fn name_of_val(val: &Value) -> &'static str {
-    match *val {
-        Value::Array(_) => "Array",
-        Value::Boolean(_) => "Boolean",
-        Value::Datetime(_) => "Datetime",
-        Value::Float(_) => "Float",
-        Value::Integer(_) => "Integer",
-        Value::String(_) => "String",
-        Value::Table(_) => "Table",
+    ValueType::of(val).name()
+}
+
+/// Append `segment` to the rendered query `path`
+pub fn child_path(path: &str, segment: &str, sep: &str) -> String {
+    if path.is_empty() {
+        segment.to_owned()
+    } else {
+        format!("{}{}{}", path, sep, segment)
     }
 }
+
+/// Whether the value at the rendered query `inner` is the value at `outer` or inside of it
+pub fn path_contains(outer: &str, inner: &str) -> bool {
+    outer.is_empty()
+        || inner == outer
+        || (inner.starts_with(outer) && inner[outer.len()..].starts_with('.'))
+}
+
+/// The key of `keys` which is most similar to the missing `ident`
+///
+/// Keys count as similar if their edit distance to `ident` is at most a third of its length
+/// (but at least 1), the first of the closest ones is suggested.
+pub fn suggest<'a, I: IntoIterator<Item = &'a str>>(ident: &str, keys: I) -> Suggestion {
+    let max = ::std::cmp::max(1, ident.chars().count() / 3);
+    let closest = keys
+        .into_iter()
+        .map(|key| (edit_distance(ident, key), key))
+        .filter(|&(distance, _)| distance <= max)
+        .min_by_key(|&(distance, _)| distance);
+
+    Suggestion(closest.map(|(_, key)| key.to_owned()))
+}
+
+/// The edit distance of `a` and `b`, counting swapped adjacent characters as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    // The rows for the prefixes of `a` which are two and one characters shorter
+    let mut before = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (previous[j] + 1)
+                .min(row[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = previous;
+        previous = row;
+    }
+
+    previous[b.len()]
+}
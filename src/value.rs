@@ -4,22 +4,40 @@
 ///
 use toml::Value;
 
-use crate::delete::TomlValueDeleteExt;
+use crate::copy::TomlValueCopyExt;
+use crate::delete::{DeleteMode, TomlValueDeleteExt};
 use crate::error::Result;
-use crate::insert::TomlValueInsertExt;
+use crate::insert::{InsertPolicy, TomlValueInsertExt};
+use crate::mv::{MoveOptions, TomlValueMoveExt};
+use crate::query::Query;
 use crate::read::TomlValueReadExt;
+use crate::rename::TomlValueRenameExt;
 use crate::set::TomlValueSetExt;
+use crate::upsert::{TomlValueUpsertExt, Upserted};
 
 /// Conveniance trait over
 ///
 ///  * TomlValueReadExt
 ///  * TomlValueSetExt
+///  * TomlValueDeleteExt
+///  * TomlValueInsertExt
+///  * TomlValueUpsertExt
+///  * TomlValueRenameExt
+///  * TomlValueMoveExt
+///  * TomlValueCopyExt
 ///
 /// for ease of use.
 ///
 /// The very same goal can be achieved by importing each trait seperately.
 pub trait TomlValueExt<'doc>:
-    TomlValueReadExt<'doc> + TomlValueSetExt + TomlValueDeleteExt + TomlValueInsertExt
+    TomlValueReadExt<'doc>
+    + TomlValueSetExt
+    + TomlValueDeleteExt
+    + TomlValueInsertExt
+    + TomlValueUpsertExt
+    + TomlValueRenameExt
+    + TomlValueMoveExt
+    + TomlValueCopyExt
 {
     //
     // READ functionality
@@ -27,7 +45,7 @@ pub trait TomlValueExt<'doc>:
 
     /// See documentation of `TomlValueReadExt`
     #[inline]
-    fn read_with_seperator(&'doc self, query: &str, sep: char) -> Result<Option<&'doc Value>> {
+    fn read_with_seperator(&'doc self, query: &str, sep: &str) -> Result<Option<&'doc Value>> {
         TomlValueReadExt::read_with_seperator(self, query, sep)
     }
 
@@ -36,21 +54,49 @@ pub trait TomlValueExt<'doc>:
     fn read_mut_with_seperator(
         &'doc mut self,
         query: &str,
-        sep: char,
+        sep: &str,
     ) -> Result<Option<&'doc mut Value>> {
         TomlValueReadExt::read_mut_with_seperator(self, query, sep)
     }
 
+    /// See documentation of `TomlValueReadExt`
+    #[inline]
+    fn read_all_with_seperator(
+        &'doc self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, &'doc Value)>> {
+        TomlValueReadExt::read_all_with_seperator(self, query, sep)
+    }
+
     /// See documentation of `TomlValueReadExt`
     #[inline]
     fn read(&'doc self, query: &str) -> Result<Option<&'doc Value>> {
-        TomlValueReadExt::read_with_seperator(self, query, '.')
+        TomlValueReadExt::read_with_seperator(self, query, ".")
     }
 
     /// See documentation of `TomlValueReadExt`
     #[inline]
     fn read_mut(&'doc mut self, query: &str) -> Result<Option<&'doc mut Value>> {
-        TomlValueReadExt::read_mut_with_seperator(self, query, '.')
+        TomlValueReadExt::read_mut_with_seperator(self, query, ".")
+    }
+
+    /// See documentation of `TomlValueReadExt`
+    #[inline]
+    fn read_query(&'doc self, query: &Query) -> Result<Option<&'doc Value>> {
+        TomlValueReadExt::read_query(self, query)
+    }
+
+    /// See documentation of `TomlValueReadExt`
+    #[inline]
+    fn read_mut_query(&'doc mut self, query: &Query) -> Result<Option<&'doc mut Value>> {
+        TomlValueReadExt::read_mut_query(self, query)
+    }
+
+    /// See documentation of `TomlValueReadExt`
+    #[inline]
+    fn read_all(&'doc self, query: &str) -> Result<Vec<(String, &'doc Value)>> {
+        TomlValueReadExt::read_all_with_seperator(self, query, ".")
     }
 
     //
@@ -62,7 +108,7 @@ pub trait TomlValueExt<'doc>:
     fn set_with_seperator(
         &mut self,
         query: &str,
-        sep: char,
+        sep: &str,
         value: Value,
     ) -> Result<Option<Value>> {
         TomlValueSetExt::set_with_seperator(self, query, sep, value)
@@ -71,7 +117,25 @@ pub trait TomlValueExt<'doc>:
     /// See documentation of `TomlValueSetExt`
     #[inline]
     fn set(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
-        TomlValueSetExt::set_with_seperator(self, query, '.', value)
+        TomlValueSetExt::set_with_seperator(self, query, ".", value)
+    }
+
+    /// See documentation of `TomlValueSetExt`
+    #[inline]
+    fn set_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        TomlValueSetExt::set_query(self, query, value)
+    }
+
+    /// See documentation of `TomlValueSetExt`
+    #[inline]
+    fn set_strict(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
+        TomlValueSetExt::set_strict(self, query, value)
+    }
+
+    /// See documentation of `TomlValueSetExt`
+    #[inline]
+    fn set_query_strict(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        TomlValueSetExt::set_query_strict(self, query, value)
     }
 
     //
@@ -80,7 +144,7 @@ pub trait TomlValueExt<'doc>:
 
     /// See documentation of `TomlValueDeleteExt`
     #[inline]
-    fn delete_with_seperator(&mut self, query: &str, sep: char) -> Result<Option<Value>> {
+    fn delete_with_seperator(&mut self, query: &str, sep: &str) -> Result<Option<Value>> {
         TomlValueDeleteExt::delete_with_seperator(self, query, sep)
     }
 
@@ -90,6 +154,62 @@ pub trait TomlValueExt<'doc>:
         TomlValueDeleteExt::delete(self, query)
     }
 
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_query(&mut self, query: &Query) -> Result<Option<Value>> {
+        TomlValueDeleteExt::delete_query(self, query)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_with_mode(&mut self, query: &str, mode: DeleteMode) -> Result<Option<Value>> {
+        TomlValueDeleteExt::delete_with_mode(self, query, mode)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_query_with_mode(&mut self, query: &Query, mode: DeleteMode) -> Result<Option<Value>> {
+        TomlValueDeleteExt::delete_query_with_mode(self, query, mode)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_all_with_seperator(
+        &mut self,
+        query: &str,
+        sep: &str,
+    ) -> Result<Vec<(String, Value)>> {
+        TomlValueDeleteExt::delete_all_with_seperator(self, query, sep)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_all(&mut self, query: &str) -> Result<Vec<(String, Value)>> {
+        TomlValueDeleteExt::delete_all(self, query)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_recursive(&mut self, query: &str) -> Result<Option<Value>> {
+        TomlValueDeleteExt::delete_recursive(self, query)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_recursive_and_prune(&mut self, query: &str) -> Result<Option<Value>> {
+        TomlValueDeleteExt::delete_recursive_and_prune(self, query)
+    }
+
+    /// See documentation of `TomlValueDeleteExt`
+    #[inline]
+    fn delete_recursive_query(
+        &mut self,
+        query: &Query,
+        prune_empty_parents: bool,
+    ) -> Result<Option<Value>> {
+        TomlValueDeleteExt::delete_recursive_query(self, query, prune_empty_parents)
+    }
+
     //
     // INSERT functionality
     //
@@ -99,7 +219,7 @@ pub trait TomlValueExt<'doc>:
     fn insert_with_seperator(
         &mut self,
         query: &str,
-        sep: char,
+        sep: &str,
         value: Value,
     ) -> Result<Option<Value>> {
         TomlValueInsertExt::insert_with_seperator(self, query, sep, value)
@@ -110,6 +230,147 @@ pub trait TomlValueExt<'doc>:
     fn insert(&mut self, query: &str, value: Value) -> Result<Option<Value>> {
         TomlValueInsertExt::insert(self, query, value)
     }
+
+    /// See documentation of `TomlValueInsertExt`
+    #[inline]
+    fn insert_query(&mut self, query: &Query, value: Value) -> Result<Option<Value>> {
+        TomlValueInsertExt::insert_query(self, query, value)
+    }
+
+    /// See documentation of `TomlValueInsertExt`
+    #[inline]
+    fn insert_with_policy(
+        &mut self,
+        query: &str,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>> {
+        TomlValueInsertExt::insert_with_policy(self, query, value, policy)
+    }
+
+    /// See documentation of `TomlValueInsertExt`
+    #[inline]
+    fn insert_query_with_policy(
+        &mut self,
+        query: &Query,
+        value: Value,
+        policy: InsertPolicy,
+    ) -> Result<Option<Value>> {
+        TomlValueInsertExt::insert_query_with_policy(self, query, value, policy)
+    }
+
+    //
+    // UPSERT functionality
+    //
+
+    /// See documentation of `TomlValueUpsertExt`
+    #[inline]
+    fn upsert_with_seperator(&mut self, query: &str, sep: &str, value: Value) -> Result<Upserted> {
+        TomlValueUpsertExt::upsert_with_seperator(self, query, sep, value)
+    }
+
+    /// See documentation of `TomlValueUpsertExt`
+    #[inline]
+    fn upsert(&mut self, query: &str, value: Value) -> Result<Upserted> {
+        TomlValueUpsertExt::upsert(self, query, value)
+    }
+
+    /// See documentation of `TomlValueUpsertExt`
+    #[inline]
+    fn upsert_query(&mut self, query: &Query, value: Value) -> Result<Upserted> {
+        TomlValueUpsertExt::upsert_query(self, query, value)
+    }
+
+    //
+    // RENAME functionality
+    //
+
+    /// See documentation of `TomlValueRenameExt`
+    #[inline]
+    fn rename(&mut self, query: &str, new_key: &str) -> Result<()> {
+        TomlValueRenameExt::rename(self, query, new_key)
+    }
+
+    /// See documentation of `TomlValueRenameExt`
+    #[inline]
+    fn rename_overwriting(&mut self, query: &str, new_key: &str) -> Result<Option<Value>> {
+        TomlValueRenameExt::rename_overwriting(self, query, new_key)
+    }
+
+    /// See documentation of `TomlValueRenameExt`
+    #[inline]
+    fn rename_query(
+        &mut self,
+        query: &Query,
+        new_key: &str,
+        overwrite: bool,
+    ) -> Result<Option<Value>> {
+        TomlValueRenameExt::rename_query(self, query, new_key, overwrite)
+    }
+
+    //
+    // MOVE functionality
+    //
+
+    /// See documentation of `TomlValueMoveExt`
+    #[inline]
+    fn mv(&mut self, from: &str, to: &str) -> Result<Option<Value>> {
+        TomlValueMoveExt::mv(self, from, to)
+    }
+
+    /// See documentation of `TomlValueMoveExt`
+    #[inline]
+    fn mv_with_options(
+        &mut self,
+        from: &str,
+        to: &str,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        TomlValueMoveExt::mv_with_options(self, from, to, options)
+    }
+
+    /// See documentation of `TomlValueMoveExt`
+    #[inline]
+    fn mv_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        TomlValueMoveExt::mv_query(self, from, to, options)
+    }
+
+    //
+    // COPY functionality
+    //
+
+    /// See documentation of `TomlValueCopyExt`
+    #[inline]
+    fn copy(&mut self, from: &str, to: &str) -> Result<Option<Value>> {
+        TomlValueCopyExt::copy(self, from, to)
+    }
+
+    /// See documentation of `TomlValueCopyExt`
+    #[inline]
+    fn copy_with_options(
+        &mut self,
+        from: &str,
+        to: &str,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        TomlValueCopyExt::copy_with_options(self, from, to, options)
+    }
+
+    /// See documentation of `TomlValueCopyExt`
+    #[inline]
+    fn copy_query(
+        &mut self,
+        from: &Query,
+        to: &Query,
+        options: MoveOptions,
+    ) -> Result<Option<Value>> {
+        TomlValueCopyExt::copy_query(self, from, to, options)
+    }
 }
 
 impl<'doc> TomlValueExt<'doc> for Value {}
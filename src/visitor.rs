@@ -0,0 +1,154 @@
+/// The Toml Visitor extensions
+use toml::value::Table;
+use toml::Value;
+
+use crate::tokenizer::quote_identifier;
+use crate::util::child_path;
+
+/// A visitor for the values of a document, see `TomlValueVisitorExt::accept`
+///
+/// All hooks do nothing by default. The `path` passed to them is the query of the value (`""`
+/// for the document itself).
+pub trait TomlVisitor {
+    /// Called with a table, before its values are visited
+    fn enter_table(&mut self, _path: &str, _table: &Table) {}
+
+    /// Called with a table, after its values were visited
+    fn leave_table(&mut self, _path: &str, _table: &Table) {}
+
+    /// Called with each element of an array, before the element itself is visited
+    fn visit_array_element(&mut self, _path: &str, _idx: usize, _value: &Value) {}
+
+    /// Called with each value which is neither a table nor an array
+    fn visit_scalar(&mut self, _path: &str, _value: &Value) {}
+}
+
+pub trait TomlValueVisitorExt {
+    /// Extension function for visiting the current toml::Value document with a `TomlVisitor`
+    ///
+    /// # Semantics
+    ///
+    /// The values are visited depth-first in document order. For tables, `enter_table` is called
+    /// first, then their values are visited, then `leave_table` is called. For arrays,
+    /// `visit_array_element` is called for each element before it is visited.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::visitor::{TomlValueVisitorExt, TomlVisitor};
+    ///
+    /// struct Secrets(Vec<String>);
+    ///
+    /// impl TomlVisitor for Secrets {
+    ///     fn visit_scalar(&mut self, path: &str, _value: &toml::Value) {
+    ///         if path.ends_with("password") {
+    ///             self.0.push(path.to_owned());
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let toml : toml::Value = toml::from_str(r#"
+    ///     [[db]]
+    ///     password = "a"
+    ///
+    ///     [[db]]
+    ///     password = "b"
+    /// "#).unwrap();
+    ///
+    /// let mut secrets = Secrets(Vec::new());
+    /// toml.accept(&mut secrets);
+    /// assert_eq!(secrets.0, vec!["db.[0].password", "db.[1].password"]);
+    /// ```
+    fn accept<V: TomlVisitor>(&self, visitor: &mut V);
+}
+
+impl TomlValueVisitorExt for Value {
+    fn accept<V: TomlVisitor>(&self, visitor: &mut V) {
+        visit(self, "", visitor)
+    }
+}
+
+fn visit<V: TomlVisitor>(value: &Value, path: &str, visitor: &mut V) {
+    match *value {
+        Value::Table(ref table) => {
+            visitor.enter_table(path, table);
+            for (key, child) in table {
+                visit(
+                    child,
+                    &child_path(path, &quote_identifier(key, "."), "."),
+                    visitor,
+                );
+            }
+            visitor.leave_table(path, table);
+        }
+        Value::Array(ref array) => {
+            for (idx, child) in array.iter().enumerate() {
+                let path = child_path(path, &format!("[{}]", idx), ".");
+                visitor.visit_array_element(&path, idx, child);
+                visit(child, &path, visitor);
+            }
+        }
+        ref scalar => visitor.visit_scalar(path, scalar),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl TomlVisitor for Recorder {
+        fn enter_table(&mut self, path: &str, _table: &Table) {
+            self.0.push(format!("enter {}", path));
+        }
+
+        fn leave_table(&mut self, path: &str, _table: &Table) {
+            self.0.push(format!("leave {}", path));
+        }
+
+        fn visit_array_element(&mut self, path: &str, idx: usize, _value: &Value) {
+            self.0.push(format!("element {} {}", path, idx));
+        }
+
+        fn visit_scalar(&mut self, path: &str, value: &Value) {
+            self.0.push(format!("scalar {} {}", path, value));
+        }
+    }
+
+    #[test]
+    fn test_accept() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = 1
+        b = [ { c = "x" }, [ true ] ]
+        "#,
+        )
+        .unwrap();
+
+        let mut recorder = Recorder::default();
+        toml.accept(&mut recorder);
+
+        assert_eq!(
+            vec![
+                "enter ",
+                "scalar a 1",
+                "element b.[0] 0",
+                "enter b.[0]",
+                "scalar b.[0].c \"x\"",
+                "leave b.[0]",
+                "element b.[1] 1",
+                "element b.[1].[0] 0",
+                "scalar b.[1].[0] true",
+                "leave ",
+            ],
+            recorder.0
+        );
+    }
+
+}
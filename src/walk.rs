@@ -0,0 +1,332 @@
+/// The Toml Walk extensions
+use std::borrow::Cow;
+
+use toml::Value;
+
+use crate::query::Query;
+use crate::tokenizer::Token;
+
+/// An iterator over all values in a document, see `TomlValueWalkExt::walk`
+pub struct Walk<'doc> {
+    stack: Vec<(Vec<Token<'doc>>, &'doc Value)>,
+}
+
+impl<'doc> Walk<'doc> {
+    fn new(doc: &'doc Value) -> Walk<'doc> {
+        let mut walk = Walk { stack: Vec::new() };
+        walk.push_children(&[], doc);
+        walk
+    }
+
+    /// Push the children of `value` so that the first child is popped first
+    fn push_children(&mut self, path: &[Token<'doc>], value: &'doc Value) {
+        let start = self.stack.len();
+        for (segment, child) in children(value) {
+            let mut child_path = path.to_vec();
+            child_path.push(segment);
+            self.stack.push((child_path, child));
+        }
+        self.stack[start..].reverse();
+    }
+}
+
+impl<'doc> Iterator for Walk<'doc> {
+    type Item = (Query<'doc>, &'doc Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+        self.push_children(&path, value);
+        Some((to_query(path), value))
+    }
+}
+
+pub trait TomlValueWalkExt {
+    /// Extension function for iterating over all values in the current toml::Value document
+    ///
+    /// # Semantics
+    ///
+    /// The values are visited depth-first in document order: a table or an array comes right
+    /// before its contents. The document itself is not part of the iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::walk::TomlValueWalkExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("a = { b = [ 1 ] }\nc = 2").unwrap();
+    ///
+    /// let paths = toml.walk().map(|(query, _)| query.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(paths, vec!["a", "a.b", "a.b.[0]", "c"]);
+    /// ```
+    fn walk(&self) -> Walk<'_>;
+
+    /// Extension function for visiting all values in the current toml::Value document mutably
+    ///
+    /// The values are visited in the same order as by `TomlValueWalkExt::walk`. Other than
+    /// `walk`, this is not an iterator, as a table could not be handed out mutably while its
+    /// contents are still to be visited. `f` is called with a table or array first, then with
+    /// its contents (as they are after `f` returned).
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::walk::TomlValueWalkExt;
+    ///
+    /// let mut toml : toml::Value = toml::from_str("a = { b = 1 }\nc = [ 2 ]").unwrap();
+    ///
+    /// toml.walk_mut(|_, value| {
+    ///     if let toml::Value::Integer(ref mut i) = *value {
+    ///         *i *= 10;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(toml, toml::from_str("a = { b = 10 }\nc = [ 20 ]").unwrap());
+    /// ```
+    fn walk_mut<F>(&mut self, f: F)
+    where
+        F: FnMut(&Query, &mut Value);
+
+    /// Extension function for finding all values in the current toml::Value document which
+    /// match a predicate
+    ///
+    /// The predicate is called with the path and the value of each value `walk` visits. The
+    /// matching values are returned with their paths, in document order.
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::walk::TomlValueWalkExt;
+    ///
+    /// let toml : toml::Value = toml::from_str(r#"
+    ///     [server]
+    ///     port = 80
+    ///
+    ///     [[upstream]]
+    ///     port = 8080
+    /// "#).unwrap();
+    ///
+    /// let ports = toml.find(|path, value| path.ends_with("port") && value.is_integer());
+    /// let paths = ports.into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+    /// assert_eq!(paths, vec!["server.port", "upstream.[0].port"]);
+    /// ```
+    fn find<P>(&self, mut predicate: P) -> Vec<(String, &Value)>
+    where
+        P: FnMut(&str, &Value) -> bool,
+    {
+        self.walk()
+            .map(|(query, value)| (query.to_string(), value))
+            .filter(|(path, value)| predicate(path, value))
+            .collect()
+    }
+
+    /// Extension function for listing the paths of all scalar values (neither tables nor arrays)
+    /// in the current toml::Value document, in document order
+    ///
+    /// ```rust
+    /// extern crate toml;
+    /// extern crate toml_query;
+    ///
+    /// use toml_query::walk::TomlValueWalkExt;
+    ///
+    /// let toml : toml::Value = toml::from_str("a = { b = [ 1, 2 ] }\nc = 3\nd = {}").unwrap();
+    ///
+    /// assert_eq!(toml.leaf_paths(), vec!["a.b.[0]", "a.b.[1]", "c"]);
+    /// ```
+    fn leaf_paths(&self) -> Vec<String> {
+        self.find(|_, value| !value.is_table() && !value.is_array())
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+}
+
+impl TomlValueWalkExt for Value {
+    fn walk(&self) -> Walk<'_> {
+        Walk::new(self)
+    }
+
+    fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Query, &mut Value),
+    {
+        walk_children_mut(self, &mut Vec::new(), &mut f)
+    }
+}
+
+fn walk_children_mut<F>(value: &mut Value, path: &mut Vec<Token<'static>>, f: &mut F)
+where
+    F: FnMut(&Query, &mut Value),
+{
+    let children: Vec<(Token, &mut Value)> = match *value {
+        Value::Table(ref mut table) => table
+            .iter_mut()
+            .map(|(key, child)| (identifier(key).into_owned(), child))
+            .collect(),
+        Value::Array(ref mut array) => array
+            .iter_mut()
+            .enumerate()
+            .map(|(idx, child)| (Token::Index { idx }, child))
+            .collect(),
+        _ => return,
+    };
+
+    for (segment, child) in children {
+        path.push(segment);
+        f(&to_query(path.clone()), child);
+        walk_children_mut(child, path, f);
+        path.pop();
+    }
+}
+
+/// The children of a table or array, together with the segment leading to them
+pub(crate) fn children(value: &Value) -> Vec<(Token<'_>, &Value)> {
+    match *value {
+        Value::Table(ref table) => table
+            .iter()
+            .map(|(key, child)| (identifier(key), child))
+            .collect(),
+        Value::Array(ref array) => array
+            .iter()
+            .enumerate()
+            .map(|(idx, child)| (Token::Index { idx }, child))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn identifier(key: &str) -> Token<'_> {
+    Token::Identifier {
+        ident: Cow::Borrowed(key),
+    }
+}
+
+/// Turn a non-empty path into a query
+pub(crate) fn to_query(path: Vec<Token<'_>>) -> Query<'_> {
+    Query::from_tokens(path.into_iter().collect(), ".")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml::from_str as toml_from_str;
+
+    #[test]
+    fn test_walk() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = 1
+        "b.c" = [ { d = true }, 2 ]
+
+        [e]
+        "#,
+        )
+        .unwrap();
+
+        let walked = toml
+            .walk()
+            .map(|(query, value)| (query.to_string(), value.clone()))
+            .collect::<Vec<_>>();
+
+        let paths = walked
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "a",
+                r#""b.c""#,
+                r#""b.c".[0]"#,
+                r#""b.c".[0].d"#,
+                r#""b.c".[1]"#,
+                "e"
+            ],
+            paths
+        );
+        assert_eq!(Value::Boolean(true), walked[3].1);
+        assert_eq!(
+            Some(&walked[3].1),
+            toml.walk().nth(3).map(|(_, value)| value)
+        );
+    }
+
+    #[test]
+    fn test_walk_queries_resolve() {
+        use crate::read::TomlValueReadExt;
+
+        let toml: Value = toml_from_str("a = { b = [ [ 1 ], { c = 2 } ] }").unwrap();
+
+        for (query, value) in toml.walk() {
+            assert_eq!(Some(value), toml.read_query(&query).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_walk_empty() {
+        let toml: Value = toml_from_str("").unwrap();
+        assert_eq!(0, toml.walk().count());
+    }
+
+    #[test]
+    fn test_find() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = "secret"
+        b = [ "public", "secret" ]
+
+        [c]
+        d = "secret"
+        "#,
+        )
+        .unwrap();
+
+        let found = toml.find(|_, value| value.as_str() == Some("secret"));
+        let paths = found
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b.[1]", "c.d"], paths);
+
+        assert!(toml.find(|path, _| path == "x").is_empty());
+    }
+
+    #[test]
+    fn test_leaf_paths() {
+        let toml: Value = toml_from_str(
+            r#"
+        a = []
+        "b.c" = 1
+
+        [[d]]
+        e = 2020-01-01
+
+        [[d]]
+        f = { g = "h" }
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(vec![r#""b.c""#, "d.[0].e", "d.[1].f.g"], toml.leaf_paths());
+    }
+
+    #[test]
+    fn test_walk_mut_visits_replaced_contents() {
+        let mut toml: Value = toml_from_str("a = 1").unwrap();
+        let mut paths = Vec::new();
+
+        toml.walk_mut(|query, value| {
+            paths.push(query.to_string());
+            if *value == Value::Integer(1) {
+                *value = Value::Array(vec![Value::Integer(2)]);
+            }
+        });
+
+        assert_eq!(vec!["a", "a.[0]"], paths);
+        assert_eq!(toml_from_str::<Value>("a = [ 2 ]").unwrap(), toml);
+    }
+
+}
@@ -0,0 +1,162 @@
+/// Reloading documents when their file changes
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use toml::Value;
+
+use crate::diff::{DiffOp, TomlValueDiffExt};
+use crate::error::{Error, Result};
+use crate::file::TomlFileExt;
+
+/// A document which is reparsed whenever its file changes
+///
+/// The differences to the previous version of the document are delivered over the channel
+/// returned by `WatchedDocument::new()`. Changes which leave the document as it is are not
+/// delivered. If the changed file cannot be read or parsed, the error is delivered instead and the
+/// previous version is kept.
+///
+/// The directory of the file is watched, so files which are replaced (like by
+/// `TomlFileExt::edit_document()`) are still watched afterwards. Writers should replace the file
+/// this way, otherwise the document may be reparsed while the file is half-written.
+///
+/// ```rust,no_run
+/// extern crate toml_query;
+///
+/// use toml_query::watch::WatchedDocument;
+///
+/// let (doc, changes) = WatchedDocument::new("config.toml").unwrap();
+/// for diff in changes {
+///     println!("{:?}, the port is now {:?}", diff, doc.document().get("port"));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct WatchedDocument {
+    path: PathBuf,
+    doc: Arc<Mutex<Value>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl WatchedDocument {
+    /// Read the document in the file at `path` and watch the file for changes
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(WatchedDocument, Receiver<Result<Vec<DiffOp>>>)> {
+        let path = path.as_ref().to_path_buf();
+        let doc = Arc::new(Mutex::new(Value::read_document(&path)?));
+        let (sender, receiver) = channel();
+
+        let handler = {
+            let path = path.clone();
+            let doc = doc.clone();
+            move |event: notify::Result<notify::Event>| match event {
+                Ok(ref event) if !is_change_of(event, &path) => {}
+                Ok(_) => reload(&path, &doc, &sender),
+                Err(e) => {
+                    let _ = sender.send(Err(Error::Watch(display(&path), e)));
+                }
+            }
+        };
+
+        let mut watcher =
+            notify::recommended_watcher(handler).map_err(|e| Error::Watch(display(&path), e))?;
+        watcher
+            .watch(directory(&path), RecursiveMode::NonRecursive)
+            .map_err(|e| Error::Watch(display(&path), e))?;
+
+        let watched = WatchedDocument {
+            path,
+            doc,
+            _watcher: watcher,
+        };
+        Ok((watched, receiver))
+    }
+
+    /// The path of the watched file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The current version of the document
+    pub fn document(&self) -> Value {
+        self.doc.lock().unwrap().clone()
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// The directory which contains the file at `path`
+fn directory(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Whether `event` may have changed the file at `path`
+fn is_change_of(event: &notify::Event, path: &Path) -> bool {
+    let changes_content = match event.kind {
+        EventKind::Create(_) | EventKind::Modify(_) => true,
+        EventKind::Access(_) | EventKind::Remove(_) | EventKind::Any | EventKind::Other => false,
+    };
+
+    changes_content
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == path.file_name())
+}
+
+fn reload(path: &Path, doc: &Mutex<Value>, sender: &Sender<Result<Vec<DiffOp>>>) {
+    let new = match Value::read_document(path) {
+        Ok(new) => new,
+        Err(e) => {
+            let _ = sender.send(Err(e));
+            return;
+        }
+    };
+
+    let mut doc = doc.lock().unwrap();
+    let diff = doc.diff(&new);
+    if !diff.is_empty() {
+        *doc = new;
+        let _ = sender.send(Ok(diff));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::set::TomlValueSetExt;
+    use std::fs;
+    use std::process;
+    use std::time::Duration;
+
+    #[test]
+    fn test_watched_document() {
+        let path = ::std::env::temp_dir().join(format!("toml-query-watch-{}.toml", process::id()));
+        fs::write(&path, "[server]\nport = 80\n").unwrap();
+
+        let (doc, changes) = WatchedDocument::new(&path).unwrap();
+        assert_eq!(Value::Integer(80), doc.document()["server"]["port"]);
+
+        Value::edit_document(&path, |doc| doc.set("server.port", Value::Integer(8080))).unwrap();
+
+        let diff = changes.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(
+            vec![DiffOp::Changed {
+                path: String::from("server.port"),
+                old: Value::Integer(80),
+                new: Value::Integer(8080),
+            }],
+            diff.unwrap()
+        );
+        assert_eq!(Value::Integer(8080), doc.document()["server"]["port"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+}
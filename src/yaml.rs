@@ -0,0 +1,247 @@
+/// The query extensions for serde_yaml::Value
+///
+/// YAML mappings are tables, so the same query strings work for TOML and YAML documents:
+///
+/// ```rust
+/// extern crate serde_yaml;
+/// extern crate toml_query;
+///
+/// use toml_query::document::DocumentReadExt;
+///
+/// let yaml : serde_yaml::Value = serde_yaml::from_str(r#"
+/// servers:
+///   - host: a
+///   - host: b
+/// "#).unwrap();
+///
+/// let host = yaml.read("servers.[1].host").unwrap();
+/// assert_eq!(host, Some(&serde_yaml::Value::from("b")));
+/// ```
+///
+/// Values are set, inserted and deleted with `DocumentSetExt`, `DocumentInsertExt` and
+/// `DocumentDeleteExt`. Only mappings with string keys can be queried.
+use serde_yaml::{Mapping, Value};
+
+use crate::document::{
+    DocumentDeleteExt, DocumentInsertExt, DocumentLike, DocumentReadExt, DocumentSetExt,
+};
+
+impl DocumentLike for Value {
+    fn get_key(&self, key: &str) -> Option<&Value> {
+        self.as_mapping().and_then(|o| o.get(key))
+    }
+
+    fn get_key_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_mapping_mut().and_then(|o| o.get_mut(key))
+    }
+
+    fn get_index(&self, idx: usize) -> Option<&Value> {
+        self.as_sequence().and_then(|a| a.get(idx))
+    }
+
+    fn get_index_mut(&mut self, idx: usize) -> Option<&mut Value> {
+        self.as_sequence_mut().and_then(|a| a.get_mut(idx))
+    }
+
+    fn is_table(&self) -> bool {
+        self.is_mapping()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.as_mapping()
+            .map(|o| o.keys().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    fn array_len(&self) -> Option<usize> {
+        self.as_sequence().map(Vec::len)
+    }
+
+    fn new_table() -> Value {
+        Value::Mapping(Mapping::new())
+    }
+
+    fn new_array() -> Value {
+        Value::Sequence(vec![])
+    }
+
+    fn insert_key(&mut self, key: &str, value: Value) -> Option<Value> {
+        self.as_mapping_mut()
+            .and_then(|o| o.insert(Value::from(key), value))
+    }
+
+    fn remove_key(&mut self, key: &str) -> Option<Value> {
+        self.as_mapping_mut().and_then(|o| o.shift_remove(key))
+    }
+
+    fn insert_index(&mut self, idx: usize, value: Value) {
+        if let Some(a) = self.as_sequence_mut() {
+            a.insert(idx, value)
+        }
+    }
+
+    fn remove_index(&mut self, idx: usize) -> Option<Value> {
+        self.as_sequence_mut()
+            .filter(|a| idx < a.len())
+            .map(|a| a.remove(idx))
+    }
+
+    fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Null => "Null",
+            Value::Bool(_) => "Boolean",
+            Value::Number(ref n) if n.is_f64() => "Float",
+            Value::Number(_) => "Integer",
+            Value::String(_) => "String",
+            Value::Sequence(_) => "Array",
+            Value::Mapping(_) => "Table",
+            Value::Tagged(ref tagged) => tagged.value.type_name(),
+        }
+    }
+
+    fn to_scalar(&self) -> Option<toml::Value> {
+        match *self {
+            Value::Bool(b) => Some(toml::Value::Boolean(b)),
+            Value::Number(ref n) => n
+                .as_i64()
+                .map(toml::Value::Integer)
+                .or_else(|| n.as_f64().map(toml::Value::Float)),
+            Value::String(ref s) => Some(toml::Value::String(s.clone())),
+            Value::Tagged(ref tagged) => tagged.value.to_scalar(),
+            _ => None,
+        }
+    }
+}
+
+impl DocumentReadExt for Value {}
+
+impl DocumentSetExt for Value {}
+
+impl DocumentInsertExt for Value {}
+
+impl DocumentDeleteExt for Value {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+    use serde_yaml::from_str as yaml_from_str;
+
+    fn doc() -> Value {
+        yaml_from_str(
+            r#"
+        a:
+          b:
+            - 1
+            - c: true
+        d: ~
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let yaml = doc();
+
+        assert_eq!(Some(&Value::from(1)), yaml.read("a.b.[0]").unwrap());
+        assert_eq!(Some(&Value::from(true)), yaml.read("a.b.[1].c").unwrap());
+        assert_eq!(Some(&Value::Null), yaml.read("d").unwrap());
+        assert_eq!(None, yaml.read("a.e").unwrap());
+        assert_eq!(Some(&yaml["a"]), yaml.read("a.b.^").unwrap());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let yaml = doc();
+
+        let res = yaml.read("a.b.c");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::NoIdentifierInArray(_)
+        ));
+
+        let res = yaml.read("a.[0]");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::NoIndexInTable(0)
+        ));
+
+        let res = yaml.read("d.e");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::QueryingValueAsTable(_)
+        ));
+
+        let res = yaml.read("a.*");
+        assert!(is_match!(res.unwrap_err(), Error::MultiValueQuery));
+    }
+
+    #[test]
+    fn test_read_mut() {
+        let mut yaml = doc();
+
+        *yaml.read_mut("a.b.[1].c").unwrap().unwrap() = Value::from("set");
+        assert_eq!(Value::from("set"), yaml["a"]["b"][1]["c"]);
+    }
+
+    #[test]
+    fn test_read_all() {
+        let yaml: Value = yaml_from_str(
+            r#"
+        bins:
+          - { n: 1, x: a }
+          - { n: 2, x: b }
+          - { n: 1, x: c }
+        "#,
+        )
+        .unwrap();
+
+        let res = yaml.read_all("bins.[?(@.n == 1)].x").unwrap();
+        assert_eq!(
+            vec![
+                (String::from("bins.[0].x"), &Value::from("a")),
+                (String::from("bins.[2].x"), &Value::from("c")),
+            ],
+            res
+        );
+
+        let res = yaml.read_all("bins.*.n").unwrap();
+        assert_eq!(3, res.len());
+    }
+
+    #[test]
+    fn test_set_insert_delete() {
+        let mut yaml = doc();
+
+        assert_eq!(
+            Some(Value::from(1)),
+            yaml.set("a.b.[0]", Value::from(2)).unwrap()
+        );
+        assert_eq!(None, yaml.insert("a.b.[1]", Value::from(3)).unwrap());
+        assert_eq!(None, yaml.insert("e.f.[+]", Value::from("g")).unwrap());
+        assert_eq!(Some(Value::Null), yaml.delete("d").unwrap());
+
+        let res = yaml.delete("a.b");
+        assert!(is_match!(
+            *res.unwrap_err().kind(),
+            Error::CannotDeleteNonEmptyArray(_)
+        ));
+
+        let expected: Value = yaml_from_str(
+            r#"
+        a:
+          b:
+            - 2
+            - 3
+            - c: true
+        e:
+          f:
+            - g
+        "#,
+        )
+        .unwrap();
+        assert_eq!(expected, yaml);
+    }
+
+}
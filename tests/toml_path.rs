@@ -0,0 +1,42 @@
+#![cfg(feature = "macros")]
+
+extern crate toml_query;
+
+use toml_query::query::Query;
+use toml_query::toml_path;
+
+macro_rules! assert_same {
+    ($($query:tt),*) => {
+        $( assert_eq!(Query::parse($query).unwrap(), toml_path!($query)); )*
+    };
+}
+
+#[test]
+fn test_toml_path_matches_parse() {
+    assert_same!(
+        "a",
+        "a.b.c",
+        "a.[0].b",
+        "bin[2]",
+        "a[0][1]",
+        r#"a."b.c".'d"e'"#,
+        r"a\.b.\*",
+        "a.[+]",
+        "a.b.^.c",
+        "**.password",
+        "servers.*.host",
+        "a.[1:4]",
+        "a.[:3]",
+        "a.[2:]",
+        "a.[0,2,5]",
+        "db.{host,port}",
+        r#"db.{"a,b",c}"#,
+        "bin.[?(@.name == 'a.b]' && @.x >= 2.5)].path"
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_toml_path_with_regex_matches_parse() {
+    assert_same!("servers./^web-[0-9]+$/.host", r"a./x\/y/");
+}
@@ -10,6 +10,11 @@ extern crate serde_derive;
 #[cfg(test)]
 extern crate serde;
 
+#[cfg(feature = "macros")]
+extern crate regex;
+#[cfg(feature = "macros")]
+extern crate toml_query_tokenizer;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{Lit, Meta, MetaNameValue};
@@ -47,3 +52,92 @@ pub fn derive_partial(tokens: TokenStream) -> TokenStream {
 
     gen.into()
 }
+
+/// Parse a query at compile time
+///
+/// The query is tokenized while compiling, with the same tokenizer `Query::parse()` uses, so
+/// syntax errors are reported by the compiler and the query is not parsed at runtime. The macro
+/// expands to a `Query<'static>` with `.` as seperator and `\` as escape character.
+///
+/// Filters are checked at compile time, but parsed again when the query is built. Regexes are
+/// checked at compile time too, they can only be used with the `regex` feature of toml-query.
+///
+/// ```rust
+/// extern crate toml;
+/// extern crate toml_query;
+///
+/// use toml_query::query::Query;
+/// use toml_query::read::TomlValueReadExt;
+///
+/// let toml : toml::Value = toml::from_str("[[bin]]\nname = 'a'").unwrap();
+/// let query = toml_query::toml_path!("bin.[0].name");
+///
+/// assert_eq!(Some(&toml::Value::from("a")), toml.read_query(&query).unwrap());
+///
+/// let query = toml_query::toml_path!("bin.[?(@.name == 'a')]");
+/// assert_eq!(Query::parse("bin.[?(@.name == 'a')]").unwrap(), query);
+/// ```
+#[cfg(feature = "macros")]
+#[proc_macro]
+pub fn toml_path(tokens: TokenStream) -> TokenStream {
+    use toml_query_tokenizer::Segment;
+
+    let query: syn::LitStr = match syn::parse(tokens) {
+        Ok(query) => query,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let invalid = |message: String| -> TokenStream {
+        let message = format!("invalid query: {}", message);
+        syn::Error::new(query.span(), message)
+            .to_compile_error()
+            .into()
+    };
+
+    let value = query.value();
+    let segments = match toml_query_tokenizer::tokenize(&value, ".", Some('\\'), true) {
+        Ok(segments) => segments,
+        Err(e) => return invalid(format!("{} `{}`", e, &value[e.offset..e.offset + e.len])),
+    };
+
+    let bound = |b: Option<usize>| match b {
+        Some(b) => quote! { ::std::option::Option::Some(#b) },
+        None => quote! { ::std::option::Option::None },
+    };
+
+    let mut tokens = Vec::with_capacity(segments.len());
+    for segment in segments {
+        tokens.push(match segment {
+            Segment::Identifier(ident) => {
+                let ident = ident.as_ref();
+                quote! { Identifier(#ident) }
+            }
+            Segment::Index(idx) => quote! { Index(#idx) },
+            Segment::RecursiveDescent => quote! { RecursiveDescent },
+            Segment::Wildcard => quote! { Wildcard },
+            Segment::Parent => quote! { Parent },
+            Segment::Append => quote! { Append },
+            Segment::Slice(start, end) => {
+                let (start, end) = (bound(start), bound(end));
+                quote! { Slice(#start, #end) }
+            }
+            Segment::IndexUnion(idxs) => quote! { IndexUnion(&[#(#idxs),*]) },
+            Segment::KeyUnion(idents) => quote! { KeyUnion(&[#(#idents),*]) },
+            Segment::Filter { expr, .. } => quote! { Filter(#expr) },
+            Segment::Regex(pattern) => {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    return invalid(format!("invalid regex `{}`: {}", pattern, e));
+                }
+                quote! { KeyRegex(#pattern) }
+            }
+        });
+    }
+
+    let gen = quote! {
+        ::toml_query::query::Query::from_static(&[
+            #(::toml_query::query::StaticToken::#tokens),*
+        ])
+    };
+
+    gen.into()
+}
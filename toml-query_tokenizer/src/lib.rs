@@ -0,0 +1,541 @@
+//! The query syntax of toml-query
+//!
+//! This crate splits queries into their segments and checks their syntax. It is used by
+//! toml-query to parse queries at runtime and by the `toml_path!()` macro to parse them at
+//! compile time, so both accept exactly the same queries.
+use std::borrow::Cow;
+use std::fmt;
+
+pub mod predicate;
+
+use crate::predicate::Expr;
+
+/// A segment of a query
+///
+/// Identifiers borrow from the query they were parsed from where possible.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment<'a> {
+    Identifier(Cow<'a, str>),
+
+    Index(usize),
+
+    /// `**`
+    RecursiveDescent,
+
+    /// `*`
+    Wildcard,
+
+    /// `^`
+    Parent,
+
+    /// `[+]`
+    Append,
+
+    /// `[start:end]`
+    Slice(Option<usize>, Option<usize>),
+
+    /// `[0,2,5]`, the indices are sorted and deduplicated
+    IndexUnion(Vec<usize>),
+
+    /// `{a,b,c}`, duplicated identifiers are removed
+    KeyUnion(Vec<String>),
+
+    /// `[?(...)]`, with the expression between the parentheses and the predicate parsed from it
+    Filter {
+        expr: &'a str,
+        predicate: Expr,
+    },
+
+    /// `/.../`, with the pattern between the slashes
+    ///
+    /// The pattern is not checked, as this crate does not depend on a regex implementation.
+    Regex(&'a str),
+}
+
+/// What is wrong with a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The query is empty
+    EmptyQuery,
+
+    /// The seperator is empty
+    EmptySeperator,
+
+    /// A segment or a key of a key union or filter is empty
+    EmptyIdentifier,
+
+    /// An array access without index, like `[]` or `[x]`
+    ArrayAccessWithoutIndex,
+
+    /// An array index which does not fit into `usize`, or a slice which ends before it starts
+    ArrayAccessWithInvalidIndex,
+
+    /// Anything else, like an unterminated quote or garbage after a quoted identifier
+    Malformed,
+}
+
+/// A syntax error, together with the part of the query it is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+
+    /// The byte offset of the malformed part in the query
+    pub offset: usize,
+
+    /// The length of the malformed part in bytes
+    pub len: usize,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match *self {
+            ErrorKind::EmptyQuery => "the query is empty",
+            ErrorKind::EmptySeperator => "the seperator is empty",
+            ErrorKind::EmptyIdentifier => "empty identifier",
+            ErrorKind::ArrayAccessWithoutIndex => "array access without index",
+            ErrorKind::ArrayAccessWithInvalidIndex => "invalid array index",
+            ErrorKind::Malformed => "malformed query",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.offset)
+    }
+}
+
+/// Split a query into its segments, with `escape` as escape character
+///
+/// The query is tokenized in a single pass over its characters. A segment which starts with `"`
+/// or `'` is a quoted identifier, which extends to the next occurrence of the same quote. A
+/// segment which starts with `{` extends to the next `}` which is not quoted, and a filter
+/// segment which starts with `[?(` extends to the next `]` which is not quoted. If `regexes` is
+/// set, a segment which starts with `/` extends to the next `/` which is not escaped with a
+/// backslash. All of them must be followed by the seperator or the end of the query, all other
+/// segments extend to the next seperator.
+///
+/// In segments which extend to the next seperator, the character following the escape character
+/// is taken literally, so `a\.b` is the identifier `a.b`. Such a segment is always an identifier,
+/// `\*` is the key `*`. Quoted identifiers, key unions, filters and regexes are not affected.
+///
+/// An identifier may be directly followed by array accesses, so `bin[2]` is the same as
+/// `bin.[2]` and `a[0][1]` the same as `a.[0].[1]`.
+///
+/// The first error in the query is returned.
+pub fn tokenize<'a>(
+    query: &'a str,
+    seperator: &str,
+    escape: Option<char>,
+    regexes: bool,
+) -> Result<Vec<Segment<'a>>, Error> {
+    /// An error together with the part of the query it is about
+    type SpanResult<'q, T> = Result<T, (ErrorKind, &'q str)>;
+
+    /// Where the tokenizer is in the query
+    #[derive(Clone, Copy)]
+    enum State {
+        /// At the start of a segment
+        Start,
+
+        /// In a segment which extends to the next seperator, which contains escaped characters
+        /// if `escaped` is set
+        Plain { escaped: bool },
+
+        /// Directly after the escape character
+        Escape,
+
+        /// In an identifier quoted with `quote`
+        Quoted { quote: char },
+
+        /// In a key union or filter which ends with `close`, and in a string if `quote` is set
+        Bracketed { close: char, quote: Option<char> },
+
+        /// In a regex, directly after a backslash if `escaped` is set
+        Regex { escaped: bool },
+
+        /// After a quoted identifier, key union, filter or regex, which the seperator must follow
+        Closed,
+    }
+
+    /// Creates the segment for a part which extends to the next seperator
+    ///
+    /// An error is returned if the part is empty, or if it is an array access (`[...]`)
+    /// without a valid index, slice, union or `+`.
+    fn plain_segment(s: &str) -> SpanResult<'_, Segment<'_>> {
+        match s {
+            "" => Err((ErrorKind::EmptyIdentifier, s)),
+            "**" => Ok(Segment::RecursiveDescent),
+            "*" => Ok(Segment::Wildcard),
+            "^" => Ok(Segment::Parent),
+            _ if has_array_brackets(s) => array_segment(s),
+            _ => Ok(Segment::Identifier(Cow::Borrowed(s))),
+        }
+    }
+
+    /// Creates the identifier for a part which contains escaped characters
+    fn escaped_segment(s: &str, escape: char) -> Segment<'static> {
+        let mut ident = String::with_capacity(s.len());
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            if c == escape {
+                // A segment never ends directly after the escape character
+                ident.extend(chars.next());
+            } else {
+                ident.push(c);
+            }
+        }
+
+        Segment::Identifier(Cow::Owned(ident))
+    }
+
+    /// Creates the segment for a key union (`{...}`) or filter (`[?(...)]`)
+    ///
+    /// A part which starts like a filter but does not end like one is an array access, which
+    /// is an error.
+    fn bracketed_segment(s: &str) -> SpanResult<'_, Segment<'_>> {
+        if s.starts_with('{') {
+            return split_key_union(&s[1..s.len() - 1]).map(Segment::KeyUnion);
+        }
+
+        if s.ends_with(")]") {
+            let expr = &s[3..s.len() - 2];
+            let predicate = predicate::parse(expr).map_err(|kind| (kind, s))?;
+            return Ok(Segment::Filter { expr, predicate });
+        }
+
+        array_segment(s)
+    }
+
+    /// Creates the segment for an array access (`[...]`)
+    fn array_segment<'s>(s: &'s str) -> SpanResult<'s, Segment<'s>> {
+        use std::str::FromStr;
+
+        /// Parse an array index, which must consist of digits only
+        fn parse_index(i: &str) -> SpanResult<'_, usize> {
+            if i.is_empty() || !i.bytes().all(|b| b.is_ascii_digit()) {
+                return Err((ErrorKind::ArrayAccessWithoutIndex, i));
+            }
+
+            usize::from_str(i).map_err(|_| (ErrorKind::ArrayAccessWithInvalidIndex, i))
+        }
+
+        let inner = &s[1..s.len() - 1];
+
+        if inner == "+" {
+            return Ok(Segment::Append);
+        }
+
+        if let Some(colon) = inner.find(':') {
+            let bound = |b: &'s str| {
+                if b.is_empty() {
+                    Ok(None)
+                } else {
+                    parse_index(b).map(Some)
+                }
+            };
+
+            let start = bound(&inner[..colon])?;
+            let end = bound(&inner[colon + 1..])?;
+
+            if let (Some(start), Some(end)) = (start, end) {
+                if start > end {
+                    return Err((ErrorKind::ArrayAccessWithInvalidIndex, inner));
+                }
+            }
+
+            return Ok(Segment::Slice(start, end));
+        }
+
+        if inner.contains(',') {
+            let mut idxs = inner
+                .split(',')
+                .map(|i| parse_index(i.trim()))
+                .collect::<SpanResult<Vec<usize>>>()?;
+
+            idxs.sort();
+            idxs.dedup();
+            return Ok(Segment::IndexUnion(idxs));
+        }
+
+        parse_index(inner).map(Segment::Index)
+    }
+
+    /// Check whether a str begins with '[' and ends with ']'
+    fn has_array_brackets(s: &str) -> bool {
+        s.as_bytes()[0] == b'[' && s.as_bytes()[s.len() - 1] == b']'
+    }
+
+    /// Split the inside of a key union (`{a, "b.c", d}`) into its identifiers
+    ///
+    /// Duplicated identifiers are removed, the order is kept otherwise.
+    fn split_key_union(s: &str) -> SpanResult<'_, Vec<String>> {
+        let mut idents: Vec<String> = Vec::new();
+        let mut rest = s;
+
+        loop {
+            let end = find_unquoted(rest, ',').unwrap_or(rest.len());
+            let ident = rest[..end].trim();
+
+            let ident = match ident.chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    if ident.len() < 2 || !ident.ends_with(quote) {
+                        return Err((ErrorKind::Malformed, ident));
+                    }
+                    &ident[1..ident.len() - 1]
+                }
+                None => return Err((ErrorKind::EmptyIdentifier, ident)),
+                Some(_) => ident,
+            };
+
+            if !idents.iter().any(|i| i == ident) {
+                idents.push(String::from(ident));
+            }
+
+            if end == rest.len() {
+                return Ok(idents);
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+
+    /// Find the first occurrence of `c` in `s` which is not inside of quotes
+    fn find_unquoted(s: &str, c: char) -> Option<usize> {
+        let mut quote = None;
+        for (pos, chr) in s.char_indices() {
+            match quote {
+                Some(q) if chr == q => quote = None,
+                Some(_) => {}
+                None if chr == c => return Some(pos),
+                None if chr == '"' || chr == '\'' => quote = Some(chr),
+                None => {}
+            }
+        }
+        None
+    }
+
+    let error = |kind, offset, len| Error { kind, offset, len };
+
+    if query.is_empty() {
+        return Err(error(ErrorKind::EmptyQuery, 0, 0));
+    }
+
+    if seperator.is_empty() {
+        return Err(error(ErrorKind::EmptySeperator, 0, 0));
+    }
+
+    let tokenize = || {
+        let at_seperator = |pos: usize| query[pos..].starts_with(seperator);
+        let is_escape = |chr: char| escape == Some(chr);
+        let plain = |s, escaped| match escape {
+            Some(escape) if escaped => Ok(escaped_segment(s, escape)),
+            _ => plain_segment(s),
+        };
+        // An identifier directly followed by array accesses (`bin[2]`) is split into its parts
+        let push_plain = |segments: &mut Vec<Segment<'a>>, s: &'a str, escaped: bool| {
+            let open = match s.find('[') {
+                Some(open) if open > 0 && !escaped && s.ends_with(']') => open,
+                _ => return plain(s, escaped).map(|segment| segments.push(segment)),
+            };
+
+            segments.push(plain_segment(&s[..open])?);
+            let mut rest = &s[open..];
+            while !rest.is_empty() {
+                match rest.find(']') {
+                    Some(close) if rest.starts_with('[') => {
+                        segments.push(array_segment(&rest[..=close])?);
+                        rest = &rest[close + 1..];
+                    }
+                    _ => return Err((ErrorKind::Malformed, rest)),
+                }
+            }
+            Ok(())
+        };
+        let mut segments = Vec::new();
+        let mut state = State::Start;
+        let mut start = 0;
+        let mut pos = 0;
+
+        while let Some(chr) = query[pos..].chars().next() {
+            let mut next = pos + chr.len_utf8();
+
+            state = match state {
+                State::Start => {
+                    start = pos;
+                    match chr {
+                        _ if is_escape(chr) => State::Escape,
+                        '"' | '\'' => State::Quoted { quote: chr },
+                        '{' => State::Bracketed {
+                            close: '}',
+                            quote: None,
+                        },
+                        '[' if query[pos..].starts_with("[?(") => State::Bracketed {
+                            close: ']',
+                            quote: None,
+                        },
+                        '/' if regexes => State::Regex { escaped: false },
+                        _ if at_seperator(pos) => {
+                            segments.push(plain_segment(&query[pos..pos])?);
+                            next = pos + seperator.len();
+                            State::Start
+                        }
+                        _ => State::Plain { escaped: false },
+                    }
+                }
+
+                State::Plain { .. } if is_escape(chr) => State::Escape,
+                State::Plain { escaped } if at_seperator(pos) => {
+                    push_plain(&mut segments, &query[start..pos], escaped)?;
+                    next = pos + seperator.len();
+                    State::Start
+                }
+                State::Plain { .. } => state,
+                State::Escape => State::Plain { escaped: true },
+
+                State::Quoted { quote } if chr == quote => {
+                    let ident = &query[start + 1..pos];
+                    segments.push(Segment::Identifier(Cow::Borrowed(ident)));
+                    State::Closed
+                }
+                State::Quoted { .. } => state,
+
+                State::Bracketed { close, quote: None } if chr == close => {
+                    segments.push(bracketed_segment(&query[start..=pos])?);
+                    State::Closed
+                }
+                State::Bracketed { close, quote: None } if chr == '"' || chr == '\'' => {
+                    State::Bracketed {
+                        close,
+                        quote: Some(chr),
+                    }
+                }
+                State::Bracketed { close, quote } if quote == Some(chr) => {
+                    State::Bracketed { close, quote: None }
+                }
+                State::Bracketed { .. } => state,
+
+                State::Regex { escaped: true } => State::Regex { escaped: false },
+                State::Regex { .. } if chr == '\\' => State::Regex { escaped: true },
+                State::Regex { .. } if chr == '/' => {
+                    segments.push(Segment::Regex(&query[start + 1..pos]));
+                    State::Closed
+                }
+                State::Regex { .. } => state,
+
+                State::Closed if at_seperator(pos) => {
+                    next = pos + seperator.len();
+                    State::Start
+                }
+                State::Closed => return Err((ErrorKind::Malformed, &query[pos..])),
+            };
+
+            pos = next;
+        }
+
+        match state {
+            // The query ends with a seperator, so the last segment is empty
+            State::Start => segments.push(plain_segment(&query[query.len()..])?),
+            State::Plain { escaped } => push_plain(&mut segments, &query[start..], escaped)?,
+            State::Closed => {}
+            // There is no character the escape character at the end could escape
+            State::Escape => {
+                let len = escape.map_or(0, char::len_utf8);
+                return Err((ErrorKind::Malformed, &query[query.len() - len..]));
+            }
+            _ => return Err((ErrorKind::Malformed, &query[start..])),
+        }
+
+        Ok(segments)
+    };
+
+    // All parts are slices of the query, so their offset is the distance of the pointers
+    tokenize().map_err(|(kind, part)| {
+        error(
+            kind,
+            part.as_ptr() as usize - query.as_ptr() as usize,
+            part.len(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ident(ident: &str) -> Segment<'_> {
+        Segment::Identifier(Cow::Borrowed(ident))
+    }
+
+    fn tokenize_default(query: &str) -> Result<Vec<Segment<'_>>, Error> {
+        tokenize(query, ".", Some('\\'), true)
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let segments =
+            tokenize_default(r#"a.'b.c'.bin[2].[1:].[3,1].{x, "y,z"}.**.*.^.[+].d\.e.[?(@.f)]"#);
+        let expected = vec![
+            ident("a"),
+            ident("b.c"),
+            ident("bin"),
+            Segment::Index(2),
+            Segment::Slice(Some(1), None),
+            Segment::IndexUnion(vec![1, 3]),
+            Segment::KeyUnion(vec![String::from("x"), String::from("y,z")]),
+            Segment::RecursiveDescent,
+            Segment::Wildcard,
+            Segment::Parent,
+            Segment::Append,
+            ident("d.e"),
+            Segment::Filter {
+                expr: "@.f",
+                predicate: Expr::Exists(vec![String::from("f")]),
+            },
+        ];
+        assert_eq!(Ok(expected), segments);
+    }
+
+    #[test]
+    fn test_tokenize_regexes() {
+        let segments = tokenize("a::/b.*\\/c/", "::", None, true);
+        assert_eq!(Ok(vec![ident("a"), Segment::Regex("b.*\\/c")]), segments);
+
+        let segments = tokenize("a./b/", ".", None, false);
+        assert_eq!(Ok(vec![ident("a"), ident("/b/")]), segments);
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        let error = |query| tokenize_default(query).map_err(|e| (e.kind, e.offset, e.len));
+
+        assert_eq!(Err((ErrorKind::EmptyQuery, 0, 0)), error(""));
+        assert_eq!(Err((ErrorKind::EmptyIdentifier, 2, 0)), error("a..b"));
+        assert_eq!(Err((ErrorKind::EmptyIdentifier, 2, 0)), error("a."));
+        assert_eq!(
+            Err((ErrorKind::ArrayAccessWithoutIndex, 3, 0)),
+            error("a.[]")
+        );
+        assert_eq!(
+            Err((ErrorKind::ArrayAccessWithoutIndex, 3, 1)),
+            error("a.[x]")
+        );
+        assert_eq!(
+            Err((ErrorKind::ArrayAccessWithInvalidIndex, 3, 3)),
+            error("a.[3:1]")
+        );
+        assert_eq!(Err((ErrorKind::Malformed, 2, 2)), error("a.'b"));
+        assert_eq!(Err((ErrorKind::Malformed, 5, 1)), error("a.'b'c"));
+        assert_eq!(Err((ErrorKind::EmptyIdentifier, 5, 0)), error("a.{b,}"));
+        assert_eq!(Err((ErrorKind::Malformed, 6, 2)), error("a.b[0]c]"));
+        assert_eq!(Err((ErrorKind::Malformed, 1, 1)), error(r"a\"));
+        assert_eq!(Err((ErrorKind::Malformed, 2, 9)), error("a.[?(@ ==)]"));
+        assert_eq!(Err((ErrorKind::Malformed, 2, 2)), error("a./b"));
+        assert_eq!(
+            Err((ErrorKind::EmptySeperator, 0, 0)),
+            tokenize("a", "", None, true).map_err(|e| (e.kind, e.offset, e.len))
+        );
+    }
+
+}
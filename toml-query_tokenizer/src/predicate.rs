@@ -0,0 +1,216 @@
+//! The expressions of filter segments (`[?(...)]`)
+//!
+//! The expression language is a small subset of the JSONPath filter expressions:
+//!
+//! ```text
+//! expr       := and_expr ( "||" and_expr )*
+//! and_expr   := comparison ( "&&" comparison )*
+//! comparison := "(" expr ")" | path [ operator literal ]
+//! path       := "@" ( "." key )*
+//! operator   := "==" | "!=" | "<" | "<=" | ">" | ">="
+//! literal    := string | integer | float | "true" | "false"
+//! ```
+//!
+//! A path without operator tests whether the path exists in the element.
+use std::fmt;
+
+use crate::ErrorKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// `@.a.b`
+    Exists(Vec<String>),
+
+    /// `@.a.b == "value"`
+    Compare(Vec<String>, Comparison, Literal),
+
+    And(Box<Expr>, Box<Expr>),
+
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match *self {
+            Comparison::Eq => "==",
+            Comparison::NotEq => "!=",
+            Comparison::Less => "<",
+            Comparison::LessEq => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterEq => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Parse a filter expression (the part between `[?(` and `)]`)
+pub fn parse(expr: &str) -> Result<Expr, ErrorKind> {
+    let mut parser = Parser { expr, pos: 0 };
+    let predicate = parser.parse_or()?;
+
+    parser.skip_whitespace();
+    if parser.pos != expr.len() {
+        return Err(ErrorKind::Malformed);
+    }
+
+    Ok(predicate)
+}
+
+struct Parser<'a> {
+    expr: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.expr[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip whitespace and consume `s`, if the remaining expression starts with it
+    fn eat(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ErrorKind> {
+        let mut predicate = self.parse_and()?;
+        while self.eat("||") {
+            let rhs = self.parse_and()?;
+            predicate = Expr::Or(Box::new(predicate), Box::new(rhs));
+        }
+        Ok(predicate)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ErrorKind> {
+        let mut predicate = self.parse_comparison()?;
+        while self.eat("&&") {
+            let rhs = self.parse_comparison()?;
+            predicate = Expr::And(Box::new(predicate), Box::new(rhs));
+        }
+        Ok(predicate)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ErrorKind> {
+        if self.eat("(") {
+            let predicate = self.parse_or()?;
+            if !self.eat(")") {
+                return Err(ErrorKind::Malformed);
+            }
+            return Ok(predicate);
+        }
+
+        let path = self.parse_path()?;
+
+        // Two-character operators first, so "<=" is not parsed as "<"
+        let operators = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::NotEq),
+            ("<=", Comparison::LessEq),
+            (">=", Comparison::GreaterEq),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+
+        for &(op, cmp) in operators.iter() {
+            if self.eat(op) {
+                let literal = self.parse_literal()?;
+                return Ok(Expr::Compare(path, cmp, literal));
+            }
+        }
+
+        Ok(Expr::Exists(path))
+    }
+
+    fn parse_path(&mut self) -> Result<Vec<String>, ErrorKind> {
+        if !self.eat("@") {
+            return Err(ErrorKind::Malformed);
+        }
+
+        let mut path = Vec::new();
+        while self.rest().starts_with('.') {
+            self.pos += 1;
+            let key = match self.rest().chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => self.parse_quoted(quote)?,
+                _ => {
+                    let rest = self.rest();
+                    let len = rest
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                        .unwrap_or(rest.len());
+                    if len == 0 {
+                        return Err(ErrorKind::EmptyIdentifier);
+                    }
+                    self.pos += len;
+                    String::from(&rest[..len])
+                }
+            };
+            path.push(key);
+        }
+
+        Ok(path)
+    }
+
+    fn parse_quoted(&mut self, quote: char) -> Result<String, ErrorKind> {
+        let rest = &self.rest()[1..];
+        let end = rest.find(quote).ok_or(ErrorKind::Malformed)?;
+        self.pos += end + 2;
+        Ok(String::from(&rest[..end]))
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, ErrorKind> {
+        self.skip_whitespace();
+        match self.rest().chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                self.parse_quoted(quote).map(Literal::String)
+            }
+            _ => {
+                if self.eat("true") {
+                    return Ok(Literal::Boolean(true));
+                }
+                if self.eat("false") {
+                    return Ok(Literal::Boolean(false));
+                }
+
+                let rest = self.rest();
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_digit() || "+-._eE".contains(c)))
+                    .unwrap_or(rest.len());
+                let number = rest[..len].replace('_', "");
+                self.pos += len;
+
+                number
+                    .parse::<i64>()
+                    .map(Literal::Integer)
+                    .or_else(|_| number.parse::<f64>().map(Literal::Float))
+                    .map_err(|_| ErrorKind::Malformed)
+            }
+        }
+    }
+}